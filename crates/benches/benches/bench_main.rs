@@ -6,6 +6,7 @@ use criterion::criterion_main;
 criterion_main! {
     benchmarks::init_db::init_db,
     benchmarks::sudt::sudt,
+    benchmarks::program_cache::program_cache,
     benchmarks::smt::smt,
     benchmarks::fee_queue::fee_queue,
 }
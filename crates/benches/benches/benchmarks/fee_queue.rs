@@ -1,6 +1,6 @@
 use criterion::{criterion_group, Bencher, Criterion};
 use gw_common::{h256_ext::H256Ext, state::State, H256};
-use gw_config::GenesisConfig;
+use gw_config::{FeeQueueConfig, GenesisConfig};
 use gw_generator::genesis::init_genesis;
 use gw_mem_pool::fee::{
     queue::FeeQueue,
@@ -20,7 +20,11 @@ use gw_types::{
 const MAX_QUEUE_SIZE: usize = 100_000;
 
 fn bench_add_full(b: &mut Bencher) {
-    let mut queue = FeeQueue::new();
+    // a single sender fills the queue
+    let mut queue = FeeQueue::with_config(FeeQueueConfig {
+        max_entries_per_account: usize::MAX,
+        ..Default::default()
+    });
 
     let store = Store::open_tmp().expect("open store");
     setup_genesis(&store);
@@ -50,20 +54,23 @@ fn bench_add_full(b: &mut Bencher) {
             sender: 2,
             order: queue.len(),
         };
-        queue.add(entry1, ());
+        queue.add(entry1, ()).expect("add");
     }
 
     assert_eq!(queue.len(), MAX_QUEUE_SIZE);
 
+    let snap = store.get_snapshot();
+    let mem_store = MemStore::new(snap);
+    let tree = mem_store.state().unwrap();
+
+    // use distinct nonces, entries with the same sender and nonce are replaced
+    let mut nonce = MAX_QUEUE_SIZE as u32;
     b.iter(|| {
+        nonce += 1;
         let entry1 = FeeEntry {
             item: FeeItem::Tx(
                 L2Transaction::new_builder()
-                    .raw(
-                        RawL2Transaction::new_builder()
-                            .nonce(10001u32.pack())
-                            .build(),
-                    )
+                    .raw(RawL2Transaction::new_builder().nonce(nonce.pack()).build())
                     .build(),
             ),
             fee: (100 * 1000u64).into(),
@@ -71,12 +78,17 @@ fn bench_add_full(b: &mut Bencher) {
             sender: 2,
             order: queue.len(),
         };
-        queue.add(entry1, ());
+        queue.add(entry1, ()).expect("add");
+        queue.evict(&tree).expect("evict");
     });
 }
 
 fn bench_add_fetch_20(b: &mut Bencher) {
-    let mut queue = FeeQueue::new();
+    // a single sender fills the queue
+    let mut queue = FeeQueue::with_config(FeeQueueConfig {
+        max_entries_per_account: usize::MAX,
+        ..Default::default()
+    });
 
     let store = Store::open_tmp().expect("open store");
     setup_genesis(&store);
@@ -107,22 +119,21 @@ fn bench_add_fetch_20(b: &mut Bencher) {
             sender: 2,
             order: queue.len(),
         };
-        queue.add(entry1, ());
+        queue.add(entry1, ()).expect("add");
     }
 
     let mem_store = MemStore::new(snap);
     let tree = mem_store.state().unwrap();
 
+    // use distinct nonces, entries with the same sender and nonce are replaced
+    let mut nonce = MAX_QUEUE_SIZE as u32;
     b.iter(|| {
-        for i in 0..20 {
+        for _ in 0..20 {
+            nonce += 1;
             let entry1 = FeeEntry {
                 item: FeeItem::Tx(
                     L2Transaction::new_builder()
-                        .raw(
-                            RawL2Transaction::new_builder()
-                                .nonce((MAX_QUEUE_SIZE + i).pack())
-                                .build(),
-                        )
+                        .raw(RawL2Transaction::new_builder().nonce(nonce.pack()).build())
                         .build(),
                 ),
                 fee: (100 * 1000u64).into(),
@@ -130,14 +141,14 @@ fn bench_add_fetch_20(b: &mut Bencher) {
                 sender: 2,
                 order: queue.len(),
             };
-            queue.add(entry1, ());
+            queue.add(entry1, ()).expect("add");
         }
         queue.fetch(&tree, 20)
     });
 }
 
 pub fn bench(c: &mut Criterion) {
-    c.bench_function("FeeQueue add when full", |b| {
+    c.bench_function("FeeQueue add and evict when full", |b| {
         bench_add_full(b);
     });
     c.bench_function("FeeQueue add and fetch 20", |b| {
@@ -1,4 +1,5 @@
 pub mod fee_queue;
 pub mod init_db;
+pub mod program_cache;
 pub mod smt;
 pub mod sudt;
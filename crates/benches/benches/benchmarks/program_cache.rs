@@ -0,0 +1,327 @@
+use std::convert::TryInto;
+
+use criterion::*;
+use gw_common::{
+    builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID},
+    registry_address::RegistryAddress,
+    state::State,
+    H256,
+};
+use gw_config::{BackendConfig, BackendSwitchConfig};
+use gw_generator::{
+    account_lock_manage::AccountLockManage, backend_manage::BackendManage,
+    constants::L2TX_MAX_CYCLES, dummy_state::DummyState, traits::StateExt, Generator,
+};
+use gw_traits::ChainView;
+use gw_types::{
+    bytes::Bytes,
+    core::{AllowedContractType, AllowedEoaType, ScriptHashType},
+    offchain::RollupContext,
+    packed::{
+        AllowedTypeHash, BlockInfo, Fee, RawL2Transaction, RollupConfig, SUDTArgs, SUDTTransfer,
+        Script,
+    },
+    prelude::*,
+    U256,
+};
+
+const ROLLUP_SCRIPT_HASH: [u8; 32] = [42u8; 32];
+const ALWAYS_SUCCESS_LOCK_HASH: [u8; 32] = [255u8; 32];
+
+// meta contract
+const META_VALIDATOR_PATH: &str = "../../.tmp/binaries/godwoken-scripts/meta-contract-validator";
+const META_GENERATOR_PATH: &str = "../../.tmp/binaries/godwoken-scripts/meta-contract-generator";
+const META_VALIDATOR_SCRIPT_TYPE_HASH: [u8; 32] = [1u8; 32];
+
+// simple UDT
+const SUDT_VALIDATOR_PATH: &str = "../../.tmp/binaries/godwoken-scripts/sudt-validator";
+const SUDT_GENERATOR_PATH: &str = "../../.tmp/binaries/godwoken-scripts/sudt-generator";
+const SUDT_VALIDATOR_SCRIPT_TYPE_HASH: [u8; 32] = [2u8; 32];
+
+// eth address registry
+const ETH_REGISTRY_VALIDATOR_SCRIPT_TYPE_HASH: [u8; 32] = [3u8; 32];
+
+// polyjuice
+const POLYJUICE_VALIDATOR_PATH: &str = "../../.tmp/binaries/godwoken-polyjuice/validator";
+const POLYJUICE_GENERATOR_PATH: &str = "../../.tmp/binaries/godwoken-polyjuice/generator";
+const POLYJUICE_VALIDATOR_SCRIPT_TYPE_HASH: [u8; 32] = [4u8; 32];
+
+// PUSH1 0x01 PUSH1 0x00 RETURN, deploys a contract which code is STOP
+const STOP_CONTRACT_INIT_CODE: [u8; 5] = [0x60, 0x01, 0x60, 0x00, 0xf3];
+
+struct DummyChainStore;
+
+impl ChainView for DummyChainStore {
+    fn get_block_hash_by_number(&self, _number: u64) -> Result<Option<H256>, gw_db::error::Error> {
+        Err("dummy chain store".to_string().into())
+    }
+}
+
+fn build_rollup_config() -> RollupConfig {
+    RollupConfig::new_builder()
+        .l2_sudt_validator_script_type_hash(SUDT_VALIDATOR_SCRIPT_TYPE_HASH.pack())
+        .allowed_eoa_type_hashes(
+            vec![AllowedTypeHash::new(
+                AllowedEoaType::Eth,
+                ALWAYS_SUCCESS_LOCK_HASH,
+            )]
+            .pack(),
+        )
+        .allowed_contract_type_hashes(
+            vec![
+                AllowedTypeHash::new(AllowedContractType::Meta, META_VALIDATOR_SCRIPT_TYPE_HASH),
+                AllowedTypeHash::new(AllowedContractType::Sudt, SUDT_VALIDATOR_SCRIPT_TYPE_HASH),
+                AllowedTypeHash::new(
+                    AllowedContractType::EthAddrReg,
+                    ETH_REGISTRY_VALIDATOR_SCRIPT_TYPE_HASH,
+                ),
+                AllowedTypeHash::new(
+                    AllowedContractType::Polyjuice,
+                    POLYJUICE_VALIDATOR_SCRIPT_TYPE_HASH,
+                ),
+            ]
+            .pack(),
+        )
+        .build()
+}
+
+fn build_generator(rollup_config: &RollupConfig, cache_programs: bool) -> Generator {
+    let configs = vec![
+        BackendConfig {
+            validator_path: META_VALIDATOR_PATH.into(),
+            generator_path: META_GENERATOR_PATH.into(),
+            validator_script_type_hash: META_VALIDATOR_SCRIPT_TYPE_HASH.into(),
+            backend_type: gw_config::BackendType::Meta,
+        },
+        BackendConfig {
+            validator_path: SUDT_VALIDATOR_PATH.into(),
+            generator_path: SUDT_GENERATOR_PATH.into(),
+            validator_script_type_hash: SUDT_VALIDATOR_SCRIPT_TYPE_HASH.into(),
+            backend_type: gw_config::BackendType::Sudt,
+        },
+        BackendConfig {
+            validator_path: POLYJUICE_VALIDATOR_PATH.into(),
+            generator_path: POLYJUICE_GENERATOR_PATH.into(),
+            validator_script_type_hash: POLYJUICE_VALIDATOR_SCRIPT_TYPE_HASH.into(),
+            backend_type: gw_config::BackendType::Polyjuice,
+        },
+    ];
+    let backend_manage = BackendManage::from_config_with_program_cache(
+        vec![BackendSwitchConfig {
+            switch_height: 0,
+            backends: configs,
+        }],
+        cache_programs,
+    )
+    .expect("default backend");
+    let rollup_ctx = RollupContext {
+        rollup_config: rollup_config.clone(),
+        rollup_script_hash: ROLLUP_SCRIPT_HASH.into(),
+    };
+    Generator::new(
+        backend_manage,
+        AccountLockManage::default(),
+        rollup_ctx,
+        Default::default(),
+    )
+}
+
+fn new_block_info(block_producer: &RegistryAddress, number: u64, timestamp: u64) -> BlockInfo {
+    BlockInfo::new_builder()
+        .block_producer(Bytes::from(block_producer.to_bytes()).pack())
+        .number(number.pack())
+        .timestamp(timestamp.pack())
+        .build()
+}
+
+fn polyjuice_args(create: bool, gas_limit: u64, value: u128, data: &[u8]) -> Bytes {
+    let mut args = vec![0u8; 52];
+    args[0..7].copy_from_slice(b"\xFF\xFF\xFFPOLY");
+    args[7] = if create { 3 } else { 0 };
+    args[8..16].copy_from_slice(&gas_limit.to_le_bytes());
+    args[16..32].copy_from_slice(&1u128.to_le_bytes());
+    args[32..48].copy_from_slice(&value.to_le_bytes());
+    let data_len: u32 = data.len().try_into().unwrap();
+    args[48..52].copy_from_slice(&data_len.to_le_bytes());
+    args.extend_from_slice(data);
+    args.into()
+}
+
+fn create_eth_account(tree: &mut DummyState, address: [u8; 20], balance: U256) -> u32 {
+    let script = {
+        let mut args = ROLLUP_SCRIPT_HASH.to_vec();
+        args.extend(address);
+        Script::new_builder()
+            .code_hash(ALWAYS_SUCCESS_LOCK_HASH.pack())
+            .args(args.pack())
+            .hash_type(ScriptHashType::Type.into())
+            .build()
+    };
+    let script_hash = script.hash().into();
+    let id = tree
+        .create_account_from_script(script)
+        .expect("create account");
+    let addr = RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, address.to_vec());
+    tree.mapping_registry_address_to_script_hash(addr.clone(), script_hash)
+        .expect("mapping address");
+    if !balance.is_zero() {
+        tree.mint_sudt(CKB_SUDT_ACCOUNT_ID, &addr, balance)
+            .expect("init balance");
+    }
+    id
+}
+
+struct Setup {
+    tree: DummyState,
+    from_id: u32,
+    block_info: BlockInfo,
+}
+
+/// Builtin accounts, a sender with balance and a block producer
+fn setup() -> Setup {
+    let mut tree = DummyState::default();
+    let builtins = [
+        (META_VALIDATOR_SCRIPT_TYPE_HASH, [0u8; 32]),
+        (SUDT_VALIDATOR_SCRIPT_TYPE_HASH, [0u8; 32]),
+        (ETH_REGISTRY_VALIDATOR_SCRIPT_TYPE_HASH, [0u8; 32]),
+    ];
+    for (code_hash, args) in builtins {
+        let mut script_args = ROLLUP_SCRIPT_HASH.to_vec();
+        script_args.extend(args);
+        tree.create_account_from_script(
+            Script::new_builder()
+                .code_hash(code_hash.pack())
+                .args(script_args.pack())
+                .hash_type(ScriptHashType::Type.into())
+                .build(),
+        )
+        .expect("create account");
+    }
+
+    let from_id = create_eth_account(&mut tree, [1u8; 20], U256::from(10_000_000_000u128));
+    create_eth_account(&mut tree, [2u8; 20], U256::zero());
+    let block_producer = {
+        create_eth_account(&mut tree, [3u8; 20], U256::zero());
+        RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, vec![3u8; 20])
+    };
+    let block_info = new_block_info(&block_producer, 1, 0);
+
+    Setup {
+        tree,
+        from_id,
+        block_info,
+    }
+}
+
+fn execute(generator: &Generator, setup: &mut Setup, to_id: u32, nonce: u32, args: Bytes) {
+    let raw_tx = RawL2Transaction::new_builder()
+        .from_id(setup.from_id.pack())
+        .to_id(to_id.pack())
+        .nonce(nonce.pack())
+        .args(args.pack())
+        .build();
+    let run_result = generator
+        .execute_transaction(
+            &DummyChainStore,
+            &setup.tree,
+            &setup.block_info,
+            &raw_tx,
+            L2TX_MAX_CYCLES,
+        )
+        .expect("execute");
+    setup
+        .tree
+        .apply_run_result(&run_result.write)
+        .expect("update state");
+}
+
+fn sudt_transfer_args() -> Bytes {
+    let to = RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, vec![2u8; 20]);
+    SUDTArgs::new_builder()
+        .set(
+            SUDTTransfer::new_builder()
+                .to_address(Bytes::from(to.to_bytes()).pack())
+                .amount(U256::from(4000u128).pack())
+                .fee(
+                    Fee::new_builder()
+                        .amount(42u128.pack())
+                        .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+                        .build(),
+                )
+                .build(),
+        )
+        .build()
+        .as_bytes()
+}
+
+/// Deploy a contract through polyjuice, returns the contract account id
+fn setup_polyjuice(generator: &Generator) -> (Setup, u32) {
+    let mut setup = setup();
+    let creator_id = {
+        let mut args = ROLLUP_SCRIPT_HASH.to_vec();
+        args.extend(CKB_SUDT_ACCOUNT_ID.to_le_bytes());
+        let script = Script::new_builder()
+            .code_hash(POLYJUICE_VALIDATOR_SCRIPT_TYPE_HASH.pack())
+            .args(args.pack())
+            .hash_type(ScriptHashType::Type.into())
+            .build();
+        setup
+            .tree
+            .create_account_from_script(script)
+            .expect("create account")
+    };
+    let deploy_args = polyjuice_args(true, 100_000, 0, &STOP_CONTRACT_INIT_CODE);
+    execute(generator, &mut setup, creator_id, 0, deploy_args);
+    let contract_id = setup.tree.get_account_count().expect("account count") - 1;
+    (setup, contract_id)
+}
+
+pub fn bench(c: &mut Criterion) {
+    let rollup_config = build_rollup_config();
+    let generators = [
+        ("cached", build_generator(&rollup_config, true)),
+        ("uncached", build_generator(&rollup_config, false)),
+    ];
+
+    let mut group = c.benchmark_group("program_cache");
+    group.throughput(Throughput::Elements(1u64));
+    for (name, generator) in generators.iter() {
+        group.bench_with_input(BenchmarkId::new("sudt", name), generator, |b, generator| {
+            b.iter_batched(
+                setup,
+                |mut setup| {
+                    execute(
+                        generator,
+                        &mut setup,
+                        CKB_SUDT_ACCOUNT_ID,
+                        0,
+                        sudt_transfer_args(),
+                    );
+                },
+                BatchSize::SmallInput,
+            );
+        });
+        group.bench_with_input(
+            BenchmarkId::new("polyjuice", name),
+            generator,
+            |b, generator| {
+                b.iter_batched(
+                    || setup_polyjuice(generator),
+                    |(mut setup, contract_id)| {
+                        // transfer CKB to the contract
+                        let args = polyjuice_args(false, 100_000, 1000, &[]);
+                        execute(generator, &mut setup, contract_id, 1, args);
+                    },
+                    BatchSize::SmallInput,
+                );
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = program_cache;
+    config = Criterion::default().sample_size(10);
+    targets = bench
+}
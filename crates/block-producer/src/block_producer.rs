@@ -280,6 +280,9 @@ impl BlockProducer {
                 }
             };
             log::debug!(target: "produce-block", "Produce l2block #{} ({}ms)", block_number, t.elapsed().as_millis());
+            gw_metrics::block_producer()
+                .produce_seconds
+                .observe(t.elapsed().as_secs_f64());
 
             let expected_next_block_number = global_state.block().count().unpack();
             if expected_next_block_number != block_number {
@@ -296,6 +299,9 @@ impl BlockProducer {
                 Ok(SubmitResult::Submitted) => {
                     log::debug!(target: "produce-block", "Submitted l2block #{} in {} ({}ms)",
                         block_number, hex::encode(&submitted_tx_hash), t.elapsed().as_millis());
+                    let metrics = gw_metrics::block_producer();
+                    metrics.submit_seconds.observe(t.elapsed().as_secs_f64());
+                    metrics.submitted_blocks.inc();
                     self.last_committed_l2_block = LastCommittedL2Block {
                         committed_tip_block_hash: l2_tip_block_hash,
                         committed_at: Instant::now(),
@@ -261,6 +261,7 @@ impl Challenger {
 
         let tx_hash = self.rpc_client.send_transaction(&tx).await?;
         log::info!("Challenge block {} in tx {}", block_numer, to_hex(&tx_hash));
+        gw_metrics::challenger().challenges.inc();
         self.last_submit_tx = Some(tx_hash);
 
         Ok(())
@@ -372,6 +373,7 @@ impl Challenger {
             Ok(tx_hash) => {
                 self.cleaner.watch_verifier(verifier, Some(tx_hash)).await;
                 log::info!("Cancel challenge in tx {}", to_hex(&tx_hash));
+                gw_metrics::challenger().cancels.inc();
                 self.last_submit_tx = Some(tx_hash);
             }
             Err(err) => {
@@ -510,6 +512,7 @@ impl Challenger {
 
         let tx_hash = self.rpc_client.send_transaction(&tx).await?;
         log::info!("Revert block in tx {}", to_hex(&tx_hash));
+        gw_metrics::challenger().reverts.inc();
         self.last_submit_tx = Some(tx_hash);
 
         Ok(())
@@ -9,6 +9,7 @@ pub mod produce_block;
 pub mod replay_block;
 pub mod runner;
 pub mod stake;
+pub mod state_pruner;
 pub mod test_mode_control;
 pub mod trace;
 pub mod types;
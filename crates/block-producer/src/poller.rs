@@ -92,7 +92,7 @@ impl ChainUpdater {
 
     // Start syncing
     #[instrument(skip_all, name = "chain updater handle_event")]
-    pub async fn handle_event(&mut self, _event: ChainEvent) -> Result<()> {
+    pub async fn handle_event(&mut self, event: ChainEvent) -> Result<()> {
         let initial_syncing = !self.initialized;
         // Always start from last valid tip on l1
         if !self.initialized {
@@ -140,6 +140,7 @@ impl ChainUpdater {
 
         let sync_monitor = self.sync_monitor.clone();
         sync_monitor.instrument(self.try_sync()).await?;
+        self.report_metrics(&event).await;
 
         if initial_syncing {
             // Start notify mem pool after synced
@@ -149,6 +150,29 @@ impl ChainUpdater {
         Ok(())
     }
 
+    async fn report_metrics(&self, event: &ChainEvent) {
+        let l1_tip_number: u64 = match event {
+            ChainEvent::NewBlock { block } => block.header().raw().number().unpack(),
+            ChainEvent::Reverted { new_block, .. } => new_block.header().raw().number().unpack(),
+        };
+        let (l1_synced_number, l2_tip_number): (u64, u64) = {
+            let chain = self.chain.lock().await;
+            let local_state = chain.local_state();
+            (
+                local_state.last_synced().number().unpack(),
+                local_state.tip().raw().number().unpack(),
+            )
+        };
+
+        let metrics = gw_metrics::chain();
+        metrics.l1_tip_number.set(l1_tip_number as i64);
+        metrics.l1_synced_number.set(l1_synced_number as i64);
+        metrics
+            .l1_lag_blocks
+            .set(l1_tip_number.saturating_sub(l1_synced_number) as i64);
+        metrics.l2_tip_number.set(l2_tip_number as i64);
+    }
+
     #[instrument(skip_all)]
     pub async fn try_sync(&mut self) -> anyhow::Result<()> {
         let valid_tip_l1_block_number = {
@@ -3,6 +3,7 @@ use crate::{
     challenger::{Challenger, ChallengerNewArgs},
     cleaner::Cleaner,
     poller::ChainUpdater,
+    state_pruner::StatePruner,
     test_mode_control::TestModeControl,
     types::ChainEvent,
     withdrawal_unlocker::FinalizedWithdrawalUnlocker,
@@ -13,8 +14,7 @@ use gw_chain::chain::Chain;
 use gw_challenge::offchain::{OffChainMockContext, OffChainMockContextBuildArgs};
 use gw_ckb_hardfork::{GLOBAL_CURRENT_EPOCH_NUMBER, GLOBAL_HARDFORK_SWITCH, GLOBAL_VM_VERSION};
 use gw_common::{blake2b::new_blake2b, registry_address::RegistryAddress, H256};
-use gw_config::{BlockProducerConfig, Config, NodeMode};
-use gw_db::migrate::open_or_create_db;
+use gw_config::{BlockProducerConfig, Config, NodeMode, StateHistoryRetention};
 use gw_dynamic_config::manager::DynamicConfigManager;
 use gw_generator::{
     account_lock_manage::{
@@ -40,8 +40,9 @@ use gw_rpc_client::{
 use gw_rpc_server::{
     registry::{Registry, RegistryArgs},
     server::start_jsonrpc_server,
+    ws_server::start_ws_server,
 };
-use gw_store::Store;
+use gw_store::{migrate::open_or_create_db, Store};
 use gw_types::{
     bytes::Bytes,
     core::AllowedEoaType,
@@ -835,6 +836,56 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
             .transpose()?;
         PolyjuiceSenderRecover::create(generator.rollup_context(), block_producer_wallet)?
     };
+    // WebSocket subscriptions are driven by mem pool events
+    let ws_server_args = match (config.rpc_server.websocket.clone(), &mem_pool) {
+        (Some(ws_config), Some(mem_pool)) => {
+            let ws_address: SocketAddr = {
+                let mut addrs: Vec<_> = ws_config.listen.to_socket_addrs()?.collect();
+                if addrs.len() != 1 {
+                    return Err(anyhow!(
+                        "Invalid WebSocket listen address `{}`",
+                        &ws_config.listen
+                    ));
+                }
+                addrs.remove(0)
+            };
+            Some((ws_address, ws_config, store.clone(), mem_pool.clone()))
+        }
+        (Some(_), None) => {
+            log::warn!("Failed to start WebSocket server, because mem_pool is None.");
+            None
+        }
+        (None, _) => None,
+    };
+    let metrics_server_args = match config.metrics.as_ref() {
+        Some(metrics_config) => {
+            let metrics_address: SocketAddr = {
+                let mut addrs: Vec<_> = metrics_config.listen.to_socket_addrs()?.collect();
+                if addrs.len() != 1 {
+                    return Err(anyhow!(
+                        "Invalid metrics listen address `{}`",
+                        &metrics_config.listen
+                    ));
+                }
+                addrs.remove(0)
+            };
+            Some((metrics_address, store.clone()))
+        }
+        None => None,
+    };
+
+    let state_pruner = match config.store.state_history {
+        StateHistoryRetention::Archive => None,
+        StateHistoryRetention::Pruned { keep_blocks } => {
+            let finality_blocks = rollup_config.finality_blocks().unpack();
+            Some(StatePruner::new(
+                store.clone(),
+                keep_blocks,
+                finality_blocks,
+            ))
+        }
+    };
+
     let args = RegistryArgs {
         store,
         mem_pool,
@@ -969,6 +1020,52 @@ pub async fn run(config: Config, skip_config_check: bool) -> Result<()> {
         }
     });
 
+    if let Some((ws_address, ws_config, store, mem_pool)) = ws_server_args {
+        let sub_shutdown = shutdown_event.subscribe();
+        let ws_shutdown_send = shutdown_send.clone();
+        spawn(async move {
+            let _ws_shutdown_send = ws_shutdown_send;
+            if let Err(err) =
+                start_ws_server(ws_address, ws_config, store, mem_pool, sub_shutdown).await
+            {
+                log::error!("Error running WebSocket server: {:?}", err);
+            }
+        });
+    }
+
+    if let Some((metrics_address, store)) = metrics_server_args {
+        let sub_shutdown = shutdown_event.subscribe();
+        let metrics_shutdown_send = shutdown_send.clone();
+        // RocksDB memory usage is gathered on scrape
+        let on_scrape = move || {
+            let memory_bytes = &gw_metrics::db().memory_bytes;
+            for stat in store.gather_mem_stats() {
+                if let Some(value) = stat.value() {
+                    memory_bytes
+                        .with_label_values(&[stat.name(), stat.type_()])
+                        .set(value as i64);
+                }
+            }
+        };
+        spawn(async move {
+            let _metrics_shutdown_send = metrics_shutdown_send;
+            if let Err(err) =
+                gw_metrics::start_metrics_server(metrics_address, on_scrape, sub_shutdown).await
+            {
+                log::error!("Error running metrics server: {:?}", err);
+            }
+        });
+    }
+
+    if let Some(state_pruner) = state_pruner {
+        let sub_shutdown = shutdown_event.subscribe();
+        let pruner_shutdown_send = shutdown_send.clone();
+        spawn(async move {
+            let _pruner_shutdown_send = pruner_shutdown_send;
+            state_pruner.run(sub_shutdown).await;
+        });
+    }
+
     tokio::select! {
         _ = sigint_or_sigterm() => { },
         _ = chain_task => {},
@@ -0,0 +1,96 @@
+//! Prune block state records of old blocks in background, so history state
+//! is only kept for the last `keep_blocks` blocks.
+
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use gw_store::{traits::chain_store::ChainStore, Store};
+use gw_types::prelude::Unpack;
+use tokio::sync::broadcast;
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(10);
+// Max blocks pruned in a round, so a pruning node catches up in small steps
+const MAX_PRUNE_BLOCKS: u64 = 100;
+
+pub struct StatePruner {
+    store: Store,
+    keep_blocks: u64,
+}
+
+impl StatePruner {
+    /// History state of unfinalized blocks is always kept, it is required to
+    /// revert blocks.
+    pub fn new(store: Store, keep_blocks: u64, finality_blocks: u64) -> Self {
+        if keep_blocks < finality_blocks {
+            log::warn!(
+                "[state pruner] keep {} blocks instead of {}, which is the rollup finality blocks",
+                finality_blocks,
+                keep_blocks
+            );
+        }
+        StatePruner {
+            store,
+            keep_blocks: keep_blocks.max(finality_blocks),
+        }
+    }
+
+    /// Prune blocks older than the retained blocks, returns the number of
+    /// pruned blocks.
+    pub fn prune(&self) -> Result<u64> {
+        let (pruned_number, prune_to) = {
+            let snap = self.store.get_snapshot();
+            let tip_number: u64 = snap.get_last_valid_tip_block()?.raw().number().unpack();
+            let pruned_number = snap.get_block_state_record_pruned_number()?;
+            let prune_to = tip_number
+                .saturating_sub(self.keep_blocks)
+                .min(pruned_number + MAX_PRUNE_BLOCKS);
+            (pruned_number, prune_to)
+        };
+
+        for block_number in (pruned_number + 1)..=prune_to {
+            let db = self.store.begin_transaction();
+            db.prune_block_state_record(block_number)?;
+            db.commit()?;
+        }
+
+        Ok(prune_to.saturating_sub(pruned_number))
+    }
+
+    pub async fn run(self, mut shutdown_event: broadcast::Receiver<()>) {
+        log::info!(
+            "[state pruner] keep history state of the last {} blocks",
+            self.keep_blocks
+        );
+        let pruner = Arc::new(self);
+        loop {
+            let interval = {
+                let pruner = Arc::clone(&pruner);
+                match tokio::task::spawn_blocking(move || pruner.prune()).await {
+                    Ok(Ok(pruned)) if pruned >= MAX_PRUNE_BLOCKS => Duration::ZERO,
+                    Ok(Ok(pruned)) => {
+                        if pruned > 0 {
+                            log::debug!("[state pruner] pruned {} blocks", pruned);
+                        }
+                        PRUNE_INTERVAL
+                    }
+                    Ok(Err(err)) => {
+                        log::error!("[state pruner] prune error: {}", err);
+                        PRUNE_INTERVAL
+                    }
+                    Err(err) => {
+                        log::error!("[state pruner] prune task error: {}", err);
+                        PRUNE_INTERVAL
+                    }
+                }
+            };
+
+            tokio::select! {
+                _ = shutdown_event.recv() => {
+                    log::info!("state pruner exited successfully");
+                    return;
+                }
+                _ = tokio::time::sleep(interval) => {}
+            }
+        }
+    }
+}
@@ -5,7 +5,7 @@ use gw_challenge::offchain::{verify_tx::TxWithContext, OffChainMockContext};
 use gw_common::{sparse_merkle_tree, state::State, H256};
 use gw_config::ChainConfig;
 use gw_generator::{
-    generator::{ApplyBlockArgs, ApplyBlockResult},
+    generator::{ApplyBlockArgs, ApplyBlockResult, VerifiedSignatures},
     traits::StateExt,
     types::vm::ChallengeContext,
     Generator,
@@ -228,7 +228,7 @@ impl Chain {
         &self.mem_pool
     }
 
-    pub fn generator(&self) -> &Generator {
+    pub fn generator(&self) -> &Arc<Generator> {
         &self.generator
     }
 
@@ -901,6 +901,32 @@ impl Chain {
         deposit_requests: Vec<DepositRequest>,
         deposit_asset_scripts: HashSet<Script>,
         withdrawals: Vec<WithdrawalRequestExtra>,
+    ) -> Result<Option<ChallengeTarget>> {
+        self.process_block_with_verified_signatures(
+            db,
+            l2block,
+            l2block_committed_info,
+            global_state,
+            deposit_requests,
+            deposit_asset_scripts,
+            withdrawals,
+            VerifiedSignatures::default(),
+        )
+    }
+
+    /// Same as `process_block`, skip signatures verified by
+    /// `Generator::verify_block_signatures`
+    #[allow(clippy::too_many_arguments)]
+    pub fn process_block_with_verified_signatures(
+        &mut self,
+        db: &StoreTransaction,
+        l2block: L2Block,
+        l2block_committed_info: L2BlockCommittedInfo,
+        global_state: GlobalState,
+        deposit_requests: Vec<DepositRequest>,
+        deposit_asset_scripts: HashSet<Script>,
+        withdrawals: Vec<WithdrawalRequestExtra>,
+        verified_signatures: VerifiedSignatures,
     ) -> Result<Option<ChallengeTarget>> {
         let tip_number: u64 = self.local_state.tip.raw().number().unpack();
         let tip_block_hash = self.local_state.tip.raw().hash();
@@ -922,6 +948,7 @@ impl Chain {
             l2block: l2block.clone(),
             deposit_requests: deposit_requests.clone(),
             withdrawals: withdrawals.clone(),
+            verified_signatures,
         };
         let tip_block_hash = self.local_state.tip().hash().into();
         let chain_view = ChainView::new(db, tip_block_hash);
@@ -7,6 +7,8 @@ use gw_jsonrpc_types::{
 use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet},
+    net::IpAddr,
+    num::NonZeroUsize,
     path::PathBuf,
 };
 
@@ -47,6 +49,8 @@ pub struct Config {
     pub dynamic_config: DynamicConfig,
     #[serde(default)]
     pub p2p_network_config: Option<P2PNetworkConfig>,
+    #[serde(default)]
+    pub metrics: Option<MetricsConfig>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Hash)]
@@ -54,6 +58,8 @@ pub struct Config {
 pub enum RPCMethods {
     PProf,
     Test,
+    Debug,
+    Admin,
 }
 
 #[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
@@ -61,6 +67,87 @@ pub struct RPCServerConfig {
     pub listen: String,
     #[serde(default)]
     pub enable_methods: HashSet<RPCMethods>,
+    /// Serve subscriptions over WebSocket, disabled if not set
+    #[serde(default)]
+    pub websocket: Option<WebSocketConfig>,
+    /// Rate limit requests of all methods by client, disabled if not set.
+    /// Can be hot reloaded.
+    #[serde(default)]
+    pub method_rate_limit: Option<RPCMethodRateLimitConfig>,
+    #[serde(default)]
+    pub request_limits: RPCRequestLimitsConfig,
+    /// Backups of `gw_admin_create_backup` are created under this directory
+    /// by name, the method is unavailable if not set
+    #[serde(default)]
+    pub backup_dir: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RPCRequestLimitsConfig {
+    /// Max size of a request body in bytes
+    pub max_body_size: usize,
+    /// Max number of calls in a batch request
+    pub max_batch_size: usize,
+    /// Requests which take longer than this are aborted
+    pub request_timeout_secs: u64,
+    /// Max number of requests served at the same time, the rest are rejected
+    pub max_concurrent_requests: usize,
+}
+
+impl Default for RPCRequestLimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_body_size: 10 * 1024 * 1024,
+            max_batch_size: 1000,
+            request_timeout_secs: 60,
+            max_concurrent_requests: 1024,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RPCMethodRateLimitConfig {
+    /// Length of a rate limit window
+    pub window_secs: u64,
+    /// Max total cost of requests a client can send in a window
+    pub max_cost_per_window: u64,
+    /// Cost of a method call, default is 1
+    #[serde(default)]
+    pub method_costs: HashMap<String, u64>,
+    /// Max number of tracked clients, zero is rejected
+    pub lru_size: NonZeroUsize,
+    /// Read client IP from this header (e.g. `X-Forwarded-For`) when the peer
+    /// is one of `trusted_proxies`, default is the peer address
+    #[serde(default)]
+    pub client_ip_header: Option<String>,
+    /// Reverse proxies appending the client IP to `client_ip_header`. The
+    /// right-most address which isn't a trusted proxy is the client.
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpAddr>,
+    /// Read API key from this header, e.g. `X-API-Key`
+    #[serde(default)]
+    pub api_key_header: Option<String>,
+    /// Max cost per window of API keys, requests with an unknown API key are
+    /// limited by client IP
+    #[serde(default)]
+    pub api_key_quotas: HashMap<String, u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WebSocketConfig {
+    pub listen: String,
+    #[serde(default = "default_ws_max_connections")]
+    pub max_connections: usize,
+    #[serde(default = "default_ws_max_subscriptions_per_connection")]
+    pub max_subscriptions_per_connection: usize,
+}
+
+fn default_ws_max_connections() -> usize {
+    1_000
+}
+
+fn default_ws_max_subscriptions_per_connection() -> usize {
+    16
 }
 
 #[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
@@ -269,6 +356,12 @@ pub struct P2PNetworkConfig {
     pub dial: Vec<String>,
 }
 
+/// Expose prometheus metrics at `http://<listen>/metrics`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    pub listen: String,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct PublishMemPoolConfig {
     pub hosts: Vec<String>,
@@ -290,6 +383,10 @@ pub struct MemPoolConfig {
     pub subscribe: Option<SubscribeMemPoolConfig>,
     #[serde(default)]
     pub mem_block: MemBlockConfig,
+    #[serde(default)]
+    pub fee_queue: FeeQueueConfig,
+    #[serde(default)]
+    pub parallel_execution: ParallelExecutionConfig,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -299,6 +396,35 @@ pub struct MemBlockConfig {
     pub max_txs: usize,
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FeeQueueConfig {
+    /// Minimal fee rate bump in percentage to replace a queued tx or
+    /// withdrawal which has the same sender and nonce
+    pub replace_by_fee_min_bump_percentage: u64,
+    /// Maximum number of queued txs and withdrawals per account whose nonce
+    /// is higher than the account's next nonce
+    pub max_future_entries_per_account: usize,
+    /// Drop future txs and withdrawals which stay in queue longer than this
+    pub future_entry_expire_secs: u64,
+    /// Maximum number of queued txs and withdrawals, entries are evicted when
+    /// the queue exceeds this size
+    pub max_queue_size: usize,
+    /// Maximum number of queued txs and withdrawals per account when the queue
+    /// is full, entries with the highest nonces are evicted first
+    pub max_entries_per_account: usize,
+    /// Evict txs and withdrawals which stay in queue longer than this
+    pub entry_expire_secs: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ParallelExecutionConfig {
+    /// Speculatively execute batches of queued txs in parallel, txs that
+    /// conflict with the txs before them are re-executed in order
+    pub enabled: bool,
+    /// Number of threads to execute a batch
+    pub workers: usize,
+}
+
 // Field default value for backward config file compitability
 fn default_restore_path() -> PathBuf {
     const DEFAULT_RESTORE_PATH: &str = "mem_block";
@@ -314,6 +440,8 @@ impl Default for MemPoolConfig {
             publish: None,
             subscribe: None,
             mem_block: MemBlockConfig::default(),
+            fee_queue: FeeQueueConfig::default(),
+            parallel_execution: ParallelExecutionConfig::default(),
         }
     }
 }
@@ -328,6 +456,28 @@ impl Default for MemBlockConfig {
     }
 }
 
+impl Default for FeeQueueConfig {
+    fn default() -> Self {
+        Self {
+            replace_by_fee_min_bump_percentage: 10,
+            max_future_entries_per_account: 64,
+            future_entry_expire_secs: 600,
+            max_queue_size: 100_000,
+            max_entries_per_account: 1_000,
+            entry_expire_secs: 3_600,
+        }
+    }
+}
+
+impl Default for ParallelExecutionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            workers: 4,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum NodeMode {
@@ -371,6 +521,25 @@ pub struct StoreConfig {
     pub options_file: Option<PathBuf>,
     #[serde(default)]
     pub options: HashMap<String, String>,
+    #[serde(default)]
+    pub state_history: StateHistoryRetention,
+}
+
+/// Retention of the history state, which is used to query state of old blocks
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "lowercase")]
+pub enum StateHistoryRetention {
+    /// Keep history state of all blocks
+    Archive,
+    /// Keep history state of the last `keep_blocks` blocks, block state
+    /// records of older blocks are pruned in background
+    Pruned { keep_blocks: u64 },
+}
+
+impl Default for StateHistoryRetention {
+    fn default() -> Self {
+        StateHistoryRetention::Archive
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
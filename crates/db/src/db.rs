@@ -6,13 +6,15 @@ use crate::write_batch::RocksDBWriteBatch;
 use crate::{internal_error, CfMemStat, Result};
 use gw_config::StoreConfig;
 use rocksdb::ops::{
-    CreateCF, DropCF, GetColumnFamilys, GetPinned, GetPinnedCF, IterateCF, OpenCF, Put, SetOptions,
-    WriteOps,
+    CreateCF, DropCF, GetColumnFamilys, GetPinned, GetPinnedCF, GetPropertyCF, IterateCF, OpenCF,
+    Put, SetOptions, WriteOps,
 };
 use rocksdb::{
-    ffi, ColumnFamily, ColumnFamilyDescriptor, DBPinnableSlice, FullOptions, IteratorMode,
-    OptimisticTransactionDB, OptimisticTransactionOptions, Options, WriteBatch, WriteOptions,
+    checkpoint::Checkpoint, ffi, ColumnFamily, ColumnFamilyDescriptor, DBPinnableSlice,
+    FullOptions, IteratorMode, OptimisticTransactionDB, OptimisticTransactionOptions, Options,
+    WriteBatch, WriteOptions,
 };
+use std::path::Path;
 use std::sync::Arc;
 
 /// RocksDB wrapper base on OptimisticTransactionDB
@@ -209,9 +211,26 @@ impl RocksDB {
         inner.drop_cf(&col.to_string()).map_err(internal_error)
     }
 
+    /// Estimated number of keys in the column
+    pub fn estimate_num_keys(&self, col: Col) -> Result<Option<u64>> {
+        let cf = cf_handle(&self.inner, col)?;
+        self.inner
+            .property_int_value_cf(cf, "rocksdb.estimate-num-keys")
+            .map_err(internal_error)
+    }
+
     pub fn gather_mem_stats(&self) -> Vec<CfMemStat> {
         self.inner.gather_memory_stats()
     }
+
+    /// Create a consistent checkpoint of the running db, sst files are hard
+    /// linked if `path` is on the same filesystem. `path` must not exist.
+    pub fn create_checkpoint<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let checkpoint = Checkpoint::new(&*self.inner).map_err(internal_error)?;
+        checkpoint
+            .create_checkpoint(path.as_ref())
+            .map_err(internal_error)
+    }
 }
 
 #[inline]
@@ -36,6 +36,24 @@ pub struct CfMemStat {
     value: PropertyValue<u64>,
 }
 
+impl CfMemStat {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn type_(&self) -> &str {
+        &self.type_
+    }
+
+    /// Returns None if the property is unavailable
+    pub fn value(&self) -> Option<u64> {
+        match self.value {
+            PropertyValue::Value(v) => Some(v),
+            PropertyValue::Null | PropertyValue::Error(_) => None,
+        }
+    }
+}
+
 /// A trait which used to track the RocksDB memory usage.
 ///
 /// References: [Memory usage in RocksDB](https://github.com/facebook/rocksdb/wiki/Memory-usage-in-RocksDB)
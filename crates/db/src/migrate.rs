@@ -1,11 +1,14 @@
 // This mod is used to init a db version when the db version is absent at the first time.
 // And check present db version is still compatible. Godwoken must run on a valid db.
 // If godwoken with an advanced verion runs on an old db, this is the time we can run migrations.
+//
+// Cheap migrations run when the db is opened, expensive ones must be run by
+// `godwoken migrate --run`, node refuses to start until they are done.
 use crate::{
     error::Error,
     read_only_db::{self, ReadOnlyDB},
-    schema::{COLUMN_META, META_TIP_BLOCK_HASH_KEY},
-    Result,
+    schema::{Col, COLUMN_META, META_TIP_BLOCK_HASH_KEY, MIGRATION_CURSOR_KEY_PREFIX},
+    DBIterator, Direction, IteratorMode, Result,
 };
 use std::{cmp::Ordering, collections::BTreeMap};
 
@@ -13,13 +16,24 @@ use gw_config::StoreConfig;
 
 use crate::{
     schema::{COLUMNS, MIGRATION_VERSION_KEY},
-    RocksDB,
+    RocksDB, RocksDBWriteBatch,
 };
 
+// Key values migrated in a write batch by `migrate_column`
+const MIGRATION_BATCH_SIZE: u64 = 10_000;
+
+/// Open the db with migrations of this crate, use `gw_store::migrate` to
+/// include migrations of the store.
 pub fn open_or_create_db(config: &StoreConfig) -> Result<RocksDB> {
+    open_or_create_db_with_factory(config, init_migration_factory())
+}
+
+pub fn open_or_create_db_with_factory(
+    config: &StoreConfig,
+    factory: MigrationFactory,
+) -> Result<RocksDB> {
     let read_only_db =
         read_only_db::ReadOnlyDB::open_cf(&config.path, vec![COLUMN_META.to_string()])?;
-    let factory = init_migration_factory();
     if let Some(db) = read_only_db {
         match check_readonly_db_version(&db, factory.last_db_version())? {
             Ordering::Greater => {
@@ -35,12 +49,21 @@ pub fn open_or_create_db(config: &StoreConfig) -> Result<RocksDB> {
             }
             Ordering::Equal => Ok(RocksDB::open(config, COLUMNS)),
             Ordering::Less => {
+                let pending = pending_migrations(&db, &factory)?;
+                if pending.iter().any(|m| m.expensive) {
+                    eprintln!(
+                        "The database needs expensive migrations, \n\
+                         please run `godwoken migrate --run` before starting the node."
+                    );
+                    return Err(Error {
+                        message: "The database needs expensive migrations".to_string(),
+                    });
+                }
+
                 log::info!("process fast migrations ...");
-                // TODO: Currently, there is only one migration: Add db version.
-                // So we can add db version by running default migration here.
-                // We should stop the process if we have more migrations and use **migration** command to run migration instead.
+                drop(db);
                 let db = RocksDB::open(config, COLUMNS);
-                let _ = factory.migrate(db)?;
+                let _ = factory.migrate(db, &())?;
 
                 Ok(RocksDB::open(config, COLUMNS))
             }
@@ -52,6 +75,47 @@ pub fn open_or_create_db(config: &StoreConfig) -> Result<RocksDB> {
     }
 }
 
+/// A migration not applied to the db yet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingMigration {
+    pub version: String,
+    pub name: String,
+    pub expensive: bool,
+    /// The migration was interrupted, it resumes from the last migrated key
+    pub interrupted: bool,
+}
+
+/// Pending migrations in order, without changing the db
+pub fn check_migrations(
+    config: &StoreConfig,
+    factory: &MigrationFactory,
+) -> Result<Vec<PendingMigration>> {
+    let db = match ReadOnlyDB::open_cf(&config.path, vec![COLUMN_META.to_string()])? {
+        Some(db) => db,
+        None => return Ok(vec![]),
+    };
+    if Ordering::Greater == check_readonly_db_version(&db, factory.last_db_version())? {
+        return Err(Error {
+            message: "The database is created by a higher version executable binary".to_string(),
+        });
+    }
+    pending_migrations(&db, factory)
+}
+
+/// Run all pending migrations, cheap and expensive ones
+pub fn run_migrations(
+    config: &StoreConfig,
+    factory: &MigrationFactory,
+    progress: &dyn MigrationProgress,
+) -> Result<()> {
+    if check_migrations(config, factory)?.is_empty() {
+        return Ok(());
+    }
+    let db = RocksDB::open(config, COLUMNS);
+    factory.migrate(db, progress)?;
+    Ok(())
+}
+
 //TODO: Replace with migration db version when we have our first migration impl.
 pub(crate) fn init_db_version(db: &RocksDB, db_ver: Option<&str>) -> Result<()> {
     if let Some(db_ver) = db_ver {
@@ -61,24 +125,49 @@ pub(crate) fn init_db_version(db: &RocksDB, db_ver: Option<&str>) -> Result<()>
     Ok(())
 }
 
+/// Version of the db, `None` if the db is fresh new, empty if the db is
+/// created before db version
+pub fn readonly_db_version(db: &ReadOnlyDB) -> Result<Option<String>> {
+    match db.get_pinned_default(MIGRATION_VERSION_KEY)? {
+        Some(version_bytes) => Ok(Some(
+            String::from_utf8(version_bytes.to_vec()).expect("version bytes to utf8"),
+        )),
+        None if is_non_empty_rdb(db) => Ok(Some(String::new())),
+        None => Ok(None),
+    }
+}
+
 fn check_readonly_db_version(db: &ReadOnlyDB, db_ver: Option<&str>) -> Result<Ordering> {
-    let version = match db.get_pinned_default(MIGRATION_VERSION_KEY)? {
-        Some(version_bytes) => {
-            String::from_utf8(version_bytes.to_vec()).expect("version bytes to utf8")
-        }
-        None => {
-            let ordering = if is_non_empty_rdb(db) {
-                Ordering::Less
-            } else {
-                Ordering::Equal
-            };
-            return Ok(ordering);
-        }
+    let version = match readonly_db_version(db)? {
+        Some(version) => version,
+        None => return Ok(Ordering::Equal),
     };
     log::debug!("current database version [{}]", version);
     Ok(version.as_str().cmp(db_ver.expect("Db version is absent!")))
 }
 
+fn pending_migrations(
+    db: &ReadOnlyDB,
+    factory: &MigrationFactory,
+) -> Result<Vec<PendingMigration>> {
+    let version = match readonly_db_version(db)? {
+        Some(version) => version,
+        None => return Ok(vec![]),
+    };
+    factory
+        .pending(&version)
+        .map(|migration| {
+            let cursor_key = migration_cursor_key(migration.version());
+            Ok(PendingMigration {
+                version: migration.version().to_string(),
+                name: migration.name().to_string(),
+                expensive: migration.expensive(),
+                interrupted: db.get_pinned(COLUMN_META, &cursor_key)?.is_some(),
+            })
+        })
+        .collect()
+}
+
 fn is_non_empty_rdb(db: &ReadOnlyDB) -> bool {
     if let Ok(v) = db.get_pinned(COLUMN_META, META_TIP_BLOCK_HASH_KEY) {
         if v.is_some() {
@@ -88,32 +177,110 @@ fn is_non_empty_rdb(db: &ReadOnlyDB) -> bool {
     false
 }
 
-trait Migration {
-    fn migrate(&self, db: RocksDB) -> Result<RocksDB>;
+/// Key in `COLUMN_META` to save the progress of an interrupted migration
+pub fn migration_cursor_key(version: &str) -> Vec<u8> {
+    let mut key = MIGRATION_CURSOR_KEY_PREFIX.to_vec();
+    key.extend_from_slice(version.as_bytes());
+    key
+}
+
+/// Progress of running migrations, `()` discards it
+pub trait MigrationProgress {
+    fn begin(&self, _version: &str, _name: &str) {}
+    fn set_length(&self, _len: u64) {}
+    fn inc(&self, _delta: u64) {}
+    fn finish(&self) {}
+}
+
+impl MigrationProgress for () {}
+
+pub trait Migration {
+    fn migrate(&self, db: RocksDB, progress: &dyn MigrationProgress) -> Result<RocksDB>;
     // Version can be genereated with: date '+%Y%m%d%H%M%S'
     fn version(&self) -> &str;
+    fn name(&self) -> &str;
+    // Expensive migrations aren't run on node start
     fn expensive(&self) -> bool;
 }
 
+/// Transform key values of a column in batches. The last migrated key is
+/// saved with each batch, including the last one, an interrupted migration
+/// resumes after it, so `transform` must tolerate keys it wrote into `col`
+/// itself.
+pub fn migrate_column<F>(
+    db: &RocksDB,
+    version: &str,
+    col: Col,
+    progress: &dyn MigrationProgress,
+    mut transform: F,
+) -> Result<()>
+where
+    F: FnMut(&[u8], &[u8], &mut RocksDBWriteBatch) -> Result<()>,
+{
+    if let Some(len) = db.estimate_num_keys(col)? {
+        progress.set_length(len);
+    }
+
+    let cursor_key = migration_cursor_key(version);
+    let cursor = db.get_pinned(COLUMN_META, &cursor_key)?.map(|c| c.to_vec());
+    let mode = match cursor {
+        Some(ref key) => IteratorMode::From(key, Direction::Forward),
+        None => IteratorMode::Start,
+    };
+
+    let mut batch = db.new_write_batch();
+    let mut count = 0;
+    let mut last_key = None;
+    for (key, value) in db.iter(col, mode)? {
+        if cursor.as_deref() == Some(key.as_ref()) {
+            continue;
+        }
+        transform(&key, &value, &mut batch)?;
+        count += 1;
+
+        if count == MIGRATION_BATCH_SIZE {
+            batch.put(COLUMN_META, &cursor_key, &key)?;
+            db.write(&batch)?;
+            batch.clear()?;
+            progress.inc(count);
+            count = 0;
+            last_key = None;
+        } else {
+            last_key = Some(key);
+        }
+    }
+    if let Some(key) = last_key {
+        batch.put(COLUMN_META, &cursor_key, &key)?;
+        db.write(&batch)?;
+        progress.inc(count);
+    }
+
+    Ok(())
+}
+
 struct DefaultMigration;
 impl Migration for DefaultMigration {
-    fn migrate(&self, db: RocksDB) -> Result<RocksDB> {
+    fn migrate(&self, db: RocksDB, _progress: &dyn MigrationProgress) -> Result<RocksDB> {
         Ok(db)
     }
     #[allow(clippy::needless_return)]
     fn version(&self) -> &str {
         return "20211229181750";
     }
+    fn name(&self) -> &str {
+        "add db version"
+    }
     fn expensive(&self) -> bool {
         false
     }
 }
 
-struct MigrationFactory {
+/// Registered migrations ordered by version
+pub struct MigrationFactory {
     migration_map: BTreeMap<String, Box<dyn Migration>>,
 }
 
-fn init_migration_factory() -> MigrationFactory {
+pub fn init_migration_factory() -> MigrationFactory {
     let mut factory = MigrationFactory::create();
     let migration = DefaultMigration;
     factory.insert(Box::new(migration));
@@ -126,34 +293,47 @@ impl MigrationFactory {
         Self { migration_map }
     }
 
-    fn insert(&mut self, migration: Box<dyn Migration>) {
+    pub fn insert(&mut self, migration: Box<dyn Migration>) {
         self.migration_map
             .insert(migration.version().to_string(), migration);
     }
 
-    fn migrate(&self, db: RocksDB) -> Result<RocksDB> {
+    /// Migrations newer than `db_version`
+    pub fn pending<'a>(&'a self, db_version: &'a str) -> impl Iterator<Item = &dyn Migration> + 'a {
+        self.migration_map
+            .iter()
+            .filter(move |(mv, _)| mv.as_str() > db_version)
+            .map(|(_, migration)| migration.as_ref())
+    }
+
+    /// Db version is updated after each migration, so the next run starts from
+    /// the first unfinished migration
+    pub fn migrate(&self, db: RocksDB, progress: &dyn MigrationProgress) -> Result<RocksDB> {
         let db_version = db
             .get_pinned_default(MIGRATION_VERSION_KEY)?
             .map(|v| String::from_utf8(v.to_vec()).expect("version bytes to utf8"))
             .unwrap_or_else(|| "".to_string());
         let mut db = db;
-        let v = db_version.as_str();
-        let mut last_version = None;
-        for (mv, migration) in &self.migration_map {
-            let mv = mv.as_str();
-            if mv > v {
-                db = migration.migrate(db)?;
-                last_version = Some(mv);
-            }
-        }
-        if let Some(v) = last_version {
-            db.put_default(MIGRATION_VERSION_KEY, v)?;
-            log::info!("Current db version is: {}", v);
+        for migration in self.pending(&db_version) {
+            let version = migration.version();
+            log::info!("Run migration {} {}", version, migration.name());
+            progress.begin(version, migration.name());
+
+            db = migration.migrate(db, progress)?;
+            // The version and the cursor are updated atomically, a finished
+            // migration never resumes from its cursor
+            let mut batch = db.new_write_batch();
+            batch.put_default(MIGRATION_VERSION_KEY, version.as_bytes())?;
+            batch.delete(COLUMN_META, &migration_cursor_key(version))?;
+            db.write(&batch)?;
+
+            progress.finish();
+            log::info!("Current db version is: {}", version);
         }
         Ok(db)
     }
 
-    fn last_db_version(&self) -> Option<&str> {
+    pub fn last_db_version(&self) -> Option<&str> {
         self.migration_map.values().last().map(|m| m.version())
     }
 }
@@ -166,11 +346,16 @@ mod tests {
     use gw_config::StoreConfig;
 
     use crate::{
-        schema::{COLUMNS, MIGRATION_VERSION_KEY},
+        error::Error,
+        schema::{COLUMNS, COLUMN_DATA, COLUMN_META, MIGRATION_VERSION_KEY},
         RocksDB,
     };
 
-    use super::{init_migration_factory, open_or_create_db};
+    use super::{
+        init_migration_factory, migrate_column, migration_cursor_key, open_or_create_db,
+        open_or_create_db_with_factory, DefaultMigration, Migration, MigrationFactory,
+        MigrationProgress, MIGRATION_BATCH_SIZE,
+    };
     #[test]
     fn test_migration() -> Result<()> {
         let dir = tempfile::tempdir().expect("create temp dir");
@@ -180,12 +365,13 @@ mod tests {
             options: HashMap::new(),
             options_file: None,
             cache_size: None,
+            state_history: Default::default(),
         };
         let old_db = RocksDB::open(&config, COLUMNS);
         let factory = init_migration_factory();
         assert!(factory.last_db_version().is_some());
 
-        let db = factory.migrate(old_db);
+        let db = factory.migrate(old_db, &());
 
         assert!(db.is_ok());
         let db = db.unwrap();
@@ -206,6 +392,7 @@ mod tests {
             options: HashMap::new(),
             options_file: None,
             cache_size: None,
+            state_history: Default::default(),
         };
         let db = open_or_create_db(&config)?;
         let v = db.get_pinned_default(MIGRATION_VERSION_KEY)?;
@@ -219,4 +406,171 @@ mod tests {
         assert_eq!(v, Some(Ok(factory.last_db_version().unwrap().to_string())));
         Ok(())
     }
+
+    const TEST_MIGRATION_VERSION: &str = "20991231000000";
+
+    // Append a byte to data values, fail after `fail_at` values if set
+    struct AppendDataMigration {
+        fail_at: Option<u64>,
+        expensive: bool,
+    }
+
+    impl Migration for AppendDataMigration {
+        fn migrate(&self, db: RocksDB, progress: &dyn MigrationProgress) -> Result<RocksDB> {
+            let mut count = 0;
+            migrate_column(
+                &db,
+                self.version(),
+                COLUMN_DATA,
+                progress,
+                |key, value, batch| {
+                    if Some(count) == self.fail_at {
+                        return Err(Error {
+                            message: "interrupted".to_string(),
+                        });
+                    }
+                    count += 1;
+                    let mut value = value.to_vec();
+                    value.push(1);
+                    batch.put(COLUMN_DATA, key, &value)
+                },
+            )?;
+            Ok(db)
+        }
+        fn version(&self) -> &str {
+            TEST_MIGRATION_VERSION
+        }
+        fn name(&self) -> &str {
+            "append data"
+        }
+        fn expensive(&self) -> bool {
+            self.expensive
+        }
+    }
+
+    fn test_factory(migration: AppendDataMigration) -> MigrationFactory {
+        let mut factory = MigrationFactory::create();
+        factory.insert(Box::new(DefaultMigration));
+        factory.insert(Box::new(migration));
+        factory
+    }
+
+    fn test_config(path: &std::path::Path) -> StoreConfig {
+        StoreConfig {
+            path: path.to_owned(),
+            options: HashMap::new(),
+            options_file: None,
+            cache_size: None,
+            state_history: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_resume_interrupted_migration() -> Result<()> {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let config = test_config(dir.path());
+
+        let total = MIGRATION_BATCH_SIZE + MIGRATION_BATCH_SIZE / 2;
+        {
+            let db = open_or_create_db(&config)?;
+            let mut batch = db.new_write_batch();
+            for i in 0..total {
+                batch.put(COLUMN_DATA, &i.to_be_bytes(), &[0])?;
+            }
+            db.write(&batch)?;
+        }
+
+        // Interrupted in the second batch, the first batch is persisted
+        let factory = test_factory(AppendDataMigration {
+            fail_at: Some(MIGRATION_BATCH_SIZE + 1),
+            expensive: true,
+        });
+        let db = RocksDB::open(&config, COLUMNS);
+        assert!(factory.migrate(db, &()).is_err());
+
+        let db = RocksDB::open(&config, COLUMNS);
+        let cursor_key = migration_cursor_key(TEST_MIGRATION_VERSION);
+        let cursor = db.get_pinned(COLUMN_META, &cursor_key)?.map(|c| c.to_vec());
+        assert_eq!(
+            cursor,
+            Some((MIGRATION_BATCH_SIZE - 1).to_be_bytes().to_vec())
+        );
+        let version = db.get_pinned_default(MIGRATION_VERSION_KEY)?.unwrap();
+        assert_eq!(version.as_ref(), DefaultMigration.version().as_bytes());
+        drop(version);
+
+        // Resume from the cursor, every value is appended exactly once
+        let factory = test_factory(AppendDataMigration {
+            fail_at: None,
+            expensive: true,
+        });
+        let db = factory.migrate(db, &())?;
+        for i in 0..total {
+            let value = db.get_pinned(COLUMN_DATA, &i.to_be_bytes())?.unwrap();
+            assert_eq!(value.as_ref(), &[0, 1]);
+        }
+        assert!(db.get_pinned(COLUMN_META, &cursor_key)?.is_none());
+        let version = db.get_pinned_default(MIGRATION_VERSION_KEY)?.unwrap();
+        assert_eq!(version.as_ref(), TEST_MIGRATION_VERSION.as_bytes());
+        Ok(())
+    }
+
+    #[test]
+    fn test_resume_after_last_batch() -> Result<()> {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let config = test_config(dir.path());
+
+        let total = MIGRATION_BATCH_SIZE / 2;
+        {
+            let db = open_or_create_db(&config)?;
+            let mut batch = db.new_write_batch();
+            for i in 0..total {
+                batch.put(COLUMN_DATA, &i.to_be_bytes(), &[0])?;
+            }
+            db.write(&batch)?;
+        }
+
+        // Interrupted after the last batch is written, before the version is
+        // updated
+        let migration = AppendDataMigration {
+            fail_at: None,
+            expensive: true,
+        };
+        let db = migration.migrate(RocksDB::open(&config, COLUMNS), &())?;
+        let cursor_key = migration_cursor_key(TEST_MIGRATION_VERSION);
+        let cursor = db.get_pinned(COLUMN_META, &cursor_key)?.map(|c| c.to_vec());
+        assert_eq!(cursor, Some((total - 1).to_be_bytes().to_vec()));
+
+        // The tail isn't applied again on resume
+        let db = test_factory(migration).migrate(db, &())?;
+        for i in 0..total {
+            let value = db.get_pinned(COLUMN_DATA, &i.to_be_bytes())?.unwrap();
+            assert_eq!(value.as_ref(), &[0, 1]);
+        }
+        assert!(db.get_pinned(COLUMN_META, &cursor_key)?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_refuse_expensive_migration_on_open() -> Result<()> {
+        let dir = tempfile::tempdir().expect("create temp dir");
+        let config = test_config(dir.path());
+        open_or_create_db(&config)?;
+
+        let expensive = test_factory(AppendDataMigration {
+            fail_at: None,
+            expensive: true,
+        });
+        let err = open_or_create_db_with_factory(&config, expensive).unwrap_err();
+        assert!(err.to_string().contains("needs expensive migrations"));
+
+        let cheap = test_factory(AppendDataMigration {
+            fail_at: None,
+            expensive: false,
+        });
+        let db = open_or_create_db_with_factory(&config, cheap)?;
+        let version = db.get_pinned_default(MIGRATION_VERSION_KEY)?.unwrap();
+        assert_eq!(version.as_ref(), TEST_MIGRATION_VERSION.as_bytes());
+        Ok(())
+    }
 }
@@ -3,7 +3,7 @@
 /// Column families alias type
 pub type Col = u8;
 /// Total column number
-pub const COLUMNS: u32 = 35;
+pub const COLUMNS: u32 = 38;
 /// Column store meta data
 pub const COLUMN_META: Col = 0;
 /// Column store chain index
@@ -58,6 +58,12 @@ pub const COLUMN_MEM_POOL_WITHDRAWAL: Col = 27;
 pub const COLUMN_WITHDRAWAL: Col = 33;
 /// Column store withdrawal extra information
 pub const COLUMN_WITHDRAWAL_INFO: Col = 34;
+/// Column store log items of main chain blocks by block number
+pub const COLUMN_LOG: Col = 35;
+/// Column index log items by account id
+pub const COLUMN_LOG_ACCOUNT_INDEX: Col = 36;
+/// Column index log items by first topic
+pub const COLUMN_LOG_TOPIC_INDEX: Col = 37;
 
 /// chain id
 pub const META_CHAIN_ID_KEY: &[u8] = b"CHAIN_ID";
@@ -69,8 +75,16 @@ pub const META_BLOCK_SMT_ROOT_KEY: &[u8] = b"BLOCK_SMT_ROOT_KEY";
 pub const META_REVERTED_BLOCK_SMT_ROOT_KEY: &[u8] = b"REVERTED_BLOCK_SMT_ROOT_KEY";
 /// track the latest known valid block hash
 pub const META_LAST_VALID_TIP_BLOCK_HASH_KEY: &[u8] = b"LAST_VALID_TIP_BLOCK_HASH";
+/// block state records are pruned up to this block number
+pub const META_BLOCK_STATE_RECORD_PRUNED_KEY: &[u8] = b"BLOCK_STATE_RECORD_PRUNED";
+/// the last block committed by import-block and its position in source file
+pub const META_IMPORT_BLOCK_CURSOR_KEY: &[u8] = b"IMPORT_BLOCK_CURSOR";
+/// set while import-state is writing a state snapshot, cleared when it's done
+pub const META_IMPORT_STATE_KEY: &[u8] = b"IMPORT_STATE";
 
 /// CHAIN_SPEC_HASH_KEY tracks the hash of chain spec which created current database
 pub const CHAIN_SPEC_HASH_KEY: &[u8] = b"chain-spec-hash";
 /// CHAIN_SPEC_HASH_KEY tracks the current database version.
 pub const MIGRATION_VERSION_KEY: &[u8] = b"db-version";
+/// Prefix of the last migrated key of an interrupted migration, suffixed with migration version
+pub const MIGRATION_CURSOR_KEY_PREFIX: &[u8] = b"db-migration-cursor-";
@@ -33,6 +33,11 @@ impl RocksDBWriteBatch {
         self.inner.put_cf(cf, key, value).map_err(internal_error)
     }
 
+    /// Put a key value into the default column family.
+    pub fn put_default(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.inner.put(key, value).map_err(internal_error)
+    }
+
     /// TODO(doc): @quake
     pub fn delete(&mut self, col: Col, key: &[u8]) -> Result<()> {
         let cf = cf_handle(&self.db, col)?;
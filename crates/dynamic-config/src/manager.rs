@@ -1,9 +1,9 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use anyhow::{anyhow, Result};
 
 use arc_swap::ArcSwap;
-use gw_config::{Config, DynamicConfig, FeeConfig};
+use gw_config::{Config, DynamicConfig, FeeConfig, RPCMethodRateLimitConfig};
 use gw_tx_filter::{
     erc20_creator_allowlist::SUDTProxyAccountAllowlist,
     polyjuice_contract_creator_allowlist::PolyjuiceContractCreatorAllowList,
@@ -21,6 +21,7 @@ pub struct DynamicConfigManager {
 
     fee_manager: FeeConfigManager,
     whitelist_manager: WhilteListConfigManager,
+    rpc_method_rate_limit: Option<RPCMethodRateLimitConfig>,
 }
 
 impl DynamicConfigManager {
@@ -36,11 +37,13 @@ impl DynamicConfigManager {
         });
         let fee_manager = FeeConfigManager::create(config.dynamic_config.fee_config.clone());
         let whitelist_manager = WhilteListConfigManager::create(config.dynamic_config.rpc_config);
+        let rpc_method_rate_limit = config.rpc_server.method_rate_limit;
 
         Self {
             config_github_url,
             fee_manager,
             whitelist_manager,
+            rpc_method_rate_limit,
         }
     }
 
@@ -51,6 +54,16 @@ impl DynamicConfigManager {
         } else {
             return Err(anyhow!("Github config url is absent!"));
         };
+        Ok(self.reload_from(new_config))
+    }
+
+    /// Apply dynamic configs of `new_config`
+    pub fn reload_from(&mut self, new_config: Config) -> DynamicConfigReloadResponse {
+        let new_rpc_method_rate_limit = new_config.rpc_server.method_rate_limit;
+        let old_rpc_method_rate_limit = std::mem::replace(
+            &mut self.rpc_method_rate_limit,
+            new_rpc_method_rate_limit.clone(),
+        );
 
         let new_config = new_config.dynamic_config;
         let backup_config = new_config.clone();
@@ -60,17 +73,22 @@ impl DynamicConfigManager {
             fee_config: old_fee_config,
             rpc_config: old_rpc_config,
         };
-        let res = DynamicConfigReloadResponse {
+        DynamicConfigReloadResponse {
             old: old_config,
             new: backup_config,
-        };
-        Ok(res)
+            old_rpc_method_rate_limit: old_rpc_method_rate_limit.as_ref().map(Into::into),
+            new_rpc_method_rate_limit: new_rpc_method_rate_limit.as_ref().map(Into::into),
+        }
     }
 
     pub fn get_fee_config(&self) -> &FeeConfig {
         self.fee_manager.get_fee_config()
     }
 
+    pub fn get_rpc_method_rate_limit(&self) -> Option<&RPCMethodRateLimitConfig> {
+        self.rpc_method_rate_limit.as_ref()
+    }
+
     pub fn get_polyjuice_contract_creator_allowlist(
         &self,
     ) -> &Option<PolyjuiceContractCreatorAllowList> {
@@ -101,6 +119,28 @@ async fn get_github_config(url: &str, token: &str) -> Result<Config> {
 pub struct DynamicConfigReloadResponse {
     old: DynamicConfig,
     new: DynamicConfig,
+    old_rpc_method_rate_limit: Option<RPCMethodRateLimitSummary>,
+    new_rpc_method_rate_limit: Option<RPCMethodRateLimitSummary>,
+}
+
+/// Method rate limit config without API keys, the reload response is public
+#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RPCMethodRateLimitSummary {
+    pub window_secs: u64,
+    pub max_cost_per_window: u64,
+    pub method_costs: HashMap<String, u64>,
+    pub api_key_count: usize,
+}
+
+impl From<&RPCMethodRateLimitConfig> for RPCMethodRateLimitSummary {
+    fn from(config: &RPCMethodRateLimitConfig) -> Self {
+        RPCMethodRateLimitSummary {
+            window_secs: config.window_secs,
+            max_cost_per_window: config.max_cost_per_window,
+            method_costs: config.method_costs.clone(),
+            api_key_count: config.api_key_quotas.len(),
+        }
+    }
 }
 
 pub async fn reload(
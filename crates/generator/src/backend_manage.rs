@@ -4,10 +4,12 @@ use gw_config::{BackendConfig, BackendSwitchConfig, BackendType};
 use gw_types::bytes::Bytes;
 use std::{collections::HashMap, fs};
 
-#[cfg(has_asm)]
-use crate::types::vm::AotCode;
+use crate::{
+    program_cache::{Program, ProgramCache},
+    types::vm::VMVersion,
+};
 
-#[derive(Default, Clone)]
+#[derive(Default, Clone, PartialEq, Eq, Hash)]
 pub struct BackendCheckSum {
     pub validator: H256,
     pub generator: H256,
@@ -74,16 +76,24 @@ impl Backend {
 pub struct BackendManage {
     backend_switches: Vec<(u64, HashMap<H256, Backend>)>,
     /// define here not in backends,
-    /// so we don't need to implement the trait `Clone` of AotCode
-    #[cfg(has_asm)]
-    aot_codes: (HashMap<H256, AotCode>, HashMap<H256, AotCode>),
+    /// so we don't need to implement the trait `Clone` of Program
+    program_cache: ProgramCache,
 }
 
 impl BackendManage {
     pub fn from_config(configs: Vec<BackendSwitchConfig>) -> Result<Self> {
+        Self::from_config_with_program_cache(configs, true)
+    }
+
+    /// Backend programs are loaded from ELF for every execution if
+    /// `cache_programs` is false, which is only useful for comparison.
+    pub fn from_config_with_program_cache(
+        configs: Vec<BackendSwitchConfig>,
+        cache_programs: bool,
+    ) -> Result<Self> {
         let mut backend_manage: BackendManage = Default::default();
         for config in configs {
-            backend_manage.register_backend_switch(config, true)?;
+            backend_manage.register_backend_switch(config, cache_programs)?;
         }
 
         Ok(backend_manage)
@@ -92,7 +102,7 @@ impl BackendManage {
     fn register_backend_switch(
         &mut self,
         config: BackendSwitchConfig,
-        #[allow(unused_variables)] compile: bool,
+        cache_programs: bool,
     ) -> Result<()> {
         if let Some((height, _backends)) = self.backend_switches.last() {
             if config.switch_height <= *height {
@@ -137,9 +147,8 @@ impl BackendManage {
                 validator,
                 generator,
             );
-            #[cfg(has_asm)]
-            if compile {
-                self.compile_backend(&backend);
+            if cache_programs {
+                self.program_cache.insert(&backend)?;
             }
 
             log::debug!(
@@ -156,20 +165,6 @@ impl BackendManage {
         Ok(())
     }
 
-    #[cfg(has_asm)]
-    fn compile_backend(&mut self, backend: &Backend) {
-        self.aot_codes.0.insert(
-            backend.checksum.generator,
-            self.aot_compile(&backend.generator, 0)
-                .expect("Ahead-of-time compile"),
-        );
-        self.aot_codes.1.insert(
-            backend.checksum.generator,
-            self.aot_compile(&backend.generator, 1)
-                .expect("Ahead-of-time compile"),
-        );
-    }
-
     pub fn get_backends_at_height(
         &self,
         block_number: u64,
@@ -194,39 +189,9 @@ impl BackendManage {
             })
     }
 
-    #[cfg(has_asm)]
-    fn aot_compile(&self, code_bytes: &Bytes, vm_version: u32) -> Result<AotCode, ckb_vm::Error> {
-        log::info!("Compile AotCode with VMVersion::V{}", vm_version);
-        let vm_version = match vm_version {
-            0 => crate::types::vm::VMVersion::V0,
-            1 => crate::types::vm::VMVersion::V1,
-            ver => panic!("Unsupport VMVersion: {}", ver),
-        };
-        let mut aot_machine = ckb_vm::machine::aot::AotCompilingMachine::load(
-            code_bytes,
-            Some(Box::new(crate::vm_cost_model::instruction_cycles)),
-            vm_version.vm_isa(),
-            vm_version.vm_version(),
-        )?;
-        aot_machine.compile()
-    }
-
-    /// get aot_code according to special VM version
-    #[cfg(has_asm)]
-    pub(crate) fn get_aot_code(&self, code_hash: &H256, vm_version: u32) -> Option<&AotCode> {
-        log::debug!(
-            "get_aot_code hash: {} version: {}",
-            hex::encode(code_hash.as_slice()),
-            vm_version
-        );
-        match vm_version {
-            0 => self.aot_codes.0.get(code_hash),
-            1 => self.aot_codes.1.get(code_hash),
-            ver => {
-                log::error!("Unsupport VMVersion: {}", ver);
-                None
-            }
-        }
+    /// get the pre-loaded generator program of the backend
+    pub fn get_program(&self, backend: &Backend, vm_version: VMVersion) -> Option<&Program> {
+        self.program_cache.get(&backend.checksum, vm_version)
     }
 }
 
@@ -1,10 +1,15 @@
-use std::{collections::HashSet, sync::atomic::Ordering::SeqCst, time::Instant};
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::atomic::Ordering::SeqCst,
+    time::Instant,
+};
 
 use crate::{
     account_lock_manage::AccountLockManage,
     backend_manage::BackendManage,
     constants::{L2TX_MAX_CYCLES, MAX_READ_DATA_BYTES_LIMIT, MAX_WRITE_DATA_BYTES_LIMIT},
     error::{BlockError, TransactionValidateError, WithdrawalError},
+    profiler::{run_profiled, VMSamples},
     run_result_state::RunResultState,
     syscalls::redir_log::RedirLogHandler,
     typed_transaction::types::TypedRawTransaction,
@@ -36,7 +41,7 @@ use gw_traits::{ChainView, CodeStore};
 use gw_types::{
     bytes::Bytes,
     core::{ChallengeTargetType, ScriptHashType},
-    offchain::{RollupContext, RunResult},
+    offchain::{ExecutionProfile, RollupContext, RunResult, SyscallTrace},
     packed::{
         AccountMerkleState, BlockInfo, ChallengeTarget, DepositRequest, L2Block, L2Transaction,
         RawL2Block, RawL2Transaction, TxReceipt, WithdrawalReceipt, WithdrawalRequestExtra,
@@ -55,6 +60,29 @@ pub struct ApplyBlockArgs {
     pub l2block: L2Block,
     pub deposit_requests: Vec<DepositRequest>,
     pub withdrawals: Vec<WithdrawalRequestExtra>,
+    pub verified_signatures: VerifiedSignatures,
+}
+
+/// Signatures of a block verified ahead of applying it, by index in block
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct VerifiedSignatures {
+    pub withdrawals: Vec<bool>,
+    pub txs: Vec<bool>,
+}
+
+impl VerifiedSignatures {
+    pub fn is_withdrawal_verified(&self, index: usize) -> bool {
+        self.withdrawals.get(index).copied().unwrap_or(false)
+    }
+
+    pub fn is_tx_verified(&self, index: usize) -> bool {
+        self.txs.get(index).copied().unwrap_or(false)
+    }
+
+    pub fn verified_count(&self) -> usize {
+        let withdrawals = self.withdrawals.iter().filter(|v| **v).count();
+        withdrawals + self.txs.iter().filter(|v| **v).count()
+    }
 }
 
 pub enum ApplyBlockResult {
@@ -123,6 +151,7 @@ impl Generator {
         &self.account_lock_manage
     }
 
+    #[allow(clippy::too_many_arguments)]
     #[instrument(skip_all, fields(backend = ?backend.backend_type))]
     fn machine_run<'a, S: State + CodeStore, C: ChainView>(
         &'a self,
@@ -132,11 +161,15 @@ impl Generator {
         raw_tx: &'a RawL2Transaction,
         max_cycles: u64,
         backend: Backend,
+        syscall_trace: Option<&'a mut Vec<SyscallTrace>>,
+        profile_sample_interval: Option<u64>,
     ) -> Result<RunResult, TransactionError> {
         const INVALID_CYCLES_EXIT_CODE: i8 = -1;
 
         self.redir_log_handler.start(raw_tx);
         let mut run_result = RunResult::default();
+        let mut syscall_profile = BTreeMap::new();
+        let mut vm_samples = VMSamples::default();
         let used_cycles;
         let exit_code;
         {
@@ -159,30 +192,53 @@ impl Generator {
                     result: &mut run_result,
                     code_store: state,
                     redir_log_handler: &self.redir_log_handler,
+                    syscall_trace,
+                    syscall_profile: profile_sample_interval.map(|_| &mut syscall_profile),
                 }))
                 .instruction_cycle_func(Box::new(instruction_cycles));
-            let default_machine = machine_builder.build();
-
-            #[cfg(has_asm)]
-            let aot_code_opt = self
-                .backend_manage
-                .get_aot_code(&backend.checksum.generator, global_vm_version);
-            #[cfg(has_asm)]
-            if aot_code_opt.is_none() {
-                log::warn!("[machine_run] Not AOT mode!");
+            let mut default_machine = machine_builder.build();
+            let program = self.backend_manage.get_program(&backend, vm_version);
+            if program.is_none() {
+                log::warn!("[machine_run] Program is not cached!");
             }
 
-            #[cfg(has_asm)]
-            let mut machine = ckb_vm::machine::asm::AsmMachine::new(default_machine, aot_code_opt);
+            let run = match profile_sample_interval {
+                Some(sample_interval) => {
+                    match program {
+                        Some(program) => program.load(&mut default_machine)?,
+                        None => {
+                            default_machine.load_program(&backend.generator, &[])?;
+                        }
+                    }
+                    run_profiled(&mut default_machine, sample_interval, &mut vm_samples)
+                        .map(|exit_code| (exit_code, default_machine.cycles()))
+                }
+                None => {
+                    #[cfg(has_asm)]
+                    let aot_code_opt = program.and_then(|program| program.aot_code());
 
-            #[cfg(not(has_asm))]
-            let mut machine = TraceMachine::new(default_machine);
+                    #[cfg(has_asm)]
+                    let mut machine =
+                        ckb_vm::machine::asm::AsmMachine::new(default_machine, aot_code_opt);
 
-            machine.load_program(&backend.generator, &[])?;
-            match machine.run() {
-                Ok(_exit_code) => {
+                    #[cfg(not(has_asm))]
+                    let mut machine = TraceMachine::new(default_machine);
+
+                    match program {
+                        Some(program) => program.load(&mut machine.machine)?,
+                        None => {
+                            machine.load_program(&backend.generator, &[])?;
+                        }
+                    }
+                    machine
+                        .run()
+                        .map(|exit_code| (exit_code, machine.machine.cycles()))
+                }
+            };
+            match run {
+                Ok((_exit_code, _used_cycles)) => {
                     exit_code = _exit_code;
-                    used_cycles = machine.machine.cycles();
+                    used_cycles = _used_cycles;
                 }
                 Err(ckb_vm::error::Error::InvalidCycles) => {
                     exit_code = INVALID_CYCLES_EXIT_CODE;
@@ -203,6 +259,16 @@ impl Generator {
         }
         run_result.used_cycles = used_cycles;
         run_result.exit_code = exit_code;
+        if let Some(sample_interval) = profile_sample_interval {
+            for (code, cycles) in vm_samples.syscall_cycles {
+                syscall_profile.entry(code).or_default().cycles = cycles;
+            }
+            run_result.profile = Some(ExecutionProfile {
+                syscalls: syscall_profile,
+                sample_interval,
+                pc_samples: vm_samples.pc_samples,
+            });
+        }
 
         Ok(run_result)
     }
@@ -291,6 +357,31 @@ impl Generator {
         Ok(())
     }
 
+    /// Verify signatures of a block ahead of applying it, against any state
+    /// before the block.
+    ///
+    /// Account scripts and registry addresses never change once created, so a
+    /// signature verified here is still valid when the block is applied.
+    /// Signatures failed here, e.g. sender is created after the state, are left
+    /// to `verify_and_apply_block`.
+    pub fn verify_block_signatures<S: State + CodeStore>(
+        &self,
+        state: &S,
+        l2block: &L2Block,
+        withdrawals: &[WithdrawalRequestExtra],
+    ) -> VerifiedSignatures {
+        let withdrawals = withdrawals.iter();
+        let txs = l2block.transactions().into_iter();
+        VerifiedSignatures {
+            withdrawals: withdrawals
+                .map(|w| self.check_withdrawal_signature(state, w).is_ok())
+                .collect(),
+            txs: txs
+                .map(|tx| self.check_transaction_signature(state, &tx).is_ok())
+                .collect(),
+        }
+    }
+
     /// Apply l2 state transition
     #[instrument(skip_all, fields(block = args.l2block.raw().number().unpack(), deposits_count = args.deposit_requests.len()))]
     pub fn verify_and_apply_block<C: ChainView>(
@@ -349,14 +440,16 @@ impl Generator {
                 request.owner_lock().hash()
             );
             let now = Instant::now();
-            if let Err(error) = self.check_withdrawal_signature(&state, &request) {
-                let target = build_challenge_target(
-                    block_hash.into(),
-                    ChallengeTargetType::Withdrawal,
-                    wth_idx as u32,
-                );
+            if !args.verified_signatures.is_withdrawal_verified(wth_idx) {
+                if let Err(error) = self.check_withdrawal_signature(&state, &request) {
+                    let target = build_challenge_target(
+                        block_hash.into(),
+                        ChallengeTargetType::Withdrawal,
+                        wth_idx as u32,
+                    );
 
-                return ApplyBlockResult::Challenge { target, error };
+                    return ApplyBlockResult::Challenge { target, error };
+                }
             }
             check_signature_total_ms += now.elapsed().as_millis();
 
@@ -413,17 +506,19 @@ impl Generator {
                 hex::encode(tx.hash())
             );
             let now = Instant::now();
-            if let Err(err) = self.check_transaction_signature(&state, &tx) {
-                let target = build_challenge_target(
-                    block_hash.into(),
-                    ChallengeTargetType::TxSignature,
-                    tx_index as u32,
-                );
+            if !args.verified_signatures.is_tx_verified(tx_index) {
+                if let Err(err) = self.check_transaction_signature(&state, &tx) {
+                    let target = build_challenge_target(
+                        block_hash.into(),
+                        ChallengeTargetType::TxSignature,
+                        tx_index as u32,
+                    );
 
-                return ApplyBlockResult::Challenge {
-                    target,
-                    error: err.into(),
-                };
+                    return ApplyBlockResult::Challenge {
+                        target,
+                        error: err.into(),
+                    };
+                }
             }
             check_signature_total_ms += now.elapsed().as_millis();
 
@@ -614,6 +709,67 @@ impl Generator {
         block_info: &BlockInfo,
         raw_tx: &RawL2Transaction,
         max_cycles: u64,
+    ) -> Result<RunResult, TransactionError> {
+        self.execute_transaction_inner(chain, state, block_info, raw_tx, max_cycles, None, None)
+    }
+
+    /// execute a layer2 tx and record syscalls made by the backend, doesn't check exit code
+    #[instrument(skip_all, fields(block = block_info.number().unpack(), tx_hash = %raw_tx.hash().pack()))]
+    pub fn trace_execute_transaction<S: State + CodeStore, C: ChainView>(
+        &self,
+        chain: &C,
+        state: &S,
+        block_info: &BlockInfo,
+        raw_tx: &RawL2Transaction,
+        max_cycles: u64,
+    ) -> Result<(RunResult, Vec<SyscallTrace>), TransactionError> {
+        let mut syscall_trace = Vec::new();
+        let run_result = self.execute_transaction_inner(
+            chain,
+            state,
+            block_info,
+            raw_tx,
+            max_cycles,
+            Some(&mut syscall_trace),
+            None,
+        )?;
+        Ok((run_result, syscall_trace))
+    }
+
+    /// execute a layer2 tx in profiling mode, doesn't check exit code
+    ///
+    /// `RunResult::profile` is set, the PC is sampled once per `sample_interval` cycles.
+    #[instrument(skip_all, fields(block = block_info.number().unpack(), tx_hash = %raw_tx.hash().pack()))]
+    pub fn profile_execute_transaction<S: State + CodeStore, C: ChainView>(
+        &self,
+        chain: &C,
+        state: &S,
+        block_info: &BlockInfo,
+        raw_tx: &RawL2Transaction,
+        max_cycles: u64,
+        sample_interval: u64,
+    ) -> Result<RunResult, TransactionError> {
+        self.execute_transaction_inner(
+            chain,
+            state,
+            block_info,
+            raw_tx,
+            max_cycles,
+            None,
+            Some(sample_interval),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn execute_transaction_inner<S: State + CodeStore, C: ChainView>(
+        &self,
+        chain: &C,
+        state: &S,
+        block_info: &BlockInfo,
+        raw_tx: &RawL2Transaction,
+        max_cycles: u64,
+        syscall_trace: Option<&mut Vec<SyscallTrace>>,
+        profile_sample_interval: Option<u64>,
     ) -> Result<RunResult, TransactionError> {
         let account_id = raw_tx.to_id().unpack();
         let script_hash = state.get_script_hash(account_id)?;
@@ -621,8 +777,16 @@ impl Generator {
             .load_backend(block_info.number().unpack(), state, &script_hash)
             .ok_or(TransactionError::BackendNotFound { script_hash })?;
 
-        let run_result: RunResult =
-            self.machine_run(chain, state, block_info, raw_tx, max_cycles, backend)?;
+        let run_result: RunResult = self.machine_run(
+            chain,
+            state,
+            block_info,
+            raw_tx,
+            max_cycles,
+            backend,
+            syscall_trace,
+            profile_sample_interval,
+        )?;
         self.handle_run_result(state, block_info, raw_tx, run_result)
     }
 
@@ -1,5 +1,5 @@
 use crate::traits::StateExt;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use gw_common::{
     blake2b::new_blake2b,
     builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID, RESERVED_ACCOUNT_ID},
@@ -196,6 +196,9 @@ pub fn init_genesis(
         let rollup_script_hash: [u8; 32] = config.rollup_type_hash.clone().into();
         rollup_script_hash.into()
     };
+    if store.has_unfinished_state_import() {
+        bail!("state snapshot import is unfinished, run import-state again");
+    }
     if store.has_genesis()? {
         let chain_id = store.get_chain_id()?;
         if chain_id == rollup_script_hash {
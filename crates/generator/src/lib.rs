@@ -8,6 +8,8 @@ pub mod dummy_state;
 pub mod error;
 pub mod generator;
 pub mod genesis;
+pub mod profiler;
+pub mod program_cache;
 pub mod sudt;
 pub mod syscalls;
 pub mod traits;
@@ -0,0 +1,70 @@
+//! Profiling mode of backend executions.
+//!
+//! The backend is executed in the interpreter step by step, so it's much
+//! slower than the normal execution and only used by debug tools.
+
+use std::collections::BTreeMap;
+
+use ckb_vm::{
+    decoder::build_decoder,
+    instructions::{execute, extract_opcode, insts},
+    machine::DefaultMachine,
+    registers::A7,
+    Error as VMError, Register, SupportMachine,
+};
+
+use crate::vm_cost_model::instruction_cycles;
+
+/// Sample the PC once per 10000 cycles by default
+pub const DEFAULT_PROFILE_SAMPLE_INTERVAL: u64 = 10_000;
+
+/// Samples and cycles collected by the VM loop
+#[derive(Debug, Default)]
+pub(crate) struct VMSamples {
+    // PC -> number of samples
+    pub pc_samples: BTreeMap<u64, u64>,
+    // syscall code -> cycles from entering the syscall to returning to the
+    // backend, including the ecall instruction
+    pub syscall_cycles: BTreeMap<u64, u64>,
+}
+
+/// Same as `DefaultMachine::run`, but samples the PC every `sample_interval`
+/// cycles and accounts syscall cycles by syscall code.
+pub(crate) fn run_profiled<Inner: SupportMachine>(
+    machine: &mut DefaultMachine<'_, Inner>,
+    sample_interval: u64,
+    samples: &mut VMSamples,
+) -> Result<i8, VMError> {
+    assert!(sample_interval > 0, "sample interval must be positive");
+
+    let mut decoder = build_decoder::<Inner::REG>(machine.isa(), machine.version());
+    let mut next_sample = machine.cycles() + sample_interval;
+    machine.set_running(true);
+    while machine.running() {
+        if machine.reset_signal() {
+            decoder.reset_instructions_cache();
+        }
+        let pc = machine.pc().to_u64();
+        let instruction = decoder.decode(machine.memory_mut(), pc)?;
+        let syscall_code = if extract_opcode(instruction) == insts::OP_ECALL {
+            Some(machine.registers()[A7].to_u64())
+        } else {
+            None
+        };
+        let entered_at = machine.cycles();
+        machine.add_cycles(instruction_cycles(instruction))?;
+        execute(instruction, machine)?;
+
+        // Cycles of a syscall are accounted by syscall code instead of sampled
+        if let Some(code) = syscall_code {
+            let cycles = machine.cycles() - entered_at;
+            *samples.syscall_cycles.entry(code).or_default() += cycles;
+            next_sample += cycles;
+        }
+        while machine.cycles() >= next_sample {
+            *samples.pc_samples.entry(pc).or_default() += 1;
+            next_sample += sample_interval;
+        }
+    }
+    Ok(machine.exit_code())
+}
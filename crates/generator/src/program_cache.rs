@@ -0,0 +1,126 @@
+//! Cache of pre-loaded backend programs
+//!
+//! Loading a backend program parses the ELF and copies its segments into the
+//! VM memory, which is repeated for every executed tx. The cache loads each
+//! backend generator once per VM version and keeps a snapshot of the loaded
+//! machine, executing a tx only needs to restore the snapshot. The AOT code of
+//! the program is kept together when the `aot` feature is enabled.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use ckb_vm::{
+    snapshot::{make_snapshot, resume, Snapshot},
+    CoreMachine, DefaultMachineBuilder, Error as VMError, SupportMachine,
+};
+
+#[cfg(has_asm)]
+use crate::types::vm::AotCode;
+use crate::{
+    backend_manage::{Backend, BackendCheckSum},
+    types::vm::VMVersion,
+    vm_cost_model::instruction_cycles,
+};
+
+const VM_VERSIONS: [VMVersion; 2] = [VMVersion::V0, VMVersion::V1];
+
+/// A backend generator loaded into the VM
+pub struct Program {
+    image: Snapshot,
+    load_cycles: u64,
+    #[cfg(all(has_asm, feature = "aot"))]
+    aot_code: AotCode,
+}
+
+impl Program {
+    fn build(backend: &Backend, vm_version: VMVersion) -> Result<Self, VMError> {
+        let core_machine = vm_version.init_core_machine_without_limit();
+        let mut machine = DefaultMachineBuilder::new(core_machine)
+            .instruction_cycle_func(Box::new(instruction_cycles))
+            .build();
+        machine.load_program(&backend.generator, &[])?;
+        let image = make_snapshot(&mut machine)?;
+        let load_cycles = machine.cycles();
+
+        #[cfg(all(has_asm, feature = "aot"))]
+        let aot_code = {
+            log::info!("Compile AotCode with VMVersion::V{}", vm_version as u32);
+            let mut aot_machine = ckb_vm::machine::aot::AotCompilingMachine::load(
+                &backend.generator,
+                Some(Box::new(instruction_cycles)),
+                vm_version.vm_isa(),
+                vm_version.vm_version(),
+            )?;
+            aot_machine.compile()?
+        };
+
+        Ok(Program {
+            image,
+            load_cycles,
+            #[cfg(all(has_asm, feature = "aot"))]
+            aot_code,
+        })
+    }
+
+    /// Restore the loaded program into a fresh machine, same as calling
+    /// `load_program` with the backend generator and no arguments.
+    pub fn load<M: CoreMachine + SupportMachine>(&self, machine: &mut M) -> Result<(), VMError> {
+        resume(machine, &self.image)?;
+        machine.set_cycles(self.load_cycles);
+        Ok(())
+    }
+
+    #[cfg(all(has_asm, feature = "aot"))]
+    pub(crate) fn aot_code(&self) -> Option<&AotCode> {
+        Some(&self.aot_code)
+    }
+
+    #[cfg(all(has_asm, not(feature = "aot")))]
+    pub(crate) fn aot_code(&self) -> Option<&AotCode> {
+        None
+    }
+}
+
+/// Pre-loaded programs keyed by backend checksum and VM version.
+///
+/// The cache is filled when backends are registered and is read only
+/// afterwards, so it is shared by executions across threads.
+#[derive(Default)]
+pub struct ProgramCache {
+    programs: HashMap<(BackendCheckSum, VMVersion), Program>,
+}
+
+impl ProgramCache {
+    /// Load the generator of the backend for all VM versions
+    pub fn insert(&mut self, backend: &Backend) -> Result<()> {
+        for vm_version in VM_VERSIONS {
+            let key = (backend.checksum.clone(), vm_version);
+            if self.programs.contains_key(&key) {
+                continue;
+            }
+            let program = Program::build(backend, vm_version).map_err(|err| {
+                anyhow!(
+                    "load backend {:?}({:?}) with VMVersion::V{}: {:?}",
+                    backend.backend_type,
+                    backend.checksum,
+                    vm_version as u32,
+                    err
+                )
+            })?;
+            self.programs.insert(key, program);
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, checksum: &BackendCheckSum, vm_version: VMVersion) -> Option<&Program> {
+        self.programs.get(&(checksum.clone(), vm_version))
+    }
+
+    pub fn len(&self) -> usize {
+        self.programs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.programs.is_empty()
+    }
+}
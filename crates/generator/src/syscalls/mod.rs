@@ -18,11 +18,11 @@ use gw_traits::{ChainView, CodeStore};
 use gw_types::{
     bytes::Bytes,
     core::ScriptHashType,
-    offchain::{RecoverAccount, RollupContext, RunResult},
+    offchain::{RecoverAccount, RollupContext, RunResult, SyscallProfile, SyscallTrace},
     packed::{BlockInfo, LogItem, RawL2Transaction, Script},
     prelude::*,
 };
-use std::cmp;
+use std::{cmp, collections::BTreeMap, time::Instant};
 
 use self::{
     error_codes::{
@@ -72,6 +72,32 @@ pub(crate) struct L2Syscalls<'a, S, C> {
     pub(crate) code_store: &'a dyn CodeStore,
     pub(crate) result: &'a mut RunResult,
     pub(crate) redir_log_handler: &'a RedirLogHandler,
+    pub(crate) syscall_trace: Option<&'a mut Vec<SyscallTrace>>,
+    // syscall code -> accounting, cycles are filled by the profiling VM loop
+    pub(crate) syscall_profile: Option<&'a mut BTreeMap<u64, SyscallProfile>>,
+}
+
+/// Name of a syscall, returns `None` for unknown syscalls
+pub fn syscall_name(code: u64) -> Option<&'static str> {
+    let name = match code {
+        SYS_CREATE => "SYS_CREATE",
+        SYS_STORE => "SYS_STORE",
+        SYS_LOAD => "SYS_LOAD",
+        SYS_LOAD_ACCOUNT_SCRIPT => "SYS_LOAD_ACCOUNT_SCRIPT",
+        SYS_SET_RETURN_DATA => "SYS_SET_RETURN_DATA",
+        SYS_STORE_DATA => "SYS_STORE_DATA",
+        SYS_LOAD_DATA => "SYS_LOAD_DATA",
+        SYS_LOAD_ROLLUP_CONFIG => "SYS_LOAD_ROLLUP_CONFIG",
+        SYS_LOAD_TRANSACTION => "SYS_LOAD_TRANSACTION",
+        SYS_LOAD_BLOCKINFO => "SYS_LOAD_BLOCKINFO",
+        SYS_GET_BLOCK_HASH => "SYS_GET_BLOCK_HASH",
+        SYS_PAY_FEE => "SYS_PAY_FEE",
+        SYS_LOG => "SYS_LOG",
+        SYS_RECOVER_ACCOUNT => "SYS_RECOVER_ACCOUNT",
+        DEBUG_PRINT_SYSCALL_NUMBER => "DEBUG_PRINT",
+        _ => return None,
+    };
+    Some(name)
 }
 
 #[allow(dead_code)]
@@ -138,6 +164,103 @@ impl<'a, S: State, C: ChainView, Mac: SupportMachine> Syscalls<Mac> for L2Syscal
 
     fn ecall(&mut self, machine: &mut Mac) -> Result<bool, VMError> {
         let code = machine.registers()[A7].to_u64();
+        if let Some(trace) = self.syscall_trace.as_mut() {
+            trace.push(SyscallTrace {
+                code,
+                cycles: machine.cycles(),
+            });
+        }
+        if self.syscall_profile.is_none() {
+            return self.handle_syscall(code, machine);
+        }
+
+        // Arguments are overwritten by return values, load them before the syscall
+        let args: Vec<u64> = [A0, A1, A2, A3, A4, A5]
+            .iter()
+            .map(|&reg| machine.registers()[reg].to_u64())
+            .collect();
+        let size_before = match code {
+            SYS_LOAD_BLOCKINFO
+            | SYS_LOAD_TRANSACTION
+            | SYS_LOAD_ACCOUNT_SCRIPT
+            | SYS_LOAD_ROLLUP_CONFIG
+            | SYS_LOAD_DATA
+            | SYS_RECOVER_ACCOUNT => load_u64(machine, args[1])?,
+            _ => 0,
+        };
+        let t = Instant::now();
+        let handled = self.handle_syscall(code, machine)?;
+        let elapsed_nanos = t.elapsed().as_nanos() as u64;
+        if !handled {
+            return Ok(false);
+        }
+
+        let bytes = transferred_bytes(code, machine, &args, size_before)?;
+        if let Some(profile) = self.syscall_profile.as_mut() {
+            let entry = profile.entry(code).or_default();
+            entry.count += 1;
+            entry.bytes += bytes;
+            entry.elapsed_nanos += elapsed_nanos;
+        }
+        Ok(true)
+    }
+}
+
+fn load_u64<Mac: SupportMachine>(machine: &mut Mac, addr: u64) -> Result<u64, VMError> {
+    let value = machine.memory_mut().load64(&Mac::REG::from_u64(addr))?;
+    Ok(value.to_u64())
+}
+
+// Bytes copied between VM memory and the host by a handled syscall
+fn transferred_bytes<Mac: SupportMachine>(
+    code: u64,
+    machine: &mut Mac,
+    args: &[u64],
+    size_before: u64,
+) -> Result<u64, VMError> {
+    let success = machine.registers()[A0].to_u64() == SUCCESS as u64;
+    // Same as `store_data`
+    let stored_bytes = |machine: &mut Mac| -> Result<u64, VMError> {
+        if !success {
+            return Ok(0);
+        }
+        let full_size = load_u64(machine, args[1])?;
+        let offset = cmp::min(full_size, args[2]);
+        Ok(cmp::min(size_before, full_size - offset))
+    };
+    let bytes = match code {
+        SYS_STORE | SYS_LOAD => 64,
+        SYS_SET_RETURN_DATA => args[1],
+        SYS_CREATE => args[1] + 4,
+        SYS_STORE_DATA => args[0],
+        SYS_LOAD_DATA => 32 + stored_bytes(machine)?,
+        SYS_LOAD_BLOCKINFO
+        | SYS_LOAD_TRANSACTION
+        | SYS_LOAD_ACCOUNT_SCRIPT
+        | SYS_LOAD_ROLLUP_CONFIG => stored_bytes(machine)?,
+        SYS_GET_BLOCK_HASH => 32,
+        SYS_RECOVER_ACCOUNT => {
+            // message, code hash, signature and script
+            let script_len = if success {
+                load_u64(machine, args[1])?
+            } else {
+                0
+            };
+            64 + args[4] + script_len
+        }
+        SYS_LOG => args[2],
+        SYS_PAY_FEE => args[1] + 32,
+        _ => 0,
+    };
+    Ok(bytes)
+}
+
+impl<'a, S: State, C: ChainView> L2Syscalls<'a, S, C> {
+    fn handle_syscall<Mac: SupportMachine>(
+        &mut self,
+        code: u64,
+        machine: &mut Mac,
+    ) -> Result<bool, VMError> {
         match code {
             SYS_STORE => {
                 let key_addr = machine.registers()[A0].to_u64();
@@ -505,9 +628,7 @@ impl<'a, S: State, C: ChainView, Mac: SupportMachine> Syscalls<Mac> for L2Syscal
             _ => Ok(false),
         }
     }
-}
 
-impl<'a, S: State, C: ChainView> L2Syscalls<'a, S, C> {
     fn get_raw(&mut self, key: &H256) -> Result<H256, VMError> {
         let value = match self.result.write.write_values.get(key) {
             Some(value) => *value,
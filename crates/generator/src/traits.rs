@@ -31,6 +31,15 @@ pub trait StateExt {
         withdrawal_request: &WithdrawalRequest,
     ) -> Result<WithdrawalReceipt, Error>;
 
+    /// Same as `apply_withdrawal_request` but doesn't calculate the post state
+    /// root, for states which can't calculate the root.
+    fn apply_withdrawal_request_without_receipt(
+        &mut self,
+        ctx: &RollupContext,
+        block_producer: &RegistryAddress,
+        withdrawal_request: &WithdrawalRequest,
+    ) -> Result<(), Error>;
+
     fn apply_deposit_requests(
         &mut self,
         ctx: &RollupContext,
@@ -221,6 +230,30 @@ impl<S: State + CodeStore> StateExt for S {
         block_producer_address: &RegistryAddress,
         request: &WithdrawalRequest,
     ) -> Result<WithdrawalReceipt, Error> {
+        self.apply_withdrawal_request_without_receipt(ctx, block_producer_address, request)?;
+
+        let post_state = {
+            let account_root = self.calculate_root()?;
+            let account_count = self.get_account_count()?;
+            AccountMerkleState::new_builder()
+                .merkle_root(account_root.pack())
+                .count(account_count.pack())
+                .build()
+        };
+
+        let receipt = WithdrawalReceipt::new_builder()
+            .post_state(post_state)
+            .build();
+
+        Ok(receipt)
+    }
+
+    fn apply_withdrawal_request_without_receipt(
+        &mut self,
+        ctx: &RollupContext,
+        block_producer_address: &RegistryAddress,
+        request: &WithdrawalRequest,
+    ) -> Result<(), Error> {
         let raw = request.raw();
         let account_script_hash: H256 = raw.account_script_hash().unpack();
         let l2_sudt_script_hash: [u8; 32] =
@@ -264,19 +297,6 @@ impl<S: State + CodeStore> StateExt for S {
         let new_nonce = nonce.checked_add(1).ok_or(WithdrawalError::NonceOverflow)?;
         self.set_nonce(id, new_nonce)?;
 
-        let post_state = {
-            let account_root = self.calculate_root()?;
-            let account_count = self.get_account_count()?;
-            AccountMerkleState::new_builder()
-                .merkle_root(account_root.pack())
-                .count(account_count.pack())
-                .build()
-        };
-
-        let receipt = WithdrawalReceipt::new_builder()
-            .post_state(post_state)
-            .build();
-
-        Ok(receipt)
+        Ok(())
     }
 }
@@ -29,7 +29,7 @@ pub type CoreMachine = Box<AsmCoreMachine>;
 pub type CoreMachine = DefaultCoreMachine<u64, WXorXMemory<SparseMemory<u64>>>;
 
 /// The version of CKB VM.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum VMVersion {
     /// CKB VM 0 with Syscall version 1.
     V0 = 0,
@@ -3,22 +3,33 @@
 static GLOBAL_ALLOC: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
 use anyhow::{Context, Result};
-use clap::{App, Arg, SubCommand};
+use clap::{App, Arg, ArgGroup, SubCommand};
 use gw_block_producer::{runner, trace};
 use gw_config::{BackendSwitchConfig, Config};
 use gw_version::Version;
 use std::{env, fs, path::Path};
 
 mod subcommand;
+use subcommand::backup::{Backup, BackupSource, Restore};
+use subcommand::check_db::{CheckDb, CheckDbArgs};
 use subcommand::db_block_validator;
 use subcommand::export_block::{ExportArgs, ExportBlock};
+use subcommand::export_state::{ExportState, ExportStateArgs};
 use subcommand::import_block::{ImportArgs, ImportBlock};
+use subcommand::import_state::{ImportState, ImportStateArgs};
+use subcommand::migrate::{Migrate, MigrateMode};
 
 const COMMAND_RUN: &str = "run";
 const COMMAND_EXAMPLE_CONFIG: &str = "generate-example-config";
 const COMMAND_VERIFY_DB_BLOCK: &str = "verify-db-block";
 const COMMAND_EXPORT_BLOCK: &str = "export-block";
 const COMMAND_IMPORT_BLOCK: &str = "import-block";
+const COMMAND_EXPORT_STATE: &str = "export-state";
+const COMMAND_IMPORT_STATE: &str = "import-state";
+const COMMAND_MIGRATE: &str = "migrate";
+const COMMAND_BACKUP: &str = "backup";
+const COMMAND_RESTORE: &str = "restore";
+const COMMAND_CHECK_DB: &str = "check-db";
 const ARG_OUTPUT_PATH: &str = "output-path";
 const ARG_CONFIG: &str = "config";
 const ARG_SKIP_CONFIG_CHECK: &str = "skip-config-check";
@@ -28,6 +39,16 @@ const ARG_SHOW_PROGRESS: &str = "show-progress";
 const ARG_SOURCE_PATH: &str = "source-path";
 const ARG_READ_BATCH: &str = "read-batch";
 const ARG_REWIND_TO_LAST_VALID_TIP: &str = "rewind-to-last-valid-tip";
+const ARG_BLOCK: &str = "block";
+const ARG_BLOCK_SOURCE_PATH: &str = "block-source-path";
+const ARG_CHECK: &str = "check";
+const ARG_DRY_RUN: &str = "dry-run";
+const ARG_RUN: &str = "run";
+const ARG_TO: &str = "to";
+const ARG_FROM: &str = "from";
+const ARG_RPC_URL: &str = "rpc-url";
+const ARG_REPAIR: &str = "repair";
+const ARG_VERIFY_THREADS: &str = "verify-threads";
 
 fn read_config<P: AsRef<Path>>(path: P) -> Result<Config> {
     let content = fs::read(&path)
@@ -193,6 +214,12 @@ async fn run_cli() -> Result<()> {
                         .takes_value(false)
                         .help("Rewind to last valid tip block before import"),
                 )
+                .arg(
+                    Arg::with_name(ARG_VERIFY_THREADS)
+                        .long("verify-threads")
+                        .takes_value(true)
+                        .help("Signature verification threads, default is the number of CPUs"),
+                )
                 .arg(
                     Arg::with_name(ARG_SHOW_PROGRESS)
                         .short("p")
@@ -202,6 +229,179 @@ async fn run_cli() -> Result<()> {
                         .help("Show progress bar"),
                 )
                 .display_order(4),
+        )
+        .subcommand(
+            SubCommand::with_name(COMMAND_EXPORT_STATE)
+                .about("Export state snapshot of a finalized block in db")
+                .arg(
+                    Arg::with_name(ARG_CONFIG)
+                        .short("c")
+                        .takes_value(true)
+                        .required(true)
+                        .default_value("./config.toml")
+                        .help("The config file path"),
+                )
+                .arg(
+                    Arg::with_name(ARG_OUTPUT_PATH)
+                        .short("o")
+                        .long("output-path")
+                        .takes_value(true)
+                        .required(true)
+                        .help("The output file for exported state"),
+                )
+                .arg(
+                    Arg::with_name(ARG_BLOCK)
+                        .long("block")
+                        .takes_value(true)
+                        .help("Block number, default is the last finalized block"),
+                )
+                .display_order(5),
+        )
+        .subcommand(
+            SubCommand::with_name(COMMAND_IMPORT_STATE)
+                .about("Import state snapshot into an empty db, then import blocks after it")
+                .arg(
+                    Arg::with_name(ARG_CONFIG)
+                        .short("c")
+                        .takes_value(true)
+                        .required(true)
+                        .default_value("./config.toml")
+                        .help("The config file path"),
+                )
+                .arg(
+                    Arg::with_name(ARG_SOURCE_PATH)
+                        .short("s")
+                        .long("source-path")
+                        .takes_value(true)
+                        .required(true)
+                        .help("The source file for exported state"),
+                )
+                .arg(
+                    Arg::with_name(ARG_BLOCK_SOURCE_PATH)
+                        .long("block-source-path")
+                        .takes_value(true)
+                        .help("The source file for exported blocks after the state block"),
+                )
+                .arg(
+                    Arg::with_name(ARG_READ_BATCH)
+                        .short("b")
+                        .long("read-batch")
+                        .takes_value(true)
+                        .help("The read block batch size"),
+                )
+                .arg(
+                    Arg::with_name(ARG_TO_BLOCK)
+                        .short("t")
+                        .long("to-block")
+                        .takes_value(true)
+                        .help("To block number"),
+                )
+                .arg(
+                    Arg::with_name(ARG_SHOW_PROGRESS)
+                        .short("p")
+                        .long("show-progress")
+                        .required(false)
+                        .takes_value(false)
+                        .help("Show progress bar"),
+                )
+                .display_order(6),
+        )
+        .subcommand(
+            SubCommand::with_name(COMMAND_MIGRATE)
+                .about("Check or run pending database migrations")
+                .arg(
+                    Arg::with_name(ARG_CONFIG)
+                        .short("c")
+                        .takes_value(true)
+                        .required(true)
+                        .default_value("./config.toml")
+                        .help("The config file path"),
+                )
+                .arg(
+                    Arg::with_name(ARG_CHECK)
+                        .long("check")
+                        .help("Exit with error if there are pending migrations"),
+                )
+                .arg(
+                    Arg::with_name(ARG_DRY_RUN)
+                        .long("dry-run")
+                        .help("List pending migrations without changing database"),
+                )
+                .arg(
+                    Arg::with_name(ARG_RUN)
+                        .long("run")
+                        .help("Run pending migrations, interrupted migration is resumed"),
+                )
+                .group(
+                    ArgGroup::with_name("mode")
+                        .args(&[ARG_CHECK, ARG_DRY_RUN, ARG_RUN])
+                        .required(true),
+                )
+                .display_order(7),
+        )
+        .subcommand(
+            SubCommand::with_name(COMMAND_BACKUP)
+                .about("Create a consistent backup of db with a manifest")
+                .arg(
+                    Arg::with_name(ARG_CONFIG)
+                        .short("c")
+                        .takes_value(true)
+                        .required(true)
+                        .default_value("./config.toml")
+                        .help("The config file path"),
+                )
+                .arg(
+                    Arg::with_name(ARG_TO)
+                        .long("to")
+                        .takes_value(true)
+                        .required(true)
+                        .help("The backup directory, must not exist or be empty. The name under rpc_server.backup_dir of the node if backup by RPC"),
+                )
+                .arg(
+                    Arg::with_name(ARG_RPC_URL)
+                        .long("rpc-url")
+                        .takes_value(true)
+                        .help("Backup a running node by admin RPC, the directory is on the node's host"),
+                )
+                .display_order(8),
+        )
+        .subcommand(
+            SubCommand::with_name(COMMAND_RESTORE)
+                .about("Validate a backup and restore it into an empty db")
+                .arg(
+                    Arg::with_name(ARG_CONFIG)
+                        .short("c")
+                        .takes_value(true)
+                        .required(true)
+                        .default_value("./config.toml")
+                        .help("The config file path"),
+                )
+                .arg(
+                    Arg::with_name(ARG_FROM)
+                        .long("from")
+                        .takes_value(true)
+                        .required(true)
+                        .help("The backup directory"),
+                )
+                .display_order(9),
+        )
+        .subcommand(
+            SubCommand::with_name(COMMAND_CHECK_DB)
+                .about("Check consistency of db, the node must be stopped")
+                .arg(
+                    Arg::with_name(ARG_CONFIG)
+                        .short("c")
+                        .takes_value(true)
+                        .required(true)
+                        .default_value("./config.toml")
+                        .help("The config file path"),
+                )
+                .arg(
+                    Arg::with_name(ARG_REPAIR)
+                        .long("repair")
+                        .help("Delete orphaned keys, other errors aren't repaired"),
+                )
+                .display_order(10),
         );
 
     // handle subcommands
@@ -254,6 +454,8 @@ async fn run_cli() -> Result<()> {
             let to_block: Option<u64> = m.value_of(ARG_TO_BLOCK).map(str::parse).transpose()?;
             let rewind_to_last_valid_tip = m.is_present(ARG_REWIND_TO_LAST_VALID_TIP);
             let show_progress = m.is_present(ARG_SHOW_PROGRESS);
+            let verify_threads: Option<usize> =
+                m.value_of(ARG_VERIFY_THREADS).map(str::parse).transpose()?;
 
             let args = ImportArgs {
                 config,
@@ -262,9 +464,86 @@ async fn run_cli() -> Result<()> {
                 to_block,
                 rewind_to_last_valid_tip,
                 show_progress,
+                verify_threads,
             };
             ImportBlock::create(args).await?.execute().await?;
         }
+        (COMMAND_EXPORT_STATE, Some(m)) => {
+            let config_path = m.value_of(ARG_CONFIG).unwrap();
+            let config = read_config(&config_path)?;
+            let _guard = trace::init(None)?;
+            let output = m.value_of(ARG_OUTPUT_PATH).unwrap().into();
+            let block: Option<u64> = m.value_of(ARG_BLOCK).map(str::parse).transpose()?;
+
+            let args = ExportStateArgs {
+                config,
+                output,
+                block,
+            };
+            ExportState::create(args)?.execute()?;
+        }
+        (COMMAND_IMPORT_STATE, Some(m)) => {
+            let config_path = m.value_of(ARG_CONFIG).unwrap();
+            let config = read_config(&config_path)?;
+            let _guard = trace::init(None)?;
+            let source = m.value_of(ARG_SOURCE_PATH).unwrap().into();
+            let block_source = m.value_of(ARG_BLOCK_SOURCE_PATH).map(Into::into);
+            let read_batch: Option<usize> =
+                m.value_of(ARG_READ_BATCH).map(str::parse).transpose()?;
+            let to_block: Option<u64> = m.value_of(ARG_TO_BLOCK).map(str::parse).transpose()?;
+            let show_progress = m.is_present(ARG_SHOW_PROGRESS);
+
+            let args = ImportStateArgs {
+                config,
+                source,
+                block_source,
+                read_batch,
+                to_block,
+                show_progress,
+            };
+            ImportState::create(args)?.execute().await?;
+        }
+        (COMMAND_MIGRATE, Some(m)) => {
+            let config_path = m.value_of(ARG_CONFIG).unwrap();
+            let config = read_config(&config_path)?;
+            let _guard = trace::init(None)?;
+            let mode = if m.is_present(ARG_RUN) {
+                MigrateMode::Run
+            } else if m.is_present(ARG_DRY_RUN) {
+                MigrateMode::DryRun
+            } else {
+                MigrateMode::Check
+            };
+            Migrate::new(config, mode).execute()?;
+        }
+        (COMMAND_BACKUP, Some(m)) => {
+            let to = m.value_of(ARG_TO).unwrap().into();
+            let source = match m.value_of(ARG_RPC_URL) {
+                Some(url) => BackupSource::Rpc(url.to_string()),
+                None => {
+                    let config_path = m.value_of(ARG_CONFIG).unwrap();
+                    BackupSource::Store(read_config(&config_path)?.store)
+                }
+            };
+            let _guard = trace::init(None)?;
+            Backup::new(source, to).execute().await?;
+        }
+        (COMMAND_RESTORE, Some(m)) => {
+            let config_path = m.value_of(ARG_CONFIG).unwrap();
+            let config = read_config(&config_path)?;
+            let _guard = trace::init(None)?;
+            let from = m.value_of(ARG_FROM).unwrap().into();
+            Restore::new(config, from).execute()?;
+        }
+        (COMMAND_CHECK_DB, Some(m)) => {
+            let config_path = m.value_of(ARG_CONFIG).unwrap();
+            let config = read_config(&config_path)?;
+            let _guard = trace::init(None)?;
+            let repair = m.is_present(ARG_REPAIR);
+
+            let args = CheckDbArgs { config, repair };
+            CheckDb::create(args)?.execute()?;
+        }
         _ => {
             // default command: start a Godwoken node
             let config_path = "./config.toml";
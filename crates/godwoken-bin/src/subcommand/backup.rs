@@ -0,0 +1,106 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+use async_jsonrpc_client::{HttpClient, Output, Params as ClientParams, Transport};
+use gw_common::H256;
+use gw_config::{Config, StoreConfig};
+use gw_db::{schema::COLUMNS, RocksDB};
+use gw_jsonrpc_types::godwoken::BackupManifest;
+use gw_store::{migrate::check_db_version, Store};
+use gw_utils::backup::{create_backup, restore_backup};
+use serde_json::json;
+
+pub enum BackupSource {
+    /// Open the store directly, the node must be stopped
+    Store(StoreConfig),
+    /// Ask a running node to create the backup by admin RPC
+    Rpc(String),
+}
+
+/// Backup
+///
+/// Create a consistent checkpoint of the store with a manifest. A running
+/// node holds the db lock, so its backup is created by the node itself
+/// through `gw_admin_create_backup`.
+pub struct Backup {
+    source: BackupSource,
+    to: PathBuf,
+}
+
+impl Backup {
+    pub fn new(source: BackupSource, to: PathBuf) -> Self {
+        Backup { source, to }
+    }
+
+    pub async fn execute(self) -> Result<()> {
+        let (tip_block_number, tip_block_hash) = match self.source {
+            BackupSource::Store(config) => {
+                if !config.path.exists() {
+                    bail!("no database at {}", config.path.display());
+                }
+                check_db_version(&config)?;
+                let store = Store::new(RocksDB::open(&config, COLUMNS));
+                let manifest = create_backup(&store, &self.to)?;
+                (manifest.tip_block_number, manifest.tip_block_hash)
+            }
+            BackupSource::Rpc(url) => {
+                // The name is resolved under `rpc_server.backup_dir` on the node's host
+                if self.to.is_absolute() {
+                    bail!("backup name must be relative when backup by rpc");
+                }
+                let manifest = request_backup(&url, &self.to).await?;
+                (manifest.tip_block_number.value(), manifest.tip_block_hash)
+            }
+        };
+
+        println!(
+            "backup tip block {} {:x} to {}",
+            tip_block_number,
+            tip_block_hash,
+            self.to.to_string_lossy()
+        );
+        Ok(())
+    }
+}
+
+async fn request_backup(url: &str, to: &Path) -> Result<BackupManifest> {
+    let client = HttpClient::new(url)?;
+    let params = ClientParams::Array(vec![json!(to.to_string_lossy())]);
+    match client
+        .request("gw_admin_create_backup", Some(params))
+        .await?
+    {
+        Output::Success(success) => Ok(serde_json::from_value(success.result)?),
+        Output::Failure(failure) => Err(anyhow!("backup failed: {}", failure.error)),
+    }
+}
+
+/// Restore
+///
+/// Validate a backup and restore it into the empty store path of the config
+pub struct Restore {
+    config: Config,
+    from: PathBuf,
+}
+
+impl Restore {
+    pub fn new(config: Config, from: PathBuf) -> Self {
+        Restore { config, from }
+    }
+
+    pub fn execute(self) -> Result<()> {
+        let rollup_type_hash: H256 = {
+            let hash: [u8; 32] = self.config.genesis.rollup_type_hash.clone().into();
+            hash.into()
+        };
+        let manifest = restore_backup(&self.from, &self.config.store.path, &rollup_type_hash)?;
+
+        println!(
+            "restore tip block {} {:x} to {}",
+            manifest.tip_block_number,
+            manifest.tip_block_hash,
+            self.config.store.path.to_string_lossy()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+use anyhow::{anyhow, bail, Result};
+use gw_config::Config;
+use gw_db::{read_only_db::ReadOnlyDB, schema::COLUMNS, RocksDB};
+use gw_store::{
+    check::{check_store, repair_orphans, CheckReport},
+    migrate::check_db_version,
+    readonly::StoreReadonly,
+    Store,
+};
+
+// Orphaned keys printed per column
+const MAX_PRINTED_ORPHANS: usize = 10;
+
+pub struct CheckDbArgs {
+    pub config: Config,
+    pub repair: bool,
+}
+
+/// CheckDb
+///
+/// Thoroughly check the store of a stopped node, optionally delete orphaned
+/// keys. Other errors aren't repaired.
+pub struct CheckDb {
+    config: Config,
+    snap: StoreReadonly,
+    repair: bool,
+}
+
+impl CheckDb {
+    pub fn create(args: CheckDbArgs) -> Result<Self> {
+        check_db_version(&args.config.store)?;
+        let snap = {
+            let cf_names = (0..COLUMNS).map(|c| c.to_string());
+            let db = ReadOnlyDB::open_cf(&args.config.store.path, cf_names)?
+                .ok_or_else(|| anyhow!("no database"))?;
+            StoreReadonly::new(db)
+        };
+
+        Ok(CheckDb {
+            config: args.config,
+            snap,
+            repair: args.repair,
+        })
+    }
+
+    pub fn execute(self) -> Result<()> {
+        let report = check_store(&self.snap)?;
+        print_report(&report);
+        if report.is_ok() {
+            println!("database is consistent");
+            return Ok(());
+        }
+
+        let orphan_count = report.orphan_count();
+        if self.repair && orphan_count > 0 {
+            drop(self.snap);
+            let store = Store::new(RocksDB::open(&self.config.store, COLUMNS));
+            let deleted = repair_orphans(&store, &report)?;
+            println!("deleted {} orphaned keys", deleted);
+            if report.error_count == 0 {
+                return Ok(());
+            }
+        }
+
+        if report.error_count > 0 {
+            bail!("{} errors found", report.error_count);
+        }
+        bail!(
+            "{} orphaned keys found, run with --repair to delete them",
+            orphan_count
+        );
+    }
+}
+
+fn print_report(report: &CheckReport) {
+    println!(
+        "tip block {}, last valid tip block {}, history pruned before block {}",
+        report.tip_block_number, report.last_valid_tip_block_number, report.pruned_block_number
+    );
+
+    for column in report.columns.iter() {
+        println!(
+            "column {} ({}): {} keys, {} orphaned",
+            column.col,
+            column.name,
+            column.keys,
+            column.orphans.len()
+        );
+        for key in column.orphans.iter().take(MAX_PRINTED_ORPHANS) {
+            println!("  orphaned key 0x{}", hex(key));
+        }
+    }
+
+    for err in report.errors.iter() {
+        println!("error: {}", err);
+    }
+    if report.error_count > report.errors.len() {
+        println!(
+            "... {} more errors",
+            report.error_count - report.errors.len()
+        );
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -1,15 +1,16 @@
 use std::fs;
-use std::io::{self, Write};
+use std::io;
 use std::path::PathBuf;
 
 use anyhow::{anyhow, bail, Result};
 use gw_config::Config;
 use gw_db::read_only_db::ReadOnlyDB;
 use gw_db::schema::COLUMNS;
+use gw_store::migrate::check_db_version;
 use gw_store::readonly::StoreReadonly;
 use gw_store::traits::chain_store::ChainStore;
-use gw_types::packed;
-use gw_types::prelude::{Entity, Unpack};
+use gw_types::prelude::Unpack;
+use gw_utils::export_block::{export_block, ExportedBlockWriter};
 use indicatif::{ProgressBar, ProgressStyle};
 
 pub struct ExportArgs {
@@ -50,6 +51,7 @@ impl ExportBlock {
     }
 
     pub fn create(args: ExportArgs) -> Result<Self> {
+        check_db_version(&args.config.store)?;
         let snap = {
             let cf_names = (0..COLUMNS).map(|c| c.to_string());
             let db = ReadOnlyDB::open_cf(&args.config.store.path, cf_names)?
@@ -131,27 +133,31 @@ impl ExportBlock {
         if let Some(parent) = self.output.parent() {
             fs::create_dir_all(parent)?;
         }
-        self.write_to_mol()
+        self.write_to_file()
     }
 
-    pub fn write_to_mol(self) -> Result<()> {
+    pub fn write_to_file(self) -> Result<()> {
         let f = fs::OpenOptions::new()
             .create_new(true)
             .read(true)
             .write(true)
             .open(self.output)?;
 
-        let mut writer = io::BufWriter::new(f);
+        let rollup_type_hash = self.snap.get_chain_id()?;
+        let mut writer = ExportedBlockWriter::new(
+            io::BufWriter::new(f),
+            &rollup_type_hash,
+            self.from_block,
+            self.to_block,
+        )?;
         for block_number in self.from_block..=self.to_block {
-            let exported_block = gw_utils::export_block::export_block(&self.snap, block_number)?;
-            let packed: packed::ExportedBlock = exported_block.into();
-
-            writer.write_all(packed.as_slice())?;
+            writer.write_block(export_block(&self.snap, block_number)?)?;
 
             if let Some(ref progress_bar) = self.progress_bar {
                 progress_bar.inc(1)
             }
         }
+        writer.finish()?;
 
         if let Some(ref progress_bar) = self.progress_bar {
             progress_bar.finish_with_message("done");
@@ -0,0 +1,107 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use gw_config::Config;
+use gw_db::read_only_db::ReadOnlyDB;
+use gw_db::schema::COLUMNS;
+use gw_store::migrate::check_db_version;
+use gw_store::readonly::StoreReadonly;
+use gw_store::traits::chain_store::ChainStore;
+use gw_types::prelude::Unpack;
+
+pub struct ExportStateArgs {
+    pub config: Config,
+    pub output: PathBuf,
+    pub block: Option<u64>,
+}
+
+/// ExportState
+///
+/// Export state snapshot of a finalized block from readonly database
+pub struct ExportState {
+    snap: StoreReadonly,
+    output: PathBuf,
+    block_number: u64,
+}
+
+impl ExportState {
+    // Disable warning for bin
+    #[allow(dead_code)]
+    pub fn new_unchecked(snap: StoreReadonly, output: PathBuf, block_number: u64) -> Self {
+        ExportState {
+            snap,
+            output,
+            block_number,
+        }
+    }
+
+    pub fn create(args: ExportStateArgs) -> Result<Self> {
+        check_db_version(&args.config.store)?;
+        let snap = {
+            let cf_names = (0..COLUMNS).map(|c| c.to_string());
+            let db = ReadOnlyDB::open_cf(&args.config.store.path, cf_names)?
+                .ok_or_else(|| anyhow!("no database"))?;
+            StoreReadonly::new(db)
+        };
+
+        // Finality and pruned history are checked on export
+        let block_number = match args.block {
+            Some(block_number) => block_number,
+            None => {
+                let tip_block_hash = snap.get_last_valid_tip_block_hash()?;
+                let tip_global_state = snap
+                    .get_block_post_global_state(&tip_block_hash)?
+                    .ok_or_else(|| anyhow!("tip block post global state not found"))?;
+                tip_global_state.last_finalized_block_number().unpack()
+            }
+        };
+
+        let output = {
+            let mut output = args.output;
+            let mut file_name = output
+                .file_name()
+                .ok_or_else(|| anyhow!("no file name in path"))?
+                .to_os_string();
+
+            file_name.push(format!("_{:x}", args.config.genesis.rollup_type_hash));
+            file_name.push(format!("_{}", block_number));
+
+            output.set_file_name(file_name);
+            output
+        };
+
+        Ok(ExportState {
+            snap,
+            output,
+            block_number,
+        })
+    }
+
+    // Disable warning for bin
+    #[allow(dead_code)]
+    pub fn store(&self) -> &StoreReadonly {
+        &self.snap
+    }
+
+    pub fn execute(self) -> Result<()> {
+        if let Some(parent) = self.output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let f = fs::OpenOptions::new()
+            .create_new(true)
+            .write(true)
+            .open(&self.output)?;
+        let writer = io::BufWriter::new(f);
+        gw_utils::export_state::export_state(&self.snap, self.block_number, writer)?;
+
+        println!(
+            "export state of block {} to {}",
+            self.block_number,
+            self.output.to_string_lossy()
+        );
+        Ok(())
+    }
+}
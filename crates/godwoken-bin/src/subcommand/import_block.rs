@@ -1,21 +1,28 @@
 use std::collections::HashSet;
 use std::fs;
-use std::io::BufReader;
+use std::io::{BufReader, Read, Seek};
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::{anyhow, bail, Result};
 use gw_block_producer::runner::BaseInitComponents;
 use gw_chain::chain::{Chain, RevertL1ActionContext, RevertedL1Action, SyncParam};
+use gw_common::H256;
 use gw_config::Config;
-use gw_store::{traits::chain_store::ChainStore, Store};
+use gw_generator::{generator::VerifiedSignatures, Generator};
+use gw_store::{mem_pool_state::MemStore, traits::chain_store::ChainStore, Store};
 use gw_types::offchain::ExportedBlock;
 use gw_types::prelude::Unpack;
 use gw_utils::export_block::{
-    check_block_post_state, insert_bad_block_hashes, ExportedBlockReader,
+    check_block_post_state, insert_bad_block_hashes, AnyExportedBlockReader, ExportedBlockReader,
+    ImportCursor,
 };
 use indicatif::{ProgressBar, ProgressStyle};
+use rayon::prelude::*;
 
 pub const DEFAULT_READ_BATCH: usize = 500;
+const VERIFY_BATCH_PER_THREAD: usize = 4;
 
 pub struct ImportArgs {
     pub config: Config,
@@ -24,12 +31,15 @@ pub struct ImportArgs {
     pub to_block: Option<u64>,
     pub rewind_to_last_valid_tip: bool,
     pub show_progress: bool,
+    /// Signature verification threads, default is the number of CPUs
+    pub verify_threads: Option<usize>,
 }
 
 pub struct ImportBlock {
     chain: Chain,
     source: PathBuf,
     read_batch: usize,
+    verify_threads: usize,
     to_block: Option<u64>,
     rewind_to_last_valid_tip: bool,
     progress_bar: Option<ProgressBar>,
@@ -43,6 +53,7 @@ impl ImportBlock {
             chain,
             source,
             read_batch: DEFAULT_READ_BATCH,
+            verify_threads: num_cpus::get(),
             to_block: None,
             rewind_to_last_valid_tip: false,
             progress_bar: None,
@@ -77,6 +88,7 @@ impl ImportBlock {
             chain,
             source: args.source,
             read_batch: args.read_batch.unwrap_or(DEFAULT_READ_BATCH),
+            verify_threads: args.verify_threads.unwrap_or_else(num_cpus::get).max(1),
             to_block: args.to_block,
             rewind_to_last_valid_tip: args.rewind_to_last_valid_tip,
             progress_bar,
@@ -126,15 +138,36 @@ impl ImportBlock {
     }
 
     pub fn read_from_mol(&mut self) -> Result<()> {
-        let store = self.chain.store();
+        let store = self.chain.store().clone();
         let f = fs::File::open(&self.source)?;
-        let mut block_reader = ExportedBlockReader::new(BufReader::new(f));
+        let source_size = f.metadata()?.len();
+        let mut block_reader = AnyExportedBlockReader::new(BufReader::new(f))?;
+        if let Some(header) = block_reader.header() {
+            let rollup_type_hash: H256 = (*self.chain.rollup_type_script_hash()).into();
+            if header.rollup_type_hash != rollup_type_hash {
+                bail!("exported blocks of another rollup");
+            }
+        }
 
         // Seek new block
         let snap = store.get_snapshot();
         let db_tip_block = snap.get_tip_block()?;
         let db_tip_block_number = db_tip_block.raw().number().unpack();
 
+        let mut source_offset = 0;
+        let cursor = ImportCursor::load(&snap)?.filter(|cursor| {
+            cursor.block_hash == db_tip_block.hash().into() && cursor.source_size == source_size
+        });
+        if let (AnyExportedBlockReader::V1(reader), Some(cursor)) = (&mut block_reader, cursor) {
+            if resume_v1(reader, &cursor)? {
+                println!("resume from block {}", cursor.block_number + 1);
+                source_offset = cursor.source_offset;
+                if let Some(ref progress_bar) = self.progress_bar {
+                    progress_bar.inc(source_offset)
+                }
+            }
+        }
+
         let (first_block, _size) = block_reader
             .peek_block()?
             .ok_or_else(|| anyhow!("empty file"))?;
@@ -154,6 +187,7 @@ impl ImportBlock {
             if n != new_block_offset {
                 bail!("no new block")
             }
+            source_offset += size;
 
             if let Some(ref progress_bar) = self.progress_bar {
                 progress_bar.inc(size as u64)
@@ -169,29 +203,77 @@ impl ImportBlock {
         }
 
         // Read blocks in background
-        let (tx, rx) = std::sync::mpsc::sync_channel(self.read_batch);
+        let (read_tx, read_rx) = std::sync::mpsc::sync_channel(self.read_batch);
         let to_block = self.to_block;
         let read_in_background = std::thread::spawn(move || {
             for maybe_new_block in block_reader {
                 match maybe_new_block.as_ref() {
-                    Err(_) => return,
+                    Err(_) => {
+                        let _ = read_tx.send(maybe_new_block);
+                        return;
+                    }
                     Ok((block, _size))
                         if to_block.is_some() && Some(block.block_number()) > to_block =>
                     {
                         return
                     }
-                    Ok(_) => tx.send(maybe_new_block).expect("send block in background"),
+                    Ok(_) => {
+                        if read_tx.send(maybe_new_block).is_err() {
+                            return;
+                        }
+                    }
                 };
             }
         });
 
+        // Verify signatures in worker pool ahead of applying blocks
+        let (verify_tx, verify_rx) = std::sync::mpsc::sync_channel(self.read_batch);
+        let verify_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.verify_threads)
+            .build()?;
+        let generator = Arc::clone(self.chain.generator());
+        let verify_batch = self.verify_threads * VERIFY_BATCH_PER_THREAD;
+        let verify_store = store.clone();
+        let verify_in_background = std::thread::spawn(move || {
+            while let Ok(maybe_new_block) = read_rx.recv() {
+                let mut batch = vec![maybe_new_block];
+                batch.extend(read_rx.try_iter().take(verify_batch - 1));
+
+                let verified: Vec<_> = verify_pool.install(|| {
+                    batch
+                        .into_par_iter()
+                        .map(|maybe_new_block| -> Result<_> {
+                            let (block, size) = maybe_new_block?;
+                            let verified_signatures =
+                                verify_block_signatures(&verify_store, &generator, &block);
+                            Ok((block, size, verified_signatures))
+                        })
+                        .collect()
+                });
+                for maybe_verified_block in verified {
+                    if verify_tx.send(maybe_verified_block).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        let mut stats = ImportStats::new();
         let mut next_block_number = db_tip_block_number + 1;
-        for maybe_new_block in rx.into_iter() {
-            let (block, size) = maybe_new_block
+        for maybe_new_block in verify_rx.into_iter() {
+            let (block, size, verified_signatures) = maybe_new_block
                 .map_err(|err| anyhow!("read block {} {}", next_block_number, err))?;
             let block_number = block.block_number();
-
-            insert_block(&mut self.chain, block)
+            source_offset += size as u64;
+            stats.add_block(&block, size, &verified_signatures);
+
+            let cursor = ImportCursor {
+                block_number,
+                block_hash: block.block_hash(),
+                source_size,
+                source_offset,
+            };
+            insert_block(&mut self.chain, block, verified_signatures, &cursor)
                 .map_err(|err| anyhow!("insert block {} {}", block_number, err))?;
 
             if let Some(ref progress_bar) = self.progress_bar {
@@ -206,16 +288,54 @@ impl ImportBlock {
         }
 
         read_in_background.join().expect("join read background");
+        verify_in_background.join().expect("join verify background");
+        stats.report();
 
         Ok(())
     }
 }
 
-fn insert_block(chain: &mut Chain, exported: ExportedBlock) -> Result<()> {
+/// Return false if cursor doesn't point to the next block of db tip
+fn resume_v1<R: Read + Seek>(
+    reader: &mut ExportedBlockReader<R>,
+    cursor: &ImportCursor,
+) -> Result<bool> {
+    reader.seek_block(cursor.source_offset)?;
+    match reader.peek_block() {
+        Ok(Some((block, _size))) if block.block_number() == cursor.block_number + 1 => Ok(true),
+        _ => {
+            reader.seek_block(0)?;
+            Ok(false)
+        }
+    }
+}
+
+// Verify against the current db tip state, which is always before the block
+fn verify_block_signatures(
+    store: &Store,
+    generator: &Generator,
+    block: &ExportedBlock,
+) -> VerifiedSignatures {
+    let mem_store = MemStore::new(store.get_snapshot());
+    match mem_store.state() {
+        Ok(state) => generator.verify_block_signatures(&state, &block.block, &block.withdrawals),
+        Err(err) => {
+            log::warn!("verify block {} signatures {}", block.block_number(), err);
+            VerifiedSignatures::default()
+        }
+    }
+}
+
+fn insert_block(
+    chain: &mut Chain,
+    exported: ExportedBlock,
+    verified_signatures: VerifiedSignatures,
+    cursor: &ImportCursor,
+) -> Result<()> {
     let tx_db = chain.store().begin_transaction();
     let block_number = exported.block_number();
 
-    if let Some(_challenge_target) = chain.process_block(
+    if let Some(_challenge_target) = chain.process_block_with_verified_signatures(
         &tx_db,
         exported.block,
         exported.committed_info,
@@ -223,6 +343,7 @@ fn insert_block(chain: &mut Chain, exported: ExportedBlock) -> Result<()> {
         exported.deposit_requests,
         HashSet::from_iter(exported.deposit_asset_scripts),
         exported.withdrawals,
+        verified_signatures,
     )? {
         bail!("bad block")
     }
@@ -234,7 +355,64 @@ fn insert_block(chain: &mut Chain, exported: ExportedBlock) -> Result<()> {
 
     check_block_post_state(&tx_db, block_number, &exported.post_global_state)?;
 
+    cursor.save(&tx_db)?;
     tx_db.commit()?;
 
     Ok(())
 }
+
+struct ImportStats {
+    started_at: Instant,
+    blocks: u64,
+    txs: u64,
+    withdrawals: u64,
+    signatures: u64,
+    verified_signatures: u64,
+    bytes: u64,
+}
+
+impl ImportStats {
+    fn new() -> Self {
+        ImportStats {
+            started_at: Instant::now(),
+            blocks: 0,
+            txs: 0,
+            withdrawals: 0,
+            signatures: 0,
+            verified_signatures: 0,
+            bytes: 0,
+        }
+    }
+
+    fn add_block(
+        &mut self,
+        block: &ExportedBlock,
+        size: usize,
+        verified_signatures: &VerifiedSignatures,
+    ) {
+        let txs = block.block.transactions().len() as u64;
+        let withdrawals = block.withdrawals.len() as u64;
+        self.blocks += 1;
+        self.txs += txs;
+        self.withdrawals += withdrawals;
+        self.signatures += txs + withdrawals;
+        self.verified_signatures += verified_signatures.verified_count() as u64;
+        self.bytes += size as u64;
+    }
+
+    fn report(&self) {
+        let secs = self.started_at.elapsed().as_secs_f64().max(f64::EPSILON);
+        println!(
+            "import {} blocks, {} txs, {} withdrawals, {} bytes in {:.2}s",
+            self.blocks, self.txs, self.withdrawals, self.bytes, secs
+        );
+        println!(
+            "{:.2} blocks/s, {:.2} txs/s, {:.2} MiB/s, {}/{} signatures verified ahead",
+            self.blocks as f64 / secs,
+            self.txs as f64 / secs,
+            self.bytes as f64 / secs / (1024.0 * 1024.0),
+            self.verified_signatures,
+            self.signatures
+        );
+    }
+}
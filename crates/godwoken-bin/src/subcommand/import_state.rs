@@ -0,0 +1,110 @@
+use std::fs;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use gw_common::H256;
+use gw_config::Config;
+use gw_store::migrate::open_or_create_db;
+use gw_store::Store;
+use gw_utils::export_state::{import_state, StateSnapshotReader};
+
+use crate::subcommand::import_block::{ImportArgs, ImportBlock};
+
+pub struct ImportStateArgs {
+    pub config: Config,
+    pub source: PathBuf,
+    pub block_source: Option<PathBuf>,
+    pub read_batch: Option<usize>,
+    pub to_block: Option<u64>,
+    pub show_progress: bool,
+}
+
+/// ImportState
+///
+/// Rebuild an empty store from state snapshot, then sync the rest blocks
+/// from exported blocks by `ImportBlock`
+pub struct ImportState {
+    store: Store,
+    source: PathBuf,
+    rollup_type_hash: Option<H256>,
+    import_block: Option<ImportArgs>,
+}
+
+impl ImportState {
+    // Disable warning for bin
+    #[allow(dead_code)]
+    pub fn new_unchecked(store: Store, source: PathBuf) -> Self {
+        ImportState {
+            store,
+            source,
+            rollup_type_hash: None,
+            import_block: None,
+        }
+    }
+
+    pub fn create(args: ImportStateArgs) -> Result<Self> {
+        let store = Store::new(open_or_create_db(&args.config.store)?);
+        let rollup_type_hash = {
+            let hash: [u8; 32] = args.config.genesis.rollup_type_hash.clone().into();
+            hash.into()
+        };
+
+        let import_block = args.block_source.map(|block_source| ImportArgs {
+            config: args.config,
+            source: block_source,
+            read_batch: args.read_batch,
+            to_block: args.to_block,
+            rewind_to_last_valid_tip: false,
+            show_progress: args.show_progress,
+            verify_threads: None,
+        });
+
+        Ok(ImportState {
+            store,
+            source: args.source,
+            rollup_type_hash: Some(rollup_type_hash),
+            import_block,
+        })
+    }
+
+    // Disable warning for bin
+    #[allow(dead_code)]
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    pub async fn execute(self) -> Result<()> {
+        let ImportState {
+            store,
+            source,
+            rollup_type_hash,
+            import_block,
+        } = self;
+
+        // Verify checksum before writing anything into store
+        let header = open_snapshot(&source)?.verify()?;
+        if let Some(rollup_type_hash) = rollup_type_hash {
+            if header.rollup_type_hash != rollup_type_hash {
+                bail!("state snapshot of another rollup");
+            }
+        }
+
+        let block_number =
+            import_state(&store, open_snapshot(&source)?).context("import state failed")?;
+        println!("import state of block {} success", block_number);
+
+        // Release db before import block reopens it
+        drop(store);
+        if let Some(args) = import_block {
+            ImportBlock::create(args).await?.execute().await?;
+        }
+
+        Ok(())
+    }
+}
+
+fn open_snapshot(source: &Path) -> Result<StateSnapshotReader<BufReader<fs::File>>> {
+    let f = fs::File::open(source)?;
+    StateSnapshotReader::new(BufReader::new(f))
+}
@@ -0,0 +1,102 @@
+use std::sync::Mutex;
+
+use anyhow::{bail, Result};
+use gw_config::Config;
+use gw_db::migrate::{MigrationProgress, PendingMigration};
+use gw_store::migrate::{check_migrations, run_migrations};
+use indicatif::{ProgressBar, ProgressStyle};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrateMode {
+    /// Fail if there are pending migrations
+    Check,
+    /// List pending migrations without changing db
+    DryRun,
+    /// Run pending migrations
+    Run,
+}
+
+/// Migrate
+///
+/// Check, list or run pending database migrations. Expensive migrations
+/// aren't run on node start, they must be run by this command.
+pub struct Migrate {
+    config: Config,
+    mode: MigrateMode,
+}
+
+impl Migrate {
+    pub fn new(config: Config, mode: MigrateMode) -> Self {
+        Migrate { config, mode }
+    }
+
+    pub fn execute(self) -> Result<()> {
+        let pending = check_migrations(&self.config.store)?;
+        if pending.is_empty() {
+            println!("database is up to date");
+            return Ok(());
+        }
+
+        for migration in pending.iter() {
+            println!("pending migration {}", describe(migration));
+        }
+
+        match self.mode {
+            MigrateMode::Check => bail!("{} pending migrations", pending.len()),
+            MigrateMode::DryRun => Ok(()),
+            MigrateMode::Run => {
+                let progress = BarProgress::default();
+                run_migrations(&self.config.store, &progress)?;
+                println!("{} migrations done", pending.len());
+                Ok(())
+            }
+        }
+    }
+}
+
+fn describe(migration: &PendingMigration) -> String {
+    let mut desc = format!("{} {}", migration.version, migration.name);
+    if migration.expensive {
+        desc.push_str(" (expensive)");
+    }
+    if migration.interrupted {
+        desc.push_str(" (interrupted, will resume)");
+    }
+    desc
+}
+
+#[derive(Default)]
+struct BarProgress {
+    bar: Mutex<Option<ProgressBar>>,
+}
+
+impl MigrationProgress for BarProgress {
+    fn begin(&self, version: &str, name: &str) {
+        let bar = ProgressBar::new(0);
+        bar.set_style(
+            ProgressStyle::default_bar()
+                .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}")
+                .progress_chars("##-"),
+        );
+        bar.set_message(format!("{} {}", version, name));
+        *self.bar.lock().unwrap() = Some(bar);
+    }
+
+    fn set_length(&self, len: u64) {
+        if let Some(ref bar) = *self.bar.lock().unwrap() {
+            bar.set_length(len);
+        }
+    }
+
+    fn inc(&self, delta: u64) {
+        if let Some(ref bar) = *self.bar.lock().unwrap() {
+            bar.inc(delta);
+        }
+    }
+
+    fn finish(&self) {
+        if let Some(bar) = self.bar.lock().unwrap().take() {
+            bar.finish();
+        }
+    }
+}
@@ -1,3 +1,8 @@
+pub mod backup;
+pub mod check_db;
 pub mod db_block_validator;
 pub mod export_block;
+pub mod export_state;
 pub mod import_block;
+pub mod import_state;
+pub mod migrate;
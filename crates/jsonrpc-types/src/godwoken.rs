@@ -1344,6 +1344,80 @@ pub struct FeeConfig {
     pub withdraw_cycles_limit: Uint64,
 }
 
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct FeeRateEstimation {
+    // fee_rate: fee / cycles limit, it is the gas price for polyjuice txs
+    pub slow: Uint128,
+    pub normal: Uint128,
+    pub fast: Uint128,
+    pub queue_len: Uint32,
+    pub sample_count: Uint32,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PendingRequestKind {
+    Tx,
+    Withdrawal,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PendingRequestStatus {
+    // waiting in the fee queue
+    Queued,
+    // waiting in the fee queue for the nonce gap to be filled
+    Future,
+    // accepted by the mem pool
+    MemPool,
+    // withdrawal waiting in the mem pool for enough balance
+    InsufficientBalance,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub struct PendingRequest {
+    pub hash: H256,
+    pub kind: PendingRequestKind,
+    pub status: PendingRequestStatus,
+    pub account_id: Uint32,
+    pub nonce: Uint32,
+    // fee_rate: fee / cycles limit, only available for requests in the fee queue
+    pub fee_rate: Option<Uint128>,
+    // seconds since the request is added, only available for requests in the fee queue
+    pub age_secs: Option<Uint64>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct PendingRequestPage {
+    pub requests: Vec<PendingRequest>,
+    pub total: Uint32,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct MemBlockStatus {
+    pub txs: Uint32,
+    pub max_txs: Uint32,
+    pub withdrawals: Uint32,
+    pub max_withdrawals: Uint32,
+    pub deposits: Uint32,
+    pub max_deposits: Uint32,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct MemPoolStatus {
+    pub queue_len: Uint32,
+    pub future_queue_len: Uint32,
+    pub pending_accounts: Uint32,
+    pub mem_block: MemBlockStatus,
+    // seconds since the oldest request in the fee queue is added
+    pub oldest_entry_age_secs: Option<Uint64>,
+}
+
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
 #[serde(rename_all = "snake_case")]
 pub struct WithdrawalLockArgs {
@@ -1415,3 +1489,251 @@ impl From<gw_common::registry_address::RegistryAddress> for RegistryAddress {
         }
     }
 }
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct LogCursor {
+    pub block_number: Uint64,
+    pub tx_index: Uint32,
+    pub log_index: Uint32,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct LogFilter {
+    pub from_block: Uint64,
+    // Inclusive
+    pub to_block: Uint64,
+    #[serde(default)]
+    pub account_id: Option<Uint32>,
+    // The first topic of Polyjuice user logs
+    #[serde(default)]
+    pub topic: Option<H256>,
+    // Returns logs start from the cursor
+    #[serde(default)]
+    pub cursor: Option<LogCursor>,
+    #[serde(default)]
+    pub limit: Option<Uint32>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct IndexedLogItem {
+    pub block_number: Uint64,
+    pub tx_hash: H256,
+    pub tx_index: Uint32,
+    pub log_index: Uint32,
+    pub log: LogItem,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct LogPage {
+    pub logs: Vec<IndexedLogItem>,
+    pub next_cursor: Option<LogCursor>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct L2BlockHeader {
+    pub hash: H256,
+    pub raw: RawL2Block,
+}
+
+impl From<packed::L2Block> for L2BlockHeader {
+    fn from(l2_block: packed::L2Block) -> L2BlockHeader {
+        let raw = l2_block.raw();
+        L2BlockHeader {
+            hash: H256::from(raw.hash()),
+            raw: raw.into(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct LogSubscriptionFilter {
+    #[serde(default)]
+    pub account_id: Option<Uint32>,
+    // The first topic of Polyjuice user logs
+    #[serde(default)]
+    pub topic: Option<H256>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct BalanceKey {
+    pub sudt_id: Uint32,
+    pub registry_address: JsonBytes,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct ProofRequest {
+    pub account_id: Uint32,
+    // Raw storage keys of the account
+    #[serde(default)]
+    pub storage_keys: Vec<JsonBytes>,
+    #[serde(default)]
+    pub balances: Vec<BalanceKey>,
+    // Default is the tip block
+    #[serde(default)]
+    pub block_number: Option<Uint64>,
+    // Must be the hash of `block_number` if both are present
+    #[serde(default)]
+    pub block_hash: Option<H256>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct StorageProofItem {
+    pub key: JsonBytes,
+    pub value: H256,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct BalanceProofItem {
+    pub sudt_id: Uint32,
+    pub registry_address: JsonBytes,
+    // Little endian u256
+    pub value: H256,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct AccountProof {
+    pub block_hash: H256,
+    pub block_number: Uint64,
+    // Post account state of the block
+    pub account: AccountMerkleState,
+    pub account_id: Uint32,
+    pub nonce: Uint32,
+    pub script_hash: H256,
+    pub storage: Vec<StorageProofItem>,
+    pub balances: Vec<BalanceProofItem>,
+    // Compiled SMT proof of all the leaves above
+    pub proof: JsonBytes,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct StateKeyValue {
+    pub key: H256,
+    pub value: H256,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct SyscallTrace {
+    pub code: Uint64,
+    // None for unknown syscalls
+    pub name: Option<String>,
+    // Cycles consumed before the syscall
+    pub cycles: Uint64,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct TransactionTrace {
+    pub tx_hash: H256,
+    pub block_hash: H256,
+    pub block_number: Uint64,
+    pub tx_index: Uint32,
+    // i8 -> u32, actual u8
+    pub exit_code: Uint32,
+    pub used_cycles: Uint64,
+    pub return_data: JsonBytes,
+    pub logs: Vec<LogItem>,
+    // Values of the keys read from the state before the tx
+    pub read_values: Vec<StateKeyValue>,
+    pub write_values: Vec<StateKeyValue>,
+    // Set if the tx creates accounts
+    pub account_count: Option<Uint32>,
+    pub read_data_hashes: Vec<H256>,
+    pub write_data_hashes: Vec<H256>,
+    pub new_script_hashes: Vec<H256>,
+    pub syscalls: Vec<SyscallTrace>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct BalanceOverride {
+    pub sudt_id: Uint32,
+    pub balance: gw_types::U256,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct AccountOverride {
+    pub account_id: Uint32,
+    #[serde(default)]
+    pub nonce: Option<Uint32>,
+    // Replace the account script, the registry address is moved to the new script
+    #[serde(default)]
+    pub script: Option<Script>,
+    // sUDT balances of the account's registry address
+    #[serde(default)]
+    pub balances: Vec<BalanceOverride>,
+    // Account storage, same keys as `gw_get_storage_at`
+    #[serde(default)]
+    pub storage: Vec<StateKeyValue>,
+    // Data stored by its blake2b hash, e.g. Polyjuice contract code
+    #[serde(default)]
+    pub data: Vec<JsonBytes>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct CyclesEstimation {
+    pub used_cycles: Uint64,
+    // Minimal gas limit of a Polyjuice tx which still succeeds, None for other txs
+    pub recommended_gas_limit: Option<Uint64>,
+    pub intrinsic_gas: Option<Uint64>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct SyscallProfile {
+    pub code: Uint64,
+    // None for unknown syscalls
+    pub name: Option<String>,
+    pub count: Uint64,
+    // Bytes copied between VM memory and the host
+    pub bytes: Uint64,
+    // Cycles from entering the syscall to returning to the backend
+    pub cycles: Uint64,
+    // Host time spent in the syscall
+    pub elapsed_nanos: Uint64,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct PcSample {
+    pub pc: Uint64,
+    pub count: Uint64,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct ExecutionProfile {
+    // i8 -> u32, actual u8
+    pub exit_code: Uint32,
+    pub used_cycles: Uint64,
+    pub return_data: JsonBytes,
+    pub syscalls: Vec<SyscallProfile>,
+    // PC is sampled once per `sample_interval` cycles
+    pub sample_interval: Uint64,
+    pub pc_samples: Vec<PcSample>,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct BackupManifest {
+    pub rollup_type_hash: H256,
+    pub tip_block_hash: H256,
+    pub tip_block_number: Uint64,
+    pub db_version: String,
+    // Unix timestamp in seconds
+    pub created_at: Uint64,
+}
@@ -0,0 +1,357 @@
+//! Verify Godwoken state proofs without the node.
+//!
+//! A light client trusts a `GlobalState` committed in the rollup cell, then
+//!
+//! 1. verifies a block is included in the block SMT of the global state with
+//!    `verify_block`, the block's `post_account` is then trusted
+//! 2. verifies account SMT leaves against an `AccountMerkleState` with
+//!    `verify_account_state`, e.g. proofs returned by `gw_get_proof`
+//!
+//! A leaf with zero value proves the key is absent.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+use core::fmt;
+
+use gw_common::{
+    h256_ext::H256Ext,
+    registry_address::RegistryAddress,
+    smt::Blake2bHasher,
+    sparse_merkle_tree::CompiledMerkleProof,
+    state::{
+        build_account_field_key, build_account_key, build_registry_address_to_script_hash_key,
+        build_script_hash_to_registry_address_key, build_sudt_key, GW_ACCOUNT_NONCE_TYPE,
+        GW_ACCOUNT_SCRIPT_HASH_TYPE, SUDT_KEY_FLAG_BALANCE, SUDT_TOTAL_SUPPLY_KEY,
+    },
+    H256,
+};
+use gw_types::{
+    packed::{AccountMerkleState, GlobalState, RawL2Block},
+    prelude::*,
+    U256,
+};
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "std")] {
+        use std::{collections::BTreeMap, vec, vec::Vec};
+    } else {
+        use alloc::{collections::BTreeMap, vec, vec::Vec};
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The proof can't be decoded or doesn't match the leaves
+    InvalidProof,
+    /// Leaves don't match the merkle root
+    RootMismatch,
+    /// The block number isn't less than the block count of the global state
+    BlockNotCommitted,
+    /// Same key is added with different values
+    ConflictingLeaves,
+    /// Registry address is too long to be mapped
+    InvalidRegistryAddress,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            Error::InvalidProof => "invalid proof",
+            Error::RootMismatch => "merkle root mismatch",
+            Error::BlockNotCommitted => "block is not committed",
+            Error::ConflictingLeaves => "conflicting leaves",
+            Error::InvalidRegistryAddress => "invalid registry address",
+        };
+        write!(f, "{}", msg)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+/// Verify the block is included in the block SMT of the global state.
+pub fn verify_block(
+    global_state: &GlobalState,
+    raw_block: &RawL2Block,
+    block_proof: &[u8],
+) -> Result<(), Error> {
+    let block_merkle_state = global_state.block();
+    let block_number: u64 = raw_block.number().unpack();
+    let block_count: u64 = block_merkle_state.count().unpack();
+    if block_number >= block_count {
+        return Err(Error::BlockNotCommitted);
+    }
+
+    let leaves = vec![(raw_block.smt_key().into(), raw_block.hash().into())];
+    verify_smt(
+        block_merkle_state.merkle_root().unpack(),
+        leaves,
+        block_proof,
+    )
+}
+
+/// Verify all leaves against the account merkle root with a compiled proof.
+pub fn verify_account_state(
+    account: &AccountMerkleState,
+    leaves: &AccountStateLeaves,
+    proof: &[u8],
+) -> Result<(), Error> {
+    let leaves = leaves.to_vec()?;
+    verify_smt(account.merkle_root().unpack(), leaves, proof)
+}
+
+fn verify_smt(root: H256, leaves: Vec<(H256, H256)>, proof: &[u8]) -> Result<(), Error> {
+    let proof = CompiledMerkleProof(proof.to_vec());
+    match proof.verify::<Blake2bHasher>(&root, leaves) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(Error::RootMismatch),
+        Err(_) => Err(Error::InvalidProof),
+    }
+}
+
+/// Account SMT leaves to verify, keys are built the same way as
+/// `gw_common::state::State`.
+#[derive(Debug, Default, Clone)]
+pub struct AccountStateLeaves {
+    leaves: Vec<(H256, H256)>,
+}
+
+impl AccountStateLeaves {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    pub fn nonce(&mut self, account_id: u32, nonce: u32) -> &mut Self {
+        let key = build_account_field_key(account_id, GW_ACCOUNT_NONCE_TYPE);
+        self.raw(key, H256::from_u32(nonce))
+    }
+
+    pub fn script_hash(&mut self, account_id: u32, script_hash: H256) -> &mut Self {
+        let key = build_account_field_key(account_id, GW_ACCOUNT_SCRIPT_HASH_TYPE);
+        self.raw(key, script_hash)
+    }
+
+    pub fn storage(&mut self, account_id: u32, key: &[u8], value: H256) -> &mut Self {
+        self.raw(build_account_key(account_id, key), value)
+    }
+
+    pub fn sudt_balance(
+        &mut self,
+        sudt_id: u32,
+        address: &RegistryAddress,
+        balance: U256,
+    ) -> &mut Self {
+        let sudt_key = build_sudt_key(SUDT_KEY_FLAG_BALANCE, address);
+        self.storage(sudt_id, &sudt_key, H256::from_u256(balance))
+    }
+
+    pub fn sudt_total_supply(&mut self, sudt_id: u32, total_supply: U256) -> &mut Self {
+        self.storage(
+            sudt_id,
+            &SUDT_TOTAL_SUPPLY_KEY,
+            H256::from_u256(total_supply),
+        )
+    }
+
+    /// Both directions of the mapping between the registry address and the
+    /// script hash, they are stored in the registry account.
+    pub fn registry_address(
+        &mut self,
+        address: &RegistryAddress,
+        script_hash: H256,
+    ) -> Result<&mut Self, Error> {
+        let mut address_buf = [0u8; 32];
+        address
+            .write_to_slice(&mut address_buf)
+            .map_err(|_| Error::InvalidRegistryAddress)?;
+
+        let registry_id = address.registry_id;
+        let to_address_key = build_script_hash_to_registry_address_key(&script_hash);
+        self.storage(registry_id, &to_address_key, address_buf.into());
+        let to_script_hash_key = build_registry_address_to_script_hash_key(address);
+        Ok(self.storage(registry_id, &to_script_hash_key, script_hash))
+    }
+
+    /// A raw SMT leaf
+    pub fn raw(&mut self, key: H256, value: H256) -> &mut Self {
+        self.leaves.push((key, value));
+        self
+    }
+
+    // Duplicated leaves are removed, since they break the proof
+    fn to_vec(&self) -> Result<Vec<(H256, H256)>, Error> {
+        let mut unique = BTreeMap::new();
+        for (key, value) in self.leaves.iter() {
+            if let Some(prev) = unique.insert(*key, *value) {
+                if &prev != value {
+                    return Err(Error::ConflictingLeaves);
+                }
+            }
+        }
+        Ok(unique.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gw_common::{smt::SMT, sparse_merkle_tree::default_store::DefaultStore};
+    use gw_types::packed::BlockMerkleState;
+
+    fn registry_address() -> RegistryAddress {
+        RegistryAddress::new(2, vec![42u8; 20])
+    }
+
+    // A state with two accounts, a sUDT balance and a registry mapping
+    fn build_account_smt() -> (SMT<DefaultStore<H256>>, AccountStateLeaves) {
+        let script_hash = H256::from_u32(100);
+        let mut leaves = AccountStateLeaves::new();
+        leaves
+            .nonce(0, 0)
+            .script_hash(0, H256::from_u32(99))
+            .nonce(3, 7)
+            .script_hash(3, script_hash)
+            .storage(3, &[1u8; 32], H256::from_u32(8))
+            .sudt_balance(1, &registry_address(), U256::from(1000u64))
+            .sudt_total_supply(1, U256::from(1000u64));
+        leaves
+            .registry_address(&registry_address(), script_hash)
+            .unwrap();
+
+        let mut smt = SMT::default();
+        for (key, value) in leaves.to_vec().unwrap() {
+            smt.update(key, value).unwrap();
+        }
+        (smt, leaves)
+    }
+
+    fn prove(smt: &SMT<DefaultStore<H256>>, leaves: &AccountStateLeaves) -> Vec<u8> {
+        let leaves = leaves.to_vec().unwrap();
+        let keys = leaves.iter().map(|(key, _)| *key).collect();
+        let proof = smt.merkle_proof(keys).unwrap().compile(leaves).unwrap();
+        proof.0
+    }
+
+    fn account_merkle_state(smt: &SMT<DefaultStore<H256>>) -> AccountMerkleState {
+        AccountMerkleState::new_builder()
+            .merkle_root(smt.root().pack())
+            .count(4u32.pack())
+            .build()
+    }
+
+    #[test]
+    fn test_verify_account_state() {
+        let (smt, leaves) = build_account_smt();
+        let account = account_merkle_state(&smt);
+        let proof = prove(&smt, &leaves);
+        assert_eq!(verify_account_state(&account, &leaves, &proof), Ok(()));
+
+        // Verify a subset of leaves
+        let mut subset = AccountStateLeaves::new();
+        subset
+            .sudt_balance(1, &registry_address(), U256::from(1000u64))
+            .nonce(3, 7)
+            .nonce(3, 7);
+        let subset_proof = prove(&smt, &subset);
+        assert_eq!(
+            verify_account_state(&account, &subset, &subset_proof),
+            Ok(())
+        );
+
+        // Absent key
+        let mut absent = AccountStateLeaves::new();
+        absent.nonce(4, 0).script_hash(4, H256::zero());
+        let absent_proof = prove(&smt, &absent);
+        assert_eq!(
+            verify_account_state(&account, &absent, &absent_proof),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_verify_account_state_failures() {
+        let (smt, leaves) = build_account_smt();
+        let account = account_merkle_state(&smt);
+
+        let mut subset = AccountStateLeaves::new();
+        subset.sudt_balance(1, &registry_address(), U256::from(1000u64));
+        let proof = prove(&smt, &subset);
+
+        let mut wrong_balance = AccountStateLeaves::new();
+        wrong_balance.sudt_balance(1, &registry_address(), U256::from(1001u64));
+        assert_eq!(
+            verify_account_state(&account, &wrong_balance, &proof),
+            Err(Error::RootMismatch)
+        );
+
+        let mut conflicting = subset.clone();
+        conflicting.sudt_balance(1, &registry_address(), U256::from(1001u64));
+        assert_eq!(
+            verify_account_state(&account, &conflicting, &proof),
+            Err(Error::ConflictingLeaves)
+        );
+
+        assert_eq!(
+            verify_account_state(&account, &leaves, &[1, 2, 3]),
+            Err(Error::InvalidProof)
+        );
+
+        let long_address = RegistryAddress::new(2, vec![0u8; 32]);
+        assert_eq!(
+            AccountStateLeaves::new()
+                .registry_address(&long_address, H256::one())
+                .err(),
+            Some(Error::InvalidRegistryAddress)
+        );
+    }
+
+    #[test]
+    fn test_verify_block() {
+        let blocks: Vec<RawL2Block> = (0..3u64)
+            .map(|number| RawL2Block::new_builder().number(number.pack()).build())
+            .collect();
+        let mut smt: SMT<DefaultStore<H256>> = SMT::default();
+        for block in blocks.iter() {
+            smt.update(block.smt_key().into(), block.hash().into())
+                .unwrap();
+        }
+        let global_state = GlobalState::new_builder()
+            .block(
+                BlockMerkleState::new_builder()
+                    .merkle_root(smt.root().pack())
+                    .count(3u64.pack())
+                    .build(),
+            )
+            .build();
+
+        let block = &blocks[1];
+        let leaves = vec![(block.smt_key().into(), block.hash().into())];
+        let proof = smt
+            .merkle_proof(vec![block.smt_key().into()])
+            .unwrap()
+            .compile(leaves)
+            .unwrap();
+        assert_eq!(verify_block(&global_state, block, &proof.0), Ok(()));
+
+        // Another block with the same number
+        let fake_block = block.clone().as_builder().timestamp(1u64.pack()).build();
+        assert_eq!(
+            verify_block(&global_state, &fake_block, &proof.0),
+            Err(Error::RootMismatch)
+        );
+
+        // Not committed
+        let next_block = RawL2Block::new_builder().number(3u64.pack()).build();
+        assert_eq!(
+            verify_block(&global_state, &next_block, &proof.0),
+            Err(Error::BlockNotCommitted)
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use super::queue::FeeQueue;
+
+/// Percentile of recent fee rates suggested for slow inclusion
+const SLOW_PERCENTILE: usize = 25;
+/// Percentile of recent fee rates suggested for normal inclusion
+const NORMAL_PERCENTILE: usize = 50;
+/// Percentile of recent fee rates suggested for fast inclusion
+const FAST_PERCENTILE: usize = 90;
+
+/// Number of mem blocks to wait for slow inclusion when the queue is congested
+const SLOW_BLOCKS: usize = 4;
+/// Number of mem blocks to wait for normal inclusion when the queue is congested
+const NORMAL_BLOCKS: usize = 2;
+/// Number of mem blocks to wait for fast inclusion when the queue is congested
+const FAST_BLOCKS: usize = 1;
+
+/// Suggested fee rates (fee / cycles limit), for Polyjuice txs the fee rate
+/// is the gas price.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FeeRateEstimation {
+    pub slow: u128,
+    pub normal: u128,
+    pub fast: u128,
+}
+
+/// Estimate fee rates from fee rates of recently committed txs and withdrawals,
+/// raised to outbid queued entries when the fee queue is congested.
+pub struct FeeRateEstimator {
+    // sorted fee rates of recent committed txs and withdrawals
+    sorted_fee_rates: Vec<u128>,
+    // max number of txs in a mem block
+    max_block_txs: usize,
+}
+
+impl FeeRateEstimator {
+    pub fn new(mut fee_rates: Vec<u128>, max_block_txs: usize) -> Self {
+        fee_rates.sort_unstable();
+        FeeRateEstimator {
+            sorted_fee_rates: fee_rates,
+            max_block_txs,
+        }
+    }
+
+    #[inline]
+    pub fn sample_count(&self) -> usize {
+        self.sorted_fee_rates.len()
+    }
+
+    pub fn estimate<T>(&self, queue: &FeeQueue<T>) -> FeeRateEstimation {
+        self.estimate_by(|rank| queue.fee_rate_at(rank))
+    }
+
+    /// Estimate gas prices, only queued Polyjuice txs are outbid since fee
+    /// rates of other entries are not gas prices
+    pub fn estimate_polyjuice<T>(&self, queue: &FeeQueue<T>) -> FeeRateEstimation {
+        self.estimate_by(|rank| queue.fee_rate_at_by(rank, |entry| entry.is_polyjuice_tx()))
+    }
+
+    fn estimate_by<F>(&self, queued_fee_rate_at: F) -> FeeRateEstimation
+    where
+        F: Fn(usize) -> Option<u128>,
+    {
+        let estimate = |percentile: usize, blocks: usize| -> u128 {
+            let recent = self.percentile(percentile);
+            // the fee rate to outbid the queued entries which fill the blocks
+            let rank = self.max_block_txs.saturating_mul(blocks).saturating_sub(1);
+            let congested = queued_fee_rate_at(rank).map_or(0, |rate| rate.saturating_add(1));
+            recent.max(congested)
+        };
+        let slow = estimate(SLOW_PERCENTILE, SLOW_BLOCKS);
+        let normal = estimate(NORMAL_PERCENTILE, NORMAL_BLOCKS).max(slow);
+        let fast = estimate(FAST_PERCENTILE, FAST_BLOCKS).max(normal);
+        FeeRateEstimation { slow, normal, fast }
+    }
+
+    // nearest-rank percentile, returns 0 if there are no samples
+    fn percentile(&self, percentile: usize) -> u128 {
+        let len = self.sorted_fee_rates.len();
+        if len == 0 {
+            return 0;
+        }
+        let rank = (percentile * len + 99) / 100;
+        self.sorted_fee_rates[rank.saturating_sub(1).min(len - 1)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gw_types::{
+        packed::{L2Transaction, RawL2Transaction},
+        prelude::{Builder, Entity, Pack},
+    };
+
+    use crate::fee::{
+        queue::FeeQueue,
+        types::{FeeEntry, FeeItem},
+    };
+
+    use super::{FeeRateEstimation, FeeRateEstimator};
+
+    fn build_entry(sender: u32, fee_rate: u64) -> FeeEntry {
+        FeeEntry {
+            item: FeeItem::Tx(
+                L2Transaction::new_builder()
+                    .raw(RawL2Transaction::new_builder().nonce(0u32.pack()).build())
+                    .build(),
+            ),
+            fee: (fee_rate * 1000).into(),
+            cycles_limit: 1000,
+            sender,
+            order: 0,
+        }
+    }
+
+    #[test]
+    fn test_estimate_by_recent_fee_rates() {
+        let queue: FeeQueue<()> = FeeQueue::new();
+        let estimator = FeeRateEstimator::new((1..=100).rev().collect(), 10);
+        assert_eq!(
+            estimator.estimate(&queue),
+            FeeRateEstimation {
+                slow: 25,
+                normal: 50,
+                fast: 90,
+            }
+        );
+
+        let estimator = FeeRateEstimator::new(Vec::new(), 10);
+        assert_eq!(estimator.estimate(&queue), FeeRateEstimation::default());
+    }
+
+    #[test]
+    fn test_estimate_by_queue_pressure() {
+        let mut queue = FeeQueue::new();
+        // senders 1..=10 pay fee rate 100..=1000
+        for sender in 1..=10 {
+            queue
+                .add(build_entry(sender, sender as u64 * 100), ())
+                .expect("add");
+        }
+        let estimator = FeeRateEstimator::new(vec![1, 2, 3], 2);
+        assert_eq!(
+            estimator.estimate(&queue),
+            FeeRateEstimation {
+                // outbid the 8th highest fee rate
+                slow: 301,
+                // outbid the 4th highest fee rate
+                normal: 701,
+                // outbid the 2nd highest fee rate
+                fast: 901,
+            }
+        );
+    }
+
+    #[test]
+    fn test_estimate_polyjuice_by_queue_pressure() {
+        let mut args = [0u8; 52];
+        args[0..7].copy_from_slice(b"\xFF\xFF\xFFPOLY");
+        let build_polyjuice_entry = |sender: u32, fee_rate: u64| {
+            let raw = RawL2Transaction::new_builder()
+                .nonce(0u32.pack())
+                .args(args.to_vec().pack())
+                .build();
+            FeeEntry {
+                item: FeeItem::Tx(L2Transaction::new_builder().raw(raw).build()),
+                ..build_entry(sender, fee_rate)
+            }
+        };
+
+        let mut queue = FeeQueue::new();
+        // Polyjuice txs pay fee rate 100..=500, other entries pay higher
+        for sender in 1..=5 {
+            queue
+                .add(build_polyjuice_entry(sender, sender as u64 * 100), ())
+                .expect("add");
+            queue
+                .add(build_entry(sender + 10, sender as u64 * 1000), ())
+                .expect("add");
+        }
+        let estimator = FeeRateEstimator::new(vec![1, 2, 3], 1);
+        assert_eq!(
+            estimator.estimate_polyjuice(&queue),
+            FeeRateEstimation {
+                // outbid the 4th highest Polyjuice fee rate
+                slow: 201,
+                // outbid the 2nd highest Polyjuice fee rate
+                normal: 401,
+                // outbid the highest Polyjuice fee rate
+                fast: 501,
+            }
+        );
+        assert_eq!(estimator.estimate(&queue).fast, 5001);
+    }
+}
@@ -0,0 +1,308 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, Instant},
+};
+
+use super::{
+    queue::FeeQueueError,
+    types::{AddedAtIndex, FeeEntry},
+};
+
+/// Max number of entries in the future queue
+const MAX_FUTURE_QUEUE_SIZE: usize = 10_000;
+
+struct FutureEntry<T> {
+    entry: FeeEntry,
+    handle: T,
+    added_at: Instant,
+}
+
+/// Txs & withdrawals whose nonce is higher than the sender's next nonce.
+///
+/// Entries are grouped by sender and sorted by nonce, they are promoted back
+/// to the fee queue once the nonce gap is filled.
+pub struct FutureQueue<T> {
+    accounts: HashMap<u32, BTreeMap<u32, FutureEntry<T>>>,
+    added_at_index: AddedAtIndex,
+    len: usize,
+    max_entries_per_account: usize,
+    expire: Duration,
+}
+
+impl<T> FutureQueue<T> {
+    pub fn new(max_entries_per_account: usize, expire: Duration) -> Self {
+        Self {
+            accounts: HashMap::new(),
+            added_at_index: AddedAtIndex::default(),
+            len: 0,
+            max_entries_per_account,
+            expire,
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the time the oldest entry is added
+    #[inline]
+    pub fn oldest_added_at(&self) -> Option<Instant> {
+        self.added_at_index.oldest()
+    }
+
+    pub fn get(&self, sender: u32, nonce: u32) -> Option<&FeeEntry> {
+        let entries = self.accounts.get(&sender)?;
+        entries.get(&nonce).map(|future| &future.entry)
+    }
+
+    /// Add a future entry with the time it's added to the fee queue, an entry
+    /// which has the same sender and nonce is replaced.
+    pub fn add(
+        &mut self,
+        entry: FeeEntry,
+        handle: T,
+        added_at: Instant,
+    ) -> Result<Option<(FeeEntry, T)>, FeeQueueError> {
+        let sender = entry.sender;
+        let nonce = entry.item.nonce();
+        let entries = self.accounts.entry(sender).or_default();
+        if !entries.contains_key(&nonce)
+            && (entries.len() >= self.max_entries_per_account || self.len >= MAX_FUTURE_QUEUE_SIZE)
+        {
+            if entries.is_empty() {
+                self.accounts.remove(&sender);
+            }
+            return Err(FeeQueueError::FutureQueueFull { sender, nonce });
+        }
+
+        let future = FutureEntry {
+            entry,
+            handle,
+            added_at,
+        };
+        self.added_at_index.insert(added_at);
+        match entries.insert(nonce, future) {
+            Some(replaced) => {
+                self.added_at_index.remove(replaced.added_at);
+                Ok(Some((replaced.entry, replaced.handle)))
+            }
+            None => {
+                self.len += 1;
+                Ok(None)
+            }
+        }
+    }
+
+    pub fn remove(&mut self, sender: u32, nonce: u32) -> Option<(FeeEntry, T)> {
+        let entries = self.accounts.get_mut(&sender)?;
+        let removed = entries.remove(&nonce)?;
+        if entries.is_empty() {
+            self.accounts.remove(&sender);
+        }
+        self.added_at_index.remove(removed.added_at);
+        self.len -= 1;
+        Some((removed.entry, removed.handle))
+    }
+
+    /// Iterate entries and the time they are added, sorted by nonce in each
+    /// sender
+    pub fn iter(&self) -> impl Iterator<Item = (&FeeEntry, Instant)> {
+        (self.accounts.values())
+            .flat_map(|entries| entries.values())
+            .map(|future| (&future.entry, future.added_at))
+    }
+
+    /// Iterate entries of the sender and the time they are added, sorted by
+    /// nonce
+    pub fn sender_iter(&self, sender: u32) -> impl Iterator<Item = (&FeeEntry, Instant)> {
+        (self.accounts.get(&sender).into_iter())
+            .flat_map(|entries| entries.values())
+            .map(|future| (&future.entry, future.added_at))
+    }
+
+    /// Returns senders which have future entries
+    pub fn senders(&self) -> Vec<u32> {
+        self.accounts.keys().copied().collect()
+    }
+
+    /// Remove entries whose nonce is lower than `nonce`, and return entries
+    /// which have continuous nonces starting from `nonce`, with the time they
+    /// are added.
+    pub fn promote(&mut self, sender: u32, nonce: u32) -> Vec<(FeeEntry, T, Instant)> {
+        let entries = match self.accounts.get_mut(&sender) {
+            Some(entries) => entries,
+            None => return Vec::new(),
+        };
+
+        let mut outdated = entries.split_off(&nonce);
+        std::mem::swap(entries, &mut outdated);
+        for (_, future) in outdated {
+            log::debug!(
+                "FutureQueueLen: {} | drop outdated entry: {:?} {} entry_nonce {} nonce {}",
+                self.len,
+                future.entry.item.kind(),
+                hex::encode(future.entry.item.hash().as_slice()),
+                future.entry.item.nonce(),
+                nonce
+            );
+            self.added_at_index.remove(future.added_at);
+            self.len -= 1;
+        }
+
+        let mut promoted = Vec::new();
+        let mut next_nonce = nonce;
+        while let Some(future) = entries.remove(&next_nonce) {
+            self.added_at_index.remove(future.added_at);
+            promoted.push((future.entry, future.handle, future.added_at));
+            next_nonce = next_nonce.saturating_add(1);
+        }
+        self.len -= promoted.len();
+
+        if entries.is_empty() {
+            self.accounts.remove(&sender);
+        }
+        promoted
+    }
+
+    /// Drop entries which stay in queue longer than the expire duration
+    pub fn remove_expired(&mut self) -> usize {
+        let expire = self.expire;
+        let added_at_index = &mut self.added_at_index;
+        let mut removed = 0;
+        self.accounts.retain(|_sender, entries| {
+            entries.retain(|_nonce, future| {
+                let expired = future.added_at.elapsed() > expire;
+                if expired {
+                    log::debug!(
+                        "drop expired future entry: {:?} {} entry_nonce {}",
+                        future.entry.item.kind(),
+                        hex::encode(future.entry.item.hash().as_slice()),
+                        future.entry.item.nonce(),
+                    );
+                    added_at_index.remove(future.added_at);
+                    removed += 1;
+                }
+                !expired
+            });
+            !entries.is_empty()
+        });
+        self.len -= removed;
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use gw_types::{
+        packed::{L2Transaction, RawL2Transaction},
+        prelude::{Builder, Entity, Pack},
+    };
+
+    use crate::fee::{
+        queue::FeeQueueError,
+        types::{FeeEntry, FeeItem},
+    };
+
+    use super::FutureQueue;
+
+    fn build_entry(sender: u32, nonce: u32) -> FeeEntry {
+        FeeEntry {
+            item: FeeItem::Tx(
+                L2Transaction::new_builder()
+                    .raw(RawL2Transaction::new_builder().nonce(nonce.pack()).build())
+                    .build(),
+            ),
+            fee: 1000,
+            cycles_limit: 1000,
+            sender,
+            order: 0,
+        }
+    }
+
+    #[test]
+    fn test_promote_continuous_nonces() {
+        let mut queue = FutureQueue::new(10, Duration::from_secs(60));
+        for nonce in [1u32, 2, 3, 5] {
+            queue
+                .add(build_entry(2, nonce), (), Instant::now())
+                .expect("add");
+        }
+        queue
+            .add(build_entry(3, 1), (), Instant::now())
+            .expect("add");
+        assert_eq!(queue.len(), 5);
+
+        // gap is not filled
+        assert!(queue.promote(2, 0).is_empty());
+        assert_eq!(queue.len(), 5);
+
+        let promoted = queue.promote(2, 1);
+        let nonces: Vec<u32> = promoted.iter().map(|(e, _, _)| e.item.nonce()).collect();
+        assert_eq!(nonces, vec![1, 2, 3]);
+        assert_eq!(queue.len(), 2);
+
+        // drop outdated entries
+        assert!(queue.promote(2, 6).is_empty());
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.senders(), vec![3]);
+    }
+
+    #[test]
+    fn test_max_entries_per_account() {
+        let mut queue = FutureQueue::new(2, Duration::from_secs(60));
+        queue
+            .add(build_entry(2, 1), (), Instant::now())
+            .expect("add");
+        queue
+            .add(build_entry(2, 2), (), Instant::now())
+            .expect("add");
+        let err = queue
+            .add(build_entry(2, 3), (), Instant::now())
+            .unwrap_err();
+        assert_eq!(
+            err,
+            FeeQueueError::FutureQueueFull {
+                sender: 2,
+                nonce: 3
+            }
+        );
+
+        // replace an existing nonce
+        assert!(queue
+            .add(build_entry(2, 2), (), Instant::now())
+            .expect("add")
+            .is_some());
+        // other senders are not affected
+        queue
+            .add(build_entry(3, 1), (), Instant::now())
+            .expect("add");
+        assert_eq!(queue.len(), 3);
+    }
+
+    #[test]
+    fn test_remove_expired() {
+        let mut queue = FutureQueue::new(10, Duration::from_millis(10));
+        queue
+            .add(build_entry(2, 1), (), Instant::now())
+            .expect("add");
+        queue
+            .add(build_entry(3, 1), (), Instant::now())
+            .expect("add");
+        std::thread::sleep(Duration::from_millis(20));
+        queue
+            .add(build_entry(3, 2), (), Instant::now())
+            .expect("add");
+
+        assert_eq!(queue.remove_expired(), 2);
+        assert_eq!(queue.len(), 1);
+        assert!(queue.get(3, 2).is_some());
+    }
+}
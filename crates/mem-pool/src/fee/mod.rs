@@ -1,2 +1,4 @@
+pub mod estimator;
+pub mod future_queue;
 pub mod queue;
 pub mod types;
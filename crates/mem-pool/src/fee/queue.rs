@@ -1,26 +1,85 @@
 use anyhow::Result;
 use gw_common::state::State;
-use std::collections::{BTreeMap, HashMap};
+use gw_config::FeeQueueConfig;
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, Instant},
+};
+use thiserror::Error;
 use tracing::instrument;
 
-/// Max queue size
-const MAX_QUEUE_SIZE: usize = 100_000;
 /// Drop size when queue is full
 const DROP_SIZE: usize = 100;
+/// Interval to check expired entries
+const EXPIRE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+use super::{
+    future_queue::FutureQueue,
+    types::{AddedAtIndex, FeeEntry},
+};
+
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum FeeQueueError {
+    #[error(
+        "Replacement fee rate too low, sender {sender} nonce {nonce} requires at least {min_bump_percentage}% fee rate bump"
+    )]
+    ReplacementUnderpriced {
+        sender: u32,
+        nonce: u32,
+        min_bump_percentage: u64,
+    },
+    #[error("Too many future entries, sender {sender} nonce {nonce}")]
+    FutureQueueFull { sender: u32, nonce: u32 },
+    #[error("Fee queue is full and fee rate is too low, sender {sender} nonce {nonce}")]
+    QueueFull { sender: u32, nonce: u32 },
+}
 
-use super::types::FeeEntry;
+/// Number of evicted entries per reason
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EvictionStats {
+    /// Entries which stay in queue longer than the expire duration
+    pub expired: u64,
+    /// Entries of senders which exceed the per account limit
+    pub sender_limit: u64,
+    /// Entries which have the lowest fee rate
+    pub low_fee_rate: u64,
+}
 
 /// Txs & withdrawals queue sorted by fee rate
 pub struct FeeQueue<T> {
     // priority queue to store tx and withdrawal
-    queue: BTreeMap<FeeEntry, T>,
+    queue: BTreeMap<FeeEntry, (T, Instant)>,
+    // queued entries indexed by the time they are added
+    added_at_index: AddedAtIndex,
+    // queued entries indexed by sender and nonce, used to replace by fee and
+    // evict by sender
+    senders: HashMap<u32, BTreeMap<u32, FeeEntry>>,
+    // entries which nonce is higher than the sender's next nonce
+    future_queue: FutureQueue<T>,
+    config: FeeQueueConfig,
+    eviction_stats: EvictionStats,
+    last_expire_check: Option<Instant>,
 }
 
 impl<T> FeeQueue<T> {
     #[inline]
     pub fn new() -> Self {
+        Self::with_config(FeeQueueConfig::default())
+    }
+
+    pub fn with_config(config: FeeQueueConfig) -> Self {
+        let future_queue = FutureQueue::new(
+            config.max_future_entries_per_account,
+            Duration::from_secs(config.future_entry_expire_secs),
+        );
         Self {
             queue: BTreeMap::new(),
+            added_at_index: AddedAtIndex::default(),
+            senders: HashMap::new(),
+            future_queue,
+            config,
+            eviction_stats: EvictionStats::default(),
+            last_expire_check: None,
         }
     }
 
@@ -34,8 +93,132 @@ impl<T> FeeQueue<T> {
         self.queue.is_empty()
     }
 
+    /// Number of entries waiting for their nonce gap to be filled
+    #[inline]
+    pub fn future_len(&self) -> usize {
+        self.future_queue.len()
+    }
+
+    /// Returns the fee rate of the entry at `rank`, the entry with the highest
+    /// fee rate is at rank 0
+    pub fn fee_rate_at(&self, rank: usize) -> Option<u128> {
+        self.queue.keys().rev().nth(rank).map(FeeEntry::fee_rate)
+    }
+
+    /// Returns the fee rate of the entry at `rank` among entries matching the
+    /// filter
+    pub fn fee_rate_at_by<F>(&self, rank: usize, filter: F) -> Option<u128>
+    where
+        F: FnMut(&&FeeEntry) -> bool,
+    {
+        (self.queue.keys().rev().filter(filter))
+            .nth(rank)
+            .map(FeeEntry::fee_rate)
+    }
+
+    /// Iterate queued entries and the time they are added, sorted by fee rate
+    /// from high to low
+    pub fn iter(&self) -> impl Iterator<Item = (&FeeEntry, Instant)> {
+        (self.queue.iter().rev()).map(|(entry, (_t, added_at))| (entry, *added_at))
+    }
+
+    /// Iterate entries waiting for their nonce gap to be filled and the time
+    /// they are added
+    pub fn future_iter(&self) -> impl Iterator<Item = (&FeeEntry, Instant)> {
+        self.future_queue.iter()
+    }
+
+    /// Returns queued entries and future entries of the sender, each paired
+    /// with the time it is added and whether it is a future entry
+    pub fn sender_entries(&self, sender: u32) -> Vec<(&FeeEntry, Instant, bool)> {
+        let queued = (self.senders.get(&sender).into_iter())
+            .flat_map(|entries| entries.values())
+            .filter_map(|entry| {
+                let (_t, added_at) = self.queue.get(entry)?;
+                Some((entry, *added_at, false))
+            });
+        let future = (self.future_queue.sender_iter(sender))
+            .map(|(entry, added_at)| (entry, added_at, true));
+        let mut entries: Vec<_> = queued.chain(future).collect();
+        entries.sort_by_key(|(entry, _, _)| entry.item.nonce());
+        entries
+    }
+
+    /// Returns the time the oldest queued or future entry is added
+    pub fn oldest_added_at(&self) -> Option<Instant> {
+        let oldest = self.added_at_index.oldest();
+        let future_oldest = self.future_queue.oldest_added_at();
+        match (oldest, future_oldest) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    #[inline]
+    pub fn eviction_stats(&self) -> &EvictionStats {
+        &self.eviction_stats
+    }
+
+    /// Check whether the entry can be added to the queue.
+    ///
+    /// Returns the queued entry which has the same sender and nonce, it will
+    /// be replaced by the new entry.
+    pub fn check_replace(&self, entry: &FeeEntry) -> Result<Option<&FeeEntry>, FeeQueueError> {
+        let (sender, nonce) = match sender_nonce_key(entry) {
+            Some(key) => key,
+            None => return Ok(None),
+        };
+        let queued = match (self.senders.get(&sender))
+            .and_then(|entries| entries.get(&nonce))
+            .or_else(|| self.future_queue.get(sender, nonce))
+        {
+            Some(queued) => queued,
+            None => return Ok(None),
+        };
+        let min_bump_percentage = self.config.replace_by_fee_min_bump_percentage;
+        if !entry.is_fee_rate_bumped(queued, min_bump_percentage) {
+            return Err(FeeQueueError::ReplacementUnderpriced {
+                sender: entry.sender,
+                nonce: entry.item.nonce(),
+                min_bump_percentage,
+            });
+        }
+        Ok(Some(queued))
+    }
+
+    /// Add an entry to the queue.
+    ///
+    /// A queued entry which has the same sender and nonce is replaced and
+    /// returned if the new entry has a sufficiently higher fee rate.
+    ///
+    /// The queue may exceed the max size, entries are evicted with the state
+    /// before fetching. If the queue grows `DROP_SIZE` entries over the max
+    /// size between fetches, entries are evicted on adding, without checking
+    /// whether they are executable, `FeeQueueError::QueueFull` is returned if
+    /// the new entry is evicted.
     #[instrument(skip_all, fields(count = self.len()))]
-    pub fn add(&mut self, entry: FeeEntry, handle: T) {
+    pub fn add(
+        &mut self,
+        entry: FeeEntry,
+        handle: T,
+    ) -> Result<Option<(FeeEntry, T)>, FeeQueueError> {
+        let replaced = match self.check_replace(&entry)?.cloned() {
+            Some(queued) => {
+                log::debug!(
+                    "QueueLen: {} | replace entry: {:?} {} by {:?} {}",
+                    self.len(),
+                    queued.item.kind(),
+                    hex::encode(queued.item.hash().as_slice()),
+                    entry.item.kind(),
+                    hex::encode(entry.item.hash().as_slice())
+                );
+                self.remove(&queued)
+                    .map(|(entry, t, _added_at)| (entry, t))
+                    .or_else(|| self.future_queue.remove(queued.sender, queued.item.nonce()))
+            }
+            None => None,
+        };
+
         // push to queue
         log::debug!(
             "QueueLen: {} | add entry: {:?} {}",
@@ -43,34 +226,219 @@ impl<T> FeeQueue<T> {
             entry.item.kind(),
             hex::encode(entry.item.hash().as_slice())
         );
-        self.queue.insert(entry, handle);
+        let key = entry.clone();
+        self.insert(entry, handle, Instant::now());
+        if self.is_over_hard_limit() {
+            self.evict_without_state();
+            // the new entry may be evicted since its fee rate is too low
+            if !self.queue.contains_key(&key) {
+                return Err(FeeQueueError::QueueFull {
+                    sender: key.sender,
+                    nonce: key.item.nonce(),
+                });
+            }
+        }
+
+        Ok(replaced)
+    }
+
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.queue.len() > self.config.max_queue_size
+    }
+
+    /// Evict expired entries, and evict entries when the queue is full.
+    ///
+    /// When the queue is full, entries of senders which exceed the per account
+    /// limit are evicted first, highest nonce first. Then entries with the
+    /// lowest fee rate are evicted, except the only entry of a sender which
+    /// is executable.
+    pub fn evict(&mut self, state: &impl State) -> Result<()> {
+        self.remove_expired();
+        if !self.is_full() {
+            return Ok(());
+        }
+
+        let queue_len = self.len();
+        let stats = self.eviction_stats;
+        self.evict_by_sender_limit();
+        self.evict_by_fee_rate(|queue, entry| queue.is_only_executable_entry(entry, state))?;
+        self.log_eviction(queue_len, stats);
+
+        Ok(())
+    }
+
+    #[inline]
+    fn is_over_hard_limit(&self) -> bool {
+        self.queue.len() >= self.config.max_queue_size.saturating_add(DROP_SIZE)
+    }
+
+    // Evict entries without the state, the only executable entry of a sender
+    // isn't protected
+    fn evict_without_state(&mut self) {
+        self.remove_expired();
+        let queue_len = self.len();
+        let stats = self.eviction_stats;
+        self.evict_by_sender_limit();
+        self.evict_by_fee_rate(|_queue, _entry| Ok(false))
+            .expect("evict without state");
+        self.log_eviction(queue_len, stats);
+    }
+
+    // Evict entries of senders which exceed the per account limit, highest
+    // nonce first
+    fn evict_by_sender_limit(&mut self) {
+        let max_entries = self.config.max_entries_per_account;
+        let exceeded: Vec<FeeEntry> = (self.senders.values())
+            .filter(|entries| entries.len() > max_entries)
+            .flat_map(|entries| {
+                let count = entries.len() - max_entries;
+                entries.values().rev().take(count).cloned()
+            })
+            .collect();
+        for entry in exceeded {
+            self.remove(&entry);
+            self.eviction_stats.sender_limit += 1;
+        }
+    }
+
+    // Evict entries with the lowest fee rate until the queue drops `DROP_SIZE`
+    // entries below the max size, protected entries are added back
+    fn evict_by_fee_rate<F>(&mut self, mut is_protected: F) -> Result<()>
+    where
+        F: FnMut(&Self, &FeeEntry) -> Result<bool>,
+    {
+        let target_len = self.config.max_queue_size.saturating_sub(DROP_SIZE);
+        let mut protected = Vec::new();
+        while self.len() > target_len {
+            let entry = match self.queue.keys().next() {
+                Some(entry) => entry.clone(),
+                None => break,
+            };
+            let keep = is_protected(self, &entry)?;
+            let removed = self.remove(&entry).expect("entry exists");
+            if keep {
+                protected.push(removed);
+            } else {
+                log::debug!(
+                    "QueueLen: {} | evict entry: {:?} {}",
+                    self.len(),
+                    entry.item.kind(),
+                    hex::encode(entry.item.hash().as_slice())
+                );
+                self.eviction_stats.low_fee_rate += 1;
+            }
+        }
+        for (entry, t, added_at) in protected {
+            self.insert(entry, t, added_at);
+        }
+        Ok(())
+    }
+
+    fn log_eviction(&self, queue_len: usize, stats: EvictionStats) {
+        log::info!(
+            "QueueLen: {} | Fee queue is full, evict {} items, sender limit {} low fee rate {}",
+            self.len(),
+            queue_len.saturating_sub(self.len()),
+            self.eviction_stats.sender_limit - stats.sender_limit,
+            self.eviction_stats.low_fee_rate - stats.low_fee_rate,
+        );
+    }
+
+    // Returns true if the entry is the only queued entry of its sender and
+    // its nonce is the sender's next nonce
+    fn is_only_executable_entry(&self, entry: &FeeEntry, state: &impl State) -> Result<bool> {
+        if 0 == entry.sender {
+            return Ok(false);
+        }
+        let is_only = (self.senders.get(&entry.sender)).map_or(false, |entries| entries.len() == 1);
+        if !is_only {
+            return Ok(false);
+        }
+        Ok(entry.item.nonce() == state.get_nonce(entry.sender)?)
+    }
 
-        // drop items if full
-        if self.is_full() {
-            if let Some(first_to_keep) = self.queue.keys().nth(DROP_SIZE + 1).cloned() {
-                self.queue = self.queue.split_off(&first_to_keep);
+    fn remove_expired(&mut self) {
+        if let Some(last_check) = self.last_expire_check {
+            if last_check.elapsed() < EXPIRE_CHECK_INTERVAL {
+                return;
             }
+        }
+        self.last_expire_check = Some(Instant::now());
+
+        let expire = Duration::from_secs(self.config.entry_expire_secs);
+        let expired: Vec<FeeEntry> = (self.queue.iter())
+            .filter(|(_entry, (_t, added_at))| added_at.elapsed() > expire)
+            .map(|(entry, _)| entry.clone())
+            .collect();
+        for entry in expired.iter() {
+            self.remove(entry);
+        }
+        let future_expired = self.future_queue.remove_expired();
+
+        let count = expired.len() + future_expired;
+        if count > 0 {
+            self.eviction_stats.expired += count as u64;
             log::debug!(
-                "QueueLen: {} | Fee queue is full, drop {} items",
+                "QueueLen: {} FutureQueueLen: {} | evict {} expired entries",
                 self.len(),
-                DROP_SIZE,
+                self.future_queue.len(),
+                count
             );
         }
     }
 
-    #[inline]
-    pub fn is_full(&self) -> bool {
-        self.queue.len() > MAX_QUEUE_SIZE
+    fn insert(&mut self, entry: FeeEntry, handle: T, added_at: Instant) {
+        if let Some((sender, nonce)) = sender_nonce_key(&entry) {
+            let entries = self.senders.entry(sender).or_default();
+            entries.insert(nonce, entry.clone());
+        }
+        self.added_at_index.insert(added_at);
+        if let Some((_t, replaced_added_at)) = self.queue.insert(entry, (handle, added_at)) {
+            self.added_at_index.remove(replaced_added_at);
+        }
+    }
+
+    fn remove(&mut self, entry: &FeeEntry) -> Option<(FeeEntry, T, Instant)> {
+        self.remove_index(entry);
+        let (entry, (t, added_at)) = self.queue.remove_entry(entry)?;
+        self.added_at_index.remove(added_at);
+        Some((entry, t, added_at))
     }
 
-    fn pop_last(&mut self) -> Option<(FeeEntry, T)> {
+    fn remove_index(&mut self, entry: &FeeEntry) {
+        if let Some((sender, nonce)) = sender_nonce_key(entry) {
+            if let Some(entries) = self.senders.get_mut(&sender) {
+                // only remove the index if it points to this entry
+                if entries.get(&nonce) == Some(entry) {
+                    entries.remove(&nonce);
+                }
+                if entries.is_empty() {
+                    self.senders.remove(&sender);
+                }
+            }
+        }
+    }
+
+    fn pop_last(&mut self) -> Option<(FeeEntry, T, Instant)> {
         if let Some(entry) = self.queue.keys().next_back().cloned() {
-            self.queue.remove_entry(&entry)
+            self.remove(&entry)
         } else {
             None
         }
     }
 
+    /// Promote entries whose nonce gap is filled by the state
+    fn promote_future_entries(&mut self, state: &impl State) -> Result<()> {
+        for sender in self.future_queue.senders() {
+            let nonce = state.get_nonce(sender)?;
+            for (entry, t, added_at) in self.future_queue.promote(sender, nonce) {
+                self.insert(entry, t, added_at);
+            }
+        }
+        Ok(())
+    }
+
     /// Fetch items by fee sort
     #[instrument(skip_all, fields(count = count))]
     pub fn fetch(&mut self, state: &impl State, count: usize) -> Result<Vec<(FeeEntry, T)>> {
@@ -80,8 +448,11 @@ impl<T> FeeQueue<T> {
         // future items, we will push back this queue
         let mut future_queue = Vec::default();
 
+        self.evict(state)?;
+        self.promote_future_entries(state)?;
+
         // Fetch item from PQ
-        while let Some((entry, t)) = self.pop_last() {
+        while let Some((entry, t, added_at)) = self.pop_last() {
             let nonce = match fetched_senders.get(&entry.sender) {
                 Some(&nonce) => nonce,
                 None => state.get_nonce(entry.sender)?,
@@ -89,13 +460,21 @@ impl<T> FeeQueue<T> {
             match entry.item.nonce().cmp(&nonce) {
                 std::cmp::Ordering::Equal => {
                     // update nonce
-                    fetched_senders.insert(entry.sender, nonce.saturating_add(1));
+                    let next_nonce = nonce.saturating_add(1);
+                    fetched_senders.insert(entry.sender, next_nonce);
+                    // promote future items since the nonce gap is filled
+                    if 0 != entry.sender {
+                        let promoted = self.future_queue.promote(entry.sender, next_nonce);
+                        for (promoted_entry, promoted_t, promoted_added_at) in promoted {
+                            self.insert(promoted_entry, promoted_t, promoted_added_at);
+                        }
+                    }
                     // fetch this item
                     fetched_items.push((entry, t));
                 }
                 std::cmp::Ordering::Greater => {
                     // push item back if it still has change to get fetched
-                    future_queue.push((entry, t));
+                    future_queue.push((entry, t, added_at));
                 }
                 _ => {
                     log::debug!(
@@ -115,17 +494,36 @@ impl<T> FeeQueue<T> {
         }
 
         // Add back future items
-        for (entry, t) in future_queue {
-            // Only add back if we fetched another item from the same sender
+        for (entry, t, added_at) in future_queue {
+            // Add back if we fetched another item from the same sender, it
+            // still has chance to get fetched in the next round
             if fetched_senders.contains_key(&entry.sender) {
-                self.add(entry, t);
-            } else {
+                self.insert(entry, t, added_at);
+                continue;
+            }
+            // Txs from id zero are dropped since their sender is unknown
+            if 0 == entry.sender {
                 log::debug!(
-                    "QueueLen: {} | drop future entry: {:?} {} entry_nonce {}",
+                    "QueueLen: {} | drop future entry from id zero: {:?} {} entry_nonce {}",
                     self.len(),
                     entry.item.kind(),
                     hex::encode(entry.item.hash().as_slice()),
-                    entry.item.nonce(),
+                    entry.item.nonce()
+                );
+                continue;
+            }
+            // Otherwise wait in the future queue until the nonce gap is filled
+            let hash = entry.item.hash();
+            let kind = entry.item.kind();
+            let nonce = entry.item.nonce();
+            if let Err(err) = self.future_queue.add(entry, t, added_at) {
+                log::debug!(
+                    "QueueLen: {} | drop future entry: {:?} {} entry_nonce {} err {}",
+                    self.len(),
+                    kind,
+                    hex::encode(hash.as_slice()),
+                    nonce,
+                    err
                 );
             }
         }
@@ -148,10 +546,19 @@ impl<T> Default for FeeQueue<T> {
     }
 }
 
+// Txs from id zero have not been assigned their sender yet, they are not
+// replaceable
+fn sender_nonce_key(entry: &FeeEntry) -> Option<(u32, u32)> {
+    if 0 == entry.sender {
+        return None;
+    }
+    Some((entry.sender, entry.item.nonce()))
+}
+
 #[cfg(test)]
 mod tests {
     use gw_common::{h256_ext::H256Ext, state::State, H256};
-    use gw_config::GenesisConfig;
+    use gw_config::{FeeQueueConfig, GenesisConfig};
     use gw_generator::genesis::init_genesis;
     use gw_store::{
         mem_pool_state::MemStore, state::state_db::StateContext, traits::chain_store::ChainStore,
@@ -166,7 +573,7 @@ mod tests {
     };
 
     use crate::fee::{
-        queue::MAX_QUEUE_SIZE,
+        queue::FeeQueueError,
         types::{FeeEntry, FeeItem},
     };
 
@@ -225,10 +632,10 @@ mod tests {
             order: queue.len(),
         };
 
-        queue.add(entry1, ());
-        queue.add(entry2, ());
-        queue.add(entry3, ());
-        queue.add(entry4, ());
+        queue.add(entry1, ()).expect("add");
+        queue.add(entry2, ()).expect("add");
+        queue.add(entry3, ()).expect("add");
+        queue.add(entry4, ()).expect("add");
 
         let mem_store = MemStore::new(snap);
         let tree = mem_store.state().unwrap();
@@ -283,7 +690,7 @@ mod tests {
             order: queue.len(),
         };
 
-        queue.add(entry1, ());
+        queue.add(entry1, ()).expect("add");
 
         let entry2 = FeeEntry {
             item: FeeItem::Tx(Default::default()),
@@ -293,7 +700,7 @@ mod tests {
             order: queue.len(),
         };
 
-        queue.add(entry2, ());
+        queue.add(entry2, ()).expect("add");
 
         let entry3 = FeeEntry {
             item: FeeItem::Tx(Default::default()),
@@ -303,7 +710,7 @@ mod tests {
             order: queue.len(),
         };
 
-        queue.add(entry3, ());
+        queue.add(entry3, ()).expect("add");
 
         let entry4 = FeeEntry {
             item: FeeItem::Withdrawal(Default::default()),
@@ -313,7 +720,7 @@ mod tests {
             order: queue.len(),
         };
 
-        queue.add(entry4, ());
+        queue.add(entry4, ()).expect("add");
 
         let mem_store = MemStore::new(snap);
         let tree = mem_store.state().unwrap();
@@ -373,8 +780,8 @@ mod tests {
             order: queue.len(),
         };
 
-        queue.add(entry1, ());
-        queue.add(entry2, ());
+        queue.add(entry1, ()).expect("add");
+        queue.add(entry2, ()).expect("add");
 
         let snap = store.get_snapshot();
         let mem_store = MemStore::new(snap);
@@ -388,6 +795,78 @@ mod tests {
             assert_eq!(items[1].0.item.nonce(), 1);
         }
     }
+    #[test]
+    fn test_promote_future_entries() {
+        let mut queue = FeeQueue::new();
+
+        let store = Store::open_tmp().expect("open store");
+        setup_genesis(&store);
+        {
+            let db = store.begin_transaction();
+            let mut state = db.state_tree(StateContext::AttachBlock(1)).expect("state");
+
+            // create accounts
+            for i in 0..4 {
+                state.create_account(H256::from_u32(i)).unwrap();
+            }
+
+            db.commit().expect("commit");
+        }
+
+        let build_entry = |nonce: u32| FeeEntry {
+            item: FeeItem::Tx(
+                L2Transaction::new_builder()
+                    .raw(RawL2Transaction::new_builder().nonce(nonce.pack()).build())
+                    .build(),
+            ),
+            fee: (100 * 1000u64).into(),
+            cycles_limit: 1000,
+            sender: 2,
+            order: 0,
+        };
+
+        queue.add(build_entry(1), ()).expect("add");
+        queue.add(build_entry(2), ()).expect("add");
+        let oldest = queue.oldest_added_at();
+
+        let snap = store.get_snapshot();
+        let mem_store = MemStore::new(snap);
+        let tree = mem_store.state().unwrap();
+
+        // nonce 0 is missing, entries wait in the future queue
+        {
+            let items = queue.fetch(&tree, 3).expect("fetch");
+            assert!(items.is_empty());
+            assert_eq!(queue.len(), 0);
+            assert_eq!(queue.future_len(), 2);
+            // entries keep the time they are added
+            assert_eq!(queue.oldest_added_at(), oldest);
+        }
+
+        // queued future entry is replaceable
+        let underpriced = queue.add(build_entry(1), ()).unwrap_err();
+        assert_eq!(
+            underpriced,
+            FeeQueueError::ReplacementUnderpriced {
+                sender: 2,
+                nonce: 1,
+                min_bump_percentage: 10,
+            }
+        );
+
+        // fill the nonce gap
+        queue.add(build_entry(0), ()).expect("add");
+        {
+            let items = queue.fetch(&tree, 1).expect("fetch");
+            let nonces: Vec<u32> = items.iter().map(|(e, _)| e.item.nonce()).collect();
+            assert_eq!(nonces, vec![0]);
+            assert_eq!(queue.len(), 2);
+            assert_eq!(queue.future_len(), 0);
+            // promoted entries keep the time they are added
+            assert_eq!(queue.oldest_added_at(), oldest);
+        }
+    }
+
     #[test]
     fn test_replace_by_fee() {
         let mut queue = FeeQueue::new();
@@ -426,14 +905,19 @@ mod tests {
                     .raw(RawL2Transaction::new_builder().nonce(0u32.pack()).build())
                     .build(),
             ),
-            fee: (101 * 1000u64).into(),
+            fee: (111 * 1000u64).into(),
             cycles_limit: 1000,
             sender: 2,
             order: queue.len(),
         };
 
-        queue.add(entry1, ());
-        queue.add(entry2, ());
+        queue.add(entry1, ()).expect("add");
+        let replaced = queue.add(entry2, ()).expect("add");
+        assert_eq!(
+            replaced.map(|(entry, _)| entry.fee),
+            Some((100 * 1000u64).into())
+        );
+        assert_eq!(queue.len(), 1);
 
         let snap = store.get_snapshot();
         let mem_store = MemStore::new(snap);
@@ -443,16 +927,72 @@ mod tests {
         {
             let items = queue.fetch(&tree, 3).expect("fetch");
             assert_eq!(items.len(), 1);
-            assert_eq!(items[0].0.fee, (101 * 1000u64).into());
+            assert_eq!(items[0].0.fee, (111 * 1000u64).into());
             // try fetch remain items
             let items = queue.fetch(&tree, 1).expect("fetch");
             assert_eq!(items.len(), 0);
         }
     }
 
+    #[test]
+    fn test_replace_by_fee_underpriced() {
+        let mut queue = FeeQueue::with_config(FeeQueueConfig {
+            replace_by_fee_min_bump_percentage: 10,
+            ..Default::default()
+        });
+
+        let build_entry = |fee: u128, order: usize| FeeEntry {
+            item: FeeItem::Tx(
+                L2Transaction::new_builder()
+                    .raw(RawL2Transaction::new_builder().nonce(0u32.pack()).build())
+                    .build(),
+            ),
+            fee,
+            cycles_limit: 1000,
+            sender: 2,
+            order,
+        };
+
+        queue
+            .add(build_entry(100 * 1000, queue.len()), ())
+            .expect("add");
+
+        // bump less than 10%
+        let err = queue
+            .add(build_entry(105 * 1000, queue.len()), ())
+            .unwrap_err();
+        assert_eq!(
+            err,
+            FeeQueueError::ReplacementUnderpriced {
+                sender: 2,
+                nonce: 0,
+                min_bump_percentage: 10
+            }
+        );
+        assert_eq!(queue.len(), 1);
+
+        // bump exactly 10%
+        let replaced = queue
+            .add(build_entry(110 * 1000, queue.len()), ())
+            .expect("add");
+        assert!(replaced.is_some());
+        assert_eq!(queue.len(), 1);
+
+        // other nonce or sender is not affected
+        let mut entry = build_entry(1000, queue.len());
+        entry.sender = 3;
+        assert!(queue.add(entry, ()).expect("add").is_none());
+        assert_eq!(queue.len(), 2);
+    }
+
     #[test]
     fn test_drop_items() {
-        let mut queue = FeeQueue::new();
+        let max_queue_size = 1000;
+        let mut queue = FeeQueue::with_config(FeeQueueConfig {
+            max_queue_size,
+            max_entries_per_account: max_queue_size * 2,
+            ..Default::default()
+        });
 
         let store = Store::open_tmp().expect("open store");
         setup_genesis(&store);
@@ -470,7 +1010,7 @@ mod tests {
             db.commit().expect("commit");
         }
 
-        for i in 0..(MAX_QUEUE_SIZE as u32) {
+        for i in 0..(max_queue_size as u32) {
             let entry1 = FeeEntry {
                 item: FeeItem::Tx(
                     L2Transaction::new_builder()
@@ -482,10 +1022,10 @@ mod tests {
                 sender: 2,
                 order: queue.len(),
             };
-            queue.add(entry1, ());
+            queue.add(entry1, ()).expect("add");
         }
 
-        assert_eq!(queue.len(), MAX_QUEUE_SIZE);
+        assert_eq!(queue.len(), max_queue_size);
 
         // add 1 more item
         {
@@ -494,7 +1034,7 @@ mod tests {
                     L2Transaction::new_builder()
                         .raw(
                             RawL2Transaction::new_builder()
-                                .nonce(10001u32.pack())
+                                .nonce((max_queue_size as u32).pack())
                                 .build(),
                         )
                         .build(),
@@ -504,11 +1044,283 @@ mod tests {
                 sender: 2,
                 order: queue.len(),
             };
-            queue.add(entry1, ());
+            queue.add(entry1, ()).expect("add");
         }
+        assert!(queue.is_full());
+
+        let snap = store.get_snapshot();
+        let mem_store = MemStore::new(snap);
+        let tree = mem_store.state().unwrap();
 
         // we should trigger the drop
-        assert!(queue.len() < MAX_QUEUE_SIZE);
+        queue.evict(&tree).expect("evict");
+        assert!(queue.len() < max_queue_size);
+        assert_eq!(
+            queue.eviction_stats().low_fee_rate,
+            (max_queue_size + 1 - queue.len()) as u64
+        );
+    }
+
+    #[test]
+    fn test_oldest_added_at() {
+        let mut queue = FeeQueue::new();
+
+        let build_entry = |sender: u32, fee: u64| FeeEntry {
+            item: FeeItem::Tx(
+                L2Transaction::new_builder()
+                    .raw(RawL2Transaction::new_builder().nonce(0u32.pack()).build())
+                    .build(),
+            ),
+            fee: (fee * 1000u64).into(),
+            cycles_limit: 1000,
+            sender,
+            order: 0,
+        };
+
+        assert!(queue.oldest_added_at().is_none());
+        queue.add(build_entry(2, 100), ()).expect("add");
+        let first = queue.oldest_added_at().expect("oldest");
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        queue.add(build_entry(3, 100), ()).expect("add");
+        assert_eq!(queue.oldest_added_at(), Some(first));
+
+        // replace the oldest entry
+        queue.add(build_entry(2, 200), ()).expect("add");
+        let oldest = queue.iter().map(|(_entry, added_at)| added_at).min();
+        assert!(queue.oldest_added_at() > Some(first));
+        assert_eq!(queue.oldest_added_at(), oldest);
+    }
+
+    #[test]
+    fn test_evict_on_add_over_hard_limit() {
+        let max_queue_size = 200;
+        let mut queue = FeeQueue::with_config(FeeQueueConfig {
+            max_queue_size,
+            ..Default::default()
+        });
+
+        let build_entry = |sender: u32, fee: u64| FeeEntry {
+            item: FeeItem::Tx(
+                L2Transaction::new_builder()
+                    .raw(RawL2Transaction::new_builder().nonce(0u32.pack()).build())
+                    .build(),
+            ),
+            fee: (fee * 1000u64).into(),
+            cycles_limit: 1000,
+            sender,
+            order: 0,
+        };
+
+        // the queue is bounded without fetching
+        for sender in 1..1000 {
+            queue
+                .add(build_entry(sender, sender as u64), ())
+                .expect("add");
+            assert!(queue.len() < max_queue_size + super::DROP_SIZE);
+        }
+        assert!(queue.eviction_stats().low_fee_rate > 0);
+
+        // entries with the highest fee rate are kept
+        let lowest_fee_rate = queue.iter().last().map(|(entry, _)| entry.fee_rate());
+        assert!(lowest_fee_rate > Some(build_entry(800, 800).fee_rate()));
+    }
+
+    #[test]
+    fn test_reject_underpriced_entry_over_hard_limit() {
+        let max_queue_size = 200;
+        let mut queue = FeeQueue::with_config(FeeQueueConfig {
+            max_queue_size,
+            ..Default::default()
+        });
+
+        let build_entry = |sender: u32, fee: u64| FeeEntry {
+            item: FeeItem::Tx(
+                L2Transaction::new_builder()
+                    .raw(RawL2Transaction::new_builder().nonce(0u32.pack()).build())
+                    .build(),
+            ),
+            fee: (fee * 1000u64).into(),
+            cycles_limit: 1000,
+            sender,
+            order: 0,
+        };
+
+        // fill the queue right below the hard limit
+        let hard_limit = max_queue_size + super::DROP_SIZE;
+        for sender in 1..hard_limit as u32 {
+            queue
+                .add(build_entry(sender, 1000 + sender as u64), ())
+                .expect("add");
+        }
+        assert_eq!(queue.len(), hard_limit - 1);
+
+        // the underpriced entry is evicted right after it's added
+        let err = queue.add(build_entry(5000, 1), ()).unwrap_err();
+        assert_eq!(
+            err,
+            FeeQueueError::QueueFull {
+                sender: 5000,
+                nonce: 0
+            }
+        );
+        assert!(queue.len() < max_queue_size);
+        assert!(queue.sender_entries(5000).is_empty());
+    }
+
+    #[test]
+    fn test_evict_by_sender_limit() {
+        let mut queue = FeeQueue::with_config(FeeQueueConfig {
+            max_queue_size: 200,
+            max_entries_per_account: 10,
+            ..Default::default()
+        });
+
+        let store = Store::open_tmp().expect("open store");
+        setup_genesis(&store);
+        {
+            let db = store.begin_transaction();
+            let mut state = db.state_tree(StateContext::AttachBlock(1)).expect("state");
+
+            // create accounts
+            for i in 0..4 {
+                state.create_account(H256::from_u32(i)).unwrap();
+            }
+
+            db.commit().expect("commit");
+        }
+
+        let build_entry = |sender: u32, nonce: u32, fee: u64| FeeEntry {
+            item: FeeItem::Tx(
+                L2Transaction::new_builder()
+                    .raw(RawL2Transaction::new_builder().nonce(nonce.pack()).build())
+                    .build(),
+            ),
+            fee: (fee * 1000u64).into(),
+            cycles_limit: 1000,
+            sender,
+            order: 0,
+        };
+
+        // a spammer with higher fee rate fills the queue
+        for nonce in 0..200 {
+            queue.add(build_entry(2, nonce, 101), ()).expect("add");
+        }
+        queue.add(build_entry(3, 0, 100), ()).expect("add");
+        assert!(queue.is_full());
+
+        let snap = store.get_snapshot();
+        let mem_store = MemStore::new(snap);
+        let tree = mem_store.state().unwrap();
+
+        queue.evict(&tree).expect("evict");
+        assert_eq!(queue.len(), 11);
+        assert_eq!(queue.eviction_stats().sender_limit, 190);
+        assert_eq!(queue.eviction_stats().low_fee_rate, 0);
+
+        // entries with the lowest nonces are kept
+        let items = queue.fetch(&tree, 20).expect("fetch");
+        let mut nonces: Vec<(u32, u32)> = items
+            .iter()
+            .map(|(e, _)| (e.sender, e.item.nonce()))
+            .collect();
+        nonces.sort_unstable();
+        let mut expected: Vec<(u32, u32)> = (0..10).map(|nonce| (2, nonce)).collect();
+        expected.push((3, 0));
+        assert_eq!(nonces, expected);
+    }
+
+    #[test]
+    fn test_evict_keep_only_executable_entry() {
+        let mut queue = FeeQueue::with_config(FeeQueueConfig {
+            max_queue_size: 200,
+            ..Default::default()
+        });
+
+        let store = Store::open_tmp().expect("open store");
+        setup_genesis(&store);
+        {
+            let db = store.begin_transaction();
+            let mut state = db.state_tree(StateContext::AttachBlock(1)).expect("state");
+
+            // create accounts
+            for i in 0..5 {
+                state.create_account(H256::from_u32(i)).unwrap();
+            }
+
+            db.commit().expect("commit");
+        }
+
+        let build_entry = |sender: u32, nonce: u32, fee: u64| FeeEntry {
+            item: FeeItem::Tx(
+                L2Transaction::new_builder()
+                    .raw(RawL2Transaction::new_builder().nonce(nonce.pack()).build())
+                    .build(),
+            ),
+            fee: (fee * 1000u64).into(),
+            cycles_limit: 1000,
+            sender,
+            order: 0,
+        };
+
+        for nonce in 0..200 {
+            queue.add(build_entry(2, nonce, 101), ()).expect("add");
+        }
+        // the only entry of sender 3 is executable
+        queue.add(build_entry(3, 0, 100), ()).expect("add");
+        // the only entry of sender 4 is not executable
+        queue.add(build_entry(4, 1, 100), ()).expect("add");
+
+        let snap = store.get_snapshot();
+        let mem_store = MemStore::new(snap);
+        let tree = mem_store.state().unwrap();
+
+        queue.evict(&tree).expect("evict");
+        // the protected entry is added back
+        assert_eq!(queue.len(), 101);
+        assert_eq!(queue.eviction_stats().low_fee_rate, 101);
+        assert!(queue.check_replace(&build_entry(3, 0, 100)).is_err());
+        assert!(queue.check_replace(&build_entry(4, 1, 100)).is_ok());
+    }
+
+    #[test]
+    fn test_evict_expired_items() {
+        let mut queue = FeeQueue::with_config(FeeQueueConfig {
+            entry_expire_secs: 0,
+            ..Default::default()
+        });
+
+        let store = Store::open_tmp().expect("open store");
+        setup_genesis(&store);
+        {
+            let db = store.begin_transaction();
+            let mut state = db.state_tree(StateContext::AttachBlock(1)).expect("state");
+
+            // create accounts
+            for i in 0..4 {
+                state.create_account(H256::from_u32(i)).unwrap();
+            }
+
+            db.commit().expect("commit");
+        }
+
+        let entry = FeeEntry {
+            item: FeeItem::Tx(Default::default()),
+            fee: (100 * 1000u64).into(),
+            cycles_limit: 1000,
+            sender: 2,
+            order: queue.len(),
+        };
+        queue.add(entry, ()).expect("add");
+        std::thread::sleep(std::time::Duration::from_millis(10));
+
+        let snap = store.get_snapshot();
+        let mem_store = MemStore::new(snap);
+        let tree = mem_store.state().unwrap();
+
+        let items = queue.fetch(&tree, 3).expect("fetch");
+        assert!(items.is_empty());
+        assert!(queue.is_empty());
+        assert_eq!(queue.eviction_stats().expired, 1);
     }
 
     const ALWAYS_SUCCESS_CODE_HASH: [u8; 32] = [42u8; 32];
@@ -8,7 +8,8 @@ use gw_types::{
     },
     prelude::{Entity, Unpack},
 };
-use std::{cmp::Ordering, convert::TryInto};
+use gw_utils::polyjuice_parser::PolyjuiceParser;
+use std::{cmp::Ordering, collections::BTreeMap, convert::TryInto, time::Instant};
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum FeeItemKind {
@@ -16,7 +17,7 @@ pub enum FeeItemKind {
     Withdrawal,
 }
 
-#[derive(PartialEq, Eq, Clone)]
+#[derive(PartialEq, Eq, Clone, Debug)]
 pub enum FeeItem {
     Tx(L2Transaction),
     Withdrawal(WithdrawalRequestExtra),
@@ -67,7 +68,7 @@ impl PartialOrd for FeeItem {
     }
 }
 
-#[derive(PartialEq, Eq, Clone)]
+#[derive(PartialEq, Eq, Clone, Debug)]
 pub struct FeeEntry {
     /// item: tx or withdrawal
     pub item: FeeItem,
@@ -114,6 +115,31 @@ impl Ord for FeeEntry {
 }
 
 impl FeeEntry {
+    /// Fee rate: fee / cycles limit
+    pub fn fee_rate(&self) -> u128 {
+        self.fee / u128::from(self.cycles_limit.max(1))
+    }
+
+    /// Returns true if the entry is a tx with Polyjuice args, its fee rate is
+    /// the gas price
+    pub fn is_polyjuice_tx(&self) -> bool {
+        match &self.item {
+            FeeItem::Tx(tx) => PolyjuiceParser::from_raw_l2_tx(&tx.raw()).is_some(),
+            FeeItem::Withdrawal(_) => false,
+        }
+    }
+
+    /// Returns true if the fee rate of this entry is higher than `other`'s by
+    /// at least `bump_percentage` percent
+    pub fn is_fee_rate_bumped(&self, other: &FeeEntry, bump_percentage: u64) -> bool {
+        // A / B > C / D => A * D > C * B
+        let fee_rate = self.fee.saturating_mul(other.cycles_limit.into());
+        let other_fee_rate = other.fee.saturating_mul(self.cycles_limit.into());
+        fee_rate > other_fee_rate
+            && fee_rate.saturating_mul(100)
+                >= other_fee_rate.saturating_mul(100u128.saturating_add(bump_percentage.into()))
+    }
+
     pub fn from_tx(
         tx: L2Transaction,
         fee_config: &FeeConfig,
@@ -152,6 +178,30 @@ impl FeeEntry {
     }
 }
 
+/// Number of entries by the time they are added, used to find the oldest entry
+/// without scanning the queue
+#[derive(Debug, Default)]
+pub(crate) struct AddedAtIndex(BTreeMap<Instant, usize>);
+
+impl AddedAtIndex {
+    pub(crate) fn insert(&mut self, added_at: Instant) {
+        *self.0.entry(added_at).or_default() += 1;
+    }
+
+    pub(crate) fn remove(&mut self, added_at: Instant) {
+        if let Some(count) = self.0.get_mut(&added_at) {
+            *count -= 1;
+            if 0 == *count {
+                self.0.remove(&added_at);
+            }
+        }
+    }
+
+    pub(crate) fn oldest(&self) -> Option<Instant> {
+        self.0.keys().next().copied()
+    }
+}
+
 struct L2Fee {
     fee: u128,
     cycles_limit: u64,
@@ -9,11 +9,12 @@ pub mod default_provider;
 mod deposit;
 pub mod fee;
 pub mod mem_block;
+mod optimistic;
 pub mod pool;
 pub mod restore_manager;
 pub mod sync;
 pub mod traits;
-mod types;
+pub mod types;
 pub mod withdrawal;
 
 pub use async_trait::*;
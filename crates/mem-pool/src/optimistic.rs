@@ -0,0 +1,284 @@
+//! Optimistic parallel execution of mem block txs
+//!
+//! A batch of txs is executed speculatively in parallel against the same
+//! snapshot of the mem state, each execution records everything it read from
+//! the state. Txs are then committed in order, the speculative result of a tx
+//! is reused only if nothing it read has been written by the txs committed
+//! before it in the batch, otherwise the tx is re-executed on the latest state.
+//! So the state is the same as executing the txs one by one.
+
+use std::{cell::RefCell, collections::HashSet, sync::Arc};
+
+use anyhow::Result;
+use gw_common::{error::Error as StateError, state::State, H256};
+use gw_generator::{constants::L2TX_MAX_CYCLES, Generator};
+use gw_store::{
+    chain_view::ChainView, mem_pool_state::MemStore, traits::chain_store::ChainStore, Store,
+};
+use gw_traits::CodeStore;
+use gw_types::{
+    bytes::Bytes,
+    offchain::{RunResult, RunResultWriteState},
+    packed::{BlockInfo, L2Transaction, Script},
+};
+
+/// Keys, scripts and data read from the state by an execution
+#[derive(Debug, Default)]
+pub(crate) struct ReadSet {
+    keys: HashSet<H256>,
+    scripts: HashSet<H256>,
+    data: HashSet<H256>,
+    account_count: bool,
+}
+
+/// Keys, scripts and data written by the committed txs of a batch
+#[derive(Debug, Default)]
+struct WriteSet {
+    keys: HashSet<H256>,
+    scripts: HashSet<H256>,
+    data: HashSet<H256>,
+    account_count: bool,
+}
+
+impl WriteSet {
+    fn record(&mut self, write: &RunResultWriteState) {
+        self.keys.extend(write.write_values.keys());
+        self.scripts.extend(write.new_scripts.keys());
+        self.data.extend(write.write_data.keys());
+        self.account_count |= write.account_count.is_some();
+    }
+
+    fn conflicts_with(&self, reads: &ReadSet) -> bool {
+        (reads.account_count && self.account_count)
+            || !self.keys.is_disjoint(&reads.keys)
+            || !self.scripts.is_disjoint(&reads.scripts)
+            || !self.data.is_disjoint(&reads.data)
+    }
+}
+
+/// Result of a speculative execution
+pub(crate) struct Speculation {
+    run_result: RunResult,
+    reads: ReadSet,
+}
+
+/// Writes committed in a batch, and how many speculative results are reused
+#[derive(Default)]
+pub(crate) struct Batch {
+    writes: WriteSet,
+    pub reused: usize,
+    pub reexecuted: usize,
+}
+
+impl Batch {
+    pub fn tx(&mut self, speculation: Option<Speculation>) -> OptimisticTx<'_> {
+        OptimisticTx {
+            batch: self,
+            speculation,
+        }
+    }
+}
+
+/// A tx to commit in a batch
+pub(crate) struct OptimisticTx<'a> {
+    batch: &'a mut Batch,
+    speculation: Option<Speculation>,
+}
+
+impl<'a> OptimisticTx<'a> {
+    /// Take the speculative run result if nothing it read has been changed
+    pub fn take_run_result(&mut self) -> Option<RunResult> {
+        let speculation = self.speculation.take();
+        match speculation {
+            Some(s) if !self.batch.writes.conflicts_with(&s.reads) => {
+                self.batch.reused += 1;
+                Some(s.run_result)
+            }
+            _ => {
+                self.batch.reexecuted += 1;
+                None
+            }
+        }
+    }
+
+    /// Record writes of the tx before applying them to the state
+    pub fn record_writes(&mut self, write: &RunResultWriteState) {
+        self.batch.writes.record(write);
+    }
+}
+
+/// Execute txs in parallel against the snapshot, returns `None` for txs
+/// failed to execute, they will be executed again in order.
+pub(crate) async fn speculate(
+    generator: Arc<Generator>,
+    store: Store,
+    mem_store: Arc<MemStore>,
+    block_info: BlockInfo,
+    txs: &[L2Transaction],
+    workers: usize,
+) -> Vec<Option<Speculation>> {
+    let workers = workers.max(1).min(txs.len());
+    let handles = (0..workers).map(|worker| {
+        let generator = Arc::clone(&generator);
+        let store = store.clone();
+        let mem_store = Arc::clone(&mem_store);
+        let block_info = block_info.clone();
+        let txs: Vec<(usize, L2Transaction)> = txs
+            .iter()
+            .cloned()
+            .enumerate()
+            .skip(worker)
+            .step_by(workers)
+            .collect();
+        tokio::task::spawn_blocking(move || {
+            let db = store.get_snapshot();
+            let tip_block_hash = db.get_tip_block_hash()?;
+            let chain_view = ChainView::new(&db, tip_block_hash);
+            let state = mem_store.state()?;
+
+            let speculations = txs.into_iter().map(|(index, tx)| {
+                let state = RecordReadState::new(&state);
+                let speculation = generator
+                    .unchecked_execute_transaction(
+                        &chain_view,
+                        &state,
+                        &block_info,
+                        &tx.raw(),
+                        L2TX_MAX_CYCLES,
+                    )
+                    .ok()
+                    .map(|run_result| Speculation {
+                        run_result,
+                        reads: state.into_reads(),
+                    });
+                (index, speculation)
+            });
+            Result::<_, anyhow::Error>::Ok(speculations.collect::<Vec<_>>())
+        })
+    });
+
+    let mut speculations: Vec<Option<Speculation>> = txs.iter().map(|_| None).collect();
+    for result in futures::future::join_all(handles).await {
+        match result.map_err(Into::into).and_then(|r| r) {
+            Ok(executed) => {
+                for (index, speculation) in executed {
+                    speculations[index] = speculation;
+                }
+            }
+            Err(err) => log::warn!("[mem-pool] speculative execution error: {}", err),
+        }
+    }
+    speculations
+}
+
+/// Read only state which records the reads
+struct RecordReadState<'a, S> {
+    inner: &'a S,
+    reads: RefCell<ReadSet>,
+}
+
+impl<'a, S: State + CodeStore> RecordReadState<'a, S> {
+    fn new(inner: &'a S) -> Self {
+        RecordReadState {
+            inner,
+            reads: Default::default(),
+        }
+    }
+
+    fn into_reads(self) -> ReadSet {
+        self.reads.into_inner()
+    }
+}
+
+impl<'a, S: State + CodeStore> State for RecordReadState<'a, S> {
+    fn get_raw(&self, key: &H256) -> Result<H256, StateError> {
+        self.reads.borrow_mut().keys.insert(*key);
+        self.inner.get_raw(key)
+    }
+
+    fn update_raw(&mut self, _key: H256, _value: H256) -> Result<(), StateError> {
+        log::error!("update_raw is unsupport in speculative execution");
+        Err(StateError::Store)
+    }
+
+    fn get_account_count(&self) -> Result<u32, StateError> {
+        self.reads.borrow_mut().account_count = true;
+        self.inner.get_account_count()
+    }
+
+    fn set_account_count(&mut self, _count: u32) -> Result<(), StateError> {
+        log::error!("set_account_count is unsupport in speculative execution");
+        Err(StateError::Store)
+    }
+
+    fn calculate_root(&self) -> Result<H256, StateError> {
+        log::error!("calculate_root is unsupport in speculative execution");
+        Err(StateError::Store)
+    }
+}
+
+impl<'a, S: State + CodeStore> CodeStore for RecordReadState<'a, S> {
+    fn insert_script(&mut self, _script_hash: H256, _script: Script) {
+        unreachable!("insert script in speculative execution");
+    }
+
+    fn get_script(&self, script_hash: &H256) -> Option<Script> {
+        self.reads.borrow_mut().scripts.insert(*script_hash);
+        self.inner.get_script(script_hash)
+    }
+
+    fn insert_data(&mut self, _data_hash: H256, _code: Bytes) {
+        unreachable!("insert data in speculative execution");
+    }
+
+    fn get_data(&self, data_hash: &H256) -> Option<Bytes> {
+        self.reads.borrow_mut().data.insert(*data_hash);
+        self.inner.get_data(data_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gw_types::offchain::RunResultWriteState;
+
+    use super::{ReadSet, WriteSet};
+
+    fn h(n: u8) -> gw_common::H256 {
+        [n; 32].into()
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let mut writes = WriteSet::default();
+        let mut write = RunResultWriteState::default();
+        write.write_values.insert(h(1), h(2));
+        writes.record(&write);
+
+        let mut reads = ReadSet::default();
+        reads.keys.insert(h(3));
+        assert!(!writes.conflicts_with(&reads));
+
+        reads.keys.insert(h(1));
+        assert!(writes.conflicts_with(&reads));
+
+        // account count
+        let mut reads = ReadSet {
+            account_count: true,
+            ..Default::default()
+        };
+        assert!(!writes.conflicts_with(&reads));
+        let mut write = RunResultWriteState::default();
+        write.account_count = Some(42);
+        writes.record(&write);
+        assert!(writes.conflicts_with(&reads));
+
+        // data
+        reads.account_count = false;
+        reads.data.insert(h(4));
+        assert!(!writes.conflicts_with(&reads));
+        let mut write = RunResultWriteState::default();
+        write.write_data.insert(h(4), Default::default());
+        writes.record(&write);
+        assert!(writes.conflicts_with(&reads));
+    }
+}
@@ -13,7 +13,7 @@ use gw_common::{
     builtins::CKB_SUDT_ACCOUNT_ID, ckb_decimal::CKBCapacity, registry_address::RegistryAddress,
     state::State, H256,
 };
-use gw_config::{MemBlockConfig, MemPoolConfig, NodeMode};
+use gw_config::{FeeQueueConfig, MemBlockConfig, MemPoolConfig, NodeMode, ParallelExecutionConfig};
 use gw_dynamic_config::manager::DynamicConfigManager;
 use gw_generator::{
     constants::L2TX_MAX_CYCLES,
@@ -54,6 +54,7 @@ use tracing::instrument;
 use crate::{
     custodian::AvailableCustodians,
     mem_block::MemBlock,
+    optimistic::{speculate, Batch, OptimisticTx},
     restore_manager::RestoreManager,
     sync::{
         mq::tokio_kafka,
@@ -65,6 +66,9 @@ use crate::{
     withdrawal::Generator as WithdrawalGenerator,
 };
 
+// Max number of buffered new tx hashes for each subscriber
+const NEW_TX_CHANNEL_SIZE: usize = 1024;
+
 #[derive(Debug, Default)]
 pub struct OutputParam {
     pub retry_count: usize,
@@ -102,7 +106,10 @@ pub struct MemPool {
     mem_pool_state: Arc<MemPoolState>,
     dynamic_config_manager: Arc<ArcSwap<DynamicConfigManager>>,
     new_tip_publisher: broadcast::Sender<(H256, u64)>,
+    new_tx_publisher: broadcast::Sender<H256>,
     mem_block_config: MemBlockConfig,
+    fee_queue_config: FeeQueueConfig,
+    parallel_execution_config: ParallelExecutionConfig,
     has_p2p_sync: bool,
 }
 
@@ -184,6 +191,7 @@ impl MemPool {
         };
 
         let (new_tip_publisher, _) = broadcast::channel(1);
+        let (new_tx_publisher, _) = broadcast::channel(NEW_TX_CHANNEL_SIZE);
 
         let mut mem_pool = MemPool {
             store,
@@ -200,7 +208,10 @@ impl MemPool {
             mem_pool_state,
             dynamic_config_manager,
             new_tip_publisher,
+            new_tx_publisher,
             mem_block_config: config.mem_block,
+            fee_queue_config: config.fee_queue,
+            parallel_execution_config: config.parallel_execution,
             has_p2p_sync,
         };
         mem_pool.restore_pending_withdrawals().await?;
@@ -279,12 +290,98 @@ impl MemPool {
 
         let snap = self.mem_pool_state.load();
         let mut state = snap.state()?;
-        self.push_transaction_with_db(&db, &mut state, tx).await?;
+        let tx_hash: H256 = tx.raw().hash().into();
+        self.push_transaction_with_db(&db, &mut state, tx, None)
+            .await?;
         db.commit()?;
         self.mem_pool_state.store(snap.into());
+        self.publish_new_txs(vec![tx_hash]);
+        self.report_metrics();
         Ok(())
     }
 
+    /// Push a batch of layer2 txs into pool, returns results in the same order.
+    ///
+    /// If parallel execution is enabled, txs are executed speculatively in
+    /// parallel then committed in order, the state is the same as pushing
+    /// them one by one.
+    #[instrument(skip_all, fields(txs = txs.len()))]
+    pub async fn push_transactions(&mut self, txs: Vec<L2Transaction>) -> Vec<Result<()>> {
+        if !self.parallel_execution_config.enabled || txs.len() < 2 {
+            let mut results = Vec::with_capacity(txs.len());
+            for tx in txs {
+                results.push(self.push_transaction(tx).await);
+            }
+            return results;
+        }
+
+        let t = Instant::now();
+        let speculations = speculate(
+            Arc::clone(&self.generator),
+            self.store.clone(),
+            Arc::new(self.mem_pool_state.load()),
+            self.mem_block.block_info().clone(),
+            &txs,
+            self.parallel_execution_config.workers,
+        )
+        .await;
+        log::debug!(
+            "[push txs] speculate {} txs time: {}ms",
+            txs.len(),
+            t.elapsed().as_millis()
+        );
+
+        let db = self.store.begin_transaction();
+        let snap = self.mem_pool_state.load();
+        let mut state = match snap.state() {
+            Ok(state) => state,
+            Err(err) => {
+                let err = err.to_string();
+                return txs.iter().map(|_| Err(anyhow!("{}", err))).collect();
+            }
+        };
+
+        let mut batch = Batch::default();
+        let mut results = Vec::with_capacity(txs.len());
+        let mut pushed_tx_hashes = Vec::with_capacity(txs.len());
+        for (tx, speculation) in txs.into_iter().zip(speculations) {
+            let tx_hash: H256 = tx.raw().hash().into();
+            let optimistic_tx = batch.tx(speculation);
+            let result = self
+                .push_transaction_with_db(&db, &mut state, tx, Some(optimistic_tx))
+                .await;
+            if result.is_ok() {
+                pushed_tx_hashes.push(tx_hash);
+            }
+            results.push(result);
+        }
+
+        if let Err(err) = db.commit() {
+            let err = err.to_string();
+            return results.iter().map(|_| Err(anyhow!("{}", err))).collect();
+        }
+        self.mem_pool_state.store(snap.into());
+        self.publish_new_txs(pushed_tx_hashes);
+
+        log::debug!(
+            "[push txs] reused {} speculative results, re-executed {} txs",
+            batch.reused,
+            batch.reexecuted
+        );
+        let metrics = gw_metrics::mem_pool();
+        metrics
+            .optimistic_txs
+            .with_label_values(&["reused"])
+            .inc_by(batch.reused as u64);
+        metrics
+            .optimistic_txs
+            .with_label_values(&["reexecuted"])
+            .inc_by(batch.reexecuted as u64);
+        self.report_metrics();
+
+        results
+    }
+
     /// Push a layer2 tx into pool
     #[instrument(skip_all, fields(tx_hash = %tx.hash().pack()))]
     async fn push_transaction_with_db(
@@ -292,6 +389,7 @@ impl MemPool {
         db: &StoreTransaction,
         state: &mut MemStateTree<'_>,
         tx: L2Transaction,
+        optimistic_tx: Option<OptimisticTx<'_>>,
     ) -> Result<()> {
         // check duplication
         let tx_hash: H256 = tx.raw().hash().into();
@@ -315,7 +413,9 @@ impl MemPool {
 
         // instantly run tx in background & update local state
         let t = Instant::now();
-        let tx_receipt = self.execute_tx(db, state, tx.clone()).await?;
+        let tx_receipt = self
+            .execute_tx(db, state, tx.clone(), optimistic_tx)
+            .await?;
         log::debug!("[push tx] finalize tx time: {}ms", t.elapsed().as_millis());
 
         // save tx receipt in mem pool
@@ -362,6 +462,7 @@ impl MemPool {
         let db = self.store.begin_transaction();
         db.insert_mem_pool_withdrawal(&withdrawal_hash, withdrawal)?;
         db.commit()?;
+        self.report_metrics();
         Ok(())
     }
 
@@ -406,10 +507,22 @@ impl MemPool {
     }
 
     /// Return pending contents
-    fn pending(&self) -> &HashMap<u32, EntryList> {
+    pub fn pending(&self) -> &HashMap<u32, EntryList> {
         &self.pending
     }
 
+    fn report_metrics(&self) {
+        let metrics = gw_metrics::mem_pool();
+        metrics.pending_accounts.set(self.pending.len() as i64);
+        metrics.mem_block_txs.set(self.mem_block.txs().len() as i64);
+        metrics
+            .mem_block_withdrawals
+            .set(self.mem_block.withdrawals().len() as i64);
+        metrics
+            .mem_block_deposits
+            .set(self.mem_block.deposits().len() as i64);
+    }
+
     /// Notify new tip
     /// this method update current state of mem pool
     #[instrument(skip_all)]
@@ -469,9 +582,11 @@ impl MemPool {
     #[instrument(skip_all, fields(old_tip = old_tip.map(|h| display(h.pack())), new_tip = new_tip.map(|h| display(h.pack()))))]
     async fn reset(&mut self, old_tip: Option<H256>, new_tip: Option<H256>) -> Result<()> {
         match self.node_mode {
-            NodeMode::FullNode | NodeMode::Test => self.reset_full(old_tip, new_tip).await,
-            NodeMode::ReadOnly => self.reset_read_only(old_tip, new_tip).await,
+            NodeMode::FullNode | NodeMode::Test => self.reset_full(old_tip, new_tip).await?,
+            NodeMode::ReadOnly => self.reset_read_only(old_tip, new_tip).await?,
         }
+        self.report_metrics();
+        Ok(())
     }
 
     /// Only **ReadOnly** node.
@@ -690,18 +805,20 @@ impl MemPool {
             self.try_package_more_withdrawals(&mem_state, &mut withdrawals);
         }
 
-        self.prepare_next_mem_block(
-            &db,
-            &mut mem_state,
-            withdrawals,
-            self.pending_deposits.clone(),
-            txs,
-        )
-        .await?;
+        let reinjected_tx_hashes = self
+            .prepare_next_mem_block(
+                &db,
+                &mut mem_state,
+                withdrawals,
+                self.pending_deposits.clone(),
+                txs,
+            )
+            .await?;
 
         // store mem state
         self.mem_pool_state.store(Arc::new(mem_store));
         db.commit()?;
+        self.publish_new_txs(reinjected_tx_hashes);
 
         Ok(())
     }
@@ -759,12 +876,12 @@ impl MemPool {
                 let tx_hash = tx.hash().into();
                 db.remove_mem_pool_transaction(&tx_hash)?;
             }
-            // Drop all withdrawals that are have no enough balance
+            // Move withdrawals that have no enough balance to future withdrawals
             let script_hash = state.get_script_hash(account_id)?;
-            if let Some(registry_id) = list
-                .withdrawals
-                .first()
-                .map(|first| first.request().raw().registry_id().unpack())
+            let first_withdrawal = (list.withdrawals.first())
+                .or_else(|| list.future_withdrawals.first().map(|(w, _)| w));
+            if let Some(registry_id) =
+                first_withdrawal.map(|first| first.request().raw().registry_id().unpack())
             {
                 let address = state
                     .get_registry_address_by_script_hash(registry_id, &script_hash)?
@@ -772,7 +889,12 @@ impl MemPool {
                 let capacity = CKBCapacity::from_layer2(
                     state.get_sudt_balance(CKB_SUDT_ACCOUNT_ID, &address)?,
                 );
-                let deprecated_withdrawals = list.remove_lower_nonce_withdrawals(nonce, capacity);
+                let mut deprecated_withdrawals =
+                    list.remove_lower_nonce_withdrawals(nonce, capacity);
+                deprecated_withdrawals.extend(list.remove_expired_future_withdrawals(
+                    Duration::from_secs(self.fee_queue_config.future_entry_expire_secs),
+                    self.fee_queue_config.max_future_entries_per_account,
+                ));
                 for withdrawal in deprecated_withdrawals {
                     let withdrawal_hash: H256 = withdrawal.hash().into();
                     db.remove_mem_pool_withdrawal(&withdrawal_hash)?;
@@ -789,7 +911,7 @@ impl MemPool {
         Ok(())
     }
 
-    /// Prepare for next mem block
+    /// Prepare for next mem block, returns hashes of re-injected txs
     #[instrument(skip_all, fields(withdrawals_count = withdrawals.len(), txs_count = txs.len()))]
     async fn prepare_next_mem_block(
         &mut self,
@@ -798,7 +920,7 @@ impl MemPool {
         withdrawals: Vec<WithdrawalRequestExtra>,
         deposit_cells: Vec<DepositInfo>,
         mut txs: Vec<L2Transaction>,
-    ) -> Result<()> {
+    ) -> Result<Vec<H256>> {
         // remove txs nonce is lower than current state
         fn filter_tx(state: &MemStateTree<'_>, tx: &L2Transaction) -> bool {
             let raw_tx = tx.raw();
@@ -847,18 +969,33 @@ impl MemPool {
         }
 
         // re-inject txs
+        let mut reinjected_tx_hashes = Vec::with_capacity(txs.len());
         for tx in txs {
-            if let Err(err) = self.push_transaction_with_db(db, state, tx.clone()).await {
-                let tx_hash = tx.hash();
-                log::info!(
-                    "[mem pool] fail to re-inject tx {}, error: {}",
-                    hex::encode(&tx_hash),
-                    err
-                );
+            let tx_hash: H256 = tx.raw().hash().into();
+            match self
+                .push_transaction_with_db(db, state, tx.clone(), None)
+                .await
+            {
+                Ok(()) => reinjected_tx_hashes.push(tx_hash),
+                Err(err) => {
+                    log::info!(
+                        "[mem pool] fail to re-inject tx {}, error: {}",
+                        hex::encode(tx.hash()),
+                        err
+                    );
+                }
             }
         }
 
-        Ok(())
+        Ok(reinjected_tx_hashes)
+    }
+
+    // Notify subscribers of txs added to the mem block, after the mem pool
+    // state is stored
+    fn publish_new_txs(&self, tx_hashes: Vec<H256>) {
+        for tx_hash in tx_hashes {
+            let _ = self.new_tx_publisher.send(tx_hash);
+        }
     }
 
     /// refresh pending deposits
@@ -1079,6 +1216,7 @@ impl MemPool {
         db: &StoreTransaction,
         state: &mut MemStateTree<'_>,
         tx: L2Transaction,
+        mut optimistic_tx: Option<OptimisticTx<'_>>,
     ) -> Result<TxReceipt> {
         let tip_block_hash = db.get_tip_block_hash()?;
         let chain_view = ChainView::new(db, tip_block_hash);
@@ -1109,17 +1247,23 @@ impl MemPool {
             }
         }
 
-        // execute tx
+        // execute tx, reuse the speculative result if there is no conflict
         let raw_tx = tx.raw();
-        let run_result = tokio::task::block_in_place(|| {
-            self.generator.unchecked_execute_transaction(
-                &chain_view,
-                state,
-                block_info,
-                &raw_tx,
-                L2TX_MAX_CYCLES,
-            )
-        })?;
+        let speculative_run_result = optimistic_tx
+            .as_mut()
+            .and_then(|optimistic_tx| optimistic_tx.take_run_result());
+        let run_result = match speculative_run_result {
+            Some(run_result) => run_result,
+            None => tokio::task::block_in_place(|| {
+                self.generator.unchecked_execute_transaction(
+                    &chain_view,
+                    state,
+                    block_info,
+                    &raw_tx,
+                    L2TX_MAX_CYCLES,
+                )
+            })?,
+        };
 
         // check account id of sudt proxy contract creator is from whitelist
         {
@@ -1137,6 +1281,9 @@ impl MemPool {
             }
         }
         // apply run result
+        if let Some(ref mut optimistic_tx) = optimistic_tx {
+            optimistic_tx.record_writes(&run_result.write);
+        }
         let t = Instant::now();
         tokio::task::block_in_place(|| state.apply_run_result(&run_result.write))?;
         log::debug!(
@@ -1242,12 +1389,14 @@ impl MemPool {
 
         // prepare next mem block
         self.try_package_more_withdrawals(&mem_state, &mut withdrawals);
-        self.prepare_next_mem_block(&db, &mut mem_state, withdrawals, deposits, mem_block_txs)
+        let reinjected_tx_hashes = self
+            .prepare_next_mem_block(&db, &mut mem_state, withdrawals, deposits, mem_block_txs)
             .await?;
 
         // update mem state
         self.mem_pool_state.store(Arc::new(mem_store));
         db.commit()?;
+        self.publish_new_txs(reinjected_tx_hashes);
 
         let mem_block = &self.mem_block;
         log::info!(
@@ -1257,6 +1406,7 @@ impl MemPool {
             mem_block.withdrawals().len(),
             mem_block.txs().len()
         );
+        self.report_metrics();
 
         Ok(Some(next_block_number))
     }
@@ -1283,10 +1433,16 @@ impl MemPool {
         self.current_tip
     }
 
-    pub(crate) fn subscribe_new_tip(&self) -> broadcast::Receiver<(H256, u64)> {
+    /// Subscribe (hash, number) of new tip blocks
+    pub fn subscribe_new_tip(&self) -> broadcast::Receiver<(H256, u64)> {
         self.new_tip_publisher.subscribe()
     }
 
+    /// Subscribe hashes of txs pushed into the mem block
+    pub fn subscribe_new_txs(&self) -> broadcast::Receiver<H256> {
+        self.new_tx_publisher.subscribe()
+    }
+
     pub async fn enable_publishing(
         &mut self,
         control: ServiceAsyncControl,
@@ -1,3 +1,5 @@
+use std::time::{Duration, Instant};
+
 use gw_common::ckb_decimal::CKBCapacity;
 use gw_types::{
     packed::{L2Transaction, WithdrawalRequestExtra},
@@ -10,11 +12,13 @@ pub struct EntryList {
     pub txs: Vec<L2Transaction>,
     // withdrawals sorted by nonce
     pub withdrawals: Vec<WithdrawalRequestExtra>,
+    // withdrawals which have not enough balance, sorted by nonce
+    pub future_withdrawals: Vec<(WithdrawalRequestExtra, Instant)>,
 }
 
 impl EntryList {
     pub fn is_empty(&self) -> bool {
-        self.txs.is_empty() && self.withdrawals.is_empty()
+        self.txs.is_empty() && self.withdrawals.is_empty() && self.future_withdrawals.is_empty()
     }
 
     // remove and return txs which tx.nonce is lower than nonce
@@ -30,7 +34,8 @@ impl EntryList {
         removed
     }
 
-    // remove and return withdrawals which withdrawal.nonce is lower than nonce & have not enough balance
+    // remove and return withdrawals which withdrawal.nonce is lower than nonce,
+    // withdrawals have not enough balance are moved to the future withdrawals
     pub fn remove_lower_nonce_withdrawals(
         &mut self,
         nonce: u32,
@@ -46,18 +51,62 @@ impl EntryList {
             }
             removed.push(self.withdrawals.remove(0));
         }
+        while !self.future_withdrawals.is_empty() {
+            let withdrawal_nonce: u32 = self.future_withdrawals[0].0.raw().nonce().unpack();
+            if withdrawal_nonce >= nonce {
+                break;
+            }
+            removed.push(self.future_withdrawals.remove(0).0);
+        }
 
-        // remove lower balance withdrawals
-        if let Some(withdrawal) = self.withdrawals.get(0) {
+        let capacity = capacity.to_layer1().unwrap();
+        let has_enough_balance = |withdrawal: &WithdrawalRequestExtra| -> bool {
             let withdrawal_capacity: u64 = withdrawal.raw().capacity().unpack();
-            let capacity = capacity.to_layer1().unwrap();
-            if withdrawal_capacity > capacity {
-                // TODO instead of remove all withdrawals, put them into future queue
-                removed.extend_from_slice(&self.withdrawals);
-                self.withdrawals.clear();
+            withdrawal_capacity <= capacity
+        };
+
+        // promote future withdrawals if the balance is enough now
+        if let Some((withdrawal, _)) = self.future_withdrawals.first() {
+            if has_enough_balance(withdrawal) {
+                self.withdrawals
+                    .extend(self.future_withdrawals.drain(..).map(|(w, _)| w));
+                self.withdrawals
+                    .sort_by_key(|w| Unpack::<u32>::unpack(&w.raw().nonce()));
+            }
+        }
+
+        // move lower balance withdrawals to future withdrawals
+        if let Some(withdrawal) = self.withdrawals.first() {
+            if !has_enough_balance(withdrawal) {
+                let now = Instant::now();
+                let mut future_withdrawals: Vec<_> =
+                    self.withdrawals.drain(..).map(|w| (w, now)).collect();
+                future_withdrawals.append(&mut self.future_withdrawals);
+                future_withdrawals.sort_by_key(|(w, _)| Unpack::<u32>::unpack(&w.raw().nonce()));
+                self.future_withdrawals = future_withdrawals;
             }
         }
 
         removed
     }
+
+    // remove and return future withdrawals which are expired or exceed the
+    // max count
+    pub fn remove_expired_future_withdrawals(
+        &mut self,
+        expire: Duration,
+        max_count: usize,
+    ) -> Vec<WithdrawalRequestExtra> {
+        let mut removed = Vec::default();
+        let mut kept = Vec::with_capacity(self.future_withdrawals.len());
+        for (withdrawal, added_at) in self.future_withdrawals.drain(..) {
+            if added_at.elapsed() > expire || kept.len() >= max_count {
+                removed.push(withdrawal);
+            } else {
+                kept.push((withdrawal, added_at));
+            }
+        }
+        self.future_withdrawals = kept;
+        removed
+    }
 }
@@ -0,0 +1,260 @@
+//! Prometheus metrics of node internals.
+//!
+//! Metrics are registered lazily in a private registry and exposed by
+//! `start_metrics_server`. Updating a metric is cheap, but collecting values
+//! which need extra works (e.g. inspect rpc responses) should be skipped unless
+//! `enabled` returns true.
+
+mod server;
+
+pub use server::start_metrics_server;
+
+use once_cell::sync::Lazy;
+use prometheus::{
+    core::Collector, exponential_buckets, Encoder, Histogram, HistogramOpts, HistogramVec,
+    IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static REGISTRY: Lazy<Registry> = Lazy::new(Registry::new);
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static MEM_POOL: Lazy<MemPoolMetrics> = Lazy::new(MemPoolMetrics::new);
+static BLOCK_PRODUCER: Lazy<BlockProducerMetrics> = Lazy::new(BlockProducerMetrics::new);
+static CHAIN: Lazy<ChainMetrics> = Lazy::new(ChainMetrics::new);
+static RPC: Lazy<RPCMetrics> = Lazy::new(RPCMetrics::new);
+static DB: Lazy<DBMetrics> = Lazy::new(DBMetrics::new);
+static CHALLENGER: Lazy<ChallengerMetrics> = Lazy::new(ChallengerMetrics::new);
+
+/// Returns true if the metrics server is started
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn mem_pool() -> &'static MemPoolMetrics {
+    &MEM_POOL
+}
+
+pub fn block_producer() -> &'static BlockProducerMetrics {
+    &BLOCK_PRODUCER
+}
+
+pub fn chain() -> &'static ChainMetrics {
+    &CHAIN
+}
+
+pub fn rpc() -> &'static RPCMetrics {
+    &RPC
+}
+
+pub fn db() -> &'static DBMetrics {
+    &DB
+}
+
+pub fn challenger() -> &'static ChallengerMetrics {
+    &CHALLENGER
+}
+
+/// Encode all metrics in prometheus text format
+pub fn encode() -> Vec<u8> {
+    // Force registration, so that untouched metrics are exported too
+    Lazy::force(&MEM_POOL);
+    Lazy::force(&BLOCK_PRODUCER);
+    Lazy::force(&CHAIN);
+    Lazy::force(&RPC);
+    Lazy::force(&DB);
+    Lazy::force(&CHALLENGER);
+
+    let mut buf = Vec::new();
+    let encoder = TextEncoder::new();
+    if let Err(err) = encoder.encode(&REGISTRY.gather(), &mut buf) {
+        log::error!("[metrics] encode error: {}", err);
+    }
+    buf
+}
+
+fn register<C: Collector + Clone + 'static>(collector: C) -> C {
+    REGISTRY
+        .register(Box::new(collector.clone()))
+        .expect("register metric");
+    collector
+}
+
+fn int_gauge(name: &str, help: &str) -> IntGauge {
+    register(IntGauge::new(name, help).expect("int gauge"))
+}
+
+fn int_counter(name: &str, help: &str) -> IntCounter {
+    register(IntCounter::new(name, help).expect("int counter"))
+}
+
+// Block producing and submitting take from hundreds of milliseconds to
+// dozens of seconds
+fn seconds_histogram(name: &str, help: &str) -> Histogram {
+    let buckets = exponential_buckets(0.05, 2.0, 12).expect("buckets");
+    let opts = HistogramOpts::new(name, help).buckets(buckets);
+    register(Histogram::with_opts(opts).expect("histogram"))
+}
+
+pub struct MemPoolMetrics {
+    pub pending_accounts: IntGauge,
+    pub mem_block_txs: IntGauge,
+    pub mem_block_withdrawals: IntGauge,
+    pub mem_block_deposits: IntGauge,
+    pub fee_queue_len: IntGauge,
+    pub fee_queue_future_len: IntGauge,
+    /// Labels: reason
+    pub fee_queue_evicted: IntCounterVec,
+    /// Labels: result
+    pub optimistic_txs: IntCounterVec,
+}
+
+impl MemPoolMetrics {
+    fn new() -> Self {
+        let evicted_opts = Opts::new(
+            "gw_fee_queue_evicted_total",
+            "Number of requests evicted from fee queue",
+        );
+        let optimistic_opts = Opts::new(
+            "gw_mem_pool_optimistic_txs_total",
+            "Number of speculatively executed txs, by whether the result is reused or re-executed",
+        );
+        MemPoolMetrics {
+            pending_accounts: int_gauge(
+                "gw_mem_pool_pending_accounts",
+                "Number of accounts which have pending requests in mem pool",
+            ),
+            mem_block_txs: int_gauge("gw_mem_pool_mem_block_txs", "Number of txs in mem block"),
+            mem_block_withdrawals: int_gauge(
+                "gw_mem_pool_mem_block_withdrawals",
+                "Number of withdrawals in mem block",
+            ),
+            mem_block_deposits: int_gauge(
+                "gw_mem_pool_mem_block_deposits",
+                "Number of deposits in mem block",
+            ),
+            fee_queue_len: int_gauge("gw_fee_queue_len", "Number of requests in fee queue"),
+            fee_queue_future_len: int_gauge(
+                "gw_fee_queue_future_len",
+                "Number of future nonce txs in fee queue",
+            ),
+            fee_queue_evicted: register(
+                IntCounterVec::new(evicted_opts, &["reason"]).expect("int counter vec"),
+            ),
+            optimistic_txs: register(
+                IntCounterVec::new(optimistic_opts, &["result"]).expect("int counter vec"),
+            ),
+        }
+    }
+}
+
+pub struct BlockProducerMetrics {
+    pub produce_seconds: Histogram,
+    pub submit_seconds: Histogram,
+    pub submitted_blocks: IntCounter,
+}
+
+impl BlockProducerMetrics {
+    fn new() -> Self {
+        BlockProducerMetrics {
+            produce_seconds: seconds_histogram(
+                "gw_block_producer_produce_seconds",
+                "Time to produce a block and compose its submit tx",
+            ),
+            submit_seconds: seconds_histogram(
+                "gw_block_producer_submit_seconds",
+                "Time to submit a block tx to L1",
+            ),
+            submitted_blocks: int_counter(
+                "gw_block_producer_submitted_blocks_total",
+                "Number of submitted blocks",
+            ),
+        }
+    }
+}
+
+pub struct ChainMetrics {
+    pub l1_tip_number: IntGauge,
+    pub l1_synced_number: IntGauge,
+    pub l1_lag_blocks: IntGauge,
+    pub l2_tip_number: IntGauge,
+}
+
+impl ChainMetrics {
+    fn new() -> Self {
+        ChainMetrics {
+            l1_tip_number: int_gauge("gw_chain_l1_tip_number", "L1 tip block number"),
+            l1_synced_number: int_gauge(
+                "gw_chain_l1_synced_number",
+                "Last L1 block number synced by chain updater",
+            ),
+            l1_lag_blocks: int_gauge(
+                "gw_chain_l1_lag_blocks",
+                "Number of L1 blocks behind L1 tip",
+            ),
+            l2_tip_number: int_gauge("gw_chain_l2_tip_number", "L2 tip block number"),
+        }
+    }
+}
+
+pub struct RPCMetrics {
+    /// Labels: method
+    pub request_seconds: HistogramVec,
+    /// Labels: method
+    pub request_errors: IntCounterVec,
+}
+
+impl RPCMetrics {
+    fn new() -> Self {
+        let seconds_opts =
+            HistogramOpts::new("gw_rpc_request_seconds", "Time to handle a rpc request");
+        let errors_opts = Opts::new(
+            "gw_rpc_request_errors_total",
+            "Number of rpc requests responded with error",
+        );
+        RPCMetrics {
+            request_seconds: register(
+                HistogramVec::new(seconds_opts, &["method"]).expect("histogram vec"),
+            ),
+            request_errors: register(
+                IntCounterVec::new(errors_opts, &["method"]).expect("int counter vec"),
+            ),
+        }
+    }
+}
+
+pub struct DBMetrics {
+    /// Labels: cf, type
+    pub memory_bytes: IntGaugeVec,
+}
+
+impl DBMetrics {
+    fn new() -> Self {
+        let opts = Opts::new("gw_db_memory_bytes", "RocksDB memory usage");
+        DBMetrics {
+            memory_bytes: register(IntGaugeVec::new(opts, &["cf", "type"]).expect("int gauge vec")),
+        }
+    }
+}
+
+pub struct ChallengerMetrics {
+    pub challenges: IntCounter,
+    pub cancels: IntCounter,
+    pub reverts: IntCounter,
+}
+
+impl ChallengerMetrics {
+    fn new() -> Self {
+        ChallengerMetrics {
+            challenges: int_counter(
+                "gw_challenger_challenges_total",
+                "Number of challenges sent",
+            ),
+            cancels: int_counter(
+                "gw_challenger_cancels_total",
+                "Number of challenge cancels sent",
+            ),
+            reverts: int_counter("gw_challenger_reverts_total", "Number of reverts sent"),
+        }
+    }
+}
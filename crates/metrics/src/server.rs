@@ -0,0 +1,57 @@
+use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+
+use anyhow::Result;
+use hyper::{
+    header::CONTENT_TYPE,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use prometheus::{Encoder, TextEncoder};
+use tokio::sync::broadcast;
+
+use crate::{encode, ENABLED};
+
+/// Serve metrics at `/metrics`. `on_scrape` is called before encoding, to
+/// refresh metrics which are collected on demand.
+pub async fn start_metrics_server<F>(
+    listen_addr: SocketAddr,
+    on_scrape: F,
+    mut sub_shutdown: broadcast::Receiver<()>,
+) -> Result<()>
+where
+    F: Fn() + Send + Sync + 'static,
+{
+    let on_scrape = Arc::new(on_scrape);
+    let server = Server::try_bind(&listen_addr)?.serve(make_service_fn(move |_| {
+        let on_scrape = Arc::clone(&on_scrape);
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let on_scrape = Arc::clone(&on_scrape);
+                async move { serve(req, on_scrape.as_ref()) }
+            }))
+        }
+    }));
+    ENABLED.store(true, std::sync::atomic::Ordering::Relaxed);
+    log::info!("Metrics server listening on http://{}/metrics", listen_addr);
+
+    let graceful = server.with_graceful_shutdown(async {
+        let _ = sub_shutdown.recv().await;
+        log::info!("metrics server exited successfully");
+    });
+    graceful.await?;
+
+    Ok(())
+}
+
+fn serve(req: Request<Body>, on_scrape: &dyn Fn()) -> Result<Response<Body>, hyper::http::Error> {
+    if req.method() != Method::GET || req.uri().path() != "/metrics" {
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty());
+    }
+
+    on_scrape();
+    Response::builder()
+        .header(CONTENT_TYPE, TextEncoder::new().format_type())
+        .body(Body::from(encode()))
+}
@@ -48,6 +48,7 @@ pub async fn setup(args: SetupArgs) -> Result<Context> {
         options: config.store.options.clone(),
         options_file: config.store.options_file.clone(),
         cache_size: config.store.cache_size,
+        state_history: config.store.state_history.clone(),
     };
     let local_store = Store::new(RocksDB::open(&store_config, COLUMNS));
     let rollup_type_script = {
@@ -140,6 +141,7 @@ pub async fn setup(args: SetupArgs) -> Result<Context> {
             options: config.store.options.clone(),
             options_file: config.store.options_file.clone(),
             cache_size: config.store.cache_size,
+            state_history: config.store.state_history.clone(),
         };
         Store::new(RocksDB::open(&store_config, from_db_columns))
     };
@@ -1,3 +1,5 @@
 pub(crate) mod in_queue_request_map;
+pub(crate) mod method_rate_limiter;
 pub mod registry;
 pub mod server;
+pub mod ws_server;
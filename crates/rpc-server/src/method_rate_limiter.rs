@@ -0,0 +1,143 @@
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use gw_config::RPCMethodRateLimitConfig;
+use gw_dynamic_config::manager::DynamicConfigManager;
+use gw_generator::ArcSwap;
+use hyper::HeaderMap;
+use lru::LruCache;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Client {
+    Ip(IpAddr),
+    ApiKey(String),
+}
+
+struct Window {
+    started_at: Instant,
+    cost: u64,
+}
+
+struct State {
+    config: RPCMethodRateLimitConfig,
+    clients: LruCache<Client, Window>,
+}
+
+/// Rate limit requests of all methods by client IP or API key. Each method
+/// call costs `method_costs` (default 1) within a fixed window, a malformed
+/// call without a method costs 1.
+///
+/// The config is read from `DynamicConfigManager`, so that it can be hot
+/// reloaded, tracked clients are reset once the config changes.
+pub(crate) struct MethodRateLimiter {
+    dynamic_config_manager: Arc<ArcSwap<DynamicConfigManager>>,
+    state: Mutex<Option<State>>,
+}
+
+impl MethodRateLimiter {
+    pub fn new(dynamic_config_manager: Arc<ArcSwap<DynamicConfigManager>>) -> Self {
+        MethodRateLimiter {
+            dynamic_config_manager,
+            state: Mutex::new(None),
+        }
+    }
+
+    /// Returns false if the client exceeds its quota, the calls are not counted
+    /// in this case.
+    pub fn check<'a>(
+        &self,
+        peer_ip: IpAddr,
+        headers: &HeaderMap,
+        methods: impl Iterator<Item = Option<&'a str>>,
+    ) -> bool {
+        let dynamic_config_manager = self.dynamic_config_manager.load();
+        let mut state = self.state.lock().expect("rate limiter lock");
+        let config = match dynamic_config_manager.get_rpc_method_rate_limit() {
+            Some(config) => config,
+            None => {
+                *state = None;
+                return true;
+            }
+        };
+        if state.as_ref().map(|s| &s.config) != Some(config) {
+            *state = Some(State {
+                config: config.clone(),
+                clients: LruCache::new(config.lru_size.get()),
+            });
+        }
+        let State { config, clients } = state.as_mut().expect("rate limiter state");
+
+        let (client, max_cost) = match api_key_quota(config, headers) {
+            Some((api_key, max_cost)) => (Client::ApiKey(api_key), max_cost),
+            None => (
+                Client::Ip(client_ip(config, peer_ip, headers)),
+                config.max_cost_per_window,
+            ),
+        };
+        let cost = methods
+            .map(|method| {
+                let cost = method.and_then(|method| config.method_costs.get(method));
+                cost.copied().unwrap_or(1)
+            })
+            .fold(0u64, u64::saturating_add);
+
+        let now = Instant::now();
+        let window_len = Duration::from_secs(config.window_secs);
+        if clients.get_mut(&client).is_none() {
+            let window = Window {
+                started_at: now,
+                cost: 0,
+            };
+            clients.put(client.clone(), window);
+        }
+        let window = clients.get_mut(&client).expect("client window");
+        if now.saturating_duration_since(window.started_at) >= window_len {
+            window.started_at = now;
+            window.cost = 0;
+        }
+        let total_cost = window.cost.saturating_add(cost);
+        if total_cost > max_cost {
+            return false;
+        }
+        window.cost = total_cost;
+        true
+    }
+}
+
+fn header_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+fn api_key_quota(config: &RPCMethodRateLimitConfig, headers: &HeaderMap) -> Option<(String, u64)> {
+    let api_key = header_value(headers, config.api_key_header.as_ref()?)?;
+    let max_cost = config.api_key_quotas.get(api_key)?;
+    Some((api_key.to_owned(), *max_cost))
+}
+
+// Proxies append the address of their peer to the header, so only addresses
+// appended by trusted proxies can be believed. The header is ignored unless the
+// peer is a trusted proxy, then it is read from right to left until an address
+// which isn't a trusted proxy.
+fn client_ip(config: &RPCMethodRateLimitConfig, peer_ip: IpAddr, headers: &HeaderMap) -> IpAddr {
+    let header = match config.client_ip_header.as_ref() {
+        Some(header) if config.trusted_proxies.contains(&peer_ip) => header,
+        _ => return peer_ip,
+    };
+    let value = match header_value(headers, header) {
+        Some(value) => value,
+        None => return peer_ip,
+    };
+
+    let mut client_ip = peer_ip;
+    for hop in value.rsplit(',') {
+        match hop.trim().parse() {
+            Ok(ip) => client_ip = ip,
+            Err(_) => break,
+        }
+        if !config.trusted_proxies.contains(&client_ip) {
+            break;
+        }
+    }
+    client_ip
+}
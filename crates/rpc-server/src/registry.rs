@@ -3,36 +3,52 @@ use async_trait::async_trait;
 use ckb_types::prelude::{Builder, Entity};
 use gw_common::blake2b::new_blake2b;
 use gw_common::builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID};
-use gw_common::{state::State, H256};
+use gw_common::{
+    h256_ext::H256Ext,
+    smt::Blake2bHasher,
+    state::{
+        build_account_field_key, build_account_key, build_registry_address_to_script_hash_key,
+        build_script_hash_to_account_id_key, build_script_hash_to_registry_address_key,
+        build_sudt_key, State, GW_ACCOUNT_NONCE_TYPE, GW_ACCOUNT_SCRIPT_HASH_TYPE,
+        SUDT_KEY_FLAG_BALANCE,
+    },
+    H256,
+};
 use gw_config::{
-    ChainConfig, ConsensusConfig, FeeConfig, MemPoolConfig, NodeMode, RPCMethods, RPCRateLimit,
-    RPCServerConfig,
+    ChainConfig, ConsensusConfig, FeeConfig, MemBlockConfig, MemPoolConfig, NodeMode, RPCMethods,
+    RPCRateLimit, RPCServerConfig,
 };
 use gw_dynamic_config::manager::{DynamicConfigManager, DynamicConfigReloadResponse};
 use gw_generator::utils::get_tx_type;
 use gw_generator::{
-    error::TransactionError, sudt::build_l2_sudt_script,
-    verification::transaction::TransactionVerifier, ArcSwap, Generator,
+    constants::L2TX_MAX_CYCLES, error::TransactionError, sudt::build_l2_sudt_script,
+    syscalls::syscall_name, traits::StateExt, verification::transaction::TransactionVerifier,
+    ArcSwap, Generator,
 };
 use gw_jsonrpc_types::godwoken::L2WithdrawalCommittedInfo;
 use gw_jsonrpc_types::{
     blockchain::Script,
     ckb_jsonrpc_types::{JsonBytes, Uint32},
     godwoken::{
-        BackendInfo, BackendType, EoaScript, EoaScriptType, ErrorTxReceipt, GlobalState, GwScript,
-        GwScriptType, L2BlockCommittedInfo, L2BlockStatus, L2BlockView, L2BlockWithStatus,
-        L2TransactionStatus, L2TransactionWithStatus, LastL2BlockCommittedInfo, NodeInfo,
-        NodeRollupConfig, RegistryAddress, RollupCell, RunResult, TxReceipt, WithdrawalStatus,
-        WithdrawalWithStatus,
+        AccountOverride, AccountProof, BackendInfo, BackendType, BackupManifest, BalanceProofItem,
+        CyclesEstimation, EoaScript, EoaScriptType, ErrorTxReceipt, ExecutionProfile, GlobalState,
+        GwScript, GwScriptType, IndexedLogItem, L2BlockCommittedInfo, L2BlockStatus, L2BlockView,
+        L2BlockWithStatus, L2TransactionStatus, L2TransactionWithStatus, LastL2BlockCommittedInfo,
+        LogCursor, LogFilter, LogPage, MemBlockStatus, MemPoolStatus, NodeInfo, NodeRollupConfig,
+        PcSample, PendingRequest, PendingRequestKind, PendingRequestPage, PendingRequestStatus,
+        ProofRequest, RegistryAddress, RollupCell, RunResult, StateKeyValue, StorageProofItem,
+        SyscallTrace, TransactionTrace, TxReceipt, WithdrawalStatus, WithdrawalWithStatus,
     },
     test_mode::TestModePayload,
 };
 use gw_mem_pool::{
     custodian::AvailableCustodians,
     fee::{
-        queue::FeeQueue,
-        types::{FeeEntry, FeeItem},
+        estimator::FeeRateEstimator,
+        queue::{EvictionStats, FeeQueue},
+        types::{FeeEntry, FeeItem, FeeItemKind},
     },
+    types::EntryList,
 };
 use gw_polyjuice_sender_recover::{
     mem_execute_tx_state::MemExecuteTxStateTree, recover::PolyjuiceSenderRecover,
@@ -40,9 +56,11 @@ use gw_polyjuice_sender_recover::{
 use gw_rpc_client::rpc_client::RPCClient;
 use gw_store::{
     chain_view::ChainView,
+    log_index::{LogFilter as StoreLogFilter, LogKey, LogRecord},
     mem_pool_state::{MemPoolState, MemStore},
     state::state_db::StateContext,
     traits::chain_store::ChainStore,
+    transaction::StoreTransaction,
     CfMemStat, Store,
 };
 use gw_traits::CodeStore;
@@ -50,6 +68,7 @@ use gw_types::offchain::RollupContext;
 use gw_types::packed::RawL2Transaction;
 use gw_types::{
     bytes::Bytes,
+    core::ScriptHashType,
     packed::{self, BlockInfo, Byte32, L2Transaction, RollupConfig, WithdrawalRequestExtra},
     prelude::*,
     U256,
@@ -60,7 +79,9 @@ use lru::LruCache;
 use once_cell::sync::Lazy;
 use pprof::ProfilerGuard;
 use std::{
+    collections::{HashMap, HashSet},
     convert::{TryFrom, TryInto},
+    path::{Component, Path, PathBuf},
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -85,15 +106,19 @@ type RegistryAddressJsonBytes = JsonBytes;
 
 const HEADER_NOT_FOUND_ERR_CODE: i64 = -32000;
 const INVALID_NONCE_ERR_CODE: i64 = -32001;
-const BUSY_ERR_CODE: i64 = -32006;
+pub(crate) const BUSY_ERR_CODE: i64 = -32006;
 const CUSTODIAN_NOT_ENOUGH_CODE: i64 = -32007;
+const REPLACEMENT_UNDERPRICED_ERR_CODE: i64 = -32008;
+pub(crate) const REQUEST_TIMEOUT_ERR_CODE: i64 = -32009;
+const STATE_PRUNED_ERR_CODE: i64 = -32010;
 const INTERNAL_ERROR_ERR_CODE: i64 = -32099;
-const INVALID_REQUEST: i64 = -32600;
+pub(crate) const INVALID_REQUEST: i64 = -32600;
 const METHOD_NOT_AVAILABLE_ERR_CODE: i64 = -32601;
 const INVALID_PARAM_ERR_CODE: i64 = -32602;
-const RATE_LIMIT_ERR_CODE: i64 = -32603;
+pub(crate) const RATE_LIMIT_ERR_CODE: i64 = -32603;
 
 type SendTransactionRateLimiter = Mutex<LruCache<u32, Instant>>;
+type RequestQueue = Arc<Mutex<FeeQueue<InQueueRequestHandle>>>;
 
 fn rate_limit_err() -> RpcError {
     RpcError::Provided {
@@ -116,6 +141,22 @@ fn mem_pool_is_disabled_err() -> RpcError {
     }
 }
 
+// History state of blocks before the pruned block number is unavailable
+fn check_state_pruned(db: &StoreTransaction, block_number: u64) -> Result<(), RpcError> {
+    let pruned_number = db.get_block_state_record_pruned_number()?;
+    if block_number < pruned_number {
+        return Err(RpcError::Full {
+            code: STATE_PRUNED_ERR_CODE,
+            message: format!(
+                "state pruned, history state is available from block {}",
+                pruned_number
+            ),
+            data: None,
+        });
+    }
+    Ok(())
+}
+
 fn invalid_param_err(msg: &'static str) -> RpcError {
     RpcError::Provided {
         code: INVALID_PARAM_ERR_CODE,
@@ -129,12 +170,12 @@ pub trait TestModeRPC {
     async fn produce_block(&self, payload: TestModePayload) -> Result<()>;
 }
 
-fn to_h256(v: JsonH256) -> H256 {
+pub(crate) fn to_h256(v: JsonH256) -> H256 {
     let h: [u8; 32] = v.into();
     h.into()
 }
 
-fn to_jsonh256(v: H256) -> JsonH256 {
+pub(crate) fn to_jsonh256(v: H256) -> JsonH256 {
     let h: [u8; 32] = v.into();
     h.into()
 }
@@ -150,12 +191,44 @@ pub struct ExecutionTransactionContext {
 pub struct SubmitTransactionContext {
     in_queue_request_map: Option<Arc<InQueueRequestMap>>,
     submit_tx: mpsc::Sender<(InQueueRequestHandle, Request)>,
+    queue: RequestQueue,
+    generator: Arc<Generator>,
+    dynamic_config_manager: Arc<ArcSwap<DynamicConfigManager>>,
+    max_future_entries_per_account: usize,
     mem_pool_state: Arc<MemPoolState>,
     rate_limiter: Option<SendTransactionRateLimiter>,
     rate_limit_config: Option<RPCRateLimit>,
     polyjuice_sender_recover: Arc<PolyjuiceSenderRecover>,
 }
 
+pub struct FeeEstimationContext {
+    mem_pool_state: Arc<MemPoolState>,
+    generator: Arc<Generator>,
+    dynamic_config_manager: Arc<ArcSwap<DynamicConfigManager>>,
+    queue: RequestQueue,
+    max_block_txs: usize,
+    // fee rates of recent blocks indexed by block hash, so a block is only
+    // sampled once
+    block_fee_rates: std::sync::Mutex<HashMap<H256, Arc<BlockFeeRates>>>,
+}
+
+// Non-zero fee rates of txs and withdrawals committed in a block
+#[derive(Default)]
+struct BlockFeeRates {
+    polyjuice: Vec<u128>,
+    others: Vec<u128>,
+}
+
+pub struct PendingRequestsContext {
+    queue: RequestQueue,
+    mem_pool: MemPool,
+    mem_block_config: MemBlockConfig,
+}
+
+pub struct BackupContext {
+    backup_dir: Option<PathBuf>,
+}
+
 pub struct RegistryArgs<T> {
     pub store: Store,
     pub mem_pool: MemPool,
@@ -184,6 +257,7 @@ pub struct Registry {
     backend_info: Vec<BackendInfo>,
     node_mode: NodeMode,
     submit_tx: mpsc::Sender<(InQueueRequestHandle, Request)>,
+    queue: RequestQueue,
     rpc_client: RPCClient,
     send_tx_rate_limit: Option<RPCRateLimit>,
     server_config: RPCServerConfig,
@@ -237,12 +311,15 @@ impl Registry {
             None
         };
         let (submit_tx, submit_rx) = mpsc::channel(RequestSubmitter::MAX_CHANNEL_SIZE);
+        let queue = Arc::new(Mutex::new(FeeQueue::with_config(
+            mem_pool_config.fee_queue.clone(),
+        )));
         let polyjuice_sender_recover = Arc::new(polyjuice_sender_recover);
         if let Some(mem_pool) = mem_pool.as_ref().to_owned() {
             let submitter = RequestSubmitter {
                 mem_pool: Arc::clone(mem_pool),
                 submit_rx,
-                queue: FeeQueue::new(),
+                queue: Arc::clone(&queue),
                 dynamic_config_manager: dynamic_config_manager.clone(),
                 generator: generator.clone(),
                 mem_pool_state: mem_pool_state.clone(),
@@ -263,6 +340,7 @@ impl Registry {
             backend_info,
             node_mode,
             submit_tx,
+            queue,
             rpc_client,
             send_tx_rate_limit,
             server_config,
@@ -276,6 +354,14 @@ impl Registry {
         }
     }
 
+    pub fn server_config(&self) -> &RPCServerConfig {
+        &self.server_config
+    }
+
+    pub fn dynamic_config_manager(&self) -> Arc<ArcSwap<DynamicConfigManager>> {
+        self.dynamic_config_manager.clone()
+    }
+
     pub fn build_rpc_server(self) -> Result<RPCServer> {
         let mut server = JsonrpcServer::new();
 
@@ -292,9 +378,29 @@ impl Registry {
                 mem_pool_state: self.mem_pool_state.clone(),
                 polyjuice_sender_recover: self.polyjuice_sender_recover.clone(),
             }))
+            .with_data(Data::new(FeeEstimationContext {
+                mem_pool_state: self.mem_pool_state.clone(),
+                generator: self.generator.clone(),
+                dynamic_config_manager: self.dynamic_config_manager.clone(),
+                queue: self.queue.clone(),
+                max_block_txs: self.mem_pool_config.mem_block.max_txs,
+                block_fee_rates: Default::default(),
+            }))
+            .with_data(Data::new(PendingRequestsContext {
+                queue: self.queue.clone(),
+                mem_pool: self.mem_pool.clone(),
+                mem_block_config: self.mem_pool_config.mem_block.clone(),
+            }))
             .with_data(Data::new(SubmitTransactionContext {
                 in_queue_request_map: self.in_queue_request_map.clone(),
                 submit_tx: self.submit_tx,
+                queue: self.queue,
+                generator: self.generator.clone(),
+                dynamic_config_manager: self.dynamic_config_manager.clone(),
+                max_future_entries_per_account: self
+                    .mem_pool_config
+                    .fee_queue
+                    .max_future_entries_per_account,
                 mem_pool_state: self.mem_pool_state.clone(),
                 rate_limiter: send_transaction_rate_limiter,
                 rate_limit_config: self.send_tx_rate_limit,
@@ -321,6 +427,7 @@ impl Registry {
             .with_method("gw_get_block_committed_info", get_block_committed_info)
             .with_method("gw_get_balance", get_balance)
             .with_method("gw_get_storage_at", get_storage_at)
+            .with_method("gw_get_proof", get_proof)
             .with_method(
                 "gw_get_account_id_by_script_hash",
                 get_account_id_by_script_hash,
@@ -340,13 +447,17 @@ impl Registry {
             .with_method("gw_get_transaction", get_transaction)
             .with_method("gw_get_transaction_receipt", get_transaction_receipt)
             .with_method("gw_get_withdrawal", get_withdrawal)
+            .with_method("gw_get_logs", get_logs)
             .with_method("gw_execute_l2transaction", execute_l2transaction)
             .with_method("gw_execute_raw_l2transaction", execute_raw_l2transaction)
+            .with_method("gw_estimate_cycles", estimate_cycles)
             .with_method(
                 "gw_compute_l2_sudt_script_hash",
                 compute_l2_sudt_script_hash,
             )
             .with_method("gw_get_fee_config", get_fee_config)
+            .with_method("gw_estimate_fee_rate", estimate_fee_rate)
+            .with_method("gw_estimate_gas_price", estimate_gas_price)
             .with_method("gw_get_mem_pool_state_root", get_mem_pool_state_root)
             .with_method("gw_get_mem_pool_state_ready", get_mem_pool_state_ready)
             .with_method("gw_get_node_info", get_node_info)
@@ -386,6 +497,32 @@ impl Registry {
                         .with_method("gw_get_rocksdb_mem_stats", get_rocksdb_memory_stats)
                         .with_method("gw_dump_jemalloc_profiling", dump_jemalloc_profiling);
                 }
+                RPCMethods::Debug => {
+                    server = server
+                        .with_method("gw_debug_trace_transaction", debug_trace_transaction)
+                        .with_method(
+                            "gw_debug_profile_raw_l2transaction",
+                            debug_profile_raw_l2transaction,
+                        );
+                    // These walk the mem pool queues under its lock, keep them
+                    // away from public clients.
+                    if self.node_mode != NodeMode::ReadOnly {
+                        server = server
+                            .with_method(
+                                "gw_get_pending_requests_by_account",
+                                get_pending_requests_by_account,
+                            )
+                            .with_method("gw_list_pending_requests", list_pending_requests)
+                            .with_method("gw_get_mem_pool_status", get_mem_pool_status);
+                    }
+                }
+                RPCMethods::Admin => {
+                    server = server
+                        .with_data(Data::new(BackupContext {
+                            backup_dir: self.server_config.backup_dir.clone(),
+                        }))
+                        .with_method("gw_admin_create_backup", admin_create_backup);
+                }
             }
         }
 
@@ -418,7 +555,7 @@ impl Request {
 struct RequestSubmitter {
     mem_pool: Arc<Mutex<gw_mem_pool::pool::MemPool>>,
     submit_rx: mpsc::Receiver<(InQueueRequestHandle, Request)>,
-    queue: FeeQueue<InQueueRequestHandle>,
+    queue: RequestQueue,
     dynamic_config_manager: Arc<ArcSwap<DynamicConfigManager>>,
     generator: Arc<Generator>,
     mem_pool_state: Arc<MemPoolState>,
@@ -464,6 +601,49 @@ impl RequestSubmitter {
     const MAX_BATCH_SIZE: usize = 20;
     const INTERVAL_MS: Duration = Duration::from_millis(100);
 
+    fn add_to_queue(
+        &self,
+        queue: &mut FeeQueue<InQueueRequestHandle>,
+        handle: InQueueRequestHandle,
+        req: Request,
+        state: &(impl State + CodeStore),
+    ) {
+        let kind = req.kind();
+        let hash = req.hash();
+        let dynamic_config_manager = self.dynamic_config_manager.load();
+        let fee_config = dynamic_config_manager.get_fee_config();
+        let entry = match req_to_entry(fee_config, self.generator.clone(), req, state, queue.len())
+        {
+            Ok(entry) => entry,
+            Err(err) => {
+                log::error!(
+                    "Failed to convert req to entry kind: {}, hash: {}, err: {}",
+                    kind,
+                    hash,
+                    err
+                );
+                return;
+            }
+        };
+        // Note: the replaced or rejected handle is dropped here, it removes
+        // the request from the in queue request map.
+        match queue.add(entry, handle) {
+            Ok(Some((replaced, _handle))) => {
+                log::info!(
+                    "replace {:?} {} by {} {}",
+                    replaced.item.kind(),
+                    replaced.item.hash().pack(),
+                    kind,
+                    hash
+                );
+            }
+            Ok(None) => {}
+            Err(err) => {
+                log::info!("reject {} {}, err: {}", kind, hash, err);
+            }
+        }
+    }
+
     async fn in_background(mut self) {
         // First mem pool reinject txs
         {
@@ -491,6 +671,7 @@ impl RequestSubmitter {
             }
         }
 
+        let mut reported_eviction_stats = EvictionStats::default();
         loop {
             // check mem block empty slots
             loop {
@@ -511,10 +692,8 @@ impl RequestSubmitter {
             }
 
             // mem-pool can process more txs
-            let queue = &mut self.queue;
-
             // wait next tx if queue is empty
-            if queue.is_empty() {
+            if self.queue.lock().await.is_empty() {
                 // blocking current task until we receive a tx
                 let (handle, req) = match self.submit_rx.recv().await {
                     Some(req) => req,
@@ -525,46 +704,16 @@ impl RequestSubmitter {
                 };
                 let snap = self.mem_pool_state.load();
                 let state = snap.state().expect("get mem state");
-                let kind = req.kind();
-                let hash = req.hash();
-                let dynamic_config_manager = self.dynamic_config_manager.load();
-                let fee_config = dynamic_config_manager.get_fee_config();
-                match req_to_entry(fee_config, self.generator.clone(), req, &state, queue.len()) {
-                    Ok(entry) => {
-                        queue.add(entry, handle);
-                    }
-                    Err(err) => {
-                        log::error!(
-                            "Failed to convert req to entry kind: {}, hash: {}, err: {}",
-                            kind,
-                            hash,
-                            err
-                        );
-                    }
-                }
+                let mut queue = self.queue.lock().await;
+                self.add_to_queue(&mut queue, handle, req, &state);
             }
 
             // push txs to fee priority queue
             let snap = self.mem_pool_state.load();
             let state = snap.state().expect("get mem state");
+            let mut queue = self.queue.lock().await;
             while let Ok((handle, req)) = self.submit_rx.try_recv() {
-                let kind = req.kind();
-                let hash = req.hash();
-                let dynamic_config_manager = self.dynamic_config_manager.load();
-                let fee_config = dynamic_config_manager.get_fee_config();
-                match req_to_entry(fee_config, self.generator.clone(), req, &state, queue.len()) {
-                    Ok(entry) => {
-                        queue.add(entry, handle);
-                    }
-                    Err(err) => {
-                        log::error!(
-                            "Failed to convert req to entry kind: {}, hash: {}, err: {}",
-                            kind,
-                            hash,
-                            err
-                        );
-                    }
-                }
+                self.add_to_queue(&mut queue, handle, req, &state);
             }
 
             // fetch items from PQ
@@ -580,6 +729,8 @@ impl RequestSubmitter {
                     continue;
                 }
             };
+            report_fee_queue_metrics(&queue, &mut reported_eviction_stats);
+            drop(queue);
 
             if !items.is_empty() {
                 // recover accounts for polyjuice tx from id zero
@@ -613,11 +764,15 @@ impl RequestSubmitter {
                 let snap = self.mem_pool_state.load();
                 let state = snap.state().expect("get mem state");
 
-                // Note: don't change `_handle` to `_`. The request should be
-                // removed from the in queue request map after pushed to mem
-                // pool.
-                for (entry, _handle) in items {
-                    let maybe_ok = match entry.item.clone() {
+                // Note: keep the handles until the requests are pushed. The
+                // request should be removed from the in queue request map
+                // after pushed to mem pool.
+                //
+                // Consecutive txs are pushed in batch, so they can be executed
+                // in parallel.
+                let mut txs = Vec::new();
+                for (entry, handle) in items {
+                    match entry.item.clone() {
                         FeeItem::Tx(tx) if 0 == entry.sender => {
                             let sig: Bytes = tx.signature().unpack();
                             let sender_id = match recovered_senders.get_account_id(&sig, &state) {
@@ -638,24 +793,68 @@ impl RequestSubmitter {
                                 tx.hash().pack()
                             );
 
-                            mem_pool.push_transaction(tx).await
+                            txs.push((entry, handle, tx));
                         }
-                        FeeItem::Tx(tx) => mem_pool.push_transaction(tx).await,
+                        FeeItem::Tx(tx) => txs.push((entry, handle, tx)),
                         FeeItem::Withdrawal(withdrawal) => {
-                            mem_pool.push_withdrawal_request(withdrawal).await
+                            push_transactions(&mut mem_pool, std::mem::take(&mut txs)).await;
+                            if let Err(err) = mem_pool.push_withdrawal_request(withdrawal).await {
+                                let hash: Byte32 = entry.item.hash().pack();
+                                log::info!("push {:?} {} failed {}", entry.item.kind(), hash, err);
+                            }
                         }
-                    };
-
-                    if let Err(err) = maybe_ok {
-                        let hash: Byte32 = entry.item.hash().pack();
-                        log::info!("push {:?} {} failed {}", entry.item.kind(), hash, err);
                     }
                 }
+                push_transactions(&mut mem_pool, txs).await;
             }
         }
     }
 }
 
+async fn push_transactions(
+    mem_pool: &mut gw_mem_pool::pool::MemPool,
+    txs: Vec<(FeeEntry, InQueueRequestHandle, L2Transaction)>,
+) {
+    if txs.is_empty() {
+        return;
+    }
+
+    let (entries, txs): (Vec<_>, Vec<_>) = txs
+        .into_iter()
+        .map(|(entry, handle, tx)| ((entry, handle), tx))
+        .unzip();
+    let results = mem_pool.push_transactions(txs).await;
+    for ((entry, _handle), result) in entries.into_iter().zip(results) {
+        if let Err(err) = result {
+            let hash: Byte32 = entry.item.hash().pack();
+            log::info!("push {:?} {} failed {}", entry.item.kind(), hash, err);
+        }
+    }
+}
+
+// Export queue lengths and entries evicted since the last report
+fn report_fee_queue_metrics(queue: &FeeQueue<InQueueRequestHandle>, reported: &mut EvictionStats) {
+    let metrics = gw_metrics::mem_pool();
+    metrics.fee_queue_len.set(queue.len() as i64);
+    metrics.fee_queue_future_len.set(queue.future_len() as i64);
+
+    let stats = *queue.eviction_stats();
+    let evicted = [
+        ("expired", stats.expired - reported.expired),
+        ("sender_limit", stats.sender_limit - reported.sender_limit),
+        ("low_fee_rate", stats.low_fee_rate - reported.low_fee_rate),
+    ];
+    for (reason, count) in evicted {
+        if count > 0 {
+            metrics
+                .fee_queue_evicted
+                .with_label_values(&[reason])
+                .inc_by(count);
+        }
+    }
+    *reported = stats;
+}
+
 async fn ping() -> Result<String> {
     Ok("pong".to_string())
 }
@@ -747,6 +946,179 @@ async fn is_request_in_queue(
         .map_or(false, |m| m.contains(&hash)))
 }
 
+// Max number of requests returned by gw_list_pending_requests
+const MAX_PENDING_REQUESTS_PAGE_SIZE: u32 = 100;
+
+fn queued_pending_request(entry: &FeeEntry, added_at: Instant, is_future: bool) -> PendingRequest {
+    let kind = match entry.item.kind() {
+        FeeItemKind::Tx => PendingRequestKind::Tx,
+        FeeItemKind::Withdrawal => PendingRequestKind::Withdrawal,
+    };
+    let status = if is_future {
+        PendingRequestStatus::Future
+    } else {
+        PendingRequestStatus::Queued
+    };
+    PendingRequest {
+        hash: to_jsonh256(entry.item.hash()),
+        kind,
+        status,
+        account_id: entry.sender.into(),
+        nonce: entry.item.nonce().into(),
+        fee_rate: Some(entry.fee_rate().into()),
+        age_secs: Some(added_at.elapsed().as_secs().into()),
+    }
+}
+
+fn mem_pool_pending_requests(
+    account_id: u32,
+    list: &EntryList,
+) -> impl Iterator<Item = PendingRequest> + '_ {
+    let txs = list.txs.iter().map(move |tx| PendingRequest {
+        hash: to_jsonh256(tx.hash().into()),
+        kind: PendingRequestKind::Tx,
+        status: PendingRequestStatus::MemPool,
+        account_id: account_id.into(),
+        nonce: Unpack::<u32>::unpack(&tx.raw().nonce()).into(),
+        fee_rate: None,
+        age_secs: None,
+    });
+    let withdrawals = list
+        .withdrawals
+        .iter()
+        .map(move |withdrawal| PendingRequest {
+            hash: to_jsonh256(withdrawal.hash().into()),
+            kind: PendingRequestKind::Withdrawal,
+            status: PendingRequestStatus::MemPool,
+            account_id: account_id.into(),
+            nonce: Unpack::<u32>::unpack(&withdrawal.raw().nonce()).into(),
+            fee_rate: None,
+            age_secs: None,
+        });
+    let future_withdrawals =
+        (list.future_withdrawals.iter()).map(move |(withdrawal, added_at)| PendingRequest {
+            hash: to_jsonh256(withdrawal.hash().into()),
+            kind: PendingRequestKind::Withdrawal,
+            status: PendingRequestStatus::InsufficientBalance,
+            account_id: account_id.into(),
+            nonce: Unpack::<u32>::unpack(&withdrawal.raw().nonce()).into(),
+            fee_rate: None,
+            age_secs: Some(added_at.elapsed().as_secs().into()),
+        });
+    txs.chain(withdrawals).chain(future_withdrawals)
+}
+
+async fn get_pending_requests_by_account(
+    Params((account_id,)): Params<(AccountID,)>,
+    ctx: Data<PendingRequestsContext>,
+) -> Result<Vec<PendingRequest>> {
+    let account_id: u32 = account_id.value();
+
+    // Note: don't hold the queue lock while locking the mem pool
+    let mut requests: Vec<PendingRequest> = {
+        let queue = ctx.queue.lock().await;
+        (queue.sender_entries(account_id).into_iter())
+            .map(|(entry, added_at, is_future)| queued_pending_request(entry, added_at, is_future))
+            .collect()
+    };
+
+    if let Some(mem_pool) = ctx.mem_pool.as_ref() {
+        let mem_pool = mem_pool.lock().await;
+        if let Some(list) = mem_pool.pending().get(&account_id) {
+            requests.extend(mem_pool_pending_requests(account_id, list));
+        }
+    }
+
+    Ok(requests)
+}
+
+async fn list_pending_requests(
+    Params((offset, limit)): Params<(Uint32, Uint32)>,
+    ctx: Data<PendingRequestsContext>,
+) -> Result<PendingRequestPage, RpcError> {
+    let offset = offset.value() as usize;
+    let limit = limit.value();
+    if limit > MAX_PENDING_REQUESTS_PAGE_SIZE {
+        return Err(invalid_param_err("limit is too large"));
+    }
+    let limit = limit as usize;
+
+    // requests in the fee queue sorted by fee rate, then future requests
+    let (mut requests, queue_total) = {
+        let queue = ctx.queue.lock().await;
+        let queued = queue
+            .iter()
+            .map(|(entry, added_at)| (entry, added_at, false));
+        let future = (queue.future_iter()).map(|(entry, added_at)| (entry, added_at, true));
+        let requests: Vec<PendingRequest> = (queued.chain(future))
+            .skip(offset)
+            .take(limit)
+            .map(|(entry, added_at, is_future)| queued_pending_request(entry, added_at, is_future))
+            .collect();
+        (requests, queue.len() + queue.future_len())
+    };
+
+    // then requests in the mem pool sorted by account id
+    let mut mem_pool_total = 0;
+    if let Some(mem_pool) = ctx.mem_pool.as_ref() {
+        let mem_pool = mem_pool.lock().await;
+        let pending = mem_pool.pending();
+        let mut account_ids: Vec<u32> = pending.keys().copied().collect();
+        account_ids.sort_unstable();
+        mem_pool_total = (pending.values())
+            .map(|list| list.txs.len() + list.withdrawals.len() + list.future_withdrawals.len())
+            .sum();
+
+        let remaining = limit - requests.len();
+        if remaining > 0 {
+            let mem_pool_requests = (account_ids.into_iter())
+                .filter_map(|account_id| {
+                    let list = pending.get(&account_id)?;
+                    Some(mem_pool_pending_requests(account_id, list))
+                })
+                .flatten()
+                .skip(offset.saturating_sub(queue_total))
+                .take(remaining);
+            requests.extend(mem_pool_requests);
+        }
+    }
+
+    let total = queue_total + mem_pool_total;
+    Ok(PendingRequestPage {
+        requests,
+        total: (total as u32).into(),
+    })
+}
+
+async fn get_mem_pool_status(ctx: Data<PendingRequestsContext>) -> Result<MemPoolStatus, RpcError> {
+    let (queue_len, future_queue_len, oldest_added_at) = {
+        let queue = ctx.queue.lock().await;
+        (queue.len(), queue.future_len(), queue.oldest_added_at())
+    };
+
+    let mem_pool = match ctx.mem_pool.as_ref() {
+        Some(mem_pool) => mem_pool.lock().await,
+        None => return Err(mem_pool_is_disabled_err()),
+    };
+    let mem_block = mem_pool.mem_block();
+    let config = &ctx.mem_block_config;
+
+    Ok(MemPoolStatus {
+        queue_len: (queue_len as u32).into(),
+        future_queue_len: (future_queue_len as u32).into(),
+        pending_accounts: (mem_pool.pending().len() as u32).into(),
+        mem_block: MemBlockStatus {
+            txs: (mem_block.txs().len() as u32).into(),
+            max_txs: (config.max_txs as u32).into(),
+            withdrawals: (mem_block.withdrawals().len() as u32).into(),
+            max_withdrawals: (config.max_withdrawals as u32).into(),
+            deposits: (mem_block.deposits().len() as u32).into(),
+            max_deposits: (config.max_deposits as u32).into(),
+        },
+        oldest_entry_age_secs: oldest_added_at.map(|added_at| added_at.elapsed().as_secs().into()),
+    })
+}
+
 async fn get_block_committed_info(
     Params((block_hash,)): Params<(JsonH256,)>,
     store: Data<Store>,
@@ -849,6 +1221,228 @@ async fn get_transaction_receipt(
         .map(Into::into))
 }
 
+pub(crate) fn to_indexed_log_item(record: LogRecord) -> IndexedLogItem {
+    IndexedLogItem {
+        block_number: record.key.block_number().into(),
+        tx_hash: to_jsonh256(record.tx_hash),
+        tx_index: record.key.tx_index().into(),
+        log_index: record.key.log_index().into(),
+        log: record.log.into(),
+    }
+}
+
+// Max number of blocks to query by gw_get_logs
+const MAX_LOGS_BLOCK_RANGE: u64 = 10_000;
+// Max number of logs returned by gw_get_logs
+const MAX_LOGS_PAGE_SIZE: u32 = 1_000;
+
+async fn get_logs(
+    Params((filter,)): Params<(LogFilter,)>,
+    store: Data<Store>,
+) -> Result<LogPage, RpcError> {
+    let from_block = filter.from_block.value();
+    let to_block = filter.to_block.value();
+    if from_block > to_block {
+        return Err(invalid_param_err("from_block is greater than to_block"));
+    }
+    if to_block - from_block >= MAX_LOGS_BLOCK_RANGE {
+        return Err(invalid_param_err("block range is too large"));
+    }
+    let limit = filter
+        .limit
+        .map_or(MAX_LOGS_PAGE_SIZE, |limit| limit.value());
+    if limit == 0 {
+        return Err(invalid_param_err("limit is zero"));
+    }
+    if limit > MAX_LOGS_PAGE_SIZE {
+        return Err(invalid_param_err("limit is too large"));
+    }
+
+    let store_filter = StoreLogFilter {
+        from_block,
+        to_block,
+        account_id: filter.account_id.map(|id| id.value()),
+        topic: filter.topic.map(to_h256),
+    };
+    let start = filter.cursor.map(|cursor| {
+        LogKey::new(
+            cursor.block_number.value(),
+            cursor.tx_index.value(),
+            cursor.log_index.value(),
+        )
+    });
+    let to_cursor = |key: LogKey| LogCursor {
+        block_number: key.block_number().into(),
+        tx_index: key.tx_index().into(),
+        log_index: key.log_index().into(),
+    };
+
+    let db = store.begin_transaction();
+    let (records, next) = db.get_logs(&store_filter, start, limit as usize)?;
+    let logs = records.into_iter().map(to_indexed_log_item).collect();
+
+    Ok(LogPage {
+        logs,
+        next_cursor: next.map(to_cursor),
+    })
+}
+
+// Max number of overridden accounts in an execute request
+const MAX_ACCOUNT_OVERRIDES: usize = 32;
+// Max number of balances, storage and data entries of all overridden accounts
+const MAX_OVERRIDE_ENTRIES: usize = 1024;
+
+fn check_state_overrides(overrides: &[AccountOverride]) -> Result<(), RpcError> {
+    if overrides.len() > MAX_ACCOUNT_OVERRIDES {
+        return Err(invalid_param_err("too many account overrides"));
+    }
+    let entries: usize = overrides
+        .iter()
+        .map(|o| o.balances.len() + o.storage.len() + o.data.len())
+        .sum();
+    if entries > MAX_OVERRIDE_ENTRIES {
+        return Err(invalid_param_err("too many override entries"));
+    }
+    Ok(())
+}
+
+// An invalid override is an invalid param, the message names the field
+fn invalid_override_err(field: &str, account_id: u32, reason: &str) -> RpcError {
+    RpcError::Full {
+        code: INVALID_PARAM_ERR_CODE,
+        message: format!(
+            "invalid override {} of account {}: {}",
+            field, account_id, reason
+        ),
+        data: None,
+    }
+}
+
+// Apply overrides on an execute state overlay, like eth_call state overrides.
+// Overridden values are only visible to the execution, they are never committed.
+fn apply_state_overrides<S: State + CodeStore>(
+    state: &mut S,
+    overrides: Vec<AccountOverride>,
+) -> Result<(), RpcError> {
+    for account in overrides {
+        let account_id: u32 = account.account_id.value();
+        let mut script_hash = state.get_script_hash(account_id)?;
+        if script_hash.is_zero() {
+            return Err(invalid_override_err(
+                "account_id",
+                account_id,
+                "account not found",
+            ));
+        }
+
+        if let Some(script) = account.script {
+            let script = packed::Script::from(script);
+            // Same as accounts created by deposits or meta contract
+            if script.hash_type() != ScriptHashType::Type.into() {
+                return Err(invalid_override_err(
+                    "script",
+                    account_id,
+                    "hash type must be type",
+                ));
+            }
+            let new_script_hash: H256 = script.hash().into();
+            if new_script_hash != script_hash {
+                if state
+                    .get_account_id_by_script_hash(&new_script_hash)?
+                    .is_some()
+                {
+                    return Err(invalid_override_err(
+                        "script",
+                        account_id,
+                        "script is used by another account",
+                    ));
+                }
+
+                // script hash <-> account id
+                state.update_raw(
+                    build_script_hash_to_account_id_key(script_hash.as_slice()),
+                    H256::zero(),
+                )?;
+                let script_hash_to_id_value: H256 = {
+                    let mut buf: [u8; 32] = H256::from_u32(account_id).into();
+                    // the first 4 bytes is id, set exists flag(fifth byte) to 1
+                    buf[4] = 1;
+                    buf.into()
+                };
+                state.update_raw(
+                    build_script_hash_to_account_id_key(new_script_hash.as_slice()),
+                    script_hash_to_id_value,
+                )?;
+                state.update_raw(
+                    build_account_field_key(account_id, GW_ACCOUNT_SCRIPT_HASH_TYPE),
+                    new_script_hash,
+                )?;
+
+                // Move registry address to the new script hash
+                if let Some(addr) = state
+                    .get_registry_address_by_script_hash(ETH_REGISTRY_ACCOUNT_ID, &script_hash)?
+                {
+                    state.update_value(
+                        addr.registry_id,
+                        &build_script_hash_to_registry_address_key(&script_hash),
+                        H256::zero(),
+                    )?;
+                    state.update_value(
+                        addr.registry_id,
+                        &build_registry_address_to_script_hash_key(&addr),
+                        H256::zero(),
+                    )?;
+                    state.mapping_registry_address_to_script_hash(addr, new_script_hash)?;
+                }
+                script_hash = new_script_hash;
+            }
+            state.insert_script(new_script_hash, script);
+        }
+
+        if let Some(nonce) = account.nonce {
+            state.set_nonce(account_id, nonce.value())?;
+        }
+
+        if !account.balances.is_empty() {
+            let addr = state
+                .get_registry_address_by_script_hash(ETH_REGISTRY_ACCOUNT_ID, &script_hash)?
+                .ok_or_else(|| {
+                    invalid_override_err("balances", account_id, "account has no registry address")
+                })?;
+            let sudt_key = build_sudt_key(SUDT_KEY_FLAG_BALANCE, &addr);
+            for balance in account.balances {
+                if state.get_script_hash(balance.sudt_id.value())?.is_zero() {
+                    return Err(invalid_override_err(
+                        "balances",
+                        account_id,
+                        "sUDT account not found",
+                    ));
+                }
+                state.update_value(
+                    balance.sudt_id.value(),
+                    &sudt_key,
+                    H256::from_u256(balance.balance),
+                )?;
+            }
+        }
+
+        for kv in account.storage {
+            state.update_value(account_id, kv.key.as_bytes(), to_h256(kv.value))?;
+        }
+
+        for data in account.data {
+            let data = data.into_bytes();
+            let mut data_hash = [0u8; 32];
+            let mut hasher = new_blake2b();
+            hasher.update(data.as_ref());
+            hasher.finalize(&mut data_hash);
+            state.store_data_hash(data_hash.into())?;
+            state.insert_data(data_hash.into(), data);
+        }
+    }
+    Ok(())
+}
+
 fn verify_sender_balance<S: State + CodeStore>(
     ctx: &RollupContext,
     state: &S,
@@ -878,15 +1472,30 @@ fn verify_sender_balance<S: State + CodeStore>(
     Ok(())
 }
 
+// l2tx, state overrides
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum ExecuteL2TransactionParams {
+    Default((JsonBytes,)),
+    StateOverrides((JsonBytes, Vec<AccountOverride>)),
+}
+
 #[instrument(skip_all)]
 async fn execute_l2transaction(
-    Params((l2tx,)): Params<(JsonBytes,)>,
+    Params(params): Params<ExecuteL2TransactionParams>,
     ctx: Data<ExecutionTransactionContext>,
 ) -> Result<RunResult, RpcError> {
     if ctx.mem_pool.is_none() {
         return Err(mem_pool_is_disabled_err());
     }
 
+    let (l2tx, overrides) = match params {
+        ExecuteL2TransactionParams::Default(p) => (p.0, vec![]),
+        ExecuteL2TransactionParams::StateOverrides(p) => p,
+    };
+    check_state_overrides(&overrides)?;
+    let has_overrides = !overrides.is_empty();
+
     let l2tx_bytes = l2tx.into_bytes();
     let tx = packed::L2Transaction::from_slice(&l2tx_bytes)?;
 
@@ -907,9 +1516,10 @@ async fn execute_l2transaction(
     let tx_hash = tx.hash();
 
     // check sender's balance
-    // NOTE: for tx from id 0, it's balance will be verified after mock account
+    // NOTE: for tx from id 0 or with state overrides, it's balance will be verified after
+    // mock account
     let from_id: u32 = tx.raw().from_id().unpack();
-    if 0 != from_id {
+    if 0 != from_id && !has_overrides {
         let snap = ctx.mem_pool_state.load();
         let state = snap.state()?;
         if let Err(err) = verify_sender_balance(ctx.generator.rollup_context(), &state, &tx.raw()) {
@@ -928,11 +1538,12 @@ async fn execute_l2transaction(
         let snap = ctx.mem_pool_state.load();
         let mem_state = snap.state()?;
         let mut state = MemExecuteTxStateTree::new(mem_state);
+        apply_state_overrides(&mut state, overrides)?;
 
         // Mock sender account if not exists
         let eth_recover = &ctx.polyjuice_sender_recover.eth;
         let tx = eth_recover.mock_sender_if_not_exists(tx, &mut state)?;
-        if 0 == from_id {
+        if 0 == from_id || has_overrides {
             verify_sender_balance(ctx.generator.rollup_context(), &state, &tx.raw())
                 .map_err(|err| anyhow!("check balance err: {}", err))?;
         }
@@ -951,7 +1562,7 @@ async fn execute_l2transaction(
             100000000,
         )?;
 
-        Result::<_, anyhow::Error>::Ok(run_result)
+        Result::<_, RpcError>::Ok(run_result)
     })
     .await??;
 
@@ -974,13 +1585,21 @@ async fn execute_l2transaction(
     Ok(run_result.into())
 }
 
-// raw_l2tx, block_number
+// raw_l2tx, block_number, registry_address, state overrides
 #[derive(serde::Serialize, serde::Deserialize)]
 #[serde(untagged)]
 enum ExecuteRawL2TransactionParams {
     Tip((JsonBytes,)),
     Number((JsonBytes, Option<GwUint64>)),
     PolyjuiceFromIdZero((JsonBytes, Option<GwUint64>, RegistryAddressJsonBytes)),
+    StateOverrides(
+        (
+            JsonBytes,
+            Option<GwUint64>,
+            Option<RegistryAddressJsonBytes>,
+            Vec<AccountOverride>,
+        ),
+    ),
 }
 
 #[instrument(skip_all)]
@@ -989,11 +1608,14 @@ async fn execute_raw_l2transaction(
     mem_pool_config: Data<MemPoolConfig>,
     ctx: Data<ExecutionTransactionContext>,
 ) -> Result<RunResult, RpcError> {
-    let (raw_l2tx, block_number_opt, registry_address_opt) = match params {
-        ExecuteRawL2TransactionParams::Tip(p) => (p.0, None, None),
-        ExecuteRawL2TransactionParams::Number(p) => (p.0, p.1, None),
-        ExecuteRawL2TransactionParams::PolyjuiceFromIdZero(p) => (p.0, p.1, Some(p.2)),
+    let (raw_l2tx, block_number_opt, registry_address_opt, overrides) = match params {
+        ExecuteRawL2TransactionParams::Tip(p) => (p.0, None, None, vec![]),
+        ExecuteRawL2TransactionParams::Number(p) => (p.0, p.1, None, vec![]),
+        ExecuteRawL2TransactionParams::PolyjuiceFromIdZero(p) => (p.0, p.1, Some(p.2), vec![]),
+        ExecuteRawL2TransactionParams::StateOverrides(p) => p,
     };
+    check_state_overrides(&overrides)?;
+    let has_overrides = !overrides.is_empty();
     let block_number_opt = block_number_opt.map(|n| n.value());
     let registry_address_opt = registry_address_opt
         .map(|json_bytes| {
@@ -1015,6 +1637,7 @@ async fn execute_raw_l2transaction(
                 Some(block_hash) => block_hash,
                 None => return Err(header_not_found_err()),
             };
+            check_state_pruned(&db, block_number)?;
             let raw_block = match ctx.store.get_block(&block_hash)? {
                 Some(block) => block.raw(),
                 None => return Err(header_not_found_err()),
@@ -1039,9 +1662,10 @@ async fn execute_raw_l2transaction(
     let block_number: u64 = block_info.number().unpack();
 
     // check sender's balance
-    // NOTE: for tx from id zero, its balance will be verified after mock account
+    // NOTE: for tx from id zero or with state overrides, its balance will be verified after
+    // mock account
     let from_id: u32 = raw_l2tx.from_id().unpack();
-    if 0 != from_id {
+    if 0 != from_id && !has_overrides {
         let check_balance_result = match block_number_opt {
             Some(block_number) => {
                 let state = db.state_tree(StateContext::ReadOnlyHistory(block_number))?;
@@ -1075,12 +1699,13 @@ async fn execute_raw_l2transaction(
             Some(block_number) => {
                 let hist_state = db.state_tree(StateContext::ReadOnlyHistory(block_number))?;
                 let mut state = MemExecuteTxStateTree::new(hist_state);
+                apply_state_overrides(&mut state, overrides)?;
                 let raw_l2tx = eth_recover.mock_sender_if_not_exists_from_raw_registry(
                     raw_l2tx,
                     registry_address_opt,
                     &mut state,
                 )?;
-                if 0 == from_id {
+                if 0 == from_id || has_overrides {
                     verify_sender_balance(rollup_context, &state, &raw_l2tx)
                         .map_err(|err| anyhow!("check balance err {}", err))?;
                 }
@@ -1096,12 +1721,13 @@ async fn execute_raw_l2transaction(
             None => {
                 let state = mem_state_snap.state()?;
                 let mut state = MemExecuteTxStateTree::new(state);
+                apply_state_overrides(&mut state, overrides)?;
                 let raw_l2tx = eth_recover.mock_sender_if_not_exists_from_raw_registry(
                     raw_l2tx,
                     registry_address_opt,
                     &mut state,
                 )?;
-                if 0 == from_id {
+                if 0 == from_id || has_overrides {
                     verify_sender_balance(rollup_context, &state, &raw_l2tx)
                         .map_err(|err| anyhow!("check balance err {}", err))?;
                 }
@@ -1115,7 +1741,7 @@ async fn execute_raw_l2transaction(
                 )?
             }
         };
-        Result::<_, anyhow::Error>::Ok(run_result)
+        Result::<_, RpcError>::Ok(run_result)
     })
     .await??;
 
@@ -1138,6 +1764,415 @@ async fn execute_raw_l2transaction(
     Ok(run_result.into())
 }
 
+// raw_l2tx, registry_address
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum EstimateCyclesParams {
+    Tip((JsonBytes,)),
+    PolyjuiceFromIdZero((JsonBytes, Option<RegistryAddressJsonBytes>)),
+}
+
+// Max executions to search the minimal gas limit after the gas used fails
+const MAX_GAS_SEARCH_STEPS: usize = 16;
+
+fn read_polyjuice_gas_used(run_result: &gw_types::offchain::RunResult) -> Option<u64> {
+    use gw_utils::script_log::{parse_log, GwLog, GW_LOG_POLYJUICE_SYSTEM};
+
+    let log = run_result
+        .write
+        .logs
+        .iter()
+        .find(|log| u8::from(log.service_flag()) == GW_LOG_POLYJUICE_SYSTEM)?;
+    match parse_log(log) {
+        Ok(GwLog::PolyjuiceSystem { gas_used, .. }) => Some(gas_used),
+        _ => None,
+    }
+}
+
+// Replace the gas limit of Polyjuice args
+fn polyjuice_tx_with_gas(raw_tx: &RawL2Transaction, gas: u64) -> RawL2Transaction {
+    let mut args = raw_tx.args().raw_data().to_vec();
+    args[8..16].copy_from_slice(&gas.to_le_bytes());
+    raw_tx.clone().as_builder().args(args.pack()).build()
+}
+
+#[instrument(skip_all)]
+async fn estimate_cycles(
+    Params(params): Params<EstimateCyclesParams>,
+    mem_pool_config: Data<MemPoolConfig>,
+    ctx: Data<ExecutionTransactionContext>,
+) -> Result<CyclesEstimation, RpcError> {
+    use gw_generator::typed_transaction::types::TypedRawTransaction;
+
+    let (raw_l2tx, registry_address_opt) = match params {
+        EstimateCyclesParams::Tip(p) => (p.0, None),
+        EstimateCyclesParams::PolyjuiceFromIdZero(p) => p,
+    };
+    let registry_address_opt = registry_address_opt
+        .map(|json_bytes| {
+            gw_common::registry_address::RegistryAddress::from_slice(json_bytes.as_bytes())
+                .ok_or_else(|| invalid_param_err("Invalid registry address"))
+        })
+        .transpose()?;
+
+    let raw_l2tx_bytes = raw_l2tx.into_bytes();
+    let raw_l2tx = packed::RawL2Transaction::from_slice(&raw_l2tx_bytes)?;
+
+    let mem_state_snap = ctx.mem_pool_state.load();
+    let block_info = mem_state_snap
+        .get_mem_pool_block_info()?
+        .expect("get mem pool block info");
+    let execute_l2tx_max_cycles = mem_pool_config.execute_l2tx_max_cycles;
+    let tx_hash: H256 = raw_l2tx.hash().into();
+    let block_number: u64 = block_info.number().unpack();
+
+    let (mut run_result, gas_estimation) = tokio::task::spawn_blocking(move || {
+        let db = ctx.store.get_snapshot();
+        let chain_view = {
+            let tip_block_hash = db.get_last_valid_tip_block_hash()?;
+            ChainView::new(&db, tip_block_hash)
+        };
+        let rollup_context = ctx.generator.rollup_context();
+        let mut state = MemExecuteTxStateTree::new(mem_state_snap.state()?);
+        let eth_recover = &ctx.polyjuice_sender_recover.eth;
+        let raw_l2tx = eth_recover.mock_sender_if_not_exists_from_raw_registry(
+            raw_l2tx,
+            registry_address_opt,
+            &mut state,
+        )?;
+        verify_sender_balance(rollup_context, &state, &raw_l2tx)
+            .map_err(|err| anyhow!("check balance err {}", err))?;
+
+        let execute = |raw_tx: &RawL2Transaction| {
+            ctx.generator.unchecked_execute_transaction(
+                &chain_view,
+                &state,
+                &block_info,
+                raw_tx,
+                execute_l2tx_max_cycles,
+            )
+        };
+        let run_result = execute(&raw_l2tx)?;
+        if run_result.exit_code != 0 {
+            return Ok((run_result, None));
+        }
+
+        let polyjuice_tx = match TypedRawTransaction::from_tx(
+            raw_l2tx.clone(),
+            get_tx_type(rollup_context, &state, &raw_l2tx)?,
+        ) {
+            Some(TypedRawTransaction::Polyjuice(tx)) => tx,
+            _ => return Ok((run_result, None)),
+        };
+        let (gas, intrinsic_gas) = match (polyjuice_tx.parser(), polyjuice_tx.intrinsic_gas()) {
+            (Some(parser), Some(intrinsic_gas)) => (parser.gas(), intrinsic_gas),
+            _ => return Ok((run_result, None)),
+        };
+
+        // The tx can't succeed with a gas limit less than its gas used, which is
+        // usually the minimal gas limit, so it's confirmed first. Some txs need
+        // more gas left, e.g. a call only takes 63/64 of the gas left, then the
+        // minimal gas limit is searched in (lo, hi] in limited steps, tx
+        // succeeds with hi.
+        let succeeds = |gas: u64| {
+            let raw_tx = polyjuice_tx_with_gas(&raw_l2tx, gas);
+            matches!(execute(&raw_tx), Ok(run_result) if run_result.exit_code == 0)
+        };
+        let gas_used = read_polyjuice_gas_used(&run_result).unwrap_or(intrinsic_gas);
+        let mut hi = gas;
+        let mut lo = gas_used.max(intrinsic_gas).min(gas);
+        if succeeds(lo) {
+            hi = lo;
+        }
+        for _ in 0..MAX_GAS_SEARCH_STEPS {
+            if hi - lo <= 1 {
+                break;
+            }
+            let mid = lo + (hi - lo) / 2;
+            if succeeds(mid) {
+                hi = mid;
+            } else {
+                lo = mid;
+            }
+        }
+
+        Result::<_, anyhow::Error>::Ok((run_result, Some((hi, intrinsic_gas))))
+    })
+    .await??;
+
+    if run_result.exit_code != 0 {
+        let receipt = gw_types::offchain::ErrorTxReceipt {
+            tx_hash,
+            block_number,
+            return_data: run_result.return_data,
+            last_log: run_result.write.logs.pop(),
+            exit_code: run_result.exit_code,
+        };
+
+        return Err(RpcError::Full {
+            code: INVALID_REQUEST,
+            message: TransactionError::InvalidExitCode(run_result.exit_code).to_string(),
+            data: Some(Box::new(ErrorTxReceipt::from(receipt))),
+        });
+    }
+
+    Ok(CyclesEstimation {
+        used_cycles: run_result.used_cycles.into(),
+        recommended_gas_limit: gas_estimation.map(|(gas, _)| gas.into()),
+        intrinsic_gas: gas_estimation.map(|(_, intrinsic_gas)| intrinsic_gas.into()),
+    })
+}
+
+// Re-execute a committed tx on the state just before it
+async fn debug_trace_transaction(
+    Params((tx_hash,)): Params<(JsonH256,)>,
+    ctx: Data<ExecutionTransactionContext>,
+) -> Result<Option<TransactionTrace>, RpcError> {
+    let tx_hash = to_h256(tx_hash);
+    let db = ctx.store.begin_transaction();
+    let tx_info = match db.get_transaction_info(&tx_hash)? {
+        Some(tx_info) => tx_info,
+        None => return Ok(None),
+    };
+    let (block_hash, tx_index) = {
+        let key: [u8; 36] = tx_info.key().unpack();
+        let mut block_hash = [0u8; 32];
+        block_hash.copy_from_slice(&key[..32]);
+        let mut index = [0u8; 4];
+        index.copy_from_slice(&key[32..]);
+        (H256::from(block_hash), u32::from_be_bytes(index))
+    };
+    let block = db
+        .get_block(&block_hash)?
+        .ok_or_else(|| anyhow!("block {} not found", block_hash.pack()))?;
+    let deposits = db
+        .get_block_deposit_requests(&block_hash)?
+        .ok_or_else(|| anyhow!("block {} deposit requests not found", block_hash.pack()))?;
+    let tx = block
+        .transactions()
+        .get(tx_index as usize)
+        .ok_or_else(|| anyhow!("tx {} not found in block", tx_index))?;
+    let raw_block = block.raw();
+    let block_number: u64 = raw_block.number().unpack();
+    check_state_pruned(&db, block_number.saturating_sub(1))?;
+
+    let generator = Arc::clone(&ctx.generator);
+    let (run_result, syscalls) = tokio::task::spawn_blocking(move || {
+        let rollup_context = generator.rollup_context();
+        let block_info = BlockInfo::new_builder()
+            .block_producer(raw_block.block_producer())
+            .timestamp(raw_block.timestamp())
+            .number(raw_block.number())
+            .build();
+        let block_producer = {
+            let address: Bytes = raw_block.block_producer().unpack();
+            gw_common::registry_address::RegistryAddress::from_slice(&address)
+                .ok_or_else(|| anyhow!("invalid block producer address"))?
+        };
+        let parent_block_hash: H256 = raw_block.parent_block_hash().unpack();
+        let chain_view = ChainView::new(&db, parent_block_hash);
+
+        // Genesis block has no txs, so the tx block always has a parent
+        let parent_state = db.state_tree(StateContext::ReadOnlyHistory(block_number - 1))?;
+        let mut state = MemExecuteTxStateTree::new(parent_state);
+
+        // Replay the block up to the tx, in the same order as `verify_and_apply_block`.
+        // The state can't calculate the root, so receipts of withdrawals are skipped.
+        for request in block.withdrawals() {
+            state.apply_withdrawal_request_without_receipt(
+                rollup_context,
+                &block_producer,
+                &request,
+            )?;
+        }
+        state.apply_deposit_requests(rollup_context, &deposits)?;
+        for prev_tx in block.transactions().into_iter().take(tx_index as usize) {
+            let run_result = generator.unchecked_execute_transaction(
+                &chain_view,
+                &state,
+                &block_info,
+                &prev_tx.raw(),
+                L2TX_MAX_CYCLES,
+            )?;
+            state.apply_run_result(&run_result.write)?;
+        }
+
+        let traced = generator.trace_execute_transaction(
+            &chain_view,
+            &state,
+            &block_info,
+            &tx.raw(),
+            L2TX_MAX_CYCLES,
+        )?;
+        Result::<_, anyhow::Error>::Ok(traced)
+    })
+    .await??;
+
+    let to_key_values = |kvs: HashMap<H256, H256>| -> Vec<StateKeyValue> {
+        let mut kvs: Vec<_> = kvs.into_iter().collect();
+        kvs.sort_unstable_by_key(|(key, _)| *key);
+        kvs.into_iter()
+            .map(|(key, value)| StateKeyValue {
+                key: to_jsonh256(key),
+                value: to_jsonh256(value),
+            })
+            .collect()
+    };
+    let to_sorted_hashes = |mut hashes: Vec<H256>| -> Vec<JsonH256> {
+        hashes.sort_unstable();
+        hashes.into_iter().map(to_jsonh256).collect()
+    };
+    let syscalls = syscalls
+        .into_iter()
+        .map(|trace| SyscallTrace {
+            code: trace.code.into(),
+            name: syscall_name(trace.code).map(ToString::to_string),
+            cycles: trace.cycles.into(),
+        })
+        .collect();
+
+    let gw_types::offchain::RunResult {
+        read_values,
+        return_data,
+        read_data,
+        used_cycles,
+        exit_code,
+        write,
+        ..
+    } = run_result;
+    Ok(Some(TransactionTrace {
+        tx_hash: to_jsonh256(tx_hash),
+        block_hash: to_jsonh256(block_hash),
+        block_number: block_number.into(),
+        tx_index: tx_index.into(),
+        exit_code: (exit_code as u8 as u32).into(),
+        used_cycles: used_cycles.into(),
+        return_data: JsonBytes::from_bytes(return_data),
+        logs: write.logs.into_iter().map(Into::into).collect(),
+        read_values: to_key_values(read_values),
+        write_values: to_key_values(write.write_values),
+        account_count: write.account_count.map(Into::into),
+        read_data_hashes: to_sorted_hashes(read_data.into_keys().collect()),
+        write_data_hashes: to_sorted_hashes(write.write_data.into_keys().collect()),
+        new_script_hashes: to_sorted_hashes(write.new_scripts.into_keys().collect()),
+        syscalls,
+    }))
+}
+
+// raw_l2tx, registry_address, sample_interval
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum DebugProfileRawL2TransactionParams {
+    Tip((JsonBytes,)),
+    PolyjuiceFromIdZero((JsonBytes, Option<RegistryAddressJsonBytes>)),
+    SampleInterval(
+        (
+            JsonBytes,
+            Option<RegistryAddressJsonBytes>,
+            Option<GwUint64>,
+        ),
+    ),
+}
+
+// Min PC sample interval of gw_debug_profile_raw_l2transaction
+const MIN_PROFILE_SAMPLE_INTERVAL: u64 = 100;
+
+// Execute a raw tx on the tip state in profiling mode
+#[instrument(skip_all)]
+async fn debug_profile_raw_l2transaction(
+    Params(params): Params<DebugProfileRawL2TransactionParams>,
+    mem_pool_config: Data<MemPoolConfig>,
+    ctx: Data<ExecutionTransactionContext>,
+) -> Result<ExecutionProfile, RpcError> {
+    use gw_generator::profiler::DEFAULT_PROFILE_SAMPLE_INTERVAL;
+
+    let (raw_l2tx, registry_address_opt, sample_interval_opt) = match params {
+        DebugProfileRawL2TransactionParams::Tip(p) => (p.0, None, None),
+        DebugProfileRawL2TransactionParams::PolyjuiceFromIdZero(p) => (p.0, p.1, None),
+        DebugProfileRawL2TransactionParams::SampleInterval(p) => p,
+    };
+    let sample_interval = sample_interval_opt
+        .map(|n| n.value())
+        .unwrap_or(DEFAULT_PROFILE_SAMPLE_INTERVAL);
+    if sample_interval < MIN_PROFILE_SAMPLE_INTERVAL {
+        return Err(invalid_param_err("sample interval is too small"));
+    }
+    let registry_address_opt = registry_address_opt
+        .map(|json_bytes| {
+            gw_common::registry_address::RegistryAddress::from_slice(json_bytes.as_bytes())
+                .ok_or_else(|| invalid_param_err("Invalid registry address"))
+        })
+        .transpose()?;
+
+    let raw_l2tx_bytes = raw_l2tx.into_bytes();
+    let raw_l2tx = packed::RawL2Transaction::from_slice(&raw_l2tx_bytes)?;
+
+    let mem_state_snap = ctx.mem_pool_state.load();
+    let block_info = mem_state_snap
+        .get_mem_pool_block_info()?
+        .expect("get mem pool block info");
+    let execute_l2tx_max_cycles = mem_pool_config.execute_l2tx_max_cycles;
+
+    let run_result = tokio::task::spawn_blocking(move || {
+        let db = ctx.store.get_snapshot();
+        let chain_view = {
+            let tip_block_hash = db.get_last_valid_tip_block_hash()?;
+            ChainView::new(&db, tip_block_hash)
+        };
+        let mut state = MemExecuteTxStateTree::new(mem_state_snap.state()?);
+        let eth_recover = &ctx.polyjuice_sender_recover.eth;
+        let raw_l2tx = eth_recover.mock_sender_if_not_exists_from_raw_registry(
+            raw_l2tx,
+            registry_address_opt,
+            &mut state,
+        )?;
+
+        let run_result = ctx.generator.profile_execute_transaction(
+            &chain_view,
+            &state,
+            &block_info,
+            &raw_l2tx,
+            execute_l2tx_max_cycles,
+            sample_interval,
+        )?;
+        Result::<_, anyhow::Error>::Ok(run_result)
+    })
+    .await??;
+
+    let profile = run_result.profile.unwrap_or_default();
+    let syscalls = profile
+        .syscalls
+        .into_iter()
+        .map(
+            |(code, syscall)| gw_jsonrpc_types::godwoken::SyscallProfile {
+                code: code.into(),
+                name: syscall_name(code).map(ToString::to_string),
+                count: syscall.count.into(),
+                bytes: syscall.bytes.into(),
+                cycles: syscall.cycles.into(),
+                elapsed_nanos: syscall.elapsed_nanos.into(),
+            },
+        )
+        .collect();
+    let pc_samples = profile
+        .pc_samples
+        .into_iter()
+        .map(|(pc, count)| PcSample {
+            pc: pc.into(),
+            count: count.into(),
+        })
+        .collect();
+
+    Ok(ExecutionProfile {
+        exit_code: (run_result.exit_code as u8 as u32).into(),
+        used_cycles: run_result.used_cycles.into(),
+        return_data: JsonBytes::from_bytes(run_result.return_data),
+        syscalls,
+        sample_interval: sample_interval.into(),
+        pc_samples,
+    })
+}
+
 // TODO: refactor complex type.
 #[allow(clippy::type_complexity)]
 #[instrument(skip_all)]
@@ -1184,7 +2219,7 @@ async fn submit_l2transaction(
     }
 
     // check sender's nonce
-    {
+    let entry = {
         // fetch mem-pool state
         let snap = ctx.mem_pool_state.load();
         let tree = snap.state()?;
@@ -1195,7 +2230,17 @@ async fn submit_l2transaction(
         } else {
             tree.get_nonce(sender_id)?
         };
-        if sender_nonce != tx_nonce {
+        // txs with future nonce wait in queue until the nonce gap is filled
+        let max_future_nonce = if 0 == sender_id {
+            sender_nonce
+        } else {
+            let max_gap = ctx
+                .max_future_entries_per_account
+                .try_into()
+                .unwrap_or(u32::MAX);
+            sender_nonce.saturating_add(max_gap)
+        };
+        if tx_nonce < sender_nonce || tx_nonce > max_future_nonce {
             let err = TransactionError::Nonce {
                 account_id: sender_id,
                 expected: sender_nonce,
@@ -1212,7 +2257,12 @@ async fn submit_l2transaction(
                 data: None,
             });
         }
-    }
+
+        to_fee_entry(&ctx, Request::Tx(tx.clone()), &tree)?
+    };
+
+    // check replace-by-fee if a tx with the same nonce is in queue
+    check_replace_by_fee(&ctx.queue, &entry).await?;
 
     let permit = ctx.submit_tx.try_reserve().map_err(|err| match err {
         mpsc::error::TrySendError::Closed(_) => RpcError::Provided {
@@ -1250,17 +2300,45 @@ async fn submit_l2transaction(
     Ok(tx_hash_json)
 }
 
-// TODO: refactor complex type.
-#[allow(clippy::type_complexity)]
+fn to_fee_entry(
+    ctx: &SubmitTransactionContext,
+    req: Request,
+    state: &(impl State + CodeStore),
+) -> Result<FeeEntry, RpcError> {
+    let dynamic_config_manager = ctx.dynamic_config_manager.load();
+    let fee_config = dynamic_config_manager.get_fee_config();
+    req_to_entry(fee_config, ctx.generator.clone(), req, state, 0).map_err(|err| RpcError::Full {
+        code: INVALID_REQUEST,
+        message: err.to_string(),
+        data: None,
+    })
+}
+
+async fn check_replace_by_fee(queue: &RequestQueue, entry: &FeeEntry) -> Result<(), RpcError> {
+    match queue.lock().await.check_replace(entry) {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            log::info!(
+                "[RPC] reject to replace {:?} {}, err: {}",
+                entry.item.kind(),
+                entry.item.hash().pack(),
+                err
+            );
+            Err(RpcError::Full {
+                code: REPLACEMENT_UNDERPRICED_ERR_CODE,
+                message: err.to_string(),
+                data: None,
+            })
+        }
+    }
+}
+
 #[instrument(skip_all)]
 async fn submit_withdrawal_request(
     Params((withdrawal_request,)): Params<(JsonBytes,)>,
     generator: Data<Generator>,
     store: Data<Store>,
-    (in_queue_request_map, submit_tx): (
-        Data<Option<Arc<InQueueRequestMap>>>,
-        Data<mpsc::Sender<(InQueueRequestHandle, Request)>>,
-    ),
+    ctx: Data<SubmitTransactionContext>,
     rpc_client: Data<RPCClient>,
 ) -> Result<JsonH256, RpcError> {
     let withdrawal_bytes = withdrawal_request.into_bytes();
@@ -1316,7 +2394,15 @@ async fn submit_withdrawal_request(
         }
     }
 
-    let permit = submit_tx.try_reserve().map_err(|err| match err {
+    // check replace-by-fee if a withdrawal with the same nonce is in queue
+    let entry = {
+        let snap = ctx.mem_pool_state.load();
+        let tree = snap.state()?;
+        to_fee_entry(&ctx, Request::Withdrawal(withdrawal.clone()), &tree)?
+    };
+    check_replace_by_fee(&ctx.queue, &entry).await?;
+
+    let permit = ctx.submit_tx.try_reserve().map_err(|err| match err {
         mpsc::error::TrySendError::Closed(_) => RpcError::Provided {
             code: INTERNAL_ERROR_ERR_CODE,
             message: "internal error, unavailable",
@@ -1329,7 +2415,8 @@ async fn submit_withdrawal_request(
 
     let request = Request::Withdrawal(withdrawal);
     // Use permit to insert before send so that remove won't happen before insert.
-    if let Some(handle) = in_queue_request_map
+    if let Some(handle) = ctx
+        .in_queue_request_map
         .as_ref()
         .expect("in_queue_request_map")
         .insert(withdrawal_hash.into(), request.clone())
@@ -1462,6 +2549,7 @@ async fn get_balance(
     let balance = match block_number {
         Some(block_number) => {
             let db = store.begin_transaction();
+            check_state_pruned(&db, block_number.into())?;
             let tree = db.state_tree(StateContext::ReadOnlyHistory(block_number.into()))?;
             tree.get_sudt_balance(sudt_id.into(), &address)?
         }
@@ -1495,6 +2583,7 @@ async fn get_storage_at(
     let value = match block_number {
         Some(block_number) => {
             let db = store.begin_transaction();
+            check_state_pruned(&db, block_number.into())?;
             let tree = db.state_tree(StateContext::ReadOnlyHistory(block_number.into()))?;
             let key: H256 = to_h256(key);
             tree.get_value(account_id.into(), key.as_slice())?
@@ -1511,6 +2600,155 @@ async fn get_storage_at(
     Ok(json_value)
 }
 
+// Max number of storage keys and balances in a gw_get_proof request
+const MAX_PROOF_KEYS: usize = 256;
+// Max number of blocks reverted to generate a proof of a history block
+const MAX_PROOF_HISTORY_BLOCKS: u64 = 1000;
+
+// The account SMT only keeps the state of the last valid tip block. The SMT of a
+// history block is reverted from the tip with state records of later blocks,
+// inside a transaction which is never committed.
+async fn get_proof(
+    Params((request,)): Params<(ProofRequest,)>,
+    store: Data<Store>,
+) -> Result<AccountProof, RpcError> {
+    let ProofRequest {
+        account_id,
+        storage_keys,
+        balances,
+        block_number,
+        block_hash,
+    } = request;
+    if storage_keys.len() + balances.len() > MAX_PROOF_KEYS {
+        return Err(invalid_param_err("too many keys"));
+    }
+    let id: u32 = account_id.value();
+
+    let db = store.begin_transaction();
+    let tip_block = db.get_last_valid_tip_block()?;
+    let tip_block_number: u64 = tip_block.raw().number().unpack();
+    let block_number = match (block_number.map(|n| n.value()), block_hash) {
+        (block_number, Some(block_hash)) => {
+            let block_hash = to_h256(block_hash);
+            let number = db
+                .get_block_number(&block_hash)?
+                .ok_or_else(header_not_found_err)?;
+            // Reverted blocks aren't on the chain
+            if db.get_block_hash_by_number(number)? != Some(block_hash) {
+                return Err(header_not_found_err());
+            }
+            if block_number.map_or(false, |n| n != number) {
+                return Err(invalid_param_err("block number and hash mismatch"));
+            }
+            number
+        }
+        (Some(block_number), None) => block_number,
+        (None, None) => tip_block_number,
+    };
+    if block_number > tip_block_number {
+        return Err(header_not_found_err());
+    }
+    if tip_block_number - block_number > MAX_PROOF_HISTORY_BLOCKS {
+        return Err(RpcError::Full {
+            code: INVALID_PARAM_ERR_CODE,
+            message: format!(
+                "proof is only available for the last {} blocks",
+                MAX_PROOF_HISTORY_BLOCKS
+            ),
+            data: None,
+        });
+    }
+    check_state_pruned(&db, block_number)?;
+
+    let block = if block_number == tip_block_number {
+        tip_block.clone()
+    } else {
+        let block_hash = db
+            .get_block_hash_by_number(block_number)?
+            .ok_or_else(header_not_found_err)?;
+        db.get_block(&block_hash)?
+            .ok_or_else(header_not_found_err)?
+    };
+    let merkle_state = block.raw().post_account();
+    let mut smt = db.account_smt_with_merkle_state(tip_block.raw().post_account())?;
+    if block_number < tip_block_number {
+        let state_keys: HashSet<H256> = (block_number + 1..=tip_block_number)
+            .flat_map(|n| {
+                db.iter_block_state_record(n)
+                    .map(|record| record.state_key())
+            })
+            .collect();
+        for state_key in state_keys {
+            let value = db
+                .get_history_state(block_number, &state_key)
+                .unwrap_or_else(H256::zero);
+            smt.update(state_key, value)?;
+        }
+    }
+
+    let mut keys = vec![
+        build_account_field_key(id, GW_ACCOUNT_NONCE_TYPE),
+        build_account_field_key(id, GW_ACCOUNT_SCRIPT_HASH_TYPE),
+    ];
+    keys.extend((storage_keys.iter()).map(|key| build_account_key(id, key.as_bytes())));
+    for balance in balances.iter() {
+        let address = gw_common::registry_address::RegistryAddress::from_slice(
+            balance.registry_address.as_bytes(),
+        )
+        .ok_or_else(|| invalid_param_err("Invalid registry address"))?;
+        let sudt_key = build_sudt_key(SUDT_KEY_FLAG_BALANCE, &address);
+        keys.push(build_account_key(balance.sudt_id.value(), &sudt_key));
+    }
+    let values = (keys.iter())
+        .map(|key| smt.get(key))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Requested keys may be duplicated
+    let leaves: Vec<(H256, H256)> = {
+        let unique: HashMap<H256, H256> = keys.into_iter().zip(values.iter().copied()).collect();
+        unique.into_iter().collect()
+    };
+    let proof = smt
+        .merkle_proof(leaves.iter().map(|(key, _)| *key).collect())?
+        .compile(leaves.clone())?;
+    // A new block may be attached while we are reading the SMT
+    let expected_root: H256 = merkle_state.merkle_root().unpack();
+    if proof.compute_root::<Blake2bHasher>(leaves)? != expected_root {
+        return Err(RpcError::Provided {
+            code: INTERNAL_ERROR_ERR_CODE,
+            message: "state is changed, please try again",
+        });
+    }
+
+    let (account_values, values) = values.split_at(2);
+    let (storage_values, balance_values) = values.split_at(storage_keys.len());
+    let storage = (storage_keys.into_iter().zip(storage_values))
+        .map(|(key, value)| StorageProofItem {
+            key,
+            value: to_jsonh256(*value),
+        })
+        .collect();
+    let balances = (balances.into_iter().zip(balance_values))
+        .map(|(balance, value)| BalanceProofItem {
+            sudt_id: balance.sudt_id,
+            registry_address: balance.registry_address,
+            value: to_jsonh256(*value),
+        })
+        .collect();
+
+    Ok(AccountProof {
+        block_hash: to_jsonh256(block.hash().into()),
+        block_number: block_number.into(),
+        account: merkle_state.into(),
+        account_id,
+        nonce: account_values[0].to_u32().into(),
+        script_hash: to_jsonh256(account_values[1]),
+        storage,
+        balances,
+        proof: JsonBytes::from_vec(proof.0),
+    })
+}
+
 async fn get_account_id_by_script_hash(
     Params((script_hash,)): Params<(JsonH256,)>,
     mem_pool_state: Data<Arc<MemPoolState>>,
@@ -1548,6 +2786,7 @@ async fn get_nonce(
     let nonce = match block_number {
         Some(block_number) => {
             let db = store.begin_transaction();
+            check_state_pruned(&db, block_number.into())?;
             let tree = db.state_tree(StateContext::ReadOnlyHistory(block_number.into()))?;
             tree.get_nonce(account_id.into())?
         }
@@ -1895,6 +3134,122 @@ async fn get_fee_config(
     Ok(fee_config)
 }
 
+// Number of recent blocks to sample fee rates
+const FEE_RATE_ESTIMATION_BLOCKS: u64 = 20;
+
+async fn estimate_fee_rate(
+    ctx: Data<FeeEstimationContext>,
+) -> Result<gw_jsonrpc_types::godwoken::FeeRateEstimation> {
+    do_estimate_fee_rate(&ctx, false).await
+}
+
+async fn estimate_gas_price(
+    ctx: Data<FeeEstimationContext>,
+) -> Result<gw_jsonrpc_types::godwoken::FeeRateEstimation> {
+    do_estimate_fee_rate(&ctx, true).await
+}
+
+async fn do_estimate_fee_rate(
+    ctx: &FeeEstimationContext,
+    polyjuice_only: bool,
+) -> Result<gw_jsonrpc_types::godwoken::FeeRateEstimation> {
+    let fee_rates = {
+        let snap = ctx.mem_pool_state.load();
+        let tip_block_hash = snap.get_last_valid_tip_block_hash()?;
+        let tip_block_number: u64 = match snap.get_block(&tip_block_hash)? {
+            Some(block) => block.raw().number().unpack(),
+            None => return Err(anyhow!("tip block not found")),
+        };
+        let from_block_number = tip_block_number.saturating_sub(FEE_RATE_ESTIMATION_BLOCKS - 1);
+
+        let mut block_hashes = Vec::new();
+        for block_number in from_block_number..=tip_block_number {
+            if let Some(block_hash) = snap.get_block_hash_by_number(block_number)? {
+                block_hashes.push(block_hash);
+            }
+        }
+
+        let state = snap.state()?;
+        let dynamic_config_manager = ctx.dynamic_config_manager.load();
+        let fee_config = dynamic_config_manager.get_fee_config();
+        let mut cached = ctx.block_fee_rates.lock().unwrap().clone();
+        for block_hash in block_hashes.iter() {
+            if cached.contains_key(block_hash) {
+                continue;
+            }
+            if let Some(block) = snap.get_block(block_hash)? {
+                let block_fee_rates =
+                    sample_block_fee_rates(&ctx.generator, &state, fee_config, block)?;
+                cached.insert(*block_hash, Arc::new(block_fee_rates));
+            }
+        }
+        // drop blocks out of the window, e.g. reverted or too old blocks
+        cached.retain(|block_hash, _| block_hashes.contains(block_hash));
+
+        let mut fee_rates = Vec::new();
+        for block_fee_rates in cached.values() {
+            fee_rates.extend_from_slice(&block_fee_rates.polyjuice);
+            if !polyjuice_only {
+                fee_rates.extend_from_slice(&block_fee_rates.others);
+            }
+        }
+        *ctx.block_fee_rates.lock().unwrap() = cached;
+        fee_rates
+    };
+
+    let estimator = FeeRateEstimator::new(fee_rates, ctx.max_block_txs);
+    let queue = ctx.queue.lock().await;
+    let estimation = if polyjuice_only {
+        estimator.estimate_polyjuice(&queue)
+    } else {
+        estimator.estimate(&queue)
+    };
+
+    Ok(gw_jsonrpc_types::godwoken::FeeRateEstimation {
+        slow: estimation.slow.into(),
+        normal: estimation.normal.into(),
+        fast: estimation.fast.into(),
+        queue_len: (queue.len() as u32).into(),
+        sample_count: (estimator.sample_count() as u32).into(),
+    })
+}
+
+fn sample_block_fee_rates(
+    generator: &Generator,
+    state: &(impl State + CodeStore),
+    fee_config: &FeeConfig,
+    block: packed::L2Block,
+) -> Result<BlockFeeRates> {
+    let mut fee_rates = BlockFeeRates::default();
+    for tx in block.transactions().into_iter() {
+        let to_id: u32 = tx.raw().to_id().unpack();
+        let script_hash = state.get_script_hash(to_id)?;
+        let backend_type = match generator.load_backend(0, state, &script_hash) {
+            Some(backend) => backend.backend_type,
+            None => continue,
+        };
+        if let Ok(entry) = FeeEntry::from_tx(tx, fee_config, backend_type, 0) {
+            if backend_type == gw_config::BackendType::Polyjuice {
+                fee_rates.polyjuice.push(entry.fee_rate());
+            } else {
+                fee_rates.others.push(entry.fee_rate());
+            }
+        }
+    }
+    for withdrawal in block.withdrawals().into_iter() {
+        let withdrawal = WithdrawalRequestExtra::new_builder()
+            .request(withdrawal)
+            .build();
+        if let Ok(entry) = FeeEntry::from_withdrawal(withdrawal, 0, fee_config, 0) {
+            fee_rates.others.push(entry.fee_rate());
+        }
+    }
+    // free txs and withdrawals don't compete for inclusion
+    fee_rates.polyjuice.retain(|&fee_rate| fee_rate > 0);
+    fee_rates.others.retain(|&fee_rate| fee_rate > 0);
+    Ok(fee_rates)
+}
+
 async fn get_mem_pool_state_root(
     mem_pool_state: Data<Arc<MemPoolState>>,
 ) -> Result<JsonH256, RpcError> {
@@ -1952,6 +3307,48 @@ async fn get_rocksdb_memory_stats(store: Data<Store>) -> Result<Vec<CfMemStat>,
     Ok(store.gather_mem_stats())
 }
 
+// Backup name is a relative path without `..`, so the backup is created under
+// the backup dir
+fn backup_path(backup_dir: &Path, name: &str) -> Result<PathBuf, RpcError> {
+    let name = Path::new(name);
+    let is_normal = |component: Component| matches!(component, Component::Normal(_));
+    if name.as_os_str().is_empty() || !name.components().all(is_normal) {
+        return Err(invalid_param_err(
+            "backup name must be a relative path without `..`",
+        ));
+    }
+    Ok(backup_dir.join(name))
+}
+
+// Create a consistent backup of the running store under the backup dir on the node's host
+async fn admin_create_backup(
+    Params((name,)): Params<(String,)>,
+    store: Data<Store>,
+    ctx: Data<BackupContext>,
+) -> Result<BackupManifest, RpcError> {
+    let backup_dir = ctx.backup_dir.as_ref().ok_or(RpcError::Provided {
+        code: METHOD_NOT_AVAILABLE_ERR_CODE,
+        message: "backup_dir isn't configured",
+    })?;
+    let path = backup_path(backup_dir, &name)?;
+    let manifest =
+        tokio::task::spawn_blocking(move || gw_utils::backup::create_backup(&store, &path))
+            .await??;
+    log::info!(
+        "[admin] backup tip block {} {:x}",
+        manifest.tip_block_number,
+        manifest.tip_block_hash
+    );
+
+    Ok(BackupManifest {
+        rollup_type_hash: manifest.rollup_type_hash,
+        tip_block_hash: manifest.tip_block_hash,
+        tip_block_number: manifest.tip_block_number.into(),
+        db_version: manifest.db_version,
+        created_at: manifest.created_at.into(),
+    })
+}
+
 async fn dump_jemalloc_profiling() -> Result<()> {
     let timestamp = std::time::SystemTime::now()
         .duration_since(std::time::SystemTime::UNIX_EPOCH)
@@ -1,17 +1,53 @@
 // Taken and adapted from https://github.com/smol-rs/smol/blob/ad0839e1b3700dd33abb9bf23c1efd3c83b5bb2d/examples/hyper-server.rs
+use std::borrow::Cow;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::{Error, Result};
+use gw_config::RPCRequestLimitsConfig;
+use hyper::server::conn::{AddrIncoming, AddrStream};
 use hyper::service::{make_service_fn, service_fn};
-use hyper::{body::HttpBody, server::conn::AddrIncoming, Body, Method, Request, Response, Server};
+use hyper::{body::HttpBody, Body, Method, Request, Response, Server};
+use serde::de::{self, Deserialize, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde_json::{json, Value};
 use tokio::net::TcpListener;
 
-use jsonrpc_v2::{RequestKind, ResponseObjects, Router, Server as JsonrpcServer};
-use tokio::sync::{broadcast, mpsc};
+use jsonrpc_v2::{
+    Error as RpcError, RequestKind, ResponseObject, ResponseObjects, Router,
+    Server as JsonrpcServer,
+};
+use tokio::sync::{broadcast, mpsc, Semaphore};
 
-use crate::registry::Registry;
+use crate::method_rate_limiter::MethodRateLimiter;
+use crate::registry::{
+    Registry, BUSY_ERR_CODE, INVALID_REQUEST, RATE_LIMIT_ERR_CODE, REQUEST_TIMEOUT_ERR_CODE,
+};
+
+// Returned by the JSONRPC server if the method is not registered. Registered
+// methods which are not available return the same code with other messages.
+const METHOD_NOT_FOUND: i64 = -32601;
+const METHOD_NOT_FOUND_MESSAGE: &str = "Method not found";
+// The request body is not valid JSON
+const PARSE_ERROR: i64 = -32700;
+
+struct RequestLimits {
+    max_body_size: usize,
+    max_batch_size: usize,
+    timeout: Duration,
+    concurrency: Semaphore,
+}
+
+impl From<&RPCRequestLimitsConfig> for RequestLimits {
+    fn from(config: &RPCRequestLimitsConfig) -> Self {
+        RequestLimits {
+            max_body_size: config.max_body_size,
+            max_batch_size: config.max_batch_size,
+            timeout: Duration::from_secs(config.request_timeout_secs),
+            concurrency: Semaphore::new(config.max_concurrent_requests),
+        }
+    }
+}
 
 pub async fn start_jsonrpc_server(
     listen_addr: SocketAddr,
@@ -19,6 +55,10 @@ pub async fn start_jsonrpc_server(
     _shutdown_send: mpsc::Sender<()>,
     mut sub_shutdown: broadcast::Receiver<()>,
 ) -> Result<()> {
+    let limits = Arc::new(RequestLimits::from(
+        &registry.server_config().request_limits,
+    ));
+    let rate_limiter = Arc::new(MethodRateLimiter::new(registry.dynamic_config_manager()));
     let rpc_server = registry.build_rpc_server()?;
 
     let listener = TcpListener::bind(listen_addr).await?;
@@ -33,9 +73,22 @@ pub async fn start_jsonrpc_server(
     // Start a hyper server.
     let server = Server::builder(incoming)
         .tcp_nodelay(true)
-        .serve(make_service_fn(move |_| {
+        .serve(make_service_fn(move |conn: &AddrStream| {
             let rpc_server = Arc::clone(&rpc_server);
-            async { Ok::<_, Error>(service_fn(move |req| serve(Arc::clone(&rpc_server), req))) }
+            let limits = Arc::clone(&limits);
+            let rate_limiter = Arc::clone(&rate_limiter);
+            let remote_addr = conn.remote_addr();
+            async move {
+                Ok::<_, Error>(service_fn(move |req| {
+                    serve(
+                        Arc::clone(&rpc_server),
+                        Arc::clone(&limits),
+                        Arc::clone(&rate_limiter),
+                        remote_addr,
+                        req,
+                    )
+                }))
+            }
         }));
     let graceful = server.with_graceful_shutdown(async {
         let _ = sub_shutdown.recv().await;
@@ -49,6 +102,9 @@ pub async fn start_jsonrpc_server(
 // Serves a request and returns a response.
 async fn serve<R: Router + 'static>(
     rpc: Arc<JsonrpcServer<R>>,
+    limits: Arc<RequestLimits>,
+    rate_limiter: Arc<MethodRateLimiter>,
+    remote_addr: SocketAddr,
     req: Request<Body>,
 ) -> Result<Response<Body>> {
     if req.method() == Method::OPTIONS {
@@ -60,6 +116,25 @@ async fn serve<R: Router + 'static>(
             .body(Body::empty())
             .map_err(|e| anyhow::anyhow!("JSONRPC Preflight Request error: {:?}", e));
     }
+
+    let _permit = match limits.concurrency.try_acquire() {
+        Ok(permit) => permit,
+        Err(_) => return error_response(BUSY_ERR_CODE, "Server is busy, please try again later"),
+    };
+    let handle = handle_request(rpc, &limits, rate_limiter, remote_addr, req);
+    match tokio::time::timeout(limits.timeout, handle).await {
+        Ok(response) => response,
+        Err(_) => error_response(REQUEST_TIMEOUT_ERR_CODE, "Request timeout"),
+    }
+}
+
+async fn handle_request<R: Router + 'static>(
+    rpc: Arc<JsonrpcServer<R>>,
+    limits: &RequestLimits,
+    rate_limiter: Arc<MethodRateLimiter>,
+    remote_addr: SocketAddr,
+    req: Request<Body>,
+) -> Result<Response<Body>> {
     // Handler here is adapted from https://github.com/kardeiz/jsonrpc-v2/blob/1acf0b911c698413950d0b101ec4255cabd0d4ec/src/lib.rs#L1302
     let mut buf = if let Some(content_length) = req
         .headers()
@@ -67,18 +142,44 @@ async fn serve<R: Router + 'static>(
         .and_then(|x| x.to_str().ok())
         .and_then(|x| x.parse().ok())
     {
+        if content_length > limits.max_body_size {
+            return error_response(INVALID_REQUEST, "Request body is too large");
+        }
         bytes_v10::BytesMut::with_capacity(content_length)
     } else {
         bytes_v10::BytesMut::default()
     };
 
-    let mut body = req.into_body();
+    let (parts, mut body) = req.into_parts();
 
     while let Some(chunk) = body.data().await {
-        buf.extend(chunk?);
+        let chunk = chunk?;
+        if buf.len() + chunk.len() > limits.max_body_size {
+            return error_response(INVALID_REQUEST, "Request body is too large");
+        }
+        buf.extend(chunk);
     }
 
-    match rpc.handle(RequestKind::Bytes(buf.freeze())).await {
+    let calls = match parse_method_calls(&buf) {
+        Ok(calls) => calls,
+        Err(_) => return error_response(PARSE_ERROR, "Parse error"),
+    };
+    if calls.as_slice().len() > limits.max_batch_size {
+        return error_response(INVALID_REQUEST, "Batch is too large");
+    }
+    let methods = calls.as_slice().iter().map(|call| call.method.as_deref());
+    let started_at = Instant::now();
+    let response = if rate_limiter.check(remote_addr.ip(), &parts.headers, methods) {
+        rpc.handle(RequestKind::Bytes(buf.freeze())).await
+    } else {
+        let json = rate_limit_response(calls);
+        return Ok(json_response(serde_json::to_vec(&json)?)?);
+    };
+    if gw_metrics::enabled() {
+        report_metrics(&calls, &response, started_at.elapsed());
+    }
+
+    match response {
         ResponseObjects::Empty => hyper::Response::builder()
             .status(hyper::StatusCode::NO_CONTENT)
             .body(hyper::Body::from(Vec::<u8>::new()))
@@ -86,15 +187,283 @@ async fn serve<R: Router + 'static>(
         json => serde_json::to_vec(&json)
             .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
             .and_then(|json| {
-                hyper::Response::builder()
-                    .status(hyper::StatusCode::OK)
-                    .header("Content-Type", "application/json")
-                    .header("Access-Control-Allow-Origin", "*")
-                    .header("Access-Control-Allow-Methods", "*")
-                    .header("Access-Control-Allow-Headers", "*")
-                    .body(hyper::Body::from(json))
+                json_response(json)
                     .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
             }),
     }
     .map_err(|e| anyhow::anyhow!("JSONRPC Request error: {:?}", e))
 }
+
+fn json_response(json: Vec<u8>) -> Result<Response<Body>, hyper::http::Error> {
+    hyper::Response::builder()
+        .status(hyper::StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .header("Access-Control-Allow-Origin", "*")
+        .header("Access-Control-Allow-Methods", "*")
+        .header("Access-Control-Allow-Headers", "*")
+        .body(hyper::Body::from(json))
+}
+
+#[derive(Default)]
+struct MethodCall {
+    id: Value,
+    method: Option<String>,
+}
+
+enum MethodCalls {
+    Single(MethodCall),
+    Batch(Vec<MethodCall>),
+}
+
+impl MethodCalls {
+    fn as_slice(&self) -> &[MethodCall] {
+        match self {
+            MethodCalls::Single(call) => std::slice::from_ref(call),
+            MethodCalls::Batch(calls) => calls,
+        }
+    }
+}
+
+// Every element of a batch is counted, even a malformed one. Invalid requests
+// are left to the JSONRPC server.
+//
+// Only `id` and `method` are kept, other fields such as `params` are skipped
+// without building values, the body is parsed again by the JSONRPC server.
+fn parse_method_calls(body: &[u8]) -> serde_json::Result<MethodCalls> {
+    serde_json::from_slice(body)
+}
+
+impl<'de> Deserialize<'de> for MethodCalls {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(MethodCallsVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for MethodCall {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(MethodCallVisitor)
+    }
+}
+
+// A method name, or `None` if the value isn't a string
+struct MethodName(Option<String>);
+
+impl<'de> Deserialize<'de> for MethodName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(MethodNameVisitor)
+    }
+}
+
+struct MethodCallsVisitor;
+
+impl<'de> Visitor<'de> for MethodCallsVisitor {
+    type Value = MethodCalls;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a JSONRPC request or a batch")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut calls = Vec::new();
+        while let Some(call) = seq.next_element()? {
+            calls.push(call);
+        }
+        Ok(MethodCalls::Batch(calls))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        MethodCallVisitor.visit_map(map).map(MethodCalls::Single)
+    }
+
+    fn visit_bool<E: de::Error>(self, _v: bool) -> Result<Self::Value, E> {
+        Ok(MethodCalls::Single(MethodCall::default()))
+    }
+
+    fn visit_i64<E: de::Error>(self, _v: i64) -> Result<Self::Value, E> {
+        Ok(MethodCalls::Single(MethodCall::default()))
+    }
+
+    fn visit_u64<E: de::Error>(self, _v: u64) -> Result<Self::Value, E> {
+        Ok(MethodCalls::Single(MethodCall::default()))
+    }
+
+    fn visit_f64<E: de::Error>(self, _v: f64) -> Result<Self::Value, E> {
+        Ok(MethodCalls::Single(MethodCall::default()))
+    }
+
+    fn visit_str<E: de::Error>(self, _v: &str) -> Result<Self::Value, E> {
+        Ok(MethodCalls::Single(MethodCall::default()))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(MethodCalls::Single(MethodCall::default()))
+    }
+}
+
+struct MethodCallVisitor;
+
+impl<'de> Visitor<'de> for MethodCallVisitor {
+    type Value = MethodCall;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a JSONRPC request")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut call = MethodCall::default();
+        while let Some(key) = map.next_key::<Cow<str>>()? {
+            match key.as_ref() {
+                "id" => call.id = map.next_value()?,
+                "method" => call.method = map.next_value::<MethodName>()?.0,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(call)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+        IgnoredAny.visit_seq(seq)?;
+        Ok(MethodCall::default())
+    }
+
+    fn visit_bool<E: de::Error>(self, _v: bool) -> Result<Self::Value, E> {
+        Ok(MethodCall::default())
+    }
+
+    fn visit_i64<E: de::Error>(self, _v: i64) -> Result<Self::Value, E> {
+        Ok(MethodCall::default())
+    }
+
+    fn visit_u64<E: de::Error>(self, _v: u64) -> Result<Self::Value, E> {
+        Ok(MethodCall::default())
+    }
+
+    fn visit_f64<E: de::Error>(self, _v: f64) -> Result<Self::Value, E> {
+        Ok(MethodCall::default())
+    }
+
+    fn visit_str<E: de::Error>(self, _v: &str) -> Result<Self::Value, E> {
+        Ok(MethodCall::default())
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(MethodCall::default())
+    }
+}
+
+struct MethodNameVisitor;
+
+impl<'de> Visitor<'de> for MethodNameVisitor {
+    type Value = MethodName;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a method name")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(MethodName(Some(v.to_owned())))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        IgnoredAny.visit_map(map)?;
+        Ok(MethodName(None))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+        IgnoredAny.visit_seq(seq)?;
+        Ok(MethodName(None))
+    }
+
+    fn visit_bool<E: de::Error>(self, _v: bool) -> Result<Self::Value, E> {
+        Ok(MethodName(None))
+    }
+
+    fn visit_i64<E: de::Error>(self, _v: i64) -> Result<Self::Value, E> {
+        Ok(MethodName(None))
+    }
+
+    fn visit_u64<E: de::Error>(self, _v: u64) -> Result<Self::Value, E> {
+        Ok(MethodName(None))
+    }
+
+    fn visit_f64<E: de::Error>(self, _v: f64) -> Result<Self::Value, E> {
+        Ok(MethodName(None))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(MethodName(None))
+    }
+}
+
+// Batches are labeled as "batch" and calls to unknown methods are labeled as
+// "unknown", so that clients can't blow up the number of label values.
+fn report_metrics(calls: &MethodCalls, response: &ResponseObjects, elapsed: Duration) {
+    let errors: Vec<&RpcError> = match response {
+        ResponseObjects::One(object) => response_error(object).into_iter().collect(),
+        ResponseObjects::Many(objects) => objects.iter().filter_map(response_error).collect(),
+        ResponseObjects::Empty => Vec::new(),
+    };
+    let method = match calls {
+        MethodCalls::Single(_) if errors.iter().copied().any(is_method_not_found) => "unknown",
+        MethodCalls::Single(call) => call.method.as_deref().unwrap_or("unknown"),
+        MethodCalls::Batch(_) => "batch",
+    };
+
+    let metrics = gw_metrics::rpc();
+    metrics
+        .request_seconds
+        .with_label_values(&[method])
+        .observe(elapsed.as_secs_f64());
+    if !errors.is_empty() {
+        metrics
+            .request_errors
+            .with_label_values(&[method])
+            .inc_by(errors.len() as u64);
+    }
+}
+
+fn response_error(object: &ResponseObject) -> Option<&RpcError> {
+    match object {
+        ResponseObject::Error { error, .. } => Some(error),
+        ResponseObject::Result { .. } => None,
+    }
+}
+
+fn is_method_not_found(error: &RpcError) -> bool {
+    match error {
+        RpcError::Provided { code, message } => {
+            *code == METHOD_NOT_FOUND && *message == METHOD_NOT_FOUND_MESSAGE
+        }
+        RpcError::Full { .. } => false,
+    }
+}
+
+fn error_object(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {
+            "code": code,
+            "message": message,
+        },
+    })
+}
+
+// Rejects the whole request
+fn error_response(code: i64, message: &str) -> Result<Response<Body>> {
+    let json = error_object(Value::Null, code, message);
+    Ok(json_response(serde_json::to_vec(&json)?)?)
+}
+
+fn rate_limit_response(calls: MethodCalls) -> Value {
+    let error = |id: Value| {
+        let message = "Rate limit, please wait few seconds and try again";
+        error_object(id, RATE_LIMIT_ERR_CODE, message)
+    };
+    match calls {
+        MethodCalls::Single(call) => error(call.id),
+        MethodCalls::Batch(calls) => calls.into_iter().map(|call| error(call.id)).collect(),
+    }
+}
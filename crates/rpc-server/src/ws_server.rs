@@ -0,0 +1,399 @@
+//! WebSocket server serves subscriptions of new blocks, new mem block txs and
+//! logs.
+//!
+//! Requests follow JSON-RPC 2.0, `gw_subscribe` returns a subscription id and
+//! notifications are pushed as `gw_subscription` messages:
+//!
+//! ```json
+//! {"jsonrpc": "2.0", "method": "gw_subscription", "params": {"subscription": "0x1", "result": {}}}
+//! ```
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::Result;
+use futures::{SinkExt, StreamExt};
+use gw_common::H256;
+use gw_config::WebSocketConfig;
+use gw_jsonrpc_types::{
+    ckb_jsonrpc_types::Uint64,
+    godwoken::{L2BlockHeader, LogSubscriptionFilter},
+};
+use gw_mem_pool::pool::MemPool;
+use gw_store::{log_index::LogFilter, traits::chain_store::ChainStore, Store};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc, Mutex};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::{protocol::WebSocketConfig as ProtocolConfig, Message};
+
+use crate::registry::{to_h256, to_indexed_log_item, to_jsonh256};
+
+const PARSE_ERROR_ERR_CODE: i64 = -32700;
+const METHOD_NOT_FOUND_ERR_CODE: i64 = -32601;
+const INVALID_PARAM_ERR_CODE: i64 = -32602;
+const SUBSCRIPTION_LIMIT_ERR_CODE: i64 = -32005;
+
+// Subscription requests are small
+const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+// Max number of buffered notifications of a connection
+const NOTIFICATION_CHANNEL_SIZE: usize = 256;
+// Max number of buffered new tips of a subscription
+const NEW_TIP_CHANNEL_SIZE: usize = 16;
+// Max number of buffered new tx hashes of a subscription
+const NEW_TX_CHANNEL_SIZE: usize = 1024;
+// Max number of blocks to push logs for when a subscription falls behind
+const MAX_LOG_BLOCKS_PER_TIP: u64 = 100;
+// Number of logs to read from the store at a time
+const LOG_BATCH_SIZE: usize = 1000;
+
+struct WsContext {
+    store: Store,
+    new_tip: broadcast::Sender<(H256, u64)>,
+    new_txs: broadcast::Sender<H256>,
+    max_subscriptions_per_connection: usize,
+}
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SubscriptionKind {
+    NewHeads,
+    NewMemBlockTxs,
+    Logs,
+}
+
+#[derive(Serialize)]
+struct SubscriptionResult<'a, T> {
+    subscription: Uint64,
+    result: &'a T,
+}
+
+pub async fn start_ws_server(
+    listen_addr: SocketAddr,
+    config: WebSocketConfig,
+    store: Store,
+    mem_pool: Arc<Mutex<MemPool>>,
+    mut sub_shutdown: broadcast::Receiver<()>,
+) -> Result<()> {
+    // Forward mem pool events so that connections don't need to lock the mem pool
+    let (new_tip_rx, new_txs_rx) = {
+        let mem_pool = mem_pool.lock().await;
+        (mem_pool.subscribe_new_tip(), mem_pool.subscribe_new_txs())
+    };
+    let (new_tip, _) = broadcast::channel(NEW_TIP_CHANNEL_SIZE);
+    let (new_txs, _) = broadcast::channel(NEW_TX_CHANNEL_SIZE);
+    forward(new_tip_rx, new_tip.clone());
+    forward(new_txs_rx, new_txs.clone());
+
+    let ctx = Arc::new(WsContext {
+        store,
+        new_tip,
+        new_txs,
+        max_subscriptions_per_connection: config.max_subscriptions_per_connection,
+    });
+
+    let listener = TcpListener::bind(listen_addr).await?;
+    log::info!(
+        "WebSocket server listening on ws://{}",
+        listener.local_addr()?
+    );
+
+    let connections = Arc::new(AtomicUsize::new(0));
+    loop {
+        tokio::select! {
+            _ = sub_shutdown.recv() => {
+                log::info!("websocket server exited successfully");
+                return Ok(());
+            }
+            accepted = listener.accept() => {
+                let (stream, peer) = match accepted {
+                    Ok(accepted) => accepted,
+                    Err(err) => {
+                        log::warn!("[ws] accept connection error: {}", err);
+                        continue;
+                    }
+                };
+                if connections.load(Ordering::SeqCst) >= config.max_connections {
+                    log::warn!("[ws] reject {}, too many connections", peer);
+                    continue;
+                }
+                connections.fetch_add(1, Ordering::SeqCst);
+
+                let ctx = Arc::clone(&ctx);
+                let connections = Arc::clone(&connections);
+                tokio::spawn(async move {
+                    if let Err(err) = handle_connection(stream, ctx).await {
+                        log::debug!("[ws] connection {} error: {}", peer, err);
+                    }
+                    connections.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        }
+    }
+}
+
+fn forward<T: Clone + Send + 'static>(
+    mut receiver: broadcast::Receiver<T>,
+    sender: broadcast::Sender<T>,
+) {
+    tokio::spawn(async move {
+        while let Some(item) = recv(&mut receiver).await {
+            // No subscribers
+            let _ = sender.send(item);
+        }
+    });
+}
+
+// Returns `None` if the channel is closed, skips lagged items.
+async fn recv<T: Clone>(receiver: &mut broadcast::Receiver<T>) -> Option<T> {
+    loop {
+        match receiver.recv().await {
+            Ok(item) => return Some(item),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                log::debug!("[ws] subscriber lagged, skip {} items", skipped);
+            }
+            Err(broadcast::error::RecvError::Closed) => return None,
+        }
+    }
+}
+
+async fn handle_connection(stream: TcpStream, ctx: Arc<WsContext>) -> Result<()> {
+    let protocol_config = ProtocolConfig {
+        max_message_size: Some(MAX_MESSAGE_SIZE),
+        ..Default::default()
+    };
+    let ws = tokio_tungstenite::accept_async_with_config(stream, Some(protocol_config)).await?;
+    let (mut sink, mut source) = ws.split();
+
+    let (notification_tx, mut notification_rx) = mpsc::channel(NOTIFICATION_CHANNEL_SIZE);
+    let mut connection = Connection {
+        ctx,
+        notification_tx,
+        subscriptions: HashMap::new(),
+        next_id: 0,
+    };
+
+    let result = loop {
+        let reply = tokio::select! {
+            msg = source.next() => match msg {
+                Some(Ok(Message::Text(text))) => Message::Text(connection.handle_request(&text)),
+                Some(Ok(Message::Ping(data))) => Message::Pong(data),
+                Some(Ok(Message::Close(_))) | None => break Ok(()),
+                Some(Ok(_)) => continue,
+                Some(Err(err)) => break Err(err.into()),
+            },
+            Some(notification) = notification_rx.recv() => Message::Text(notification),
+        };
+        if let Err(err) = sink.send(reply).await {
+            break Err(err.into());
+        }
+    };
+
+    for (_id, handle) in connection.subscriptions.drain() {
+        handle.abort();
+    }
+    result
+}
+
+struct Connection {
+    ctx: Arc<WsContext>,
+    notification_tx: mpsc::Sender<String>,
+    subscriptions: HashMap<u64, JoinHandle<()>>,
+    next_id: u64,
+}
+
+impl Connection {
+    fn handle_request(&mut self, text: &str) -> String {
+        let request: Request = match serde_json::from_str(text) {
+            Ok(request) => request,
+            Err(_) => return error_response(Value::Null, PARSE_ERROR_ERR_CODE, "parse error"),
+        };
+        let id = request.id;
+        let result = match request.method.as_str() {
+            "gw_subscribe" => self.subscribe(request.params),
+            "gw_unsubscribe" => self.unsubscribe(request.params),
+            _ => Err((METHOD_NOT_FOUND_ERR_CODE, "method not found")),
+        };
+        match result {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}).to_string(),
+            Err((code, message)) => error_response(id, code, message),
+        }
+    }
+
+    fn subscribe(&mut self, params: Value) -> Result<Value, (i64, &'static str)> {
+        let mut params = match params {
+            Value::Array(params) if !params.is_empty() && params.len() <= 2 => params,
+            _ => return Err((INVALID_PARAM_ERR_CODE, "invalid params")),
+        };
+        let filter = match params.len() {
+            2 => params.pop(),
+            _ => None,
+        };
+        let kind: SubscriptionKind = serde_json::from_value(params.remove(0))
+            .map_err(|_| (INVALID_PARAM_ERR_CODE, "invalid subscription kind"))?;
+
+        if self.subscriptions.len() >= self.ctx.max_subscriptions_per_connection {
+            return Err((SUBSCRIPTION_LIMIT_ERR_CODE, "too many subscriptions"));
+        }
+
+        self.next_id += 1;
+        let id = self.next_id;
+        let ctx = Arc::clone(&self.ctx);
+        let notification_tx = self.notification_tx.clone();
+        let handle = match kind {
+            SubscriptionKind::NewHeads => tokio::spawn(push_new_heads(ctx, id, notification_tx)),
+            SubscriptionKind::NewMemBlockTxs => {
+                tokio::spawn(push_new_mem_block_txs(ctx, id, notification_tx))
+            }
+            SubscriptionKind::Logs => {
+                let filter: LogSubscriptionFilter = match filter {
+                    Some(filter) => serde_json::from_value(filter)
+                        .map_err(|_| (INVALID_PARAM_ERR_CODE, "invalid log filter"))?,
+                    None => LogSubscriptionFilter::default(),
+                };
+                tokio::spawn(push_logs(ctx, id, filter, notification_tx))
+            }
+        };
+        self.subscriptions.insert(id, handle);
+
+        Ok(json!(Uint64::from(id)))
+    }
+
+    fn unsubscribe(&mut self, params: Value) -> Result<Value, (i64, &'static str)> {
+        let (id,): (Uint64,) = serde_json::from_value(params)
+            .map_err(|_| (INVALID_PARAM_ERR_CODE, "invalid subscription id"))?;
+        let removed = match self.subscriptions.remove(&id.value()) {
+            Some(handle) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        };
+        Ok(json!(removed))
+    }
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {"code": code, "message": message},
+    })
+    .to_string()
+}
+
+// Returns error if the connection is closed
+async fn notify<T: Serialize>(
+    notification_tx: &mpsc::Sender<String>,
+    id: u64,
+    result: &T,
+) -> Result<()> {
+    let params = SubscriptionResult {
+        subscription: id.into(),
+        result,
+    };
+    let notification = json!({
+        "jsonrpc": "2.0",
+        "method": "gw_subscription",
+        "params": params,
+    });
+    notification_tx.send(notification.to_string()).await?;
+    Ok(())
+}
+
+async fn push_new_heads(ctx: Arc<WsContext>, id: u64, notification_tx: mpsc::Sender<String>) {
+    let mut new_tip = ctx.new_tip.subscribe();
+    while let Some((block_hash, _number)) = recv(&mut new_tip).await {
+        let block = match ctx.store.get_snapshot().get_block(&block_hash) {
+            Ok(Some(block)) => block,
+            Ok(None) => continue,
+            Err(err) => {
+                log::warn!("[ws] get block {} error: {}", to_jsonh256(block_hash), err);
+                continue;
+            }
+        };
+        let header: L2BlockHeader = block.into();
+        if notify(&notification_tx, id, &header).await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn push_new_mem_block_txs(
+    ctx: Arc<WsContext>,
+    id: u64,
+    notification_tx: mpsc::Sender<String>,
+) {
+    let mut new_txs = ctx.new_txs.subscribe();
+    while let Some(tx_hash) = recv(&mut new_txs).await {
+        if notify(&notification_tx, id, &to_jsonh256(tx_hash))
+            .await
+            .is_err()
+        {
+            return;
+        }
+    }
+}
+
+async fn push_logs(
+    ctx: Arc<WsContext>,
+    id: u64,
+    filter: LogSubscriptionFilter,
+    notification_tx: mpsc::Sender<String>,
+) {
+    let account_id = filter.account_id.map(|id| id.value());
+    let topic = filter.topic.map(to_h256);
+    let mut new_tip = ctx.new_tip.subscribe();
+    let mut last_number: Option<u64> = None;
+    while let Some((_block_hash, number)) = recv(&mut new_tip).await {
+        // Push logs of skipped blocks, only the new tip after a reorg
+        let from_block = match last_number {
+            Some(last) if last < number => last + 1,
+            _ => number,
+        };
+        let from_block = from_block.max(number.saturating_sub(MAX_LOG_BLOCKS_PER_TIP - 1));
+        last_number = Some(number);
+
+        let log_filter = LogFilter {
+            from_block,
+            to_block: number,
+            account_id,
+            topic,
+        };
+        let mut start = None;
+        loop {
+            let db = ctx.store.begin_transaction();
+            let (records, next) = match db.get_logs(&log_filter, start, LOG_BATCH_SIZE) {
+                Ok(logs) => logs,
+                Err(err) => {
+                    log::warn!("[ws] get logs error: {}", err);
+                    break;
+                }
+            };
+            for record in records {
+                if notify(&notification_tx, id, &to_indexed_log_item(record))
+                    .await
+                    .is_err()
+                {
+                    return;
+                }
+            }
+            match next {
+                Some(next) => start = Some(next),
+                None => break,
+            }
+        }
+    }
+}
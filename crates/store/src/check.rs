@@ -0,0 +1,478 @@
+//! Offline store consistency checker
+//!
+//! Unlike `Store::check_state` which samples the tip state, the checker walks
+//! every block number:
+//!
+//! * the main chain index and blocks, which must link to their parents
+//! * the block SMT rebuilt from the index against `META_BLOCK_SMT_ROOT_KEY`
+//! * the account SMT replayed from block state records against each
+//!   block's `post_account`
+//! * transactions and withdrawals of main chain blocks, their receipts and
+//!   info entries
+//!
+//! Then it reports keys no block refers to, see `ORPHAN_CHECKED_COLUMNS`.
+//! Blocks before the history pruned number may lack their bodies, e.g. a
+//! store imported from a state snapshot. So may the block at the pruned
+//! number lack transactions, receipts and withdrawals, e.g. the snapshot block.
+
+use std::collections::HashSet;
+
+use anyhow::{anyhow, Result};
+use gw_common::{
+    smt::{default_store::DefaultStore, SMT},
+    H256,
+};
+use gw_db::schema::{
+    Col, COLUMN_BAD_BLOCK_CHALLENGE_TARGET, COLUMN_BLOCK, COLUMN_BLOCK_DEPOSIT_REQUESTS,
+    COLUMN_BLOCK_GLOBAL_STATE, COLUMN_BLOCK_STATE_RECORD, COLUMN_BLOCK_STATE_REVERSE_RECORD,
+    COLUMN_INDEX, COLUMN_L2BLOCK_COMMITTED_INFO, COLUMN_LOG, COLUMN_LOG_ACCOUNT_INDEX,
+    COLUMN_LOG_TOPIC_INDEX, COLUMN_TRANSACTION, COLUMN_TRANSACTION_INFO,
+    COLUMN_TRANSACTION_RECEIPT, COLUMN_WITHDRAWAL, COLUMN_WITHDRAWAL_INFO,
+};
+use gw_types::{
+    offchain::{TransactionKey, WithdrawalKey},
+    packed,
+    prelude::{Entity, FromSliceShouldBeOk, Pack, Reader, Unpack},
+};
+
+use crate::{
+    log_index::LogKey,
+    readonly::StoreReadonly,
+    state::block_state_record::{BlockStateRecordKey, BlockStateRecordKeyReverse},
+    traits::{chain_store::ChainStore, kv_store::KVStoreRead},
+    Store,
+};
+
+/// Columns checked for orphaned keys. The other columns are either
+/// verified by roots (SMT columns), content addressed (scripts and data),
+/// or not bound to blocks (meta and mem pool).
+pub const ORPHAN_CHECKED_COLUMNS: &[(Col, &str)] = &[
+    (COLUMN_INDEX, "index"),
+    (COLUMN_BLOCK, "block"),
+    (COLUMN_BLOCK_GLOBAL_STATE, "block global state"),
+    (COLUMN_L2BLOCK_COMMITTED_INFO, "l2block committed info"),
+    (COLUMN_BLOCK_DEPOSIT_REQUESTS, "block deposit requests"),
+    (
+        COLUMN_BAD_BLOCK_CHALLENGE_TARGET,
+        "bad block challenge target",
+    ),
+    (COLUMN_TRANSACTION, "transaction"),
+    (COLUMN_TRANSACTION_RECEIPT, "transaction receipt"),
+    (COLUMN_TRANSACTION_INFO, "transaction info"),
+    (COLUMN_WITHDRAWAL, "withdrawal"),
+    (COLUMN_WITHDRAWAL_INFO, "withdrawal info"),
+    (COLUMN_BLOCK_STATE_RECORD, "block state record"),
+    (
+        COLUMN_BLOCK_STATE_REVERSE_RECORD,
+        "block state reverse record",
+    ),
+    (COLUMN_LOG, "log"),
+    (COLUMN_LOG_ACCOUNT_INDEX, "log account index"),
+    (COLUMN_LOG_TOPIC_INDEX, "log topic index"),
+];
+
+// Error messages kept in report, the rest are only counted
+const MAX_REPORTED_ERRORS: usize = 1000;
+// Orphaned keys deleted in a write batch
+const REPAIR_BATCH_SIZE: usize = 10_000;
+// Log progress once per blocks
+const PROGRESS_INTERVAL: u64 = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnReport {
+    pub col: Col,
+    pub name: &'static str,
+    pub keys: u64,
+    pub orphans: Vec<Box<[u8]>>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CheckReport {
+    pub tip_block_number: u64,
+    pub last_valid_tip_block_number: u64,
+    pub pruned_block_number: u64,
+    pub error_count: usize,
+    /// First `MAX_REPORTED_ERRORS` errors
+    pub errors: Vec<String>,
+    pub columns: Vec<ColumnReport>,
+}
+
+impl CheckReport {
+    pub fn is_ok(&self) -> bool {
+        self.error_count == 0 && self.orphan_count() == 0
+    }
+
+    pub fn orphan_count(&self) -> usize {
+        self.columns.iter().map(|c| c.orphans.len()).sum()
+    }
+
+    fn error(&mut self, msg: String) {
+        log::debug!("[check db] {}", msg);
+        self.error_count += 1;
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(msg);
+        }
+    }
+}
+
+// Blocks referred by the main chain index
+struct Chain {
+    // Indexed by block number, `None` if the index is missing
+    hashes: Vec<Option<H256>>,
+    // Main chain and reverted blocks
+    referenced: HashSet<H256>,
+}
+
+impl Chain {
+    fn is_main(&self, block_number: u64, block_hash: &H256) -> bool {
+        self.hashes.get(block_number as usize) == Some(&Some(*block_hash))
+    }
+}
+
+/// Check the store, nothing is written
+pub fn check_store(snap: &StoreReadonly) -> Result<CheckReport> {
+    let mut report = CheckReport::default();
+
+    let tip_block_hash = snap.get_tip_block_hash()?;
+    let last_valid_tip_block_hash = snap.get_last_valid_tip_block_hash()?;
+    match (
+        snap.get_block_number(&tip_block_hash)?,
+        snap.get_block_number(&last_valid_tip_block_hash)?,
+    ) {
+        (Some(tip), Some(last_valid_tip)) => {
+            report.tip_block_number = tip;
+            report.last_valid_tip_block_number = last_valid_tip;
+        }
+        _ => {
+            report.error("tip block index not found".to_string());
+            return Ok(report);
+        }
+    }
+    report.pruned_block_number = snap.get_block_state_record_pruned_number()?;
+
+    let chain = check_chain(snap, &mut report)?;
+    check_account_state(snap, &chain, &mut report)?;
+    check_orphans(snap, &chain, &mut report);
+
+    Ok(report)
+}
+
+/// Delete orphaned keys of the report, returns the number of deleted keys
+pub fn repair_orphans(store: &Store, report: &CheckReport) -> Result<usize> {
+    let mut batch = store.new_write_batch();
+    let mut deleted = 0;
+    for column in report.columns.iter() {
+        for key in column.orphans.iter() {
+            batch.delete(column.col, key)?;
+            deleted += 1;
+            if batch.len() >= REPAIR_BATCH_SIZE {
+                store.write(&batch)?;
+                batch.clear()?;
+            }
+        }
+    }
+    if !batch.is_empty() {
+        store.write(&batch)?;
+    }
+    Ok(deleted)
+}
+
+fn check_chain(snap: &StoreReadonly, report: &mut CheckReport) -> Result<Chain> {
+    let tip = report.tip_block_number;
+    let last_valid_tip = report.last_valid_tip_block_number;
+    let pruned = report.pruned_block_number;
+
+    let mut hashes = Vec::with_capacity(tip as usize + 1);
+    let mut block_smt: SMT<DefaultStore<H256>> = SMT::default();
+    for number in 0..=tip {
+        if number % PROGRESS_INTERVAL == 0 {
+            log::info!("[check db] check block {}/{}", number, tip);
+        }
+
+        let block_hash = match snap.get_block_hash_by_number(number)? {
+            Some(hash) => hash,
+            None => {
+                report.error(format!("block {} index not found", number));
+                hashes.push(None);
+                continue;
+            }
+        };
+        hashes.push(Some(block_hash));
+        block_smt
+            .update(
+                packed::RawL2Block::compute_smt_key(number).into(),
+                block_hash,
+            )
+            .map_err(|err| anyhow!("block smt error {}", err))?;
+
+        if snap.get_block_number(&block_hash)? != Some(number) {
+            report.error(format!("block {} reverse index mismatch", number));
+        }
+
+        let block = match snap.get_block(&block_hash)? {
+            Some(block) => block,
+            None => {
+                if number >= pruned {
+                    report.error(format!("block {} not found", number));
+                }
+                continue;
+            }
+        };
+        let raw = block.raw();
+        let block_number: u64 = raw.number().unpack();
+        if block_number != number || H256::from(raw.hash()) != block_hash {
+            report.error(format!("block {} hash or number mismatch", number));
+        }
+        if number > 0 {
+            let parent_block_hash: H256 = raw.parent_block_hash().unpack();
+            if let Some(Some(parent)) = hashes.get(number as usize - 1) {
+                if *parent != parent_block_hash {
+                    report.error(format!("block {} parent mismatch", number));
+                }
+            }
+        }
+        if snap.get_block_post_global_state(&block_hash)?.is_none() {
+            report.error(format!("block {} post global state not found", number));
+        }
+        if snap.get_l2block_committed_info(&block_hash)?.is_none() {
+            report.error(format!("block {} committed info not found", number));
+        }
+
+        // Bad blocks aren't attached
+        if number <= last_valid_tip {
+            check_block_body(snap, number, &block, report)?;
+        }
+    }
+
+    let block_smt_root = snap.get_block_smt_root()?;
+    if block_smt_root != *block_smt.root() {
+        report.error(format!(
+            "block smt root mismatch, expected {:?} actual {:?}",
+            block_smt.root(),
+            block_smt_root
+        ));
+    }
+
+    let mut referenced: HashSet<H256> = hashes.iter().flatten().cloned().collect();
+    let reverted_root = snap.get_reverted_block_smt_root()?;
+    for (_root, block_hashes) in snap.iter_reverted_block_smt_root(reverted_root) {
+        referenced.extend(block_hashes);
+    }
+
+    Ok(Chain { hashes, referenced })
+}
+
+fn check_block_body(
+    snap: &StoreReadonly,
+    number: u64,
+    block: &packed::L2Block,
+    report: &mut CheckReport,
+) -> Result<()> {
+    // Bodies up to the pruned number may be absent, e.g. a snapshot block only
+    // has its deposit requests
+    let pruned = report.pruned_block_number;
+    let block_hash = block.hash();
+
+    if number >= pruned
+        && snap
+            .get_block_deposit_requests(&block_hash.into())?
+            .is_none()
+    {
+        report.error(format!("block {} deposit requests not found", number));
+    }
+
+    for (index, tx) in block.transactions().into_iter().enumerate() {
+        let key = TransactionKey::build_transaction_key(block_hash.pack(), index as u32);
+        if number > pruned {
+            if snap.get(COLUMN_TRANSACTION, key.as_slice()).is_none() {
+                report.error(format!("block {} tx {} not found", number, index));
+            }
+            if snap
+                .get(COLUMN_TRANSACTION_RECEIPT, key.as_slice())
+                .is_none()
+            {
+                report.error(format!("block {} tx {} receipt not found", number, index));
+            }
+        }
+        match snap.get_transaction_info(&tx.hash().into())? {
+            Some(info) if is_info_match(&info.key(), &info.block_number(), &key, number) => {}
+            Some(_) => report.error(format!("block {} tx {} info mismatch", number, index)),
+            None => report.error(format!("block {} tx {} info not found", number, index)),
+        }
+    }
+
+    for (index, withdrawal) in block.withdrawals().into_iter().enumerate() {
+        let key = WithdrawalKey::build_withdrawal_key(block_hash.pack(), index as u32);
+        if number > pruned && snap.get(COLUMN_WITHDRAWAL, key.as_slice()).is_none() {
+            report.error(format!("block {} withdrawal {} not found", number, index));
+        }
+        match snap.get_withdrawal_info(&withdrawal.hash().into())? {
+            Some(info) if is_info_match(&info.key(), &info.block_number(), &key, number) => {}
+            Some(_) => report.error(format!(
+                "block {} withdrawal {} info mismatch",
+                number, index
+            )),
+            None => report.error(format!(
+                "block {} withdrawal {} info not found",
+                number, index
+            )),
+        }
+    }
+
+    Ok(())
+}
+
+// Replay block state records from the pruned number to the last valid tip
+fn check_account_state(
+    snap: &StoreReadonly,
+    chain: &Chain,
+    report: &mut CheckReport,
+) -> Result<()> {
+    let start = report.pruned_block_number;
+    let end = report.last_valid_tip_block_number;
+
+    let mut account_smt: SMT<DefaultStore<H256>> = SMT::default();
+    for number in start..=end {
+        if number % PROGRESS_INTERVAL == 0 {
+            log::info!("[check db] replay block state {}/{}", number, end);
+        }
+
+        let leaves: Vec<(H256, H256)> = if number == start {
+            snap.iter_history_state(start).collect()
+        } else {
+            snap.iter_block_state_record(number)
+                .map(|(key, value)| (key.state_key(), value))
+                .collect()
+        };
+        for (key, value) in leaves {
+            account_smt
+                .update(key, value)
+                .map_err(|err| anyhow!("account smt error {}", err))?;
+        }
+
+        let block = match chain.hashes[number as usize] {
+            Some(block_hash) => snap.get_block(&block_hash)?,
+            None => None,
+        };
+        if let Some(block) = block {
+            let post_root: H256 = block.raw().post_account().merkle_root().unpack();
+            if post_root != *account_smt.root() {
+                report.error(format!(
+                    "block {} post account root mismatch replayed state records",
+                    number
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn check_orphans(snap: &StoreReadonly, chain: &Chain, report: &mut CheckReport) {
+    let tip = report.tip_block_number;
+    let last_valid_tip = report.last_valid_tip_block_number;
+
+    for (col, name) in ORPHAN_CHECKED_COLUMNS.iter().cloned() {
+        log::info!("[check db] check orphaned keys of column {}", name);
+
+        let mut keys = 0;
+        let mut orphans = Vec::new();
+        for (key, value) in snap.iter_column(col) {
+            keys += 1;
+            let is_orphan = match col {
+                COLUMN_INDEX => match key.len() {
+                    8 => {
+                        let number = packed::Uint64Reader::from_slice_should_be_ok(&key).unpack();
+                        number > tip
+                            || value.len() != 32
+                            || !chain.is_main(number, &to_h256(&value))
+                    }
+                    32 => match value.len() {
+                        8 => {
+                            let number =
+                                packed::Uint64Reader::from_slice_should_be_ok(&value).unpack();
+                            !chain.is_main(number, &to_h256(&key))
+                        }
+                        _ => true,
+                    },
+                    _ => true,
+                },
+                COLUMN_BLOCK
+                | COLUMN_BLOCK_GLOBAL_STATE
+                | COLUMN_L2BLOCK_COMMITTED_INFO
+                | COLUMN_BLOCK_DEPOSIT_REQUESTS
+                | COLUMN_BAD_BLOCK_CHALLENGE_TARGET => {
+                    key.len() != 32 || !chain.referenced.contains(&to_h256(&key))
+                }
+                COLUMN_TRANSACTION | COLUMN_TRANSACTION_RECEIPT | COLUMN_WITHDRAWAL => {
+                    key.len() != 36 || !chain.referenced.contains(&to_h256(&key[..32]))
+                }
+                COLUMN_TRANSACTION_INFO => {
+                    match packed::TransactionInfoReader::verify(&value, false) {
+                        Ok(()) => {
+                            let info = packed::TransactionInfoReader::new_unchecked(&value);
+                            let number = info.block_number().unpack();
+                            let block_hash = to_h256(&info.key().as_slice()[..32]);
+                            number > last_valid_tip || !chain.is_main(number, &block_hash)
+                        }
+                        Err(_) => true,
+                    }
+                }
+                COLUMN_WITHDRAWAL_INFO => {
+                    match packed::WithdrawalInfoReader::verify(&value, false) {
+                        Ok(()) => {
+                            let info = packed::WithdrawalInfoReader::new_unchecked(&value);
+                            let number = info.block_number().unpack();
+                            let block_hash = to_h256(&info.key().as_slice()[..32]);
+                            number > last_valid_tip || !chain.is_main(number, &block_hash)
+                        }
+                        Err(_) => true,
+                    }
+                }
+                COLUMN_BLOCK_STATE_RECORD => {
+                    key.len() != 40
+                        || BlockStateRecordKey::from_slice(&key).block_number() > last_valid_tip
+                }
+                COLUMN_BLOCK_STATE_REVERSE_RECORD => {
+                    key.len() != 40
+                        || BlockStateRecordKeyReverse::from_slice(&key).block_number()
+                            > last_valid_tip
+                }
+                COLUMN_LOG => {
+                    key.len() != 16 || LogKey::from_slice(&key).block_number() > last_valid_tip
+                }
+                // Index keys end with log key
+                COLUMN_LOG_ACCOUNT_INDEX | COLUMN_LOG_TOPIC_INDEX => {
+                    key.len() < 16 || snap.get(COLUMN_LOG, &key[key.len() - 16..]).is_none()
+                }
+                _ => false,
+            };
+            if is_orphan {
+                orphans.push(key);
+            }
+        }
+
+        report.columns.push(ColumnReport {
+            col,
+            name,
+            keys,
+            orphans,
+        });
+    }
+}
+
+fn is_info_match<K: Entity>(
+    info_key: &K,
+    info_block_number: &packed::Uint64,
+    key: &K,
+    block_number: u64,
+) -> bool {
+    let info_block_number: u64 = info_block_number.unpack();
+    info_key.as_slice() == key.as_slice() && info_block_number == block_number
+}
+
+fn to_h256(bytes: &[u8]) -> H256 {
+    let mut buf = [0u8; 32];
+    buf.copy_from_slice(bytes);
+    buf.into()
+}
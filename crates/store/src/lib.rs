@@ -1,5 +1,8 @@
 pub mod chain_view;
+pub mod check;
+pub mod log_index;
 pub mod mem_pool_state;
+pub mod migrate;
 pub mod readonly;
 pub mod smt;
 pub mod snapshot;
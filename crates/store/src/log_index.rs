@@ -0,0 +1,188 @@
+//! Index log items of main chain blocks by block number, account id and
+//! first topic.
+
+use gw_common::H256;
+use gw_types::{packed::LogItem, prelude::*};
+use std::convert::TryInto;
+
+/// Polyjuice user log service flag, same as `gw_utils::script_log`
+const GW_LOG_POLYJUICE_USER: u8 = 0x3;
+
+// block_number(8 bytes) | tx_index(4 bytes) | log_index(4 bytes)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LogKey([u8; 16]);
+
+impl LogKey {
+    pub fn new(block_number: u64, tx_index: u32, log_index: u32) -> Self {
+        let mut inner = [0u8; 16];
+        inner[..8].copy_from_slice(&block_number.to_be_bytes());
+        inner[8..12].copy_from_slice(&tx_index.to_be_bytes());
+        inner[12..].copy_from_slice(&log_index.to_be_bytes());
+        LogKey(inner)
+    }
+
+    pub fn block_number(&self) -> u64 {
+        u64::from_be_bytes(self.0[..8].try_into().expect("block number"))
+    }
+
+    pub fn tx_index(&self) -> u32 {
+        u32::from_be_bytes(self.0[8..12].try_into().expect("tx index"))
+    }
+
+    pub fn log_index(&self) -> u32 {
+        u32::from_be_bytes(self.0[12..].try_into().expect("log index"))
+    }
+
+    pub fn from_slice(bytes: &[u8]) -> Self {
+        let mut inner = [0u8; 16];
+        inner.copy_from_slice(bytes);
+        LogKey(inner)
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+// account_id(4 bytes) | log key(16 bytes)
+pub(crate) struct LogAccountKey([u8; 20]);
+
+impl LogAccountKey {
+    pub fn new(account_id: u32, log_key: &LogKey) -> Self {
+        let mut inner = [0u8; 20];
+        inner[..4].copy_from_slice(&account_id.to_be_bytes());
+        inner[4..].copy_from_slice(log_key.as_slice());
+        LogAccountKey(inner)
+    }
+
+    pub fn account_id(&self) -> u32 {
+        u32::from_be_bytes(self.0[..4].try_into().expect("account id"))
+    }
+
+    pub fn log_key(&self) -> LogKey {
+        LogKey::from_slice(&self.0[4..])
+    }
+
+    pub fn from_slice(bytes: &[u8]) -> Self {
+        let mut inner = [0u8; 20];
+        inner.copy_from_slice(bytes);
+        LogAccountKey(inner)
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+// topic(32 bytes) | log key(16 bytes)
+pub(crate) struct LogTopicKey([u8; 48]);
+
+impl LogTopicKey {
+    pub fn new(topic: &H256, log_key: &LogKey) -> Self {
+        let mut inner = [0u8; 48];
+        inner[..32].copy_from_slice(topic.as_slice());
+        inner[32..].copy_from_slice(log_key.as_slice());
+        LogTopicKey(inner)
+    }
+
+    pub fn topic(&self) -> H256 {
+        let mut inner = [0u8; 32];
+        inner.copy_from_slice(&self.0[..32]);
+        inner.into()
+    }
+
+    pub fn log_key(&self) -> LogKey {
+        LogKey::from_slice(&self.0[32..])
+    }
+
+    pub fn from_slice(bytes: &[u8]) -> Self {
+        let mut inner = [0u8; 48];
+        inner.copy_from_slice(bytes);
+        LogTopicKey(inner)
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// Filter logs by block range, account id and first topic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFilter {
+    pub from_block: u64,
+    /// Inclusive
+    pub to_block: u64,
+    pub account_id: Option<u32>,
+    pub topic: Option<H256>,
+}
+
+impl LogFilter {
+    pub fn is_match(&self, record: &LogRecord) -> bool {
+        let block_number = record.key.block_number();
+        if block_number < self.from_block || block_number > self.to_block {
+            return false;
+        }
+        if let Some(account_id) = self.account_id {
+            let log_account_id: u32 = record.log.account_id().unpack();
+            if log_account_id != account_id {
+                return false;
+            }
+        }
+        if let Some(topic) = self.topic {
+            if first_topic(&record.log) != Some(topic) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// An indexed log item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogRecord {
+    pub key: LogKey,
+    pub tx_hash: H256,
+    pub log: LogItem,
+}
+
+impl LogRecord {
+    // tx_hash(32 bytes) | log item
+    pub(crate) fn to_value(&self) -> Vec<u8> {
+        let mut value = Vec::with_capacity(32 + self.log.as_slice().len());
+        value.extend_from_slice(self.tx_hash.as_slice());
+        value.extend_from_slice(self.log.as_slice());
+        value
+    }
+
+    pub(crate) fn from_kv(key: &[u8], value: &[u8]) -> Self {
+        let mut tx_hash = [0u8; 32];
+        tx_hash.copy_from_slice(&value[..32]);
+        let log = LogItem::from_slice(&value[32..]).expect("valid log item");
+        LogRecord {
+            key: LogKey::from_slice(key),
+            tx_hash: tx_hash.into(),
+            log,
+        }
+    }
+}
+
+/// Returns the first topic of a Polyjuice user log.
+///
+/// data layout: address(20 bytes) | data_size(4 bytes) | data | topics_count(4 bytes) | topics
+pub fn first_topic(log: &LogItem) -> Option<H256> {
+    let service_flag: u8 = log.service_flag().into();
+    if service_flag != GW_LOG_POLYJUICE_USER {
+        return None;
+    }
+
+    let data = log.data().raw_data();
+    let data_size = u32::from_le_bytes(data.get(20..24)?.try_into().ok()?) as usize;
+    let offset = 24usize.checked_add(data_size)?;
+    let topics_count = u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?);
+    if topics_count == 0 {
+        return None;
+    }
+    let mut topic = [0u8; 32];
+    topic.copy_from_slice(data.get(offset + 4..offset + 36)?);
+    Some(topic.into())
+}
@@ -0,0 +1,133 @@
+//! Migrations of the store, registered after migrations of `gw_db`.
+//!
+//! Use `open_or_create_db` of this mod instead of `gw_db::migrate` to open the
+//! db, so the db version includes migrations of the store.
+
+use gw_config::StoreConfig;
+use gw_db::{
+    error::Error,
+    migrate::{
+        self, migration_cursor_key, Migration, MigrationFactory, MigrationProgress,
+        PendingMigration,
+    },
+    schema::{COLUMN_META, META_TIP_BLOCK_HASH_KEY},
+    RocksDB,
+};
+use gw_types::{packed, prelude::*};
+
+use crate::{
+    traits::{
+        chain_store::ChainStore,
+        kv_store::{KVStoreRead, KVStoreWrite},
+    },
+    Store,
+};
+
+// Blocks indexed in a transaction by `LogIndexMigration`
+const LOG_INDEX_MIGRATION_BATCH_BLOCKS: u64 = 1000;
+
+pub fn init_migration_factory() -> MigrationFactory {
+    let mut factory = migrate::init_migration_factory();
+    factory.insert(Box::new(LogIndexMigration));
+    factory
+}
+
+pub fn open_or_create_db(config: &StoreConfig) -> Result<RocksDB, Error> {
+    migrate::open_or_create_db_with_factory(config, init_migration_factory())
+}
+
+/// Pending migrations in order, without changing the db
+pub fn check_migrations(config: &StoreConfig) -> Result<Vec<PendingMigration>, Error> {
+    migrate::check_migrations(config, &init_migration_factory())
+}
+
+/// Check the db can be opened without migrating it, for tools which open the
+/// db directly and may run beside the node. Fails if the db is created by a
+/// higher version binary or has pending migrations, e.g. log items of old
+/// blocks aren't indexed yet.
+pub fn check_db_version(config: &StoreConfig) -> Result<(), Error> {
+    let pending = check_migrations(config)?;
+    if !pending.is_empty() {
+        let versions: Vec<&str> = pending.iter().map(|m| m.version.as_str()).collect();
+        return Err(Error {
+            message: format!(
+                "The database has pending migrations {}, please run `godwoken migrate --run`",
+                versions.join(", ")
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Run all pending migrations, cheap and expensive ones
+pub fn run_migrations(config: &StoreConfig, progress: &dyn MigrationProgress) -> Result<(), Error> {
+    migrate::run_migrations(config, &init_migration_factory(), progress)
+}
+
+/// Index log items of blocks committed before the log index is added.
+///
+/// The last indexed block number is saved with each batch, indexing a block
+/// again writes the same entries.
+pub(crate) struct LogIndexMigration;
+
+impl Migration for LogIndexMigration {
+    fn migrate(&self, db: RocksDB, progress: &dyn MigrationProgress) -> Result<RocksDB, Error> {
+        let store = Store::new(db.clone());
+        let tip_number: u64 = {
+            let db_txn = store.begin_transaction();
+            if db_txn.get(COLUMN_META, META_TIP_BLOCK_HASH_KEY).is_none() {
+                return Ok(db);
+            }
+            db_txn.get_tip_block()?.raw().number().unpack()
+        };
+
+        let cursor_key = migration_cursor_key(self.version());
+        let mut number = match db.get_pinned(COLUMN_META, &cursor_key)? {
+            Some(slice) => {
+                let indexed: u64 =
+                    packed::Uint64Reader::from_slice_should_be_ok(slice.as_ref()).unpack();
+                indexed.saturating_add(1)
+            }
+            None => 0,
+        };
+        progress.set_length(tip_number.saturating_add(1));
+        progress.inc(number);
+
+        while number <= tip_number {
+            let end = number
+                .saturating_add(LOG_INDEX_MIGRATION_BATCH_BLOCKS)
+                .min(tip_number.saturating_add(1));
+            let db_txn = store.begin_transaction();
+            for block_number in number..end {
+                // blocks before an imported state snapshot don't exist
+                let block = match db_txn.get_block_hash_by_number(block_number)? {
+                    Some(block_hash) => db_txn.get_block(&block_hash)?,
+                    None => None,
+                };
+                if let Some(block) = block {
+                    db_txn.insert_block_logs(&block)?;
+                }
+            }
+            let indexed: packed::Uint64 = (end - 1).pack();
+            db_txn.insert_raw(COLUMN_META, &cursor_key, indexed.as_slice())?;
+            db_txn.commit()?;
+
+            progress.inc(end - number);
+            number = end;
+        }
+
+        Ok(db)
+    }
+
+    fn version(&self) -> &str {
+        "20220613000000"
+    }
+
+    fn name(&self) -> &str {
+        "index log items of committed blocks"
+    }
+
+    fn expensive(&self) -> bool {
+        true
+    }
+}
@@ -1,14 +1,22 @@
 use gw_common::H256;
 use gw_db::{
     read_only_db::ReadOnlyDB,
-    schema::{Col, COLUMN_REVERTED_BLOCK_SMT_ROOT},
+    schema::{
+        Col, COLUMN_ASSET_SCRIPT, COLUMN_BLOCK_STATE_RECORD, COLUMN_BLOCK_STATE_REVERSE_RECORD,
+        COLUMN_DATA, COLUMN_REVERTED_BLOCK_SMT_ROOT, COLUMN_SCRIPT,
+    },
+    DBIterator, Direction, IteratorMode,
 };
 use gw_types::{
+    bytes::Bytes,
     from_box_should_be_ok, packed,
     prelude::{Entity, FromSliceShouldBeOk, Unpack},
 };
 
-use crate::traits::{chain_store::ChainStore, kv_store::KVStoreRead};
+use crate::{
+    state::block_state_record::{BlockStateRecordKey, BlockStateRecordKeyReverse},
+    traits::{chain_store::ChainStore, kv_store::KVStoreRead},
+};
 
 #[derive(Clone)]
 pub struct StoreReadonly {
@@ -29,6 +37,95 @@ impl StoreReadonly {
             next_root: root,
         }
     }
+
+    /// Iterate non-zero account SMT leaves at `block_number`, rebuilt from
+    /// block state records.
+    pub fn iter_history_state(&self, block_number: u64) -> impl Iterator<Item = (H256, H256)> + '_ {
+        let mut records = self
+            .inner
+            .iter(COLUMN_BLOCK_STATE_REVERSE_RECORD, IteratorMode::Start)
+            .expect("db operation should be ok")
+            .map(|(key, _value)| BlockStateRecordKeyReverse::from_slice(&key))
+            .filter(move |key| key.block_number() <= block_number)
+            .peekable();
+
+        // Records are sorted by state key then block number, the last record
+        // of a state key holds its value at `block_number`.
+        let last_records = std::iter::from_fn(move || loop {
+            let record = records.next()?;
+            match records.peek() {
+                Some(next) if next.state_key() == record.state_key() => continue,
+                _ => return Some(record),
+            }
+        });
+
+        last_records.filter_map(move |record| {
+            let state_key = record.state_key();
+            let key = BlockStateRecordKey::new(record.block_number(), &state_key);
+            let value = self.get(COLUMN_BLOCK_STATE_RECORD, key.as_slice())?;
+            let mut buf = [0u8; 32];
+            buf.copy_from_slice(&value);
+            let value: H256 = buf.into();
+            (!value.is_zero()).then(|| (state_key, value))
+        })
+    }
+
+    /// Iterate block state records of `block_number`
+    pub fn iter_block_state_record(
+        &self,
+        block_number: u64,
+    ) -> impl Iterator<Item = (BlockStateRecordKey, H256)> + '_ {
+        let start_key = BlockStateRecordKey::new(block_number, &H256::zero());
+        self.inner
+            .iter(
+                COLUMN_BLOCK_STATE_RECORD,
+                IteratorMode::From(start_key.as_slice(), Direction::Forward),
+            )
+            .expect("db operation should be ok")
+            .map(|(key, value)| {
+                let mut buf = [0u8; 32];
+                buf.copy_from_slice(&value);
+                (BlockStateRecordKey::from_slice(&key), buf.into())
+            })
+            .take_while(move |(key, _value)| key.block_number() == block_number)
+    }
+
+    pub(crate) fn iter_column(&self, col: Col) -> impl Iterator<Item = (Box<[u8]>, Box<[u8]>)> {
+        self.inner
+            .iter(col, IteratorMode::Start)
+            .expect("db operation should be ok")
+    }
+
+    pub fn iter_scripts(&self) -> impl Iterator<Item = (H256, packed::Script)> {
+        self.inner
+            .iter(COLUMN_SCRIPT, IteratorMode::Start)
+            .expect("db operation should be ok")
+            .map(|(key, value)| {
+                (
+                    packed::Byte32Reader::from_slice_should_be_ok(key.as_ref()).unpack(),
+                    from_box_should_be_ok!(packed::ScriptReader, value),
+                )
+            })
+    }
+
+    pub fn iter_asset_scripts(&self) -> impl Iterator<Item = packed::Script> {
+        self.inner
+            .iter(COLUMN_ASSET_SCRIPT, IteratorMode::Start)
+            .expect("db operation should be ok")
+            .map(|(_key, value)| from_box_should_be_ok!(packed::ScriptReader, value))
+    }
+
+    pub fn iter_data(&self) -> impl Iterator<Item = (H256, Bytes)> {
+        self.inner
+            .iter(COLUMN_DATA, IteratorMode::Start)
+            .expect("db operation should be ok")
+            .map(|(key, value)| {
+                (
+                    packed::Byte32Reader::from_slice_should_be_ok(key.as_ref()).unpack(),
+                    Bytes::from(value.to_vec()),
+                )
+            })
+    }
 }
 
 impl ChainStore for StoreReadonly {}
@@ -12,9 +12,10 @@ use gw_common::smt::Blake2bHasher;
 
 use gw_db::{
     schema::{Col, COLUMNS},
-    CfMemStat, DBPinnableSlice, RocksDB,
+    CfMemStat, DBIterator, DBPinnableSlice, IteratorMode, RocksDB,
 };
 use gw_types::prelude::*;
+use std::path::Path;
 
 #[derive(Clone)]
 pub struct Store {
@@ -61,6 +62,20 @@ impl<'a> Store {
         Ok(())
     }
 
+    /// Delete all keys of the column
+    pub fn clear_column(&self, col: Col) -> Result<()> {
+        let first_key = self.db.iter(col, IteratorMode::Start)?.next();
+        let last_key = self.db.iter(col, IteratorMode::End)?.next();
+        if let (Some((first_key, _)), Some((last_key, _))) = (first_key, last_key) {
+            let mut batch = self.new_write_batch();
+            // The end key of a range is excluded
+            batch.inner.delete_range(col, &first_key, &last_key)?;
+            batch.inner.delete(col, &last_key)?;
+            self.write(&batch)?;
+        }
+        Ok(())
+    }
+
     pub fn check_state(&self) -> Result<()> {
         let db = self.begin_transaction();
 
@@ -89,6 +104,12 @@ impl<'a> Store {
     pub fn get_snapshot(&self) -> StoreSnapshot {
         StoreSnapshot::new(self.db.get_snapshot())
     }
+
+    /// Create a consistent checkpoint of the store at `path`, see `RocksDB::create_checkpoint`
+    pub fn create_checkpoint(&self, path: &Path) -> Result<()> {
+        self.db.create_checkpoint(path)?;
+        Ok(())
+    }
 }
 
 impl ChainStore for Store {}
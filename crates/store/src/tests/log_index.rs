@@ -0,0 +1,232 @@
+use crate::{
+    log_index::{LogFilter, LogKey},
+    migrate::LogIndexMigration,
+    traits::kv_store::KVStoreWrite,
+    Store,
+};
+use gw_common::H256;
+use gw_db::{
+    migrate::Migration,
+    schema::{
+        COLUMNS, COLUMN_BLOCK, COLUMN_LOG, COLUMN_LOG_ACCOUNT_INDEX, COLUMN_LOG_TOPIC_INDEX,
+        COLUMN_TRANSACTION_RECEIPT,
+    },
+    IteratorMode, RocksDB,
+};
+use gw_types::{
+    packed::{
+        L2Block, L2Transaction, LogItem, RawL2Block, RawL2Transaction, TransactionKey, TxReceipt,
+    },
+    prelude::*,
+};
+
+const GW_LOG_SUDT_TRANSFER: u8 = 0x0;
+const GW_LOG_POLYJUICE_USER: u8 = 0x3;
+
+fn polyjuice_log(account_id: u32, topic: Option<H256>) -> LogItem {
+    let mut data = vec![0u8; 20];
+    data.extend_from_slice(&0u32.to_le_bytes());
+    match topic {
+        Some(topic) => {
+            data.extend_from_slice(&1u32.to_le_bytes());
+            data.extend_from_slice(topic.as_slice());
+        }
+        None => data.extend_from_slice(&0u32.to_le_bytes()),
+    }
+    LogItem::new_builder()
+        .account_id(account_id.pack())
+        .service_flag(GW_LOG_POLYJUICE_USER.into())
+        .data(data.pack())
+        .build()
+}
+
+fn sudt_log(account_id: u32) -> LogItem {
+    LogItem::new_builder()
+        .account_id(account_id.pack())
+        .service_flag(GW_LOG_SUDT_TRANSFER.into())
+        .build()
+}
+
+fn build_block(number: u64, txs_logs: Vec<Vec<LogItem>>, store: &Store) -> L2Block {
+    let txs: Vec<L2Transaction> = (0..txs_logs.len())
+        .map(|nonce| {
+            let raw = RawL2Transaction::new_builder()
+                .nonce((nonce as u32).pack())
+                .build();
+            L2Transaction::new_builder().raw(raw).build()
+        })
+        .collect();
+    let block = L2Block::new_builder()
+        .raw(RawL2Block::new_builder().number(number.pack()).build())
+        .transactions(txs.pack())
+        .build();
+
+    let store_txn = store.begin_transaction();
+    for (index, logs) in txs_logs.into_iter().enumerate() {
+        let key = TransactionKey::build_transaction_key(block.hash().pack(), index as u32);
+        let receipt = TxReceipt::new_builder().logs(logs.pack()).build();
+        store_txn
+            .insert_raw(
+                COLUMN_TRANSACTION_RECEIPT,
+                key.as_slice(),
+                receipt.as_slice(),
+            )
+            .unwrap();
+    }
+    store_txn.commit().unwrap();
+    block
+}
+
+#[test]
+fn test_log_index() {
+    let store = Store::open_tmp().unwrap();
+    let topic_a: H256 = [1u8; 32].into();
+    let topic_b: H256 = [2u8; 32].into();
+
+    let genesis = build_block(0, vec![], &store);
+    let block = build_block(
+        1,
+        vec![
+            vec![sudt_log(1), polyjuice_log(2, Some(topic_a))],
+            vec![polyjuice_log(3, Some(topic_b)), polyjuice_log(2, None)],
+        ],
+        &store,
+    );
+    let store_txn = store.begin_transaction();
+    store_txn.attach_block(genesis).unwrap();
+    store_txn.attach_block(block.clone()).unwrap();
+    store_txn.commit().unwrap();
+
+    let filter = LogFilter {
+        from_block: 0,
+        to_block: 1,
+        account_id: None,
+        topic: None,
+    };
+    let store_txn = store.begin_transaction();
+    let (logs, next) = store_txn.get_logs(&filter, None, 10).unwrap();
+    assert_eq!(logs.len(), 4);
+    assert!(next.is_none());
+    let tx_hash: H256 = block.transactions().get(1).unwrap().hash().into();
+    assert_eq!(logs[2].key, LogKey::new(1, 1, 0));
+    assert_eq!(logs[2].tx_hash, tx_hash);
+
+    // paginate
+    let (logs, next) = store_txn.get_logs(&filter, None, 3).unwrap();
+    assert_eq!(logs.len(), 3);
+    assert_eq!(next, Some(LogKey::new(1, 1, 1)));
+    let (logs, next) = store_txn.get_logs(&filter, next, 3).unwrap();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].key, LogKey::new(1, 1, 1));
+    assert!(next.is_none());
+
+    // zero limit still progresses
+    let (logs, next) = store_txn.get_logs(&filter, None, 0).unwrap();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(next, Some(LogKey::new(1, 0, 1)));
+
+    // filter by account
+    let account_filter = LogFilter {
+        account_id: Some(2),
+        ..filter.clone()
+    };
+    let (logs, _) = store_txn.get_logs(&account_filter, None, 10).unwrap();
+    let keys: Vec<LogKey> = logs.iter().map(|log| log.key).collect();
+    assert_eq!(keys, vec![LogKey::new(1, 0, 1), LogKey::new(1, 1, 1)]);
+
+    // filter by topic
+    let topic_filter = LogFilter {
+        topic: Some(topic_b),
+        ..filter.clone()
+    };
+    let (logs, _) = store_txn.get_logs(&topic_filter, None, 10).unwrap();
+    let keys: Vec<LogKey> = logs.iter().map(|log| log.key).collect();
+    assert_eq!(keys, vec![LogKey::new(1, 1, 0)]);
+
+    // filter by account and topic
+    let both_filter = LogFilter {
+        account_id: Some(2),
+        topic: Some(topic_b),
+        ..filter.clone()
+    };
+    let (logs, _) = store_txn.get_logs(&both_filter, None, 10).unwrap();
+    assert!(logs.is_empty());
+
+    // out of block range
+    let range_filter = LogFilter {
+        from_block: 2,
+        to_block: 10,
+        ..filter.clone()
+    };
+    let (logs, _) = store_txn.get_logs(&range_filter, None, 10).unwrap();
+    assert!(logs.is_empty());
+
+    // detach block removes its logs
+    store_txn.detach_block(&block).unwrap();
+    store_txn.commit().unwrap();
+    let store_txn = store.begin_transaction();
+    let (logs, _) = store_txn.get_logs(&filter, None, 10).unwrap();
+    assert!(logs.is_empty());
+    let (logs, _) = store_txn.get_logs(&account_filter, None, 10).unwrap();
+    assert!(logs.is_empty());
+    let (logs, _) = store_txn.get_logs(&topic_filter, None, 10).unwrap();
+    assert!(logs.is_empty());
+}
+
+#[test]
+fn test_log_index_migration() {
+    let db = RocksDB::open_tmp(COLUMNS);
+    let store = Store::new(db.clone());
+    let topic: H256 = [1u8; 32].into();
+
+    let genesis = build_block(0, vec![], &store);
+    let block = build_block(
+        1,
+        vec![vec![sudt_log(1), polyjuice_log(2, Some(topic))]],
+        &store,
+    );
+    let store_txn = store.begin_transaction();
+    for block in [genesis, block].iter() {
+        store_txn
+            .insert_raw(COLUMN_BLOCK, &block.hash(), block.as_slice())
+            .unwrap();
+        store_txn.attach_block(block.clone()).unwrap();
+    }
+    store_txn.commit().unwrap();
+
+    // blocks attached before log items are indexed
+    let store_txn = store.begin_transaction();
+    for &col in &[COLUMN_LOG, COLUMN_LOG_ACCOUNT_INDEX, COLUMN_LOG_TOPIC_INDEX] {
+        let keys: Vec<_> = store_txn
+            .get_iter(col, IteratorMode::Start)
+            .map(|(key, _value)| key)
+            .collect();
+        for key in keys {
+            store_txn.delete(col, &key).unwrap();
+        }
+    }
+    store_txn.commit().unwrap();
+
+    let filter = LogFilter {
+        from_block: 0,
+        to_block: 1,
+        account_id: None,
+        topic: None,
+    };
+    let store_txn = store.begin_transaction();
+    let (logs, _) = store_txn.get_logs(&filter, None, 10).unwrap();
+    assert!(logs.is_empty());
+
+    LogIndexMigration.migrate(db, &()).unwrap();
+
+    let store_txn = store.begin_transaction();
+    let (logs, _) = store_txn.get_logs(&filter, None, 10).unwrap();
+    let keys: Vec<LogKey> = logs.iter().map(|log| log.key).collect();
+    assert_eq!(keys, vec![LogKey::new(1, 0, 0), LogKey::new(1, 0, 1)]);
+    let topic_filter = LogFilter {
+        topic: Some(topic),
+        ..filter
+    };
+    let (logs, _) = store_txn.get_logs(&topic_filter, None, 10).unwrap();
+    assert_eq!(logs.len(), 1);
+}
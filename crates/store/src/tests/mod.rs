@@ -1,2 +1,3 @@
+mod log_index;
 mod state_db;
 mod transaction;
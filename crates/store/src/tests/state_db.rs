@@ -200,3 +200,91 @@ fn test_state_with_version() {
         assert_eq!(v, H256::zero());
     }
 }
+
+#[test]
+fn test_prune_block_state_record() {
+    let store = Store::open_tmp().unwrap();
+    let mut prev_txs_state_checkpoint = calculate_state_checkpoint(&H256::zero(), 0);
+    // setup genesis block
+    let genesis = L2Block::new_builder()
+        .raw(
+            RawL2Block::new_builder()
+                .submit_transactions(
+                    SubmitTransactions::new_builder()
+                        .prev_state_checkpoint(prev_txs_state_checkpoint.pack())
+                        .build(),
+                )
+                .build(),
+        )
+        .build();
+    let db = store.begin_transaction();
+    db.set_block_smt_root(H256::zero()).unwrap();
+    commit_block(&db, genesis);
+    db.commit().unwrap();
+
+    let blocks: Vec<Vec<(u32, u32)>> = vec![
+        vec![(1, 1), (2, 2), (3, 3)],
+        vec![(1, 11), (2, 12)],
+        vec![(1, 21)],
+    ];
+    for (index, kvs) in blocks.into_iter().enumerate() {
+        let block_number = index as u64 + 1;
+        let db = store.begin_transaction();
+        let mut state = db
+            .state_tree(StateContext::AttachBlock(block_number))
+            .unwrap();
+        for (k, v) in kvs {
+            state
+                .update_raw(H256::from_u32(k), H256::from_u32(v))
+                .unwrap();
+        }
+        commit_block(
+            &db,
+            build_block(&state, block_number, prev_txs_state_checkpoint),
+        );
+        prev_txs_state_checkpoint = state.calculate_state_checkpoint().unwrap();
+        db.commit().unwrap();
+    }
+
+    // blocks must be pruned in order
+    let db = store.begin_transaction();
+    assert!(db.prune_block_state_record(2).is_err());
+
+    for block_number in 1..=2 {
+        let db = store.begin_transaction();
+        db.prune_block_state_record(block_number).unwrap();
+        db.commit().unwrap();
+    }
+
+    let db = store.begin_transaction();
+    assert_eq!(db.get_block_state_record_pruned_number().unwrap(), 2);
+    let err = db
+        .state_tree(StateContext::ReadOnlyHistory(1))
+        .err()
+        .expect("state pruned");
+    assert!(err.to_string().contains("pruned"));
+
+    // superseded records are removed
+    let records: Vec<_> = db
+        .iter_block_state_record(1)
+        .map(|record| record.state_key())
+        .collect();
+    assert_eq!(records, vec![H256::from_u32(3)]);
+
+    // history state of retained blocks is unchanged
+    let state = db.state_tree(StateContext::ReadOnlyHistory(2)).unwrap();
+    let v = state.get_raw(&H256::from_u32(1)).unwrap();
+    assert_eq!(v, H256::from_u32(11));
+    let v = state.get_raw(&H256::from_u32(2)).unwrap();
+    assert_eq!(v, H256::from_u32(12));
+    let v = state.get_raw(&H256::from_u32(3)).unwrap();
+    assert_eq!(v, H256::from_u32(3));
+
+    let state = db.state_tree(StateContext::ReadOnlyHistory(3)).unwrap();
+    let v = state.get_raw(&H256::from_u32(1)).unwrap();
+    assert_eq!(v, H256::from_u32(21));
+    let v = state.get_raw(&H256::from_u32(2)).unwrap();
+    assert_eq!(v, H256::from_u32(12));
+    let v = state.get_raw(&H256::from_u32(3)).unwrap();
+    assert_eq!(v, H256::from_u32(3));
+}
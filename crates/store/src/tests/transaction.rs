@@ -335,6 +335,26 @@ fn delete_range() {
     assert!(store_txn.get(1, &[3]).is_some());
 }
 
+#[test]
+fn clear_column() {
+    let store = Store::open_tmp().unwrap();
+
+    let store_txn = store.begin_transaction();
+    store_txn.insert_raw(1, &[0], &[0, 0]).unwrap();
+    store_txn.insert_raw(1, &[1, 1], &[1, 1]).unwrap();
+    store_txn.insert_raw(1, &[2], &[2, 2]).unwrap();
+    store_txn.insert_raw(2, &[0], &[0, 0]).unwrap();
+    store_txn.commit().unwrap();
+
+    store.clear_column(1).unwrap();
+    // Clearing an empty column is ok
+    store.clear_column(1).unwrap();
+
+    let store_txn = store.begin_transaction();
+    assert!(store_txn.get_iter(1, IteratorMode::Start).next().is_none());
+    assert!(store_txn.get(2, &[0]).is_some());
+}
+
 #[test]
 fn range_search_and_delete() {
     let store = Store::open_tmp().unwrap();
@@ -11,8 +11,8 @@ use gw_db::schema::{
     COLUMN_MEM_POOL_TRANSACTION_RECEIPT, COLUMN_MEM_POOL_WITHDRAWAL, COLUMN_META,
     COLUMN_REVERTED_BLOCK_SMT_ROOT, COLUMN_TRANSACTION, COLUMN_TRANSACTION_INFO,
     COLUMN_TRANSACTION_RECEIPT, COLUMN_WITHDRAWAL, COLUMN_WITHDRAWAL_INFO, META_BLOCK_SMT_ROOT_KEY,
-    META_CHAIN_ID_KEY, META_LAST_VALID_TIP_BLOCK_HASH_KEY, META_REVERTED_BLOCK_SMT_ROOT_KEY,
-    META_TIP_BLOCK_HASH_KEY,
+    META_BLOCK_STATE_RECORD_PRUNED_KEY, META_CHAIN_ID_KEY, META_IMPORT_STATE_KEY,
+    META_LAST_VALID_TIP_BLOCK_HASH_KEY, META_REVERTED_BLOCK_SMT_ROOT_KEY, META_TIP_BLOCK_HASH_KEY,
 };
 use gw_types::offchain::{global_state_from_slice, SMTRevertedBlockHashes};
 use gw_types::packed::{Script, WithdrawalKey};
@@ -27,6 +27,11 @@ pub trait ChainStore: KVStoreRead {
         Ok(self.get_block_hash_by_number(0)?.is_some())
     }
 
+    /// A state snapshot import is failed or interrupted, the store is partially written
+    fn has_unfinished_state_import(&self) -> bool {
+        self.get(COLUMN_META, META_IMPORT_STATE_KEY).is_some()
+    }
+
     fn get_chain_id(&self) -> Result<H256, Error> {
         let slice = self
             .get(COLUMN_META, META_CHAIN_ID_KEY)
@@ -97,6 +102,17 @@ pub trait ChainStore: KVStoreRead {
         Ok(self.get_block(&tip_block_hash)?.expect("get tip block"))
     }
 
+    /// History state is available from the returned block number, block state
+    /// records of older blocks are pruned. Returns 0 if nothing is pruned.
+    fn get_block_state_record_pruned_number(&self) -> Result<u64, Error> {
+        match self.get(COLUMN_META, META_BLOCK_STATE_RECORD_PRUNED_KEY) {
+            Some(slice) => {
+                Ok(packed::Uint64Reader::from_slice_should_be_ok(slice.as_ref()).unpack())
+            }
+            None => Ok(0),
+        }
+    }
+
     fn get_block_hash_by_number(&self, number: u64) -> Result<Option<H256>, Error> {
         let block_number: packed::Uint64 = number.pack();
         match self.get(COLUMN_INDEX, block_number.as_slice()) {
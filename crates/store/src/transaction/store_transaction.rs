@@ -11,13 +11,13 @@ use gw_db::schema::{
     COLUMN_BAD_BLOCK_CHALLENGE_TARGET, COLUMN_BLOCK, COLUMN_BLOCK_DEPOSIT_REQUESTS,
     COLUMN_BLOCK_GLOBAL_STATE, COLUMN_BLOCK_SMT_BRANCH, COLUMN_BLOCK_SMT_LEAF,
     COLUMN_BLOCK_STATE_RECORD, COLUMN_BLOCK_STATE_REVERSE_RECORD, COLUMN_INDEX,
-    COLUMN_L2BLOCK_COMMITTED_INFO, COLUMN_MEM_POOL_TRANSACTION,
-    COLUMN_MEM_POOL_TRANSACTION_RECEIPT, COLUMN_MEM_POOL_WITHDRAWAL, COLUMN_META,
-    COLUMN_REVERTED_BLOCK_SMT_BRANCH, COLUMN_REVERTED_BLOCK_SMT_LEAF,
+    COLUMN_L2BLOCK_COMMITTED_INFO, COLUMN_LOG, COLUMN_LOG_ACCOUNT_INDEX, COLUMN_LOG_TOPIC_INDEX,
+    COLUMN_MEM_POOL_TRANSACTION, COLUMN_MEM_POOL_TRANSACTION_RECEIPT, COLUMN_MEM_POOL_WITHDRAWAL,
+    COLUMN_META, COLUMN_REVERTED_BLOCK_SMT_BRANCH, COLUMN_REVERTED_BLOCK_SMT_LEAF,
     COLUMN_REVERTED_BLOCK_SMT_ROOT, COLUMN_TRANSACTION, COLUMN_TRANSACTION_INFO,
     COLUMN_TRANSACTION_RECEIPT, COLUMN_WITHDRAWAL, COLUMN_WITHDRAWAL_INFO, META_BLOCK_SMT_ROOT_KEY,
-    META_CHAIN_ID_KEY, META_LAST_VALID_TIP_BLOCK_HASH_KEY, META_REVERTED_BLOCK_SMT_ROOT_KEY,
-    META_TIP_BLOCK_HASH_KEY,
+    META_BLOCK_STATE_RECORD_PRUNED_KEY, META_CHAIN_ID_KEY, META_LAST_VALID_TIP_BLOCK_HASH_KEY,
+    META_REVERTED_BLOCK_SMT_ROOT_KEY, META_TIP_BLOCK_HASH_KEY,
 };
 use gw_db::{error::Error, iter::DBIter, DBIterator, IteratorMode, RocksDBTransaction};
 use gw_db::{DBRawIterator, Direction};
@@ -31,6 +31,7 @@ use gw_types::{
 };
 use std::collections::HashSet;
 
+use crate::log_index::{first_topic, LogAccountKey, LogFilter, LogKey, LogRecord, LogTopicKey};
 use crate::state::block_state_record::{BlockStateRecordKey, BlockStateRecordKeyReverse};
 
 pub struct StoreTransaction {
@@ -386,17 +387,11 @@ impl StoreTransaction {
             self.insert_raw(COLUMN_WITHDRAWAL_INFO, &withdrawal_hash, info.as_slice())?;
         }
 
-        // build main chain index
-        self.insert_raw(COLUMN_INDEX, raw_number.as_slice(), &block_hash)?;
-        self.insert_raw(COLUMN_INDEX, &block_hash, raw_number.as_slice())?;
+        // build log index
+        self.insert_block_logs(&block)?;
 
-        // update block tree
-        let mut block_smt = self.block_smt()?;
-        block_smt
-            .update(raw.smt_key().into(), raw.hash().into())
-            .map_err(|err| Error::from(format!("SMT error {}", err)))?;
-        let root = block_smt.root();
-        self.set_block_smt_root(*root)?;
+        // build main chain index and update block tree
+        self.attach_block_hash(raw_number.unpack(), &block_hash.into())?;
 
         // update tip
         self.insert_raw(COLUMN_META, META_TIP_BLOCK_HASH_KEY, &block_hash)?;
@@ -405,6 +400,53 @@ impl StoreTransaction {
         Ok(())
     }
 
+    /// Add block hash to the main chain index and the block SMT, the block
+    /// itself isn't required. Used by `attach_block` and state snapshot import.
+    pub fn attach_block_hash(&self, block_number: u64, block_hash: &H256) -> Result<(), Error> {
+        let raw_number: packed::Uint64 = block_number.pack();
+        self.insert_raw(COLUMN_INDEX, raw_number.as_slice(), block_hash.as_slice())?;
+        self.insert_raw(COLUMN_INDEX, block_hash.as_slice(), raw_number.as_slice())?;
+
+        let mut block_smt = self.block_smt()?;
+        block_smt
+            .update(
+                packed::RawL2Block::compute_smt_key(block_number).into(),
+                *block_hash,
+            )
+            .map_err(|err| Error::from(format!("SMT error {}", err)))?;
+        let root = block_smt.root();
+        self.set_block_smt_root(*root)
+    }
+
+    /// Insert the block of a state snapshot. Transactions, withdrawals and
+    /// their receipts aren't included in the snapshot.
+    pub fn insert_snapshot_block(
+        &self,
+        block: &packed::L2Block,
+        committed_info: &packed::L2BlockCommittedInfo,
+        global_state: &packed::GlobalState,
+        deposit_requests: Vec<packed::DepositRequest>,
+    ) -> Result<(), Error> {
+        let block_hash = block.hash();
+        self.insert_raw(COLUMN_BLOCK, &block_hash, block.as_slice())?;
+        self.insert_raw(
+            COLUMN_L2BLOCK_COMMITTED_INFO,
+            &block_hash,
+            committed_info.as_slice(),
+        )?;
+        self.insert_raw(
+            COLUMN_BLOCK_GLOBAL_STATE,
+            &block_hash,
+            global_state.as_slice(),
+        )?;
+        let deposit_requests_vec: packed::DepositRequestVec = deposit_requests.pack();
+        self.insert_raw(
+            COLUMN_BLOCK_DEPOSIT_REQUESTS,
+            &block_hash,
+            deposit_requests_vec.as_slice(),
+        )
+    }
+
     /// Delete block from DB
     pub fn detach_block(&self, block: &packed::L2Block) -> Result<(), Error> {
         // check
@@ -423,6 +465,9 @@ impl StoreTransaction {
             self.delete(COLUMN_TRANSACTION_INFO, &tx_hash)?;
         }
 
+        // remove log index
+        self.remove_block_logs(block.raw().number().unpack())?;
+
         let block_hash: H256 = block.hash().into();
 
         // remove index
@@ -454,19 +499,208 @@ impl StoreTransaction {
         Ok(())
     }
 
+    pub(crate) fn insert_block_logs(&self, block: &packed::L2Block) -> Result<(), Error> {
+        let block_number: u64 = block.raw().number().unpack();
+        let block_hash = block.hash();
+        for (tx_index, tx) in block.transactions().into_iter().enumerate() {
+            let tx_key = TransactionKey::build_transaction_key(block_hash.pack(), tx_index as u32);
+            let receipt = match self.get_transaction_receipt_by_key(&tx_key)? {
+                Some(receipt) => receipt,
+                None => continue,
+            };
+            for (log_index, log) in receipt.logs().into_iter().enumerate() {
+                let record = LogRecord {
+                    key: LogKey::new(block_number, tx_index as u32, log_index as u32),
+                    tx_hash: tx.hash().into(),
+                    log,
+                };
+                self.insert_raw(COLUMN_LOG, record.key.as_slice(), &record.to_value())?;
+                let account_id: u32 = record.log.account_id().unpack();
+                let account_key = LogAccountKey::new(account_id, &record.key);
+                self.insert_raw(COLUMN_LOG_ACCOUNT_INDEX, account_key.as_slice(), &[])?;
+                if let Some(topic) = first_topic(&record.log) {
+                    let topic_key = LogTopicKey::new(&topic, &record.key);
+                    self.insert_raw(COLUMN_LOG_TOPIC_INDEX, topic_key.as_slice(), &[])?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_block_logs(&self, block_number: u64) -> Result<(), Error> {
+        let records: Vec<LogRecord> = self
+            .iter_logs_from(LogKey::new(block_number, 0, 0))
+            .take_while(|record| record.key.block_number() == block_number)
+            .collect();
+        for record in records {
+            self.delete(COLUMN_LOG, record.key.as_slice())?;
+            let account_id: u32 = record.log.account_id().unpack();
+            let account_key = LogAccountKey::new(account_id, &record.key);
+            self.delete(COLUMN_LOG_ACCOUNT_INDEX, account_key.as_slice())?;
+            if let Some(topic) = first_topic(&record.log) {
+                let topic_key = LogTopicKey::new(&topic, &record.key);
+                self.delete(COLUMN_LOG_TOPIC_INDEX, topic_key.as_slice())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn iter_logs_from(&self, start: LogKey) -> impl Iterator<Item = LogRecord> + '_ {
+        self.get_iter(
+            COLUMN_LOG,
+            IteratorMode::From(start.as_slice(), Direction::Forward),
+        )
+        .map(|(key, value)| LogRecord::from_kv(&key, &value))
+    }
+
+    fn get_log(&self, key: &LogKey) -> Option<LogRecord> {
+        self.get(COLUMN_LOG, key.as_slice())
+            .map(|value| LogRecord::from_kv(key.as_slice(), &value))
+    }
+
+    /// Returns at most `limit` logs matching the filter start from `start`, and
+    /// the key of the next matched log if there are more. At least one log is
+    /// returned if there is any, so paging by the next key always progresses.
+    pub fn get_logs(
+        &self,
+        filter: &LogFilter,
+        start: Option<LogKey>,
+        limit: usize,
+    ) -> Result<(Vec<LogRecord>, Option<LogKey>), Error> {
+        let limit = limit.max(1);
+        let start = match start {
+            Some(start) if start.block_number() >= filter.from_block => start,
+            _ => LogKey::new(filter.from_block, 0, 0),
+        };
+        let to_block = filter.to_block;
+
+        // use the most selective index
+        let records: Box<dyn Iterator<Item = LogRecord> + '_> = if let Some(topic) = filter.topic {
+            let start_key = LogTopicKey::new(&topic, &start);
+            let iter = self
+                .get_iter(
+                    COLUMN_LOG_TOPIC_INDEX,
+                    IteratorMode::From(start_key.as_slice(), Direction::Forward),
+                )
+                .map(|(key, _value)| LogTopicKey::from_slice(&key))
+                .take_while(move |key| key.topic() == topic)
+                .map(|key| key.log_key())
+                .take_while(move |key| key.block_number() <= to_block)
+                .filter_map(move |key| self.get_log(&key));
+            Box::new(iter)
+        } else if let Some(account_id) = filter.account_id {
+            let start_key = LogAccountKey::new(account_id, &start);
+            let iter = self
+                .get_iter(
+                    COLUMN_LOG_ACCOUNT_INDEX,
+                    IteratorMode::From(start_key.as_slice(), Direction::Forward),
+                )
+                .map(|(key, _value)| LogAccountKey::from_slice(&key))
+                .take_while(move |key| key.account_id() == account_id)
+                .map(|key| key.log_key())
+                .take_while(move |key| key.block_number() <= to_block)
+                .filter_map(move |key| self.get_log(&key));
+            Box::new(iter)
+        } else {
+            let iter = self
+                .iter_logs_from(start)
+                .take_while(move |record| record.key.block_number() <= to_block);
+            Box::new(iter)
+        };
+
+        let mut logs: Vec<LogRecord> = records
+            .filter(|record| filter.is_match(record))
+            .take(limit.saturating_add(1))
+            .collect();
+        let next = if logs.len() > limit {
+            logs.pop().map(|record| record.key)
+        } else {
+            None
+        };
+        Ok((logs, next))
+    }
+
     pub fn remove_block_state_record(&self, block_number: u64) -> Result<(), Error> {
         let iter = self.iter_block_state_record(block_number);
         for record_key in iter {
-            // delete record key
-            self.delete(COLUMN_BLOCK_STATE_RECORD, record_key.as_slice())?;
-            // delete reverse record key
-            let reverse_key =
-                BlockStateRecordKeyReverse::new(record_key.block_number(), &record_key.state_key());
-            self.delete(COLUMN_BLOCK_STATE_REVERSE_RECORD, reverse_key.as_slice())?;
+            self.delete_block_state_record(record_key.block_number(), &record_key.state_key())?;
         }
         Ok(())
     }
 
+    fn delete_block_state_record(&self, block_number: u64, state_key: &H256) -> Result<(), Error> {
+        // delete record key
+        let record_key = BlockStateRecordKey::new(block_number, state_key);
+        self.delete(COLUMN_BLOCK_STATE_RECORD, record_key.as_slice())?;
+        // delete reverse record key
+        let reverse_key = BlockStateRecordKeyReverse::new(block_number, state_key);
+        self.delete(COLUMN_BLOCK_STATE_REVERSE_RECORD, reverse_key.as_slice())?;
+        Ok(())
+    }
+
+    /// Prune history state before `block_number`.
+    ///
+    /// A state key keeps its value since the last record, so the records of
+    /// `block_number` can't be removed, instead the records superseded by them
+    /// are removed. History state of `block_number` and later blocks is kept.
+    /// Blocks must be pruned in order.
+    pub fn prune_block_state_record(&self, block_number: u64) -> Result<(), Error> {
+        let pruned_number = self.get_block_state_record_pruned_number()?;
+        if block_number != pruned_number + 1 {
+            return Err(Error::from(format!(
+                "prune block state record of block {}, expected block {}",
+                block_number,
+                pruned_number + 1
+            )));
+        }
+
+        let superseded_records: Vec<_> = self
+            .iter_block_state_record(block_number)
+            .filter_map(|record_key| {
+                let state_key = record_key.state_key();
+                self.get_prev_block_state_record_number(block_number, &state_key)
+                    .map(|prev_block_number| (prev_block_number, state_key))
+            })
+            .collect();
+        for (prev_block_number, state_key) in superseded_records {
+            self.delete_block_state_record(prev_block_number, &state_key)?;
+        }
+
+        self.set_block_state_record_pruned_number(block_number)
+    }
+
+    /// History state before `block_number` is unavailable, e.g. a store
+    /// imported from a state snapshot of `block_number`.
+    pub fn set_block_state_record_pruned_number(&self, block_number: u64) -> Result<(), Error> {
+        let block_number: packed::Uint64 = block_number.pack();
+        self.insert_raw(
+            COLUMN_META,
+            META_BLOCK_STATE_RECORD_PRUNED_KEY,
+            block_number.as_slice(),
+        )
+    }
+
+    // block number of the last record of the state key before `block_number`
+    fn get_prev_block_state_record_number(
+        &self,
+        block_number: u64,
+        state_key: &H256,
+    ) -> Option<u64> {
+        let key = BlockStateRecordKeyReverse::new(block_number.checked_sub(1)?, state_key);
+        let mut raw_iter: DBRawIterator = self
+            .get_iter(COLUMN_BLOCK_STATE_REVERSE_RECORD, IteratorMode::Start)
+            .into();
+        raw_iter.seek_for_prev(key.as_slice());
+        if !raw_iter.valid() {
+            return None;
+        }
+        raw_iter
+            .key()
+            .map(BlockStateRecordKeyReverse::from_slice)
+            .filter(|prev_key| &prev_key.state_key() == state_key)
+            .map(|prev_key| prev_key.block_number())
+    }
+
     pub fn get_history_state(&self, block_number: u64, state_key: &H256) -> Option<H256> {
         let key = BlockStateRecordKeyReverse::new(block_number, state_key);
         let mut raw_iter: DBRawIterator = self
@@ -542,6 +776,13 @@ impl StoreTransaction {
     pub fn state_tree(&self, context: StateContext) -> Result<StateTree, Error> {
         let block = match context {
             StateContext::ReadOnlyHistory(block_number) => {
+                let pruned_number = self.get_block_state_record_pruned_number()?;
+                if block_number < pruned_number {
+                    return Err(Error::from(format!(
+                        "state of block {} is pruned, history state is available from block {}",
+                        block_number, pruned_number
+                    )));
+                }
                 let block_hash = self
                     .get_block_hash_by_number(block_number)?
                     .ok_or_else(|| Error::from("can't find block".to_string()))?;
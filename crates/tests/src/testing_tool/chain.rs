@@ -203,6 +203,21 @@ impl TestChain {
         }
     }
 
+    pub async fn setup_with_mem_pool_config(
+        rollup_type_script: Script,
+        mem_pool_config: MemPoolConfig,
+    ) -> Self {
+        let inner =
+            setup_chain_with_mem_pool_config(rollup_type_script.clone(), Some(mem_pool_config))
+                .await;
+
+        Self {
+            l1_committed_block_number: 1,
+            rollup_type_script,
+            inner,
+        }
+    }
+
     pub fn chain_id(&self) -> u64 {
         let config = &self.inner.generator().rollup_context().rollup_config;
         config.chain_id().unpack()
@@ -273,6 +288,13 @@ impl TestChain {
 }
 
 pub fn build_backend_manage(rollup_config: &RollupConfig) -> BackendManage {
+    build_backend_manage_with_program_cache(rollup_config, true)
+}
+
+pub fn build_backend_manage_with_program_cache(
+    rollup_config: &RollupConfig,
+    cache_programs: bool,
+) -> BackendManage {
     let sudt_validator_script_type_hash: [u8; 32] =
         rollup_config.l2_sudt_validator_script_type_hash().unpack();
     let backends = vec![
@@ -301,14 +323,24 @@ pub fn build_backend_manage(rollup_config: &RollupConfig) -> BackendManage {
             backend_type: gw_config::BackendType::Polyjuice,
         },
     ];
-    BackendManage::from_config(vec![BackendSwitchConfig {
-        switch_height: 0,
-        backends,
-    }])
+    BackendManage::from_config_with_program_cache(
+        vec![BackendSwitchConfig {
+            switch_height: 0,
+            backends,
+        }],
+        cache_programs,
+    )
     .expect("default backend")
 }
 
 pub async fn setup_chain(rollup_type_script: Script) -> Chain {
+    setup_chain_with_mem_pool_config(rollup_type_script, None).await
+}
+
+pub async fn setup_chain_with_mem_pool_config(
+    rollup_type_script: Script,
+    opt_mem_pool_config: Option<MemPoolConfig>,
+) -> Chain {
     let mut account_lock_manage = AccountLockManage::default();
     let rollup_config = RollupConfig::new_builder()
         .allowed_eoa_type_hashes(
@@ -347,7 +379,7 @@ pub async fn setup_chain(rollup_type_script: Script) -> Chain {
         rollup_config,
         account_lock_manage,
         None,
-        None,
+        opt_mem_pool_config,
         None,
     )
     .await;
@@ -8,8 +8,11 @@ use gw_common::H256;
 use gw_config::{NodeMode::FullNode, RPCClientConfig};
 
 use gw_jsonrpc_types::{
-    ckb_jsonrpc_types::{Byte32, JsonBytes, Uint64},
-    godwoken::RunResult,
+    ckb_jsonrpc_types::{Byte32, JsonBytes, Uint32, Uint64},
+    godwoken::{
+        AccountOverride, AccountProof, BackupManifest, CyclesEstimation, ExecutionProfile,
+        ProofRequest, RunResult, TransactionTrace,
+    },
 };
 use gw_polyjuice_sender_recover::recover::PolyjuiceSenderRecover;
 use gw_rpc_client::{
@@ -166,6 +169,47 @@ impl RPCServer {
         Ok(run_result)
     }
 
+    pub async fn execute_raw_l2transaction_with_overrides(
+        &self,
+        raw_tx: &RawL2Transaction,
+        opt_block_number: Option<u64>,
+        opt_registry_address: Option<Bytes>,
+        overrides: &[AccountOverride],
+    ) -> Result<RunResult> {
+        let raw_tx_bytes = JsonBytes::from_bytes(raw_tx.as_bytes());
+        let block_number: Option<Uint64> = opt_block_number.map(Into::into);
+        let address_bytes = opt_registry_address.map(JsonBytes::from_bytes);
+        let params = serde_json::to_value(&(raw_tx_bytes, block_number, address_bytes, overrides))?;
+
+        let req = RequestBuilder::default()
+            .with_id(1)
+            .with_method("gw_execute_raw_l2transaction")
+            .with_params(params)
+            .finish();
+
+        let run_result = self.handle_single_request(req).await?;
+        Ok(run_result)
+    }
+
+    pub async fn estimate_cycles(
+        &self,
+        raw_tx: &RawL2Transaction,
+        opt_registry_address: Option<Bytes>,
+    ) -> Result<CyclesEstimation> {
+        let raw_tx_bytes = JsonBytes::from_bytes(raw_tx.as_bytes());
+        let address_bytes = opt_registry_address.map(JsonBytes::from_bytes);
+        let params = serde_json::to_value(&(raw_tx_bytes, address_bytes))?;
+
+        let req = RequestBuilder::default()
+            .with_id(1)
+            .with_method("gw_estimate_cycles")
+            .with_params(params)
+            .finish();
+
+        let estimation = self.handle_single_request(req).await?;
+        Ok(estimation)
+    }
+
     pub async fn is_request_in_queue(&self, hash: H256) -> Result<bool> {
         let fixed_hash = ckb_fixed_hash::H256(hash.into());
         let params = serde_json::to_value(&(fixed_hash,))?;
@@ -180,6 +224,81 @@ impl RPCServer {
         Ok(result)
     }
 
+    pub async fn get_proof(&self, request: &ProofRequest) -> Result<AccountProof> {
+        let params = serde_json::to_value(&(request,))?;
+
+        let req = RequestBuilder::default()
+            .with_id(1)
+            .with_method("gw_get_proof")
+            .with_params(params)
+            .finish();
+
+        let proof = self.handle_single_request(req).await?;
+        Ok(proof)
+    }
+
+    pub async fn get_nonce(&self, account_id: u32, block_number: Option<u64>) -> Result<u32> {
+        let params =
+            serde_json::to_value(&(Uint32::from(account_id), block_number.map(Uint64::from)))?;
+
+        let req = RequestBuilder::default()
+            .with_id(1)
+            .with_method("gw_get_nonce")
+            .with_params(params)
+            .finish();
+
+        let nonce: Uint32 = self.handle_single_request(req).await?;
+        Ok(nonce.value())
+    }
+
+    pub async fn debug_trace_transaction(&self, tx_hash: H256) -> Result<Option<TransactionTrace>> {
+        let fixed_hash = ckb_fixed_hash::H256(tx_hash.into());
+        let params = serde_json::to_value(&(fixed_hash,))?;
+
+        let req = RequestBuilder::default()
+            .with_id(1)
+            .with_method("gw_debug_trace_transaction")
+            .with_params(params)
+            .finish();
+
+        let trace = self.handle_single_request(req).await?;
+        Ok(trace)
+    }
+
+    pub async fn debug_profile_raw_l2transaction(
+        &self,
+        raw_tx: &RawL2Transaction,
+        opt_registry_address: Option<Bytes>,
+        opt_sample_interval: Option<u64>,
+    ) -> Result<ExecutionProfile> {
+        let raw_tx_bytes = JsonBytes::from_bytes(raw_tx.as_bytes());
+        let address_bytes = opt_registry_address.map(JsonBytes::from_bytes);
+        let sample_interval = opt_sample_interval.map(Uint64::from);
+        let params = serde_json::to_value(&(raw_tx_bytes, address_bytes, sample_interval))?;
+
+        let req = RequestBuilder::default()
+            .with_id(1)
+            .with_method("gw_debug_profile_raw_l2transaction")
+            .with_params(params)
+            .finish();
+
+        let profile = self.handle_single_request(req).await?;
+        Ok(profile)
+    }
+
+    pub async fn admin_create_backup(&self, name: &str) -> Result<BackupManifest> {
+        let params = serde_json::to_value(&(name,))?;
+
+        let req = RequestBuilder::default()
+            .with_id(1)
+            .with_method("gw_admin_create_backup")
+            .with_params(params)
+            .finish();
+
+        let manifest = self.handle_single_request(req).await?;
+        Ok(manifest)
+    }
+
     async fn handle_single_request<R: DeserializeOwned>(&self, req: RequestObject) -> Result<R> {
         let ret = match self.inner.handle(req).await {
             ResponseObjects::One(ResponseObject::Result { result, .. }) => {
@@ -0,0 +1,87 @@
+use crate::testing_tool::chain::setup_chain_with_account_lock_manage;
+
+use super::export_import_block::random_always_success_script;
+
+use ckb_types::prelude::{Builder, Entity};
+use gw_common::H256;
+use gw_config::StoreConfig;
+use gw_db::{schema::COLUMNS, RocksDB};
+use gw_generator::account_lock_manage::AccountLockManage;
+use gw_store::{traits::chain_store::ChainStore, Store};
+use gw_types::{core::ScriptHashType, packed::RollupConfig};
+use gw_utils::backup::{
+    create_backup, read_backup_manifest, restore_backup, verify_backup, BACKUP_MANIFEST_FILE,
+};
+
+#[tokio::test]
+async fn test_backup_and_restore() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = random_always_success_script(None)
+        .as_builder()
+        .hash_type(ScriptHashType::Type.into())
+        .build();
+    let rollup_type_hash: H256 = rollup_type_script.hash().into();
+    let store_dir = tempfile::tempdir().expect("create temp dir");
+    let store = {
+        let config = StoreConfig {
+            path: store_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        Store::new(RocksDB::open(&config, COLUMNS))
+    };
+    let chain = setup_chain_with_account_lock_manage(
+        rollup_type_script,
+        RollupConfig::default(),
+        AccountLockManage::default(),
+        Some(store),
+        None,
+        None,
+    )
+    .await;
+    let tip_block_hash = chain.store().get_tip_block_hash().unwrap();
+
+    let backup_dir = tempfile::tempdir().expect("create temp dir");
+    let backup_path = backup_dir.path().join("backup");
+    let manifest = create_backup(chain.store(), &backup_path).unwrap();
+    assert_eq!(manifest.tip_block_number, 0);
+    assert_eq!(manifest.tip_block_hash.0, <[u8; 32]>::from(tip_block_hash));
+    assert_eq!(
+        manifest.rollup_type_hash.0,
+        <[u8; 32]>::from(rollup_type_hash)
+    );
+    assert_eq!(read_backup_manifest(&backup_path).unwrap(), manifest);
+
+    // Backup into a non-empty dir
+    let err = create_backup(chain.store(), &backup_path).unwrap_err();
+    assert!(err.to_string().contains("isn't empty"));
+
+    // Restore backup of another rollup
+    let restore_dir = tempfile::tempdir().expect("create temp dir");
+    let restore_path = restore_dir.path().join("db");
+    let err = restore_backup(&backup_path, &restore_path, &[1u8; 32].into()).unwrap_err();
+    assert!(err.to_string().contains("another rollup"));
+
+    let restored = restore_backup(&backup_path, &restore_path, &rollup_type_hash).unwrap();
+    assert_eq!(restored, manifest);
+    let store = {
+        let config = StoreConfig {
+            path: restore_path,
+            ..Default::default()
+        };
+        Store::new(RocksDB::open(&config, COLUMNS))
+    };
+    assert_eq!(store.get_tip_block_hash().unwrap(), tip_block_hash);
+    assert_eq!(store.get_chain_id().unwrap(), rollup_type_hash);
+
+    // Manifest doesn't match checkpoint
+    let mut tampered = manifest;
+    tampered.tip_block_number = 1;
+    std::fs::write(
+        backup_path.join(BACKUP_MANIFEST_FILE),
+        serde_json::to_vec(&tampered).unwrap(),
+    )
+    .unwrap();
+    let err = verify_backup(&backup_path, &rollup_type_hash).unwrap_err();
+    assert!(err.to_string().contains("mismatch checkpoint"));
+}
@@ -0,0 +1,128 @@
+use crate::testing_tool::chain::setup_chain_with_account_lock_manage;
+
+use super::export_import_block::random_always_success_script;
+
+use ckb_types::prelude::{Builder, Entity};
+use gw_common::H256;
+use gw_config::StoreConfig;
+use gw_db::{
+    read_only_db::ReadOnlyDB,
+    schema::{
+        COLUMNS, COLUMN_BLOCK, COLUMN_LOG, COLUMN_META, COLUMN_TRANSACTION, META_BLOCK_SMT_ROOT_KEY,
+    },
+    RocksDB,
+};
+use gw_generator::account_lock_manage::AccountLockManage;
+use gw_store::{
+    check::{check_store, repair_orphans},
+    log_index::LogKey,
+    migrate::{check_db_version, open_or_create_db, run_migrations},
+    readonly::StoreReadonly,
+    traits::kv_store::KVStoreWrite,
+    Store,
+};
+use gw_types::{
+    core::ScriptHashType,
+    packed::{L2Block, RollupConfig},
+};
+
+#[tokio::test]
+async fn test_check_db() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = random_always_success_script(None)
+        .as_builder()
+        .hash_type(ScriptHashType::Type.into())
+        .build();
+    let store_dir = tempfile::tempdir().expect("create temp dir");
+    let store = {
+        let config = StoreConfig {
+            path: store_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        Store::new(RocksDB::open(&config, COLUMNS))
+    };
+    let chain = setup_chain_with_account_lock_manage(
+        rollup_type_script,
+        RollupConfig::default(),
+        AccountLockManage::default(),
+        Some(store),
+        None,
+        None,
+    )
+    .await;
+    let store = chain.store();
+    let open_readonly = || {
+        let cf_names = (0..COLUMNS).map(|c| c.to_string());
+        let db = ReadOnlyDB::open_cf(store_dir.path(), cf_names)
+            .unwrap()
+            .unwrap();
+        StoreReadonly::new(db)
+    };
+
+    let report = check_store(&open_readonly()).unwrap();
+    assert!(report.is_ok(), "{:?}", report.errors);
+
+    // Transaction of an unknown block and log of a future block
+    let mut batch = store.new_write_batch();
+    batch.put(COLUMN_TRANSACTION, &[1u8; 36], &[]).unwrap();
+    let log_key = LogKey::new(100, 0, 0);
+    batch.put(COLUMN_LOG, log_key.as_slice(), &[]).unwrap();
+    store.write(&batch).unwrap();
+
+    let report = check_store(&open_readonly()).unwrap();
+    assert_eq!(report.error_count, 0, "{:?}", report.errors);
+    assert_eq!(report.orphan_count(), 2);
+
+    assert_eq!(repair_orphans(store, &report).unwrap(), 2);
+    let report = check_store(&open_readonly()).unwrap();
+    assert!(report.is_ok(), "{:?}", report.errors);
+
+    // Corrupted block smt root
+    let mut batch = store.new_write_batch();
+    batch
+        .put(
+            COLUMN_META,
+            META_BLOCK_SMT_ROOT_KEY,
+            H256::zero().as_slice(),
+        )
+        .unwrap();
+    store.write(&batch).unwrap();
+
+    let report = check_store(&open_readonly()).unwrap();
+    assert_eq!(report.error_count, 1);
+    assert!(report.errors[0].contains("block smt root mismatch"));
+}
+
+#[test]
+fn test_check_db_version() {
+    let store_dir = tempfile::tempdir().expect("create temp dir");
+    let config = StoreConfig {
+        path: store_dir.path().to_path_buf(),
+        ..Default::default()
+    };
+    drop(open_or_create_db(&config).unwrap());
+    check_db_version(&config).unwrap();
+
+    // A db created before the log index
+    let old_store_dir = tempfile::tempdir().expect("create temp dir");
+    let old_config = StoreConfig {
+        path: old_store_dir.path().to_path_buf(),
+        ..Default::default()
+    };
+    {
+        let store = Store::new(RocksDB::open(&old_config, COLUMNS));
+        let block = L2Block::default();
+        let store_txn = store.begin_transaction();
+        store_txn
+            .insert_raw(COLUMN_BLOCK, &block.hash(), block.as_slice())
+            .unwrap();
+        store_txn.set_tip_block_hash(block.hash().into()).unwrap();
+        store_txn.commit().unwrap();
+    }
+    let err = check_db_version(&old_config).unwrap_err();
+    assert!(err.message.contains("godwoken migrate --run"), "{}", err);
+
+    run_migrations(&old_config, &()).unwrap();
+    check_db_version(&old_config).unwrap();
+}
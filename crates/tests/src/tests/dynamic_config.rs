@@ -0,0 +1,35 @@
+use std::num::NonZeroUsize;
+
+use gw_config::{Config, RPCMethodRateLimitConfig};
+use gw_dynamic_config::manager::DynamicConfigManager;
+
+#[test]
+fn test_reload_response_without_api_keys() {
+    let rate_limit = |api_key: &str| RPCMethodRateLimitConfig {
+        window_secs: 60,
+        max_cost_per_window: 100,
+        method_costs: Default::default(),
+        lru_size: NonZeroUsize::new(10).unwrap(),
+        client_ip_header: None,
+        trusted_proxies: Vec::new(),
+        api_key_header: Some("X-API-Key".to_string()),
+        api_key_quotas: vec![(api_key.to_string(), 10000)].into_iter().collect(),
+    };
+
+    let mut config = Config::default();
+    config.rpc_server.method_rate_limit = Some(rate_limit("old-secret-key"));
+    let mut manager = DynamicConfigManager::create(config);
+
+    let mut new_config = Config::default();
+    new_config.rpc_server.method_rate_limit = Some(rate_limit("new-secret-key"));
+    let resp = manager.reload_from(new_config);
+
+    let json = serde_json::to_string(&resp).unwrap();
+    assert!(!json.contains("old-secret-key"));
+    assert!(!json.contains("new-secret-key"));
+    assert!(json.contains("\"api_key_count\":1"));
+
+    // Reloaded keys take effect
+    let quotas = &manager.get_rpc_method_rate_limit().unwrap().api_key_quotas;
+    assert!(quotas.contains_key("new-secret-key"));
+}
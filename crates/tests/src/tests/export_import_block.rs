@@ -1,7 +1,10 @@
 #![allow(clippy::mutable_key_type)]
 
 use std::collections::HashSet;
+use std::convert::TryInto;
+use std::io::Cursor;
 use std::iter::FromIterator;
+use std::path::Path;
 use std::time::SystemTime;
 
 use crate::testing_tool::bad_block::generate_bad_block_using_first_withdrawal;
@@ -25,15 +28,18 @@ use gw_types::core::Status;
 use gw_types::{
     bytes::Bytes,
     core::{AllowedEoaType, ScriptHashType},
-    offchain::CellInfo,
+    offchain::{CellInfo, ExportedBlock},
     packed::{
-        AllowedTypeHash, CellInput, CellOutput, DepositRequest, GlobalState, L2BlockCommittedInfo,
-        OutPoint, RawWithdrawalRequest, RollupConfig, Script, WithdrawalRequest,
-        WithdrawalRequestExtra,
+        self, AllowedTypeHash, CellInput, CellOutput, DepositRequest, GlobalState, L2Block,
+        L2BlockCommittedInfo, OutPoint, RawL2Block, RawWithdrawalRequest, RollupConfig, Script,
+        WithdrawalRequest, WithdrawalRequestExtra,
     },
     prelude::{Pack, PackVec, Unpack},
 };
-use gw_utils::export_block::check_block_post_state;
+use gw_utils::export_block::{
+    check_block_post_state, AnyExportedBlockReader, ExportedBlockReaderV2, ExportedBlockWriter,
+    ImportCursor, EXPORTED_BLOCK_CHUNK_SIZE, MAX_CHUNK_RAW_SIZE,
+};
 
 const CKB: u64 = 100000000;
 const MAX_MEM_BLOCK_WITHDRAWALS: u8 = 50;
@@ -239,6 +245,35 @@ async fn test_export_import_block() {
     let import_tx_db = import_store.begin_transaction();
     check_block_post_state(&import_tx_db, tip_block_number, &post_global_state).unwrap();
 
+    let cursor = ImportCursor::load(&import_store).unwrap().unwrap();
+    assert_eq!(cursor.block_number, tip_block_number);
+    assert_eq!(cursor.block_hash, tip_block_hash);
+
+    // Import v1 exported blocks
+    let v1_dir = tempfile::tempdir().expect("create temp dir");
+    let v1_path = v1_dir.path().join("export_block_v1");
+    let v1: Vec<u8> = (0..=tip_block_number)
+        .flat_map(|number| {
+            let block = gw_utils::export_block::export_block(&export_store, number).unwrap();
+            let packed: packed::ExportedBlock = block.into();
+            packed.as_slice().to_vec()
+        })
+        .collect();
+    std::fs::write(&v1_path, &v1).unwrap();
+
+    let v1_store_dir = tempfile::tempdir().expect("create temp dir");
+    let v1_chain =
+        setup_import_chain(&rollup_type_script, &rollup_config, v1_store_dir.path()).await;
+    let import_block = ImportBlock::new_unchecked(v1_chain, v1_path);
+    let v1_store = import_block.store().clone();
+    import_block.execute().await.unwrap();
+
+    assert_eq!(v1_store.get_tip_block_hash().unwrap(), tip_block_hash);
+    let cursor = ImportCursor::load(&v1_store).unwrap().unwrap();
+    assert_eq!(cursor.block_number, tip_block_number);
+    assert_eq!(cursor.source_size, v1.len() as u64);
+    assert_eq!(cursor.source_offset, v1.len() as u64);
+
     // Test reverted block root
     generate_and_revert_a_bad_block(&mut chain, &rollup_cell, accounts[0].clone()).await;
 
@@ -330,6 +365,140 @@ async fn test_export_import_block() {
     check_block_post_state(&import_tx_db, tip_block_number, &post_global_state).unwrap();
 }
 
+#[test]
+fn test_exported_block_v2_format() {
+    // Big script args to spread blocks over chunks
+    let exported_block = |number: u64| ExportedBlock {
+        block: L2Block::new_builder()
+            .raw(RawL2Block::new_builder().number(number.pack()).build())
+            .build(),
+        committed_info: Default::default(),
+        post_global_state: Default::default(),
+        deposit_requests: vec![],
+        deposit_asset_scripts: vec![Script::new_builder()
+            .args(vec![number as u8; 1024 * 1024].pack())
+            .build()],
+        withdrawals: vec![],
+        bad_block_hashes: None,
+    };
+
+    let rollup_type_hash: H256 = [1u8; 32].into();
+    let mut writer = ExportedBlockWriter::new(Vec::new(), &rollup_type_hash, 10, 21).unwrap();
+    let err = writer.write_block(exported_block(11)).unwrap_err();
+    assert!(err.to_string().contains("unexpected block 11"));
+    let mut too_large = exported_block(10);
+    too_large.deposit_asset_scripts = vec![Script::new_builder()
+        .args(vec![0u8; EXPORTED_BLOCK_CHUNK_SIZE].pack())
+        .build()];
+    let err = writer.write_block(too_large).unwrap_err();
+    assert!(err.to_string().contains("too large"));
+    for number in 10..=21 {
+        writer.write_block(exported_block(number)).unwrap();
+    }
+    let exported = writer.finish().unwrap();
+
+    let mut reader = ExportedBlockReaderV2::new(Cursor::new(exported.as_slice())).unwrap();
+    assert_eq!(reader.header().rollup_type_hash, rollup_type_hash);
+    assert_eq!(reader.header().from_block, 10);
+    assert_eq!(reader.header().to_block, 21);
+
+    // Skip over chunks by index
+    let (first_block, _size) = reader.peek_block().unwrap().unwrap();
+    assert_eq!(first_block.block_number(), 10);
+    let (count, skipped_size) = reader.skip_blocks(7).unwrap();
+    assert_eq!(count, 7);
+    let mut read_size = 0;
+    let mut block_numbers = vec![];
+    for maybe_block in reader {
+        let (block, size) = maybe_block.unwrap();
+        assert_eq!(block, exported_block(block.block_number()));
+        block_numbers.push(block.block_number());
+        read_size += size as u64;
+    }
+    assert_eq!(block_numbers, (17..=21).collect::<Vec<_>>());
+    assert!(skipped_size > 0 && read_size > 0);
+    assert!(skipped_size + read_size < exported.len() as u64);
+
+    // v1 stream is detected by magic
+    let v1: Vec<u8> = (10..=21u64)
+        .flat_map(|number| {
+            let packed: packed::ExportedBlock = exported_block(number).into();
+            packed.as_slice().to_vec()
+        })
+        .collect();
+    let mut reader = AnyExportedBlockReader::new(Cursor::new(v1.as_slice())).unwrap();
+    assert!(reader.header().is_none());
+    assert_eq!(reader.skip_blocks(7).unwrap().0, 7);
+    assert_eq!(reader.count(), 5);
+    let reader = AnyExportedBlockReader::new(Cursor::new(exported.as_slice())).unwrap();
+    assert_eq!(reader.header().unwrap().from_block, 10);
+
+    // Corrupt compressed blocks of the first chunk
+    let mut corrupted = exported.clone();
+    corrupted[60 + 52 + 10] ^= 1;
+    let mut reader = ExportedBlockReaderV2::new(Cursor::new(corrupted.as_slice())).unwrap();
+    let err = reader.next().unwrap().unwrap_err();
+    assert!(err.to_string().contains("chunk of block 10"));
+    assert!(reader.next().is_none());
+
+    // Chunk header is covered by checksum, raw size is capped before decompression
+    let raw_size_offset = 60 + 12;
+    let raw_size = u32::from_le_bytes(
+        exported[raw_size_offset..raw_size_offset + 4]
+            .try_into()
+            .unwrap(),
+    );
+    for (tampered, msg) in vec![
+        (raw_size - 1, "checksum mismatch"),
+        (MAX_CHUNK_RAW_SIZE as u32, "too large"),
+    ] {
+        let mut corrupted = exported.clone();
+        corrupted[raw_size_offset..raw_size_offset + 4].copy_from_slice(&tampered.to_le_bytes());
+        let mut reader = ExportedBlockReaderV2::new(Cursor::new(corrupted.as_slice())).unwrap();
+        let err = reader.next().unwrap().unwrap_err();
+        assert!(err.to_string().contains(msg), "{}", err);
+    }
+
+    // Truncated
+    let truncated = &exported[..exported.len() - 1];
+    let err = ExportedBlockReaderV2::new(Cursor::new(truncated))
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("truncated"));
+}
+
+async fn setup_import_chain(
+    rollup_type_script: &Script,
+    rollup_config: &RollupConfig,
+    store_path: &Path,
+) -> Chain {
+    let store = {
+        let config = StoreConfig {
+            path: store_path.to_path_buf(),
+            ..Default::default()
+        };
+        Store::new(RocksDB::open(&config, COLUMNS))
+    };
+    let mut account_lock_manage = AccountLockManage::default();
+    account_lock_manage
+        .register_lock_algorithm((*ALWAYS_SUCCESS_CODE_HASH).into(), Box::new(AlwaysSuccess));
+    account_lock_manage.register_lock_algorithm(
+        (*ETH_ACCOUNT_LOCK_CODE_HASH).into(),
+        Box::new(Secp256k1Eth::default()),
+    );
+    let mut chain = setup_chain_with_account_lock_manage(
+        rollup_type_script.clone(),
+        rollup_config.clone(),
+        account_lock_manage,
+        Some(store),
+        None,
+        None,
+    )
+    .await;
+    chain.complete_initial_syncing().await.unwrap();
+    chain
+}
+
 async fn generate_and_revert_a_bad_block(
     chain: &mut Chain,
     rollup_cell: &CellInfo,
@@ -521,7 +690,7 @@ async fn generate_and_revert_a_bad_block(
     assert_eq!(local_reverted_block_smt_root, reverted_block_smt_root);
 }
 
-async fn produce_block(chain: &mut Chain, rollup_cell: &CellInfo) {
+pub(crate) async fn produce_block(chain: &mut Chain, rollup_cell: &CellInfo) {
     let l1_block_number = chain.local_state().last_synced().number().unpack();
 
     let block_result = {
@@ -555,7 +724,7 @@ async fn produce_block(chain: &mut Chain, rollup_cell: &CellInfo) {
     assert!(chain.last_sync_event().is_success());
 }
 
-fn random_always_success_script(opt_rollup_script_hash: Option<&H256>) -> Script {
+pub(crate) fn random_always_success_script(opt_rollup_script_hash: Option<&H256>) -> Script {
     let random_bytes: [u8; 20] = rand::random();
     Script::new_builder()
         .code_hash(ALWAYS_SUCCESS_CODE_HASH.clone().pack())
@@ -0,0 +1,368 @@
+#![allow(clippy::mutable_key_type)]
+
+use std::collections::HashSet;
+use std::iter::FromIterator;
+
+use crate::testing_tool::chain::{
+    build_sync_tx, construct_block, setup_chain_with_account_lock_manage, ALWAYS_SUCCESS_CODE_HASH,
+};
+
+use super::export_import_block::{produce_block, random_always_success_script};
+
+use ckb_types::prelude::{Builder, Entity};
+use godwoken_bin::subcommand::{
+    export_block::ExportBlock, export_state::ExportState, import_block::ImportBlock,
+    import_state::ImportState,
+};
+use gw_chain::chain::{L1Action, L1ActionContext, SyncParam};
+use gw_common::{state::State, H256};
+use gw_config::StoreConfig;
+use gw_db::schema::{COLUMN_META, META_IMPORT_STATE_KEY};
+use gw_db::{read_only_db::ReadOnlyDB, schema::COLUMNS, RocksDB};
+use gw_generator::account_lock_manage::always_success::AlwaysSuccess;
+use gw_generator::account_lock_manage::AccountLockManage;
+use gw_store::{
+    check::check_store,
+    readonly::StoreReadonly,
+    state::state_db::StateContext,
+    traits::{chain_store::ChainStore, kv_store::KVStoreWrite},
+    Store,
+};
+use gw_types::{
+    core::{AllowedEoaType, ScriptHashType},
+    offchain::CellInfo,
+    packed::{
+        AllowedTypeHash, CellOutput, DepositRequest, GlobalState, L2BlockCommittedInfo, OutPoint,
+        RawWithdrawalRequest, RollupConfig, Script, WithdrawalRequest, WithdrawalRequestExtra,
+    },
+    prelude::{Pack, PackVec},
+};
+use gw_utils::export_block::check_block_post_state;
+use gw_utils::export_state::{
+    import_state, StateSnapshotEntry, StateSnapshotReader, StateSnapshotWriter,
+};
+
+const CKB: u64 = 100000000;
+
+#[tokio::test]
+async fn test_export_import_state() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let always_type = random_always_success_script(None);
+    let sudt_script = Script::new_builder()
+        .code_hash(always_type.hash().pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args(vec![rand::random::<u8>(), 32].pack())
+        .build();
+
+    let rollup_config = RollupConfig::new_builder()
+        .withdrawal_script_type_hash(random_always_success_script(None).hash().pack())
+        .deposit_script_type_hash(random_always_success_script(None).hash().pack())
+        .l1_sudt_script_type_hash(always_type.hash().pack())
+        .allowed_eoa_type_hashes(
+            vec![AllowedTypeHash::new(
+                AllowedEoaType::Eth,
+                *ALWAYS_SUCCESS_CODE_HASH,
+            )]
+            .pack(),
+        )
+        .finality_blocks(0u64.pack())
+        .build();
+    let global_state = GlobalState::new_builder()
+        .rollup_config_hash(rollup_config.hash().pack())
+        .build();
+
+    let rollup_type_script = Script::new_builder()
+        .code_hash(random_always_success_script(None).hash().pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args(vec![1u8; 32].pack())
+        .build();
+    let rollup_script_hash: H256 = rollup_type_script.hash().into();
+    let rollup_cell = CellInfo {
+        data: global_state.as_bytes(),
+        out_point: OutPoint::new_builder()
+            .tx_hash(rand::random::<[u8; 32]>().pack())
+            .build(),
+        output: CellOutput::new_builder()
+            .type_(Some(rollup_type_script.clone()).pack())
+            .build(),
+    };
+
+    let store_dir = tempfile::tempdir().expect("create temp dir");
+    let mut chain = setup_chain(&rollup_type_script, &rollup_config, store_dir.path()).await;
+
+    // Block 1 deposits to random accounts
+    let deposits = (0..10).map(|_| {
+        let account_script = random_always_success_script(Some(&rollup_script_hash))
+            .as_builder()
+            .hash_type(ScriptHashType::Type.into())
+            .build();
+        DepositRequest::new_builder()
+            .capacity((1000000 * CKB).pack())
+            .sudt_script_hash(sudt_script.hash().pack())
+            .amount(1000u128.pack())
+            .script(account_script)
+            .registry_id(gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID.pack())
+            .build()
+    });
+    let deposits: Vec<_> = deposits.collect();
+    let withdrawal_account_script = deposits[0].script();
+    let deposit_block_result = {
+        let mem_pool = chain.mem_pool().as_ref().unwrap();
+        let mut mem_pool = mem_pool.lock().await;
+        construct_block(&chain, &mut mem_pool, deposits.clone())
+            .await
+            .unwrap()
+    };
+    let apply_deposits = L1Action {
+        context: L1ActionContext::SubmitBlock {
+            l2block: deposit_block_result.block.clone(),
+            deposit_requests: deposits,
+            deposit_asset_scripts: HashSet::from_iter(vec![sudt_script.clone()].into_iter()),
+            withdrawals: Default::default(),
+        },
+        transaction: build_sync_tx(rollup_cell.output.clone(), deposit_block_result),
+        l2block_committed_info: L2BlockCommittedInfo::new_builder()
+            .number(1u64.pack())
+            .build(),
+    };
+    let param = SyncParam {
+        updates: vec![apply_deposits],
+        reverts: Default::default(),
+    };
+    chain.sync(param).await.unwrap();
+    assert!(chain.last_sync_event().is_success());
+
+    // Block 2 withdraws from an account, its state is exported
+    let withdrawal = {
+        let owner_lock = Script::default();
+        let raw = RawWithdrawalRequest::new_builder()
+            .capacity((1000 * CKB).pack())
+            .account_script_hash(withdrawal_account_script.hash().pack())
+            .sudt_script_hash(H256::zero().pack())
+            .owner_lock_hash(owner_lock.hash().pack())
+            .registry_id(gw_common::builtins::ETH_REGISTRY_ACCOUNT_ID.pack())
+            .build();
+        WithdrawalRequestExtra::new_builder()
+            .request(WithdrawalRequest::new_builder().raw(raw).build())
+            .owner_lock(owner_lock)
+            .build()
+    };
+    {
+        let mem_pool = chain.mem_pool().as_ref().unwrap();
+        let mut mem_pool = mem_pool.lock().await;
+        mem_pool.push_withdrawal_request(withdrawal).await.unwrap();
+    }
+    produce_block(&mut chain, &rollup_cell).await;
+
+    // Block 3 is synced by import block
+    produce_block(&mut chain, &rollup_cell).await;
+
+    let export_dir = tempfile::tempdir().expect("create temp dir");
+    let state_path = export_dir.path().join("export_state");
+    let block_path = export_dir.path().join("export_block");
+    let store_readonly = open_readonly(store_dir.path());
+    let snapshot_block_hash = store_readonly.get_block_hash_by_number(2).unwrap().unwrap();
+    let snapshot_block = store_readonly
+        .get_block(&snapshot_block_hash)
+        .unwrap()
+        .unwrap();
+    assert_eq!(snapshot_block.withdrawals().len(), 1);
+    ExportState::new_unchecked(store_readonly.clone(), state_path.clone(), 2)
+        .execute()
+        .unwrap();
+    ExportBlock::new_unchecked(store_readonly.clone(), block_path.clone(), 3, 3)
+        .execute()
+        .unwrap();
+
+    // Asset scripts deposited before the snapshot block are exported
+    let snapshot = std::fs::read(&state_path).unwrap();
+    let entries: Vec<_> = StateSnapshotReader::new(snapshot.as_slice())
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert!(entries.iter().any(|entry| matches!(
+        entry,
+        StateSnapshotEntry::AssetScript(script) if script.as_slice() == sudt_script.as_slice()
+    )));
+
+    // Import state
+    let import_store_dir = tempfile::tempdir().expect("create temp dir");
+    let import_store = {
+        let config = StoreConfig {
+            path: import_store_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        Store::new(RocksDB::open(&config, COLUMNS))
+    };
+
+    // A failed import is cleared
+    let corrupted = {
+        let reader = StateSnapshotReader::new(snapshot.as_slice()).unwrap();
+        let header = reader.header().clone();
+        let mut writer =
+            StateSnapshotWriter::new(Vec::new(), &header.rollup_type_hash, header.block_number)
+                .unwrap();
+        for entry in entries {
+            let entry = match entry {
+                StateSnapshotEntry::AccountLeaf { key, .. } => StateSnapshotEntry::AccountLeaf {
+                    key,
+                    value: H256::one(),
+                },
+                entry => entry,
+            };
+            writer.write_entry(entry).unwrap();
+        }
+        writer.finish().unwrap()
+    };
+    let err = import_state(
+        &import_store,
+        StateSnapshotReader::new(corrupted.as_slice()).unwrap(),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("account smt diff"));
+    assert!(!import_store.has_unfinished_state_import());
+    assert!(!import_store.has_genesis().unwrap());
+
+    // An interrupted import is cleared by the next import
+    {
+        let db = import_store.begin_transaction();
+        db.insert_raw(COLUMN_META, META_IMPORT_STATE_KEY, &[])
+            .unwrap();
+        db.attach_block_hash(0, &H256::one()).unwrap();
+        db.commit().unwrap();
+    }
+    let import_state = ImportState::new_unchecked(import_store.clone(), state_path.clone());
+    import_state.execute().await.unwrap();
+    assert!(!import_store.has_unfinished_state_import());
+
+    let post_global_state = store_readonly
+        .get_block_post_global_state(&snapshot_block_hash)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        import_store.get_tip_block_hash().unwrap(),
+        snapshot_block_hash
+    );
+    assert_eq!(
+        import_store.get_block_state_record_pruned_number().unwrap(),
+        2
+    );
+    import_store.check_state().unwrap();
+    assert!(import_store
+        .get_asset_script(&sudt_script.hash().into())
+        .unwrap()
+        .is_some());
+    {
+        let db = import_store.begin_transaction();
+        check_block_post_state(&db, 2, &post_global_state).unwrap();
+
+        let tree = db.state_tree(StateContext::ReadOnly).unwrap();
+        for (key, value) in store_readonly.iter_history_state(2) {
+            assert_eq!(tree.get_raw(&key).unwrap(), value);
+        }
+    }
+
+    // Bodies of the snapshot block are absent
+    let report = check_store(&open_readonly(import_store_dir.path())).unwrap();
+    assert!(report.is_ok(), "{:?}", report);
+
+    // Import state into a non-empty store
+    let err = ImportState::new_unchecked(import_store.clone(), state_path)
+        .execute()
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("store isn't empty"));
+
+    // Sync the rest blocks, reopen imported store
+    drop(import_store);
+    let import_chain =
+        setup_chain(&rollup_type_script, &rollup_config, import_store_dir.path()).await;
+    let import_block = ImportBlock::new_unchecked(import_chain, block_path);
+    let import_store = import_block.store().clone();
+    import_block.execute().await.unwrap();
+
+    let tip_block_hash = store_readonly.get_tip_block_hash().unwrap();
+    let post_global_state = store_readonly
+        .get_block_post_global_state(&tip_block_hash)
+        .unwrap()
+        .unwrap();
+    assert_eq!(import_store.get_tip_block_hash().unwrap(), tip_block_hash);
+    let db = import_store.begin_transaction();
+    check_block_post_state(&db, 3, &post_global_state).unwrap();
+
+    let report = check_store(&open_readonly(import_store_dir.path())).unwrap();
+    assert!(report.is_ok(), "{:?}", report);
+}
+
+#[test]
+fn test_state_snapshot_checksum() {
+    let rollup_type_hash: H256 = [1u8; 32].into();
+    let mut writer = StateSnapshotWriter::new(Vec::new(), &rollup_type_hash, 1).unwrap();
+    writer
+        .write_entry(StateSnapshotEntry::AccountLeaf {
+            key: [2u8; 32].into(),
+            value: [3u8; 32].into(),
+        })
+        .unwrap();
+    let snapshot = writer.finish().unwrap();
+
+    let header = StateSnapshotReader::new(snapshot.as_slice())
+        .unwrap()
+        .verify()
+        .unwrap();
+    assert_eq!(header.rollup_type_hash, rollup_type_hash);
+    assert_eq!(header.block_number, 1);
+
+    // Corrupt account leaf value
+    let mut corrupted = snapshot.clone();
+    let value_offset = snapshot.len() - 37 - 1;
+    corrupted[value_offset] ^= 1;
+    let err = StateSnapshotReader::new(corrupted.as_slice())
+        .unwrap()
+        .verify()
+        .unwrap_err();
+    assert!(err.to_string().contains("checksum mismatch"));
+
+    // Truncated
+    let truncated = &snapshot[..snapshot.len() - 1];
+    let err = StateSnapshotReader::new(truncated)
+        .unwrap()
+        .verify()
+        .unwrap_err();
+    assert!(err.to_string().contains("truncated"));
+}
+
+fn open_readonly(store_path: &std::path::Path) -> StoreReadonly {
+    let cf_names = (0..COLUMNS).map(|c| c.to_string());
+    let db = ReadOnlyDB::open_cf(store_path, cf_names).unwrap().unwrap();
+    StoreReadonly::new(db)
+}
+
+async fn setup_chain(
+    rollup_type_script: &Script,
+    rollup_config: &RollupConfig,
+    store_path: &std::path::Path,
+) -> gw_chain::chain::Chain {
+    let store = {
+        let config = StoreConfig {
+            path: store_path.to_path_buf(),
+            ..Default::default()
+        };
+        Store::new(RocksDB::open(&config, COLUMNS))
+    };
+    let mut account_lock_manage = AccountLockManage::default();
+    account_lock_manage
+        .register_lock_algorithm((*ALWAYS_SUCCESS_CODE_HASH).into(), Box::new(AlwaysSuccess));
+    let mut chain = setup_chain_with_account_lock_manage(
+        rollup_type_script.clone(),
+        rollup_config.clone(),
+        account_lock_manage,
+        Some(store),
+        None,
+        None,
+    )
+    .await;
+    chain.complete_initial_syncing().await.unwrap();
+    chain
+}
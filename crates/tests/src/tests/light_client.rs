@@ -0,0 +1,169 @@
+use gw_common::{
+    builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID},
+    h256_ext::H256Ext,
+    state::{
+        build_registry_address_to_script_hash_key, build_script_hash_to_registry_address_key, State,
+    },
+    H256,
+};
+use gw_jsonrpc_types::{
+    ckb_jsonrpc_types::JsonBytes,
+    godwoken::{BalanceKey, ProofRequest},
+};
+use gw_light_client::{verify_account_state, verify_block, AccountStateLeaves, Error};
+use gw_store::{state::state_db::StateContext, traits::chain_store::ChainStore};
+use gw_types::{
+    packed::{DepositRequest, RawL2Block, Script},
+    prelude::*,
+    U256,
+};
+
+use crate::testing_tool::{chain::TestChain, eth_wallet::EthWallet, rpc_server::RPCServer};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_light_client_verify_chain_proofs() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let mut chain = TestChain::setup(rollup_type_script).await;
+    let rpc_server = RPCServer::build(&chain, None).await.unwrap();
+
+    const DEPOSIT_CAPACITY: u64 = 1000 * 10u64.pow(8);
+    let wallet = EthWallet::random(chain.rollup_type_hash());
+    let deposit = DepositRequest::new_builder()
+        .capacity(DEPOSIT_CAPACITY.pack())
+        .sudt_script_hash(H256::zero().pack())
+        .amount(0.pack())
+        .script(wallet.account_script().to_owned())
+        .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+        .build();
+    chain.produce_block(vec![deposit], vec![]).await.unwrap();
+    let deposit_block = chain.last_valid_block();
+    chain.produce_block(vec![], vec![]).await.unwrap();
+
+    // Trust the global state of the tip block
+    let block = chain.last_valid_block();
+    let global_state = chain
+        .store()
+        .get_block_post_global_state(&block.hash().into())
+        .unwrap()
+        .unwrap();
+
+    // Block inclusion
+    let block_proof = |raw_block: &RawL2Block| {
+        let db = chain.store().begin_transaction();
+        let block_smt = db.block_smt().unwrap();
+        let leaves = vec![(raw_block.smt_key().into(), raw_block.hash().into())];
+        block_smt
+            .merkle_proof(vec![raw_block.smt_key().into()])
+            .unwrap()
+            .compile(leaves)
+            .unwrap()
+            .0
+    };
+    let proof = block_proof(&block.raw());
+    verify_block(&global_state, &block.raw(), &proof).unwrap();
+    let proof = block_proof(&deposit_block.raw());
+    verify_block(&global_state, &deposit_block.raw(), &proof).unwrap();
+
+    let deposit_global_state = chain
+        .store()
+        .get_block_post_global_state(&deposit_block.hash().into())
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        verify_block(&deposit_global_state, &block.raw(), &proof),
+        Err(Error::BlockNotCommitted)
+    );
+
+    // Account fields and balance
+    let post_account = block.raw().post_account();
+    let (account_id, balance) = {
+        let db = chain.store().begin_transaction();
+        let tree = db.state_tree(StateContext::ReadOnly).unwrap();
+        let account_id = tree
+            .get_account_id_by_script_hash(&wallet.account_script_hash())
+            .unwrap()
+            .unwrap();
+        let balance = tree
+            .get_sudt_balance(CKB_SUDT_ACCOUNT_ID, wallet.reg_address())
+            .unwrap();
+        (account_id, balance)
+    };
+    assert!(!balance.is_zero());
+
+    let request = ProofRequest {
+        account_id: account_id.into(),
+        storage_keys: vec![],
+        balances: vec![BalanceKey {
+            sudt_id: CKB_SUDT_ACCOUNT_ID.into(),
+            registry_address: JsonBytes::from_vec(wallet.reg_address().to_bytes()),
+        }],
+        block_number: None,
+        block_hash: None,
+    };
+    let account_proof = rpc_server.get_proof(&request).await.unwrap();
+    let proof = account_proof.proof.into_bytes();
+
+    let mut leaves = AccountStateLeaves::new();
+    leaves
+        .nonce(account_id, 0)
+        .script_hash(account_id, wallet.account_script_hash())
+        .sudt_balance(CKB_SUDT_ACCOUNT_ID, wallet.reg_address(), balance);
+    verify_account_state(&post_account, &leaves, &proof).unwrap();
+
+    let mut tampered_leaves = AccountStateLeaves::new();
+    tampered_leaves
+        .nonce(account_id, 0)
+        .script_hash(account_id, wallet.account_script_hash())
+        .sudt_balance(
+            CKB_SUDT_ACCOUNT_ID,
+            wallet.reg_address(),
+            balance + U256::one(),
+        );
+    assert_eq!(
+        verify_account_state(&post_account, &tampered_leaves, &proof),
+        Err(Error::RootMismatch)
+    );
+
+    // Registry address mapping
+    let to_address_key = build_script_hash_to_registry_address_key(&wallet.account_script_hash());
+    let to_script_hash_key = build_registry_address_to_script_hash_key(wallet.reg_address());
+    let request = ProofRequest {
+        account_id: ETH_REGISTRY_ACCOUNT_ID.into(),
+        storage_keys: vec![
+            JsonBytes::from_vec(to_address_key.clone()),
+            JsonBytes::from_vec(to_script_hash_key.clone()),
+        ],
+        balances: vec![],
+        block_number: None,
+        block_hash: None,
+    };
+    let registry_proof = rpc_server.get_proof(&request).await.unwrap();
+    let proof = registry_proof.proof.into_bytes();
+
+    let mut leaves = AccountStateLeaves::new();
+    leaves
+        .nonce(ETH_REGISTRY_ACCOUNT_ID, registry_proof.nonce.value())
+        .script_hash(ETH_REGISTRY_ACCOUNT_ID, registry_proof.script_hash.0.into())
+        .registry_address(wallet.reg_address(), wallet.account_script_hash())
+        .unwrap();
+    verify_account_state(&post_account, &leaves, &proof).unwrap();
+
+    // Map the address to another script hash
+    let mut address_buf = [0u8; 32];
+    wallet
+        .reg_address()
+        .write_to_slice(&mut address_buf)
+        .unwrap();
+    let mut tampered_leaves = AccountStateLeaves::new();
+    tampered_leaves
+        .nonce(ETH_REGISTRY_ACCOUNT_ID, registry_proof.nonce.value())
+        .script_hash(ETH_REGISTRY_ACCOUNT_ID, registry_proof.script_hash.0.into())
+        .storage(ETH_REGISTRY_ACCOUNT_ID, &to_address_key, address_buf.into())
+        .storage(ETH_REGISTRY_ACCOUNT_ID, &to_script_hash_key, H256::one());
+    assert_eq!(
+        verify_account_state(&post_account, &tampered_leaves, &proof),
+        Err(Error::RootMismatch)
+    );
+}
@@ -1,9 +1,16 @@
+mod backup;
 mod chain;
+mod check_db;
 mod deposit_withdrawal;
+mod dynamic_config;
 mod export_import_block;
+mod export_import_state;
+mod light_client;
 mod mem_block_repackage;
 mod meta_contract_args;
+mod parallel_mem_pool;
 mod polyjuice_sender_recover;
+mod program_cache;
 mod restore_mem_block;
 mod restore_mem_pool_pending_withdrawal;
 mod rpc_server;
@@ -0,0 +1,184 @@
+use ckb_types::prelude::{Builder, Entity};
+use gw_common::{
+    builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID},
+    registry_address::RegistryAddress,
+    state::State,
+    H256,
+};
+use gw_config::{MemPoolConfig, ParallelExecutionConfig};
+use gw_generator::{constants::L2TX_MAX_CYCLES, traits::StateExt};
+use gw_store::{chain_view::ChainView, traits::chain_store::ChainStore};
+use gw_types::{
+    bytes::Bytes,
+    packed::{Fee, L2Transaction, RawL2Transaction, SUDTArgs, SUDTTransfer, Script},
+    prelude::{Pack, Unpack},
+    U256,
+};
+
+use crate::testing_tool::{chain::TestChain, common::random_always_success_script};
+
+fn build_transfer(
+    chain_id: u64,
+    from_id: u32,
+    nonce: u32,
+    to: &RegistryAddress,
+    amount: u128,
+) -> L2Transaction {
+    let transfer = SUDTTransfer::new_builder()
+        .amount(U256::from(amount).pack())
+        .to_address(Bytes::from(to.to_bytes()).pack())
+        .fee(
+            Fee::new_builder()
+                .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+                .build(),
+        )
+        .build();
+    let args = SUDTArgs::new_builder().set(transfer).build();
+    let raw = RawL2Transaction::new_builder()
+        .chain_id(chain_id.pack())
+        .from_id(from_id.pack())
+        .to_id(CKB_SUDT_ACCOUNT_ID.pack())
+        .nonce(nonce.pack())
+        .args(args.as_bytes().pack())
+        .build();
+    L2Transaction::new_builder().raw(raw).build()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_parallel_execution_same_as_sequential() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let rollup_script_hash: H256 = rollup_type_script.hash().into();
+    let mem_pool_config = MemPoolConfig {
+        restore_path: tempfile::TempDir::new().unwrap().path().to_path_buf(),
+        parallel_execution: ParallelExecutionConfig {
+            enabled: true,
+            workers: 4,
+        },
+        ..Default::default()
+    };
+    let mut chain =
+        TestChain::setup_with_mem_pool_config(rollup_type_script, mem_pool_config).await;
+    chain.produce_block(vec![], vec![]).await.unwrap();
+
+    // Create accounts
+    const BALANCE: u128 = 1_000_000;
+    let mem_pool_state = chain.mem_pool_state().await;
+    let snap = mem_pool_state.load();
+    let mut state = snap.state().unwrap();
+    let accounts: Vec<(u32, RegistryAddress)> = (0..8)
+        .map(|_| {
+            let script = random_always_success_script(&rollup_script_hash);
+            let script_hash: H256 = script.hash().into();
+            let address =
+                RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, script.hash()[0..20].to_vec());
+            let id = state.create_account_from_script(script).unwrap();
+            state
+                .mapping_registry_address_to_script_hash(address.clone(), script_hash)
+                .unwrap();
+            state
+                .mint_sudt(CKB_SUDT_ACCOUNT_ID, &address, BALANCE.into())
+                .unwrap();
+            (id, address)
+        })
+        .collect();
+    state.submit_tree_to_mem_block();
+    mem_pool_state.store(snap.into());
+
+    // Independent transfers, then transfers depend on the txs before them
+    let chain_id = chain.chain_id();
+    let mut txs: Vec<_> = accounts
+        .iter()
+        .map(|(id, _)| {
+            let to = random_always_success_script(&rollup_script_hash);
+            let to = RegistryAddress::new(ETH_REGISTRY_ACCOUNT_ID, to.hash()[0..20].to_vec());
+            build_transfer(chain_id, *id, 0, &to, 1000)
+        })
+        .collect();
+    txs.push(build_transfer(
+        chain_id,
+        accounts[0].0,
+        1,
+        &accounts[1].1,
+        2000,
+    ));
+    txs.push(build_transfer(
+        chain_id,
+        accounts[1].0,
+        1,
+        &accounts[2].1,
+        BALANCE,
+    ));
+    txs.push(build_transfer(
+        chain_id,
+        accounts[2].0,
+        1,
+        &accounts[0].1,
+        500,
+    ));
+
+    // Execute txs one by one on a copy of the mem state
+    let expected_post_states = {
+        let mem_pool = chain.mem_pool().await;
+        let block_info = mem_pool.mem_block().block_info().to_owned();
+        let generator = chain.inner.generator();
+        let db = chain.store().get_snapshot();
+        let chain_view = ChainView::new(&db, db.get_tip_block_hash().unwrap());
+        let snap = mem_pool_state.load();
+        let mut state = snap.state().unwrap();
+        txs.iter()
+            .map(|tx| {
+                let run_result = generator
+                    .unchecked_execute_transaction(
+                        &chain_view,
+                        &state,
+                        &block_info,
+                        &tx.raw(),
+                        L2TX_MAX_CYCLES,
+                    )
+                    .unwrap();
+                assert_eq!(run_result.exit_code, 0);
+                state.apply_run_result(&run_result.write).unwrap();
+                state.merkle_state().unwrap()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let results = {
+        let mut mem_pool = chain.mem_pool().await;
+        mem_pool.push_transactions(txs.clone()).await
+    };
+    assert!(results.iter().all(Result::is_ok));
+
+    // Same post states as sequential execution
+    let db = chain.store().get_snapshot();
+    for (tx, expected_post_state) in txs.iter().zip(expected_post_states.iter()) {
+        let tx_hash: H256 = tx.raw().hash().into();
+        let receipt = db
+            .get_mem_pool_transaction_receipt(&tx_hash)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            receipt.post_state().as_slice(),
+            expected_post_state.as_slice()
+        );
+    }
+    let snap = mem_pool_state.load();
+    let state = snap.state().unwrap();
+    assert_eq!(
+        state
+            .get_sudt_balance(CKB_SUDT_ACCOUNT_ID, &accounts[2].1)
+            .unwrap(),
+        U256::from(BALANCE * 2 - 1500)
+    );
+    let nonce: u32 = txs.last().unwrap().raw().nonce().unpack();
+    assert_eq!(state.get_nonce(accounts[2].0).unwrap(), nonce + 1);
+
+    // Duplicated txs are rejected in order
+    let results = {
+        let mut mem_pool = chain.mem_pool().await;
+        mem_pool.push_transactions(txs[..2].to_vec()).await
+    };
+    assert!(results.iter().all(Result::is_err));
+}
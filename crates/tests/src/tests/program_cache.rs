@@ -0,0 +1,182 @@
+use gw_common::{builtins::CKB_SUDT_ACCOUNT_ID, H256};
+use gw_generator::{
+    account_lock_manage::AccountLockManage, constants::L2TX_MAX_CYCLES, types::vm::VMVersion,
+    Generator,
+};
+use gw_store::{chain_view::ChainView, traits::chain_store::ChainStore};
+use gw_types::{
+    offchain::RunResult,
+    packed::{RawL2Transaction, Script},
+    prelude::{Builder, Entity, Pack},
+    U256,
+};
+
+use crate::testing_tool::{
+    chain::{build_backend_manage_with_program_cache, TestChain, POLYJUICE_VALIDATOR_CODE_HASH},
+    eth_wallet::EthWallet,
+    polyjuice::{erc20::SudtErc20ArgsBuilder, PolyjuiceAccount, PolyjuiceSystemLog},
+};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_program_cache_same_as_load_program() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let mut chain = TestChain::setup(rollup_type_script).await;
+    let (_test_wallet, test_account_id, erc20_contract_account_id) = deploy_erc20(&mut chain).await;
+
+    let to_wallet = EthWallet::random(chain.rollup_type_hash());
+    let transfer_args =
+        SudtErc20ArgsBuilder::transfer(to_wallet.reg_address(), U256::from(1000)).finish();
+    let raw_tx = RawL2Transaction::new_builder()
+        .chain_id(chain.chain_id().pack())
+        .from_id(test_account_id.pack())
+        .to_id(erc20_contract_account_id.pack())
+        .nonce(1u32.pack())
+        .args(transfer_args.pack())
+        .build();
+
+    // Execute with cached programs and programs loaded from ELF
+    let cached_generator = chain.inner.generator();
+    let uncached_generator = build_uncached_generator(cached_generator);
+
+    let backend = cached_generator
+        .backend_manage()
+        .get_backend(0, &(*POLYJUICE_VALIDATOR_CODE_HASH).into())
+        .expect("polyjuice backend");
+    for vm_version in [VMVersion::V0, VMVersion::V1] {
+        let backend_manage = cached_generator.backend_manage();
+        assert!(backend_manage.get_program(backend, vm_version).is_some());
+        let backend_manage = uncached_generator.backend_manage();
+        assert!(backend_manage.get_program(backend, vm_version).is_none());
+    }
+
+    let cached = execute(&chain, cached_generator, &raw_tx).await;
+    let uncached = execute(&chain, &uncached_generator, &raw_tx).await;
+    assert_eq!(cached.exit_code, 0);
+    assert_same_run_result(&cached, &uncached);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_program_cache_back_to_back_txs() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let mut chain = TestChain::setup(rollup_type_script).await;
+    let (test_wallet, test_account_id, erc20_contract_account_id) = deploy_erc20(&mut chain).await;
+
+    let build_tx = |args: Vec<u8>| {
+        RawL2Transaction::new_builder()
+            .chain_id(chain.chain_id().pack())
+            .from_id(test_account_id.pack())
+            .to_id(erc20_contract_account_id.pack())
+            .nonce(1u32.pack())
+            .args(args.pack())
+            .build()
+    };
+    let to_wallet = EthWallet::random(chain.rollup_type_hash());
+    let transfer_tx = build_tx(
+        SudtErc20ArgsBuilder::transfer(to_wallet.reg_address(), U256::from(1000)).finish(),
+    );
+    let balance_tx = build_tx(SudtErc20ArgsBuilder::balance_of(test_wallet.reg_address()).finish());
+
+    let cached_generator = chain.inner.generator();
+    let uncached_generator = build_uncached_generator(cached_generator);
+
+    // Both txs run the same cached polyjuice program, the second one reads
+    // the stack and heap which the first one wrote. Each must run as if the
+    // program is freshly loaded.
+    for (first, second) in [(&transfer_tx, &balance_tx), (&balance_tx, &transfer_tx)] {
+        let first_run = execute(&chain, cached_generator, first).await;
+        assert_eq!(first_run.exit_code, 0);
+        let second_run = execute(&chain, cached_generator, second).await;
+        assert_eq!(second_run.exit_code, 0);
+
+        let uncached = execute(&chain, &uncached_generator, second).await;
+        assert_same_run_result(&second_run, &uncached);
+    }
+}
+
+// Deploy an erc20 contract, returns the deployer wallet, its account id and
+// the contract account id
+async fn deploy_erc20(chain: &mut TestChain) -> (EthWallet, u32, u32) {
+    chain.produce_block(vec![], vec![]).await.unwrap();
+
+    let mem_pool_state = chain.mem_pool_state().await;
+    let snap = mem_pool_state.load();
+    let mut state = snap.state().unwrap();
+
+    let test_wallet = EthWallet::random(chain.rollup_type_hash());
+    let test_account_id = test_wallet
+        .create_account(&mut state, 10000000u128.into())
+        .unwrap();
+    let polyjuice_account = PolyjuiceAccount::create(chain.rollup_type_hash(), &mut state).unwrap();
+    state.submit_tree_to_mem_block();
+
+    let deploy_args = SudtErc20ArgsBuilder::deploy(CKB_SUDT_ACCOUNT_ID, 18).finish();
+    let raw_tx = RawL2Transaction::new_builder()
+        .chain_id(chain.chain_id().pack())
+        .from_id(test_account_id.pack())
+        .to_id(polyjuice_account.id.pack())
+        .nonce(0u32.pack())
+        .args(deploy_args.pack())
+        .build();
+    let deploy_tx = test_wallet.sign_polyjuice_tx(&state, raw_tx).unwrap();
+    let deploy_tx_hash: H256 = deploy_tx.hash().into();
+
+    mem_pool_state.store(snap.into());
+    {
+        let mut mem_pool = chain.mem_pool().await;
+        mem_pool.push_transaction(deploy_tx).await.unwrap();
+    }
+
+    let system_log = PolyjuiceSystemLog::parse_from_tx_hash(chain, deploy_tx_hash).unwrap();
+    assert_eq!(system_log.status_code, 0);
+
+    let snap = mem_pool_state.load();
+    let state = snap.state().unwrap();
+    let erc20_contract_account_id = system_log.contract_account_id(&state).unwrap();
+
+    (test_wallet, test_account_id, erc20_contract_account_id)
+}
+
+fn build_uncached_generator(cached_generator: &Generator) -> Generator {
+    let rollup_context = cached_generator.rollup_context().to_owned();
+    let backend_manage =
+        build_backend_manage_with_program_cache(&rollup_context.rollup_config, false);
+    Generator::new(
+        backend_manage,
+        AccountLockManage::default(),
+        rollup_context,
+        Default::default(),
+    )
+}
+
+async fn execute(chain: &TestChain, generator: &Generator, raw_tx: &RawL2Transaction) -> RunResult {
+    let snap = chain.mem_pool_state().await.load();
+    let state = snap.state().unwrap();
+    let block_info = {
+        let mem_pool = chain.mem_pool().await;
+        mem_pool.mem_block().block_info().to_owned()
+    };
+    let db = chain.store().get_snapshot();
+    let chain_view = ChainView::new(&db, db.get_tip_block_hash().unwrap());
+
+    generator
+        .unchecked_execute_transaction(&chain_view, &state, &block_info, raw_tx, L2TX_MAX_CYCLES)
+        .unwrap()
+}
+
+fn assert_same_run_result(cached: &RunResult, uncached: &RunResult) {
+    assert_eq!(cached.exit_code, uncached.exit_code);
+    assert_eq!(cached.return_data, uncached.return_data);
+    assert_eq!(cached.used_cycles, uncached.used_cycles);
+    assert_eq!(cached.write.write_values, uncached.write.write_values);
+    assert_eq!(cached.write.account_count, uncached.write.account_count);
+    assert_eq!(cached.write.write_data, uncached.write.write_data);
+    let logs = |run_result: &RunResult| -> Vec<Vec<u8>> {
+        let logs = run_result.write.logs.iter();
+        logs.map(|log| log.as_slice().to_vec()).collect()
+    };
+    assert_eq!(logs(cached), logs(uncached));
+}
@@ -0,0 +1,55 @@
+use gw_config::RPCMethods;
+use gw_types::packed::Script;
+use gw_utils::backup::read_backup_manifest;
+
+use crate::testing_tool::{chain::TestChain, rpc_server::RPCServer};
+
+const INVALID_PARAM_ERR_CODE: &str = "-32602";
+const METHOD_NOT_AVAILABLE_ERR_CODE: &str = "-32601";
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_admin_create_backup() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let chain = TestChain::setup(rollup_type_script.clone()).await;
+    let backup_dir = tempfile::tempdir().expect("create temp dir");
+    let rpc_server = {
+        let mut args =
+            RPCServer::default_registry_args(&chain.inner, rollup_type_script.clone(), None);
+        args.server_config.enable_methods.insert(RPCMethods::Admin);
+        args.server_config.backup_dir = Some(backup_dir.path().to_path_buf());
+        RPCServer::build_from_registry_args(args).await.unwrap()
+    };
+
+    let manifest = rpc_server.admin_create_backup("20221018").await.unwrap();
+    let backup_path = backup_dir.path().join("20221018");
+    let created = read_backup_manifest(&backup_path).unwrap();
+    assert_eq!(created.tip_block_hash.0, manifest.tip_block_hash.0);
+
+    // Names escaping the backup dir
+    let outside_dir = tempfile::tempdir().expect("create temp dir");
+    let outside_path = outside_dir.path().join("backup");
+    let escaped = vec![
+        outside_path.to_string_lossy().to_string(),
+        "../backup".to_string(),
+        "20221018/../../backup".to_string(),
+        "".to_string(),
+    ];
+    for name in escaped {
+        let err = rpc_server.admin_create_backup(&name).await.unwrap_err();
+        assert!(err.to_string().contains(INVALID_PARAM_ERR_CODE), "{}", name);
+    }
+    assert!(!outside_path.exists());
+    assert!(!backup_dir.path().parent().unwrap().join("backup").exists());
+
+    // Backup dir isn't configured
+    let rpc_server = {
+        let mut args =
+            RPCServer::default_registry_args(&chain.inner, rollup_type_script.clone(), None);
+        args.server_config.enable_methods.insert(RPCMethods::Admin);
+        RPCServer::build_from_registry_args(args).await.unwrap()
+    };
+    let err = rpc_server.admin_create_backup("backup").await.unwrap_err();
+    assert!(err.to_string().contains(METHOD_NOT_AVAILABLE_ERR_CODE));
+}
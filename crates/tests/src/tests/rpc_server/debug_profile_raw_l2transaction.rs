@@ -0,0 +1,128 @@
+use gw_common::{builtins::CKB_SUDT_ACCOUNT_ID, H256};
+use gw_config::RPCMethods;
+use gw_types::{
+    packed::{RawL2Transaction, Script},
+    prelude::{Builder, Entity, Pack},
+    U256,
+};
+
+use crate::testing_tool::{
+    chain::TestChain,
+    eth_wallet::EthWallet,
+    polyjuice::{erc20::SudtErc20ArgsBuilder, PolyjuiceAccount, PolyjuiceSystemLog},
+    rpc_server::RPCServer,
+};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_debug_profile_raw_l2transaction() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let mut chain = TestChain::setup(rollup_type_script.clone()).await;
+    let rpc_server = {
+        let mut args =
+            RPCServer::default_registry_args(&chain.inner, rollup_type_script.clone(), None);
+        args.server_config.enable_methods.insert(RPCMethods::Debug);
+        RPCServer::build_from_registry_args(args).await.unwrap()
+    };
+    chain.produce_block(vec![], vec![]).await.unwrap();
+
+    let mem_pool_state = chain.mem_pool_state().await;
+    let snap = mem_pool_state.load();
+    let mut state = snap.state().unwrap();
+
+    let test_wallet = EthWallet::random(chain.rollup_type_hash());
+    let test_account_id = test_wallet
+        .create_account(&mut state, 10000000u128.into())
+        .unwrap();
+
+    let polyjuice_account = PolyjuiceAccount::create(chain.rollup_type_hash(), &mut state).unwrap();
+
+    state.submit_tree_to_mem_block();
+
+    // Deploy erc20 for test
+    let deploy_args = SudtErc20ArgsBuilder::deploy(CKB_SUDT_ACCOUNT_ID, 18).finish();
+    let raw_tx = RawL2Transaction::new_builder()
+        .chain_id(chain.chain_id().pack())
+        .from_id(test_account_id.pack())
+        .to_id(polyjuice_account.id.pack())
+        .nonce(0u32.pack())
+        .args(deploy_args.pack())
+        .build();
+
+    let deploy_tx = test_wallet.sign_polyjuice_tx(&state, raw_tx).unwrap();
+    let deploy_tx_hash: H256 = deploy_tx.hash().into();
+
+    mem_pool_state.store(snap.into());
+    {
+        let mut mem_pool = chain.mem_pool().await;
+        mem_pool.push_transaction(deploy_tx).await.unwrap();
+    }
+
+    let system_log = PolyjuiceSystemLog::parse_from_tx_hash(&chain, deploy_tx_hash).unwrap();
+    assert_eq!(system_log.status_code, 0);
+
+    let snap = mem_pool_state.load();
+    let state = snap.state().unwrap();
+    let erc20_contract_account_id = system_log.contract_account_id(&state).unwrap();
+
+    let to_wallet = EthWallet::random(chain.rollup_type_hash());
+    let transfer_args =
+        SudtErc20ArgsBuilder::transfer(to_wallet.reg_address(), U256::from(1000)).finish();
+    let raw_tx = RawL2Transaction::new_builder()
+        .chain_id(chain.chain_id().pack())
+        .from_id(test_account_id.pack())
+        .to_id(erc20_contract_account_id.pack())
+        .nonce(1u32.pack())
+        .args(transfer_args.pack())
+        .build();
+
+    // Profile result matches the normal execution
+    let run_result = rpc_server
+        .execute_raw_l2transaction(&raw_tx, None, None)
+        .await
+        .unwrap();
+
+    const SAMPLE_INTERVAL: u64 = 1000;
+    let profile = rpc_server
+        .debug_profile_raw_l2transaction(&raw_tx, None, Some(SAMPLE_INTERVAL))
+        .await
+        .unwrap();
+    assert_eq!(profile.exit_code.value(), 0);
+    assert_eq!(profile.return_data, run_result.return_data);
+    assert_eq!(profile.sample_interval.value(), SAMPLE_INTERVAL);
+
+    let used_cycles = profile.used_cycles.value();
+    assert!(used_cycles > 0);
+
+    let load = profile
+        .syscalls
+        .iter()
+        .find(|syscall| syscall.name.as_deref() == Some("SYS_LOAD"))
+        .expect("sys load profile");
+    assert!(load.count.value() > 0);
+    assert!(load.bytes.value() > 0);
+    assert!(load.cycles.value() > 0);
+
+    let samples: u64 = profile.pc_samples.iter().map(|s| s.count.value()).sum();
+    assert!(samples > 0);
+    assert!(samples <= used_cycles / SAMPLE_INTERVAL);
+
+    // Syscall cycles aren't sampled to the PC
+    let syscall_cycles: u64 = profile.syscalls.iter().map(|s| s.cycles.value()).sum();
+    assert!(syscall_cycles + samples * SAMPLE_INTERVAL <= used_cycles);
+
+    // Sample interval is too small
+    let err = rpc_server
+        .debug_profile_raw_l2transaction(&raw_tx, None, Some(1))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("sample interval"));
+
+    // Debug methods are disabled by default
+    let rpc_server = RPCServer::build(&chain, None).await.unwrap();
+    assert!(rpc_server
+        .debug_profile_raw_l2transaction(&raw_tx, None, None)
+        .await
+        .is_err());
+}
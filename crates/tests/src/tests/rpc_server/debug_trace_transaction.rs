@@ -0,0 +1,322 @@
+use ckb_types::prelude::{Builder, Entity};
+use gw_common::{
+    builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID, RESERVED_ACCOUNT_ID},
+    h256_ext::H256Ext,
+    state::{build_account_field_key, State, GW_ACCOUNT_NONCE_TYPE},
+    H256,
+};
+use gw_config::RPCMethods;
+use gw_generator::account_lock_manage::secp256k1::Secp256k1Eth;
+use gw_jsonrpc_types::godwoken::LogItem;
+use gw_store::traits::chain_store::ChainStore;
+use gw_types::{
+    core::ScriptHashType,
+    packed::{
+        CreateAccount, DepositRequest, Fee, L2Transaction, MetaContractArgs, RawL2Transaction,
+        RawWithdrawalRequest, Script, WithdrawalRequest, WithdrawalRequestExtra,
+    },
+    prelude::{Pack, Unpack},
+};
+
+use crate::testing_tool::{
+    chain::{TestChain, ALWAYS_SUCCESS_CODE_HASH, DEFAULT_FINALITY_BLOCKS},
+    eth_wallet::EthWallet,
+    polyjuice::{erc20::SudtErc20ArgsBuilder, PolyjuiceAccount},
+    rpc_server::RPCServer,
+};
+
+const META_CONTRACT_ACCOUNT_ID: u32 = RESERVED_ACCOUNT_ID;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_debug_trace_transaction() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let mut chain = TestChain::setup(rollup_type_script.clone()).await;
+    let rpc_server = {
+        let mut args =
+            RPCServer::default_registry_args(&chain.inner, rollup_type_script.clone(), None);
+        args.server_config.enable_methods.insert(RPCMethods::Debug);
+        RPCServer::build_from_registry_args(args).await.unwrap()
+    };
+
+    // Deposit test account
+    const DEPOSIT_CAPACITY: u64 = 12345768 * 10u64.pow(8);
+    let test_wallet = EthWallet::random(chain.rollup_type_hash());
+    let deposit = DepositRequest::new_builder()
+        .capacity(DEPOSIT_CAPACITY.pack())
+        .sudt_script_hash(H256::zero().pack())
+        .amount(0.pack())
+        .script(test_wallet.account_script().to_owned())
+        .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+        .build();
+    chain.produce_block(vec![deposit], vec![]).await.unwrap();
+
+    let mem_pool_state = chain.mem_pool_state().await;
+    let snap = mem_pool_state.load();
+    let state = snap.state().unwrap();
+    let test_account_id = state
+        .get_account_id_by_script_hash(&test_wallet.account_script_hash())
+        .unwrap()
+        .unwrap();
+
+    // Create polyjuice account, then deploy erc20 contract in the same block
+    let polyjuice_account = PolyjuiceAccount::build_script(chain.rollup_type_hash());
+    let meta_contract_script_hash = state.get_script_hash(META_CONTRACT_ACCOUNT_ID).unwrap();
+    let fee = Fee::new_builder()
+        .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+        .amount(0u128.pack())
+        .build();
+    let create_polyjuice = CreateAccount::new_builder()
+        .fee(fee)
+        .script(polyjuice_account.clone())
+        .build();
+    let args = MetaContractArgs::new_builder()
+        .set(create_polyjuice)
+        .build();
+    let raw_l2tx = RawL2Transaction::new_builder()
+        .chain_id(chain.chain_id().pack())
+        .from_id(test_account_id.pack())
+        .to_id(META_CONTRACT_ACCOUNT_ID.pack())
+        .nonce(0u32.pack())
+        .args(args.as_bytes().pack())
+        .build();
+    let signing_message = Secp256k1Eth::eip712_signing_message(
+        chain.chain_id(),
+        &raw_l2tx,
+        test_wallet.reg_address().to_owned(),
+        meta_contract_script_hash,
+    )
+    .unwrap();
+    let sign = test_wallet.sign_message(signing_message.into()).unwrap();
+    let create_tx = L2Transaction::new_builder()
+        .raw(raw_l2tx)
+        .signature(sign.pack())
+        .build();
+    let create_tx_hash: H256 = create_tx.hash().into();
+    {
+        let mut mem_pool = chain.mem_pool().await;
+        mem_pool.push_transaction(create_tx).await.unwrap();
+    }
+
+    let snap = mem_pool_state.load();
+    let state = snap.state().unwrap();
+    let polyjuice_account_id = state
+        .get_account_id_by_script_hash(&polyjuice_account.hash().into())
+        .unwrap()
+        .unwrap();
+    let deploy_args = SudtErc20ArgsBuilder::deploy(CKB_SUDT_ACCOUNT_ID, 18).finish();
+    let raw_tx = RawL2Transaction::new_builder()
+        .chain_id(chain.chain_id().pack())
+        .from_id(test_account_id.pack())
+        .to_id(polyjuice_account_id.pack())
+        .nonce(1u32.pack())
+        .args(deploy_args.pack())
+        .build();
+    let deploy_tx = test_wallet.sign_polyjuice_tx(&state, raw_tx).unwrap();
+    let deploy_tx_hash: H256 = deploy_tx.hash().into();
+    {
+        let mut mem_pool = chain.mem_pool().await;
+        mem_pool.push_transaction(deploy_tx).await.unwrap();
+    }
+
+    chain.produce_block(vec![], vec![]).await.unwrap();
+    let block = chain.last_valid_block();
+    let block_number: u64 = block.raw().number().unpack();
+
+    // The deploy tx is replayed after the create tx
+    let trace = rpc_server
+        .debug_trace_transaction(deploy_tx_hash)
+        .await
+        .unwrap()
+        .expect("trace");
+    assert_eq!(H256::from(trace.tx_hash.0), deploy_tx_hash);
+    assert_eq!(H256::from(trace.block_hash.0), block.hash().into());
+    assert_eq!(trace.block_number.value(), block_number);
+    assert_eq!(trace.tx_index.value(), 1);
+    assert_eq!(trace.exit_code.value(), 0);
+    assert!(trace.used_cycles.value() > 0);
+
+    // Same logs as the committed receipt
+    let receipt = chain
+        .store()
+        .get_snapshot()
+        .get_transaction_receipt(&deploy_tx_hash)
+        .unwrap()
+        .unwrap();
+    let receipt_logs: Vec<LogItem> = receipt.logs().into_iter().map(Into::into).collect();
+    assert_eq!(trace.logs, receipt_logs);
+
+    // Sender's nonce is increased on the replayed state
+    let nonce_key = build_account_field_key(test_account_id, GW_ACCOUNT_NONCE_TYPE);
+    let written_nonce = trace
+        .write_values
+        .iter()
+        .find(|kv| H256::from(kv.key.0) == nonce_key)
+        .expect("write nonce");
+    assert_eq!(H256::from(written_nonce.value.0), H256::from_u32(2));
+
+    // Contract account is created
+    assert!(trace.account_count.is_some());
+    assert!(!trace.new_script_hashes.is_empty());
+
+    assert!(!trace.syscalls.is_empty());
+    assert!(trace
+        .syscalls
+        .iter()
+        .any(|syscall| syscall.name.as_deref() == Some("SYS_STORE")));
+    assert!(trace
+        .syscalls
+        .windows(2)
+        .all(|w| w[0].cycles.value() <= w[1].cycles.value()));
+
+    // First tx of the block
+    let trace = rpc_server
+        .debug_trace_transaction(create_tx_hash)
+        .await
+        .unwrap()
+        .expect("trace");
+    assert_eq!(trace.tx_index.value(), 0);
+    assert_eq!(trace.exit_code.value(), 0);
+
+    // Unknown tx
+    let trace = rpc_server
+        .debug_trace_transaction(H256::from_u32(42))
+        .await
+        .unwrap();
+    assert!(trace.is_none());
+
+    // Debug methods are disabled by default
+    let rpc_server = RPCServer::build(&chain, None).await.unwrap();
+    assert!(rpc_server
+        .debug_trace_transaction(deploy_tx_hash)
+        .await
+        .is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_debug_trace_transaction_after_withdrawal() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let mut chain = TestChain::setup(rollup_type_script.clone()).await;
+    let rpc_server = {
+        let mut args =
+            RPCServer::default_registry_args(&chain.inner, rollup_type_script.clone(), None);
+        args.server_config.enable_methods.insert(RPCMethods::Debug);
+        RPCServer::build_from_registry_args(args).await.unwrap()
+    };
+
+    // Deposit test account and withdrawal account
+    const DEPOSIT_CAPACITY: u64 = 12345768 * 10u64.pow(8);
+    let test_wallet = EthWallet::random(chain.rollup_type_hash());
+    let withdrawal_script = Script::new_builder()
+        .code_hash(ALWAYS_SUCCESS_CODE_HASH.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args({
+            let mut args = chain.rollup_type_hash().as_slice().to_vec();
+            args.extend(&[42u8; 20]);
+            args.pack()
+        })
+        .build();
+    let deposits = vec![
+        test_wallet.account_script().to_owned(),
+        withdrawal_script.clone(),
+    ]
+    .into_iter()
+    .map(|script| {
+        DepositRequest::new_builder()
+            .capacity(DEPOSIT_CAPACITY.pack())
+            .sudt_script_hash(H256::zero().pack())
+            .amount(0.pack())
+            .script(script)
+            .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+            .build()
+    })
+    .collect();
+    chain.produce_block(deposits, vec![]).await.unwrap();
+    // Wait for deposit finalize
+    for _ in 0..DEFAULT_FINALITY_BLOCKS {
+        chain.produce_block(vec![], vec![]).await.unwrap();
+    }
+
+    let mem_pool_state = chain.mem_pool_state().await;
+    let snap = mem_pool_state.load();
+    let state = snap.state().unwrap();
+    let test_account_id = state
+        .get_account_id_by_script_hash(&test_wallet.account_script_hash())
+        .unwrap()
+        .unwrap();
+
+    // Withdrawals are applied before txs of the block
+    let withdrawal = {
+        let owner_lock = Script::default();
+        let raw = RawWithdrawalRequest::new_builder()
+            .capacity((1000 * 10u64.pow(8)).pack())
+            .account_script_hash(withdrawal_script.hash().pack())
+            .sudt_script_hash(H256::zero().pack())
+            .owner_lock_hash(owner_lock.hash().pack())
+            .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+            .build();
+        let withdrawal = WithdrawalRequest::new_builder().raw(raw).build();
+        WithdrawalRequestExtra::new_builder()
+            .request(withdrawal)
+            .owner_lock(owner_lock)
+            .build()
+    };
+    let polyjuice_account = PolyjuiceAccount::build_script(chain.rollup_type_hash());
+    let meta_contract_script_hash = state.get_script_hash(META_CONTRACT_ACCOUNT_ID).unwrap();
+    let fee = Fee::new_builder()
+        .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+        .amount(0u128.pack())
+        .build();
+    let create_polyjuice = CreateAccount::new_builder()
+        .fee(fee)
+        .script(polyjuice_account)
+        .build();
+    let args = MetaContractArgs::new_builder()
+        .set(create_polyjuice)
+        .build();
+    let raw_l2tx = RawL2Transaction::new_builder()
+        .chain_id(chain.chain_id().pack())
+        .from_id(test_account_id.pack())
+        .to_id(META_CONTRACT_ACCOUNT_ID.pack())
+        .nonce(0u32.pack())
+        .args(args.as_bytes().pack())
+        .build();
+    let signing_message = Secp256k1Eth::eip712_signing_message(
+        chain.chain_id(),
+        &raw_l2tx,
+        test_wallet.reg_address().to_owned(),
+        meta_contract_script_hash,
+    )
+    .unwrap();
+    let sign = test_wallet.sign_message(signing_message.into()).unwrap();
+    let create_tx = L2Transaction::new_builder()
+        .raw(raw_l2tx)
+        .signature(sign.pack())
+        .build();
+    let create_tx_hash: H256 = create_tx.hash().into();
+    {
+        let mut mem_pool = chain.mem_pool().await;
+        mem_pool
+            .push_withdrawal_request(withdrawal.clone())
+            .await
+            .unwrap();
+        mem_pool.push_transaction(create_tx).await.unwrap();
+    }
+
+    chain.produce_block(vec![], vec![withdrawal]).await.unwrap();
+    let block = chain.last_valid_block();
+    assert_eq!(block.withdrawals().len(), 1);
+    assert_eq!(block.transactions().len(), 1);
+
+    let trace = rpc_server
+        .debug_trace_transaction(create_tx_hash)
+        .await
+        .unwrap()
+        .expect("trace");
+    assert_eq!(H256::from(trace.block_hash.0), block.hash().into());
+    assert_eq!(trace.tx_index.value(), 0);
+    assert_eq!(trace.exit_code.value(), 0);
+}
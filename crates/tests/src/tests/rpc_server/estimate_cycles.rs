@@ -0,0 +1,113 @@
+use gw_common::{builtins::CKB_SUDT_ACCOUNT_ID, H256};
+use gw_generator::typed_transaction::types::PolyjuiceTx;
+use gw_types::{
+    packed::{RawL2Transaction, Script},
+    prelude::{Builder, Entity, Pack},
+    U256,
+};
+
+use crate::testing_tool::{
+    chain::TestChain,
+    eth_wallet::EthWallet,
+    polyjuice::{erc20::SudtErc20ArgsBuilder, PolyjuiceAccount, PolyjuiceSystemLog},
+    rpc_server::RPCServer,
+};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_estimate_cycles() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let mut chain = TestChain::setup(rollup_type_script).await;
+    let rpc_server = RPCServer::build(&chain, None).await.unwrap();
+    chain.produce_block(vec![], vec![]).await.unwrap();
+
+    let mem_pool_state = chain.mem_pool_state().await;
+    let snap = mem_pool_state.load();
+    let mut state = snap.state().unwrap();
+
+    let test_wallet = EthWallet::random(chain.rollup_type_hash());
+    let test_account_id = test_wallet
+        .create_account(&mut state, 10000000u128.into())
+        .unwrap();
+
+    let polyjuice_account = PolyjuiceAccount::create(chain.rollup_type_hash(), &mut state).unwrap();
+
+    state.submit_tree_to_mem_block();
+
+    // Deploy erc20 for test
+    let deploy_args = SudtErc20ArgsBuilder::deploy(CKB_SUDT_ACCOUNT_ID, 18).finish();
+    let raw_tx = RawL2Transaction::new_builder()
+        .chain_id(chain.chain_id().pack())
+        .from_id(test_account_id.pack())
+        .to_id(polyjuice_account.id.pack())
+        .nonce(0u32.pack())
+        .args(deploy_args.pack())
+        .build();
+
+    let deploy_tx = test_wallet.sign_polyjuice_tx(&state, raw_tx).unwrap();
+    let deploy_tx_hash: H256 = deploy_tx.hash().into();
+
+    mem_pool_state.store(snap.into());
+    {
+        let mut mem_pool = chain.mem_pool().await;
+        mem_pool.push_transaction(deploy_tx).await.unwrap();
+    }
+
+    let system_log = PolyjuiceSystemLog::parse_from_tx_hash(&chain, deploy_tx_hash).unwrap();
+    assert_eq!(system_log.status_code, 0);
+
+    let snap = mem_pool_state.load();
+    let state = snap.state().unwrap();
+    let erc20_contract_account_id = system_log.contract_account_id(&state).unwrap();
+
+    // Transfer with an overestimated gas limit
+    const GAS_LIMIT: u64 = 500000;
+    let to_wallet = EthWallet::random(chain.rollup_type_hash());
+    let transfer_args = SudtErc20ArgsBuilder::transfer(to_wallet.reg_address(), U256::from(1000))
+        .gas_limit(GAS_LIMIT)
+        .finish();
+    let raw_tx = RawL2Transaction::new_builder()
+        .chain_id(chain.chain_id().pack())
+        .from_id(test_account_id.pack())
+        .to_id(erc20_contract_account_id.pack())
+        .nonce(1u32.pack())
+        .args(transfer_args.pack())
+        .build();
+
+    let estimation = rpc_server.estimate_cycles(&raw_tx, None).await.unwrap();
+    assert!(estimation.used_cycles.value() > 0);
+
+    let intrinsic_gas = PolyjuiceTx::new(raw_tx.clone()).intrinsic_gas().unwrap();
+    assert_eq!(estimation.intrinsic_gas.unwrap().value(), intrinsic_gas);
+
+    let gas_limit = estimation.recommended_gas_limit.unwrap().value();
+    assert!(gas_limit > intrinsic_gas);
+    assert!(gas_limit < GAS_LIMIT);
+
+    // Succeeds with the recommended gas limit, but fails with less gas
+    let with_gas = |gas: u64| {
+        let args = SudtErc20ArgsBuilder::transfer(to_wallet.reg_address(), U256::from(1000))
+            .gas_limit(gas)
+            .finish();
+        raw_tx.clone().as_builder().args(args.pack()).build()
+    };
+    let run_result = rpc_server
+        .execute_raw_l2transaction(&with_gas(gas_limit), None, None)
+        .await
+        .unwrap();
+    let logs = run_result.logs.into_iter().map(Into::into);
+    let system_log = PolyjuiceSystemLog::parse_logs(logs).unwrap();
+    assert_eq!(system_log.status_code, 0);
+
+    assert!(rpc_server
+        .execute_raw_l2transaction(&with_gas(gas_limit - 1), None, None)
+        .await
+        .is_err());
+
+    // Underestimated gas limit
+    assert!(rpc_server
+        .estimate_cycles(&with_gas(intrinsic_gas), None)
+        .await
+        .is_err());
+}
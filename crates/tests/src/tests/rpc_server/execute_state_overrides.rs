@@ -0,0 +1,177 @@
+use gw_common::{builtins::CKB_SUDT_ACCOUNT_ID, state::State, H256};
+use gw_jsonrpc_types::godwoken::{AccountOverride, BalanceOverride};
+use gw_types::{
+    core::ScriptHashType,
+    packed::{RawL2Transaction, Script},
+    prelude::{Builder, Entity, Pack},
+    U256,
+};
+
+use crate::testing_tool::{
+    chain::TestChain,
+    eth_wallet::EthWallet,
+    polyjuice::{erc20::SudtErc20ArgsBuilder, PolyjuiceAccount, PolyjuiceSystemLog},
+    rpc_server::RPCServer,
+};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_execute_raw_l2transaction_with_state_overrides() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let mut chain = TestChain::setup(rollup_type_script).await;
+    let rpc_server = RPCServer::build(&chain, None).await.unwrap();
+    chain.produce_block(vec![], vec![]).await.unwrap();
+
+    let mem_pool_state = chain.mem_pool_state().await;
+    let snap = mem_pool_state.load();
+    let mut state = snap.state().unwrap();
+
+    let deployer_wallet = EthWallet::random(chain.rollup_type_hash());
+    let deployer_id = deployer_wallet
+        .create_account(&mut state, 1000000u128.into())
+        .unwrap();
+
+    let test_wallet = EthWallet::random(chain.rollup_type_hash());
+    let test_balance: U256 = 1000000u128.into();
+    let test_account_id = test_wallet
+        .create_account(&mut state, test_balance)
+        .unwrap();
+
+    let polyjuice_account = PolyjuiceAccount::create(chain.rollup_type_hash(), &mut state).unwrap();
+
+    state.submit_tree_to_mem_block();
+
+    // Deploy erc20 for test
+    let deploy_args = SudtErc20ArgsBuilder::deploy(CKB_SUDT_ACCOUNT_ID, 18).finish();
+    let raw_tx = RawL2Transaction::new_builder()
+        .chain_id(chain.chain_id().pack())
+        .from_id(deployer_id.pack())
+        .to_id(polyjuice_account.id.pack())
+        .nonce(0u32.pack())
+        .args(deploy_args.pack())
+        .build();
+
+    let deploy_tx = deployer_wallet.sign_polyjuice_tx(&state, raw_tx).unwrap();
+    let deploy_tx_hash: H256 = deploy_tx.hash().into();
+
+    mem_pool_state.store(snap.into());
+    {
+        let mut mem_pool = chain.mem_pool().await;
+        mem_pool.push_transaction(deploy_tx).await.unwrap();
+    }
+
+    let system_log = PolyjuiceSystemLog::parse_from_tx_hash(&chain, deploy_tx_hash).unwrap();
+    assert_eq!(system_log.status_code, 0);
+
+    let snap = mem_pool_state.load();
+    let state = snap.state().unwrap();
+    let erc20_contract_account_id = system_log.contract_account_id(&state).unwrap();
+
+    let balance_args = SudtErc20ArgsBuilder::balance_of(test_wallet.reg_address()).finish();
+    let raw_tx = RawL2Transaction::new_builder()
+        .chain_id(chain.chain_id().pack())
+        .from_id(test_account_id.pack())
+        .to_id(erc20_contract_account_id.pack())
+        .nonce(0u32.pack())
+        .args(balance_args.pack())
+        .build();
+
+    // Override sender's balance and nonce
+    let override_balance: U256 = 424242u128.into();
+    let overrides = vec![AccountOverride {
+        account_id: test_account_id.into(),
+        nonce: Some(7u32.into()),
+        balances: vec![BalanceOverride {
+            sudt_id: CKB_SUDT_ACCOUNT_ID.into(),
+            balance: override_balance,
+        }],
+        ..Default::default()
+    }];
+    let run_result = rpc_server
+        .execute_raw_l2transaction_with_overrides(&raw_tx, None, None, &overrides)
+        .await
+        .unwrap();
+    assert_eq!(
+        override_balance,
+        U256::from_big_endian(run_result.return_data.as_bytes())
+    );
+
+    // Overrides are never written to mem pool state
+    let run_result = rpc_server
+        .execute_raw_l2transaction(&raw_tx, None, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        test_balance,
+        U256::from_big_endian(run_result.return_data.as_bytes())
+    );
+    let snap = mem_pool_state.load();
+    let state = snap.state().unwrap();
+    assert_eq!(state.get_nonce(test_account_id).unwrap(), 0);
+
+    // Unknown account
+    let account_count = state.get_account_count().unwrap();
+    let overrides = vec![AccountOverride {
+        account_id: (account_count + 1).into(),
+        nonce: Some(1u32.into()),
+        ..Default::default()
+    }];
+    let err = rpc_server
+        .execute_raw_l2transaction_with_overrides(&raw_tx, None, None, &overrides)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("-32602"));
+    assert!(err.to_string().contains("invalid override account_id"));
+
+    // Invalid script
+    let overrides = vec![AccountOverride {
+        account_id: test_account_id.into(),
+        script: Some(
+            test_wallet
+                .account_script()
+                .to_owned()
+                .as_builder()
+                .hash_type(ScriptHashType::Data.into())
+                .build()
+                .into(),
+        ),
+        ..Default::default()
+    }];
+    let err = rpc_server
+        .execute_raw_l2transaction_with_overrides(&raw_tx, None, None, &overrides)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("-32602"));
+    assert!(err.to_string().contains("invalid override script"));
+
+    // Unknown sUDT
+    let overrides = vec![AccountOverride {
+        account_id: test_account_id.into(),
+        balances: vec![BalanceOverride {
+            sudt_id: (account_count + 1).into(),
+            balance: override_balance,
+        }],
+        ..Default::default()
+    }];
+    let err = rpc_server
+        .execute_raw_l2transaction_with_overrides(&raw_tx, None, None, &overrides)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("-32602"));
+    assert!(err.to_string().contains("invalid override balances"));
+
+    // Too many overrides
+    let overrides = vec![
+        AccountOverride {
+            account_id: test_account_id.into(),
+            ..Default::default()
+        };
+        33
+    ];
+    let err = rpc_server
+        .execute_raw_l2transaction_with_overrides(&raw_tx, None, None, &overrides)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("too many account overrides"));
+}
@@ -0,0 +1,176 @@
+use ckb_types::prelude::{Builder, Entity};
+use gw_block_producer::state_pruner::StatePruner;
+use gw_common::{
+    builtins::{CKB_SUDT_ACCOUNT_ID, ETH_REGISTRY_ACCOUNT_ID},
+    h256_ext::H256Ext,
+    registry_address::RegistryAddress,
+    smt::Blake2bHasher,
+    sparse_merkle_tree::CompiledMerkleProof,
+    state::{
+        build_account_field_key, build_account_key, build_sudt_key, State, GW_ACCOUNT_NONCE_TYPE,
+        GW_ACCOUNT_SCRIPT_HASH_TYPE, SUDT_KEY_FLAG_BALANCE, SUDT_TOTAL_SUPPLY_KEY,
+    },
+    H256,
+};
+use gw_jsonrpc_types::{
+    ckb_jsonrpc_types::JsonBytes,
+    godwoken::{AccountProof, BalanceKey, ProofRequest},
+};
+use gw_store::state::state_db::StateContext;
+use gw_types::{
+    bytes::Bytes,
+    packed::{AccountMerkleState, DepositRequest, Script},
+    prelude::{Pack, Unpack},
+};
+
+use crate::testing_tool::{chain::TestChain, eth_wallet::EthWallet, rpc_server::RPCServer};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_get_proof() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let mut chain = TestChain::setup(rollup_type_script).await;
+    let rpc_server = RPCServer::build(&chain, None).await.unwrap();
+
+    chain.produce_block(vec![], vec![]).await.unwrap();
+    chain.produce_block(vec![], vec![]).await.unwrap();
+    let block = chain.last_valid_block();
+    let block_number: u64 = block.raw().number().unpack();
+    let block_producer = {
+        let address: Bytes = block.raw().block_producer().unpack();
+        RegistryAddress::from_slice(&address).unwrap()
+    };
+
+    let total_supply_key = JsonBytes::from_vec(SUDT_TOTAL_SUPPLY_KEY.to_vec());
+    let request = ProofRequest {
+        account_id: CKB_SUDT_ACCOUNT_ID.into(),
+        // Duplicated keys are allowed
+        storage_keys: vec![total_supply_key.clone(), total_supply_key],
+        balances: vec![BalanceKey {
+            sudt_id: CKB_SUDT_ACCOUNT_ID.into(),
+            registry_address: JsonBytes::from_vec(block_producer.to_bytes()),
+        }],
+        block_number: Some(block_number.into()),
+        block_hash: None,
+    };
+    let proof = rpc_server.get_proof(&request).await.unwrap();
+
+    let post_account = block.raw().post_account();
+    assert_eq!(proof.block_hash, block.hash().into());
+    assert_eq!(proof.block_number.value(), block_number);
+    assert_eq!(
+        AccountMerkleState::from(proof.account.clone()).as_slice(),
+        post_account.as_slice()
+    );
+    assert_eq!(proof.storage.len(), 2);
+    assert_eq!(proof.balances.len(), 1);
+
+    // Proved values are the same as state
+    let db = chain.store().begin_transaction();
+    let tree = db.state_tree(StateContext::ReadOnly).unwrap();
+    let script_hash: H256 = proof.script_hash.0.into();
+    let total_supply: H256 = proof.storage[0].value.0.into();
+    let balance: H256 = proof.balances[0].value.0.into();
+    assert_eq!(
+        proof.nonce.value(),
+        tree.get_nonce(CKB_SUDT_ACCOUNT_ID).unwrap()
+    );
+    assert_eq!(
+        script_hash,
+        tree.get_script_hash(CKB_SUDT_ACCOUNT_ID).unwrap()
+    );
+    assert_eq!(
+        total_supply.to_u256(),
+        tree.get_sudt_total_supply(CKB_SUDT_ACCOUNT_ID).unwrap()
+    );
+    assert_eq!(
+        balance.to_u256(),
+        tree.get_sudt_balance(CKB_SUDT_ACCOUNT_ID, &block_producer)
+            .unwrap()
+    );
+
+    // Verify proof against the block's post account state
+    let leaves = vec![
+        (
+            build_account_field_key(CKB_SUDT_ACCOUNT_ID, GW_ACCOUNT_NONCE_TYPE),
+            H256::from_u32(proof.nonce.value()),
+        ),
+        (
+            build_account_field_key(CKB_SUDT_ACCOUNT_ID, GW_ACCOUNT_SCRIPT_HASH_TYPE),
+            script_hash,
+        ),
+        (
+            build_account_key(CKB_SUDT_ACCOUNT_ID, &SUDT_TOTAL_SUPPLY_KEY),
+            total_supply,
+        ),
+        (
+            build_account_key(
+                CKB_SUDT_ACCOUNT_ID,
+                &build_sudt_key(SUDT_KEY_FLAG_BALANCE, &block_producer),
+            ),
+            balance,
+        ),
+    ];
+    let root: H256 = post_account.merkle_root().unpack();
+    let compiled_proof = CompiledMerkleProof(proof.proof.clone().into_bytes().to_vec());
+    assert!(compiled_proof
+        .verify::<Blake2bHasher>(&root, leaves.clone())
+        .unwrap());
+
+    // Tampered value
+    let mut tampered_leaves = leaves.clone();
+    tampered_leaves[0].1 = H256::from_u32(proof.nonce.value() + 1);
+    assert!(!compiled_proof
+        .verify::<Blake2bHasher>(&root, tampered_leaves)
+        .unwrap());
+
+    // Proof of a history block is the same as the proof generated at that time
+    let test_wallet = EthWallet::random(chain.rollup_type_hash());
+    let deposit = DepositRequest::new_builder()
+        .capacity((1000 * 10u64.pow(8)).pack())
+        .sudt_script_hash(H256::zero().pack())
+        .amount(0.pack())
+        .script(test_wallet.account_script().to_owned())
+        .registry_id(ETH_REGISTRY_ACCOUNT_ID.pack())
+        .build();
+    chain.produce_block(vec![deposit], vec![]).await.unwrap();
+    let tip_request = ProofRequest {
+        block_number: None,
+        ..request.clone()
+    };
+    let tip_proof = rpc_server.get_proof(&tip_request).await.unwrap();
+    assert_eq!(tip_proof.block_number.value(), block_number + 1);
+    assert_ne!(tip_proof.storage[0].value, proof.storage[0].value);
+
+    let history_request = ProofRequest {
+        block_number: None,
+        block_hash: Some(block.hash().into()),
+        ..request.clone()
+    };
+    let history_proof = rpc_server.get_proof(&history_request).await.unwrap();
+    let compiled_proof = CompiledMerkleProof(history_proof.proof.clone().into_bytes().to_vec());
+    assert!(compiled_proof
+        .verify::<Blake2bHasher>(&root, leaves)
+        .unwrap());
+    let history_proof = AccountProof {
+        proof: proof.proof.clone(),
+        ..history_proof
+    };
+    assert_eq!(history_proof, proof);
+
+    // Mismatched block number and hash
+    let mismatched_request = ProofRequest {
+        block_number: Some((block_number + 1).into()),
+        ..history_request
+    };
+    let err = rpc_server.get_proof(&mismatched_request).await.unwrap_err();
+    assert!(err.to_string().contains("mismatch"));
+
+    // Keep the last block
+    chain.produce_block(vec![], vec![]).await.unwrap();
+    let pruner = StatePruner::new(chain.store().clone(), 1, 0);
+    pruner.prune().unwrap();
+    let err = rpc_server.get_proof(&request).await.unwrap_err();
+    assert!(err.to_string().contains("state pruned"));
+}
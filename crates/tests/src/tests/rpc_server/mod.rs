@@ -1,3 +1,11 @@
+pub mod admin_create_backup;
+pub mod debug_profile_raw_l2transaction;
+pub mod debug_trace_transaction;
+pub mod estimate_cycles;
 pub mod execute_l2transaction;
 pub mod execute_raw_l2transaction;
+pub mod execute_state_overrides;
+pub mod get_proof;
+pub mod request_limits;
+pub mod state_pruned;
 pub mod submit_l2transaction;
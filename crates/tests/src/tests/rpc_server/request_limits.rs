@@ -0,0 +1,157 @@
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use gw_block_producer::test_mode_control::TestModeControl;
+use gw_config::{Config, RPCMethodRateLimitConfig};
+use gw_dynamic_config::manager::DynamicConfigManager;
+use gw_generator::ArcSwap;
+use gw_rpc_server::{
+    registry::{Registry, RegistryArgs},
+    server::start_jsonrpc_server,
+};
+use gw_types::packed::Script;
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, mpsc};
+
+use crate::testing_tool::{chain::TestChain, rpc_server::RPCServer};
+
+const INVALID_REQUEST: i64 = -32600;
+const PARSE_ERROR: i64 = -32700;
+const RATE_LIMIT_ERR_CODE: i64 = -32603;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_batch_size_limit() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let chain = TestChain::setup(Script::default()).await;
+    let mut args =
+        RPCServer::default_registry_args(&chain.inner, chain.rollup_type_script.clone(), None);
+    args.server_config.request_limits.max_batch_size = 2;
+    let (addr, _shutdown) = start_server(args).await;
+
+    let call = |id: u64| json!({"jsonrpc": "2.0", "id": id, "method": "gw_get_tip_block_hash"});
+    let batch = json!([call(1), call(2)]);
+    let resp = post(addr, batch.to_string(), &[]).await;
+    assert_eq!(resp.as_array().map(Vec::len), Some(2));
+
+    // A malformed call is counted too
+    let malformed = json!({"jsonrpc": "2.0", "id": 3});
+    let batch = json!([call(1), call(2), malformed]);
+    let resp = post(addr, batch.to_string(), &[]).await;
+    assert_eq!(error_code(&resp), Some(INVALID_REQUEST));
+    assert_eq!(resp["error"]["message"], "Batch is too large");
+
+    // So are elements which aren't objects, and params are skipped
+    let params = json!([{"nested": [1, 2, {"deep": null}]}]);
+    let call_with_params =
+        json!({"jsonrpc": "2.0", "id": 4, "method": "gw_ping", "params": params});
+    let batch = json!([call_with_params, 1, "call"]);
+    let resp = post(addr, batch.to_string(), &[]).await;
+    assert_eq!(error_code(&resp), Some(INVALID_REQUEST));
+    assert_eq!(resp["error"]["message"], "Batch is too large");
+
+    let resp = post(addr, "[{\"jsonrpc\": \"2.0\",".to_string(), &[]).await;
+    assert_eq!(error_code(&resp), Some(PARSE_ERROR));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_method_rate_limit() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let chain = TestChain::setup(Script::default()).await;
+    let mut args =
+        RPCServer::default_registry_args(&chain.inner, chain.rollup_type_script.clone(), None);
+    let mut config = Config::default();
+    config.rpc_server.method_rate_limit = Some(RPCMethodRateLimitConfig {
+        window_secs: 600,
+        max_cost_per_window: 2,
+        method_costs: Default::default(),
+        lru_size: NonZeroUsize::new(10).unwrap(),
+        client_ip_header: Some("X-Forwarded-For".to_string()),
+        trusted_proxies: Vec::new(),
+        api_key_header: None,
+        api_key_quotas: Default::default(),
+    });
+    let manager = DynamicConfigManager::create(config);
+    args.dynamic_config_manager = Arc::new(ArcSwap::from_pointee(manager));
+    let (addr, _shutdown) = start_server(args).await;
+
+    // Malformed calls are counted
+    let malformed = |id: u64| json!({"jsonrpc": "2.0", "id": id});
+    let batch = json!([malformed(1), malformed(2), malformed(3)]);
+    let resp = post(addr, batch.to_string(), &[]).await;
+    let codes: Vec<_> = resp.as_array().unwrap().iter().map(error_code).collect();
+    assert_eq!(codes, vec![Some(RATE_LIMIT_ERR_CODE); 3]);
+
+    let call = |id: u64| json!({"jsonrpc": "2.0", "id": id, "method": "gw_get_tip_block_hash"});
+    let batch = json!([call(1), call(2)]);
+    let resp = post(addr, batch.to_string(), &[]).await;
+    assert!(resp
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|r| r.get("result").is_some()));
+
+    // The header is ignored since the peer isn't a trusted proxy
+    let header = ("X-Forwarded-For", "10.0.0.1");
+    let resp = post(addr, call(3).to_string(), &[header]).await;
+    assert_eq!(error_code(&resp), Some(RATE_LIMIT_ERR_CODE));
+}
+
+fn error_code(resp: &Value) -> Option<i64> {
+    resp.get("error")?.get("code")?.as_i64()
+}
+
+// Dropping the returned sender shuts down the server
+async fn start_server(args: RegistryArgs<TestModeControl>) -> (SocketAddr, broadcast::Sender<()>) {
+    let addr = {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap()
+    };
+    let registry = Registry::create(args).await;
+    let (shutdown_send, _shutdown_recv) = mpsc::channel(1);
+    let (sub_shutdown_send, sub_shutdown) = broadcast::channel(1);
+    tokio::spawn(start_jsonrpc_server(
+        addr,
+        registry,
+        shutdown_send,
+        sub_shutdown,
+    ));
+
+    for _ in 0..50 {
+        if TcpStream::connect(addr).is_ok() {
+            return (addr, sub_shutdown_send);
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("rpc server isn't started");
+}
+
+async fn post(addr: SocketAddr, body: String, headers: &[(&str, &str)]) -> Value {
+    let headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}: {}\r\n", name, value))
+        .collect();
+    let request = format!(
+        "POST / HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n{}",
+        addr,
+        body.len(),
+        headers,
+        body
+    );
+    let response = tokio::task::spawn_blocking(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    })
+    .await
+    .unwrap();
+
+    let (_headers, body) = response.split_once("\r\n\r\n").expect("http response");
+    serde_json::from_str(body).unwrap()
+}
@@ -0,0 +1,40 @@
+use gw_block_producer::state_pruner::StatePruner;
+use gw_common::builtins::RESERVED_ACCOUNT_ID;
+use gw_store::traits::chain_store::ChainStore;
+use gw_types::packed::Script;
+
+use crate::testing_tool::{chain::TestChain, rpc_server::RPCServer};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_state_pruned() {
+    let _ = env_logger::builder().is_test(true).try_init();
+
+    let rollup_type_script = Script::default();
+    let mut chain = TestChain::setup(rollup_type_script).await;
+    let rpc_server = RPCServer::build(&chain, None).await.unwrap();
+    for _ in 0..3 {
+        chain.produce_block(vec![], vec![]).await.unwrap();
+    }
+
+    // Keep the last block
+    let pruner = StatePruner::new(chain.store().clone(), 1, 0);
+    assert_eq!(pruner.prune().unwrap(), 2);
+    assert_eq!(pruner.prune().unwrap(), 0);
+    let db = chain.store().get_snapshot();
+    assert_eq!(db.get_block_state_record_pruned_number().unwrap(), 2);
+
+    let err = rpc_server
+        .get_nonce(RESERVED_ACCOUNT_ID, Some(1))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("-32010"));
+    assert!(err.to_string().contains("state pruned"));
+
+    for block_number in [2, 3] {
+        let nonce = rpc_server
+            .get_nonce(RESERVED_ACCOUNT_ID, Some(block_number))
+            .await
+            .unwrap();
+        assert_eq!(nonce, 0);
+    }
+}
@@ -190,6 +190,7 @@ pub async fn generate_node_config(args: GenerateNodeConfigArgs<'_>) -> Result<Co
         options: HashMap::new(),
         options_file: None,
         cache_size: None,
+        state_history: Default::default(),
     };
     let genesis_committed_info = L2BlockCommittedInfo {
         block_hash,
@@ -261,6 +262,7 @@ pub async fn generate_node_config(args: GenerateNodeConfigArgs<'_>) -> Result<Co
         dynamic_config: Default::default(),
         p2p_network_config,
         contract_log_config: Default::default(),
+        metrics: None,
     };
 
     Ok(config)
@@ -3,9 +3,9 @@ use ckb_jsonrpc_types::Script;
 use ckb_types::H256;
 use gw_common::{builtins::ETH_REGISTRY_ACCOUNT_ID, registry_address::RegistryAddress};
 use gw_jsonrpc_types::{
-    ckb_jsonrpc_types::{JsonBytes, Uint32},
+    ckb_jsonrpc_types::{JsonBytes, Uint32, Uint64},
     debugger::{DumpChallengeTarget, ReprMockTransaction},
-    godwoken::{RunResult, TxReceipt},
+    godwoken::{ExecutionProfile, RunResult, TxReceipt},
 };
 use gw_types::U256;
 use std::{
@@ -146,6 +146,18 @@ impl GodwokenRpcClient {
             .map(Into::into)
     }
 
+    pub async fn debug_profile_raw_l2transaction(
+        &self,
+        raw_l2tx: JsonBytes,
+        sample_interval: Option<u64>,
+    ) -> Result<ExecutionProfile> {
+        let registry_address: Option<JsonBytes> = None;
+        let sample_interval = sample_interval.map(Uint64::from);
+        let params = serde_json::to_value((raw_l2tx, registry_address, sample_interval))?;
+        self.rpc::<ExecutionProfile>("debug_profile_raw_l2transaction", params)
+            .await
+    }
+
     pub async fn get_transaction_receipt(&self, tx_hash: &H256) -> Result<Option<TxReceipt>> {
         let params = serde_json::to_value((tx_hash,))?;
         self.rpc::<Option<TxReceipt>>("get_transaction_receipt", params)
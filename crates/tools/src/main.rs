@@ -13,6 +13,7 @@ pub mod godwoken_rpc;
 mod hasher;
 mod polyjuice;
 mod prepare_scripts;
+mod profile;
 mod report_accounts;
 mod setup;
 mod stat;
@@ -742,6 +743,77 @@ async fn main() -> Result<()> {
                         .help("to eth address"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("polyjuice-profile")
+                .about("Profile a static call of EVM contract, output flamegraph folded stacks")
+                .arg(arg_godwoken_rpc_url.clone())
+                .arg(
+                    Arg::with_name("from")
+                        .short("f")
+                        .long("from")
+                        .takes_value(true)
+                        .required(true)
+                        .help("from address OR from id"),
+                )
+                .arg(
+                    Arg::with_name("gas-limit")
+                        .short("l")
+                        .long("gas-limit")
+                        .takes_value(true)
+                        .required(false)
+                        .default_value("16777216")
+                        .help("gas limit"),
+                )
+                .arg(
+                    Arg::with_name("gas-price")
+                        .short("p")
+                        .long("gas-price")
+                        .takes_value(true)
+                        .required(false)
+                        .default_value("1")
+                        .help("gas price"),
+                )
+                .arg(
+                    Arg::with_name("data")
+                        .short("a")
+                        .long("data")
+                        .takes_value(true)
+                        .required(true)
+                        .help("data"),
+                )
+                .arg(
+                    Arg::with_name("value")
+                        .short("v")
+                        .long("value")
+                        .takes_value(true)
+                        .required(false)
+                        .default_value("0")
+                        .help("value"),
+                )
+                .arg(
+                    Arg::with_name("to-address")
+                        .short("t")
+                        .long("to-address")
+                        .takes_value(true)
+                        .required(true)
+                        .help("to eth address"),
+                )
+                .arg(
+                    Arg::with_name("sample-interval")
+                        .long("sample-interval")
+                        .takes_value(true)
+                        .required(false)
+                        .help("sample the PC once per the cycles, default by the node"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .required(true)
+                        .help("output path of the folded stacks"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("to-script-hash")
                 .about("Eth eoa address to godwoken script hash")
@@ -1407,6 +1479,49 @@ async fn main() -> Result<()> {
                 std::process::exit(-1);
             };
         }
+        ("polyjuice-profile", Some(m)) => {
+            let godwoken_rpc_url = m.value_of("godwoken-rpc-url").unwrap();
+
+            let data = m.value_of("data").unwrap();
+            let from = m.value_of("from").unwrap();
+            let gas_price = m
+                .value_of("gas-price")
+                .unwrap()
+                .parse()
+                .expect("gas price format error");
+            let gas_limit = m
+                .value_of("gas-limit")
+                .unwrap()
+                .parse()
+                .expect("gas limit format error");
+            let value = m
+                .value_of("value")
+                .unwrap()
+                .parse()
+                .expect("value format error");
+            let to_address = m.value_of("to-address").unwrap();
+            let sample_interval = m
+                .value_of("sample-interval")
+                .map(|s| s.parse().expect("sample interval format error"));
+            let output = Path::new(m.value_of("output").unwrap());
+
+            if let Err(err) = profile::polyjuice_profile(
+                godwoken_rpc_url,
+                gas_limit,
+                gas_price,
+                data,
+                value,
+                to_address,
+                from,
+                sample_interval,
+                output,
+            )
+            .await
+            {
+                log::error!("Polyjuice profile error: {}", err);
+                std::process::exit(-1);
+            };
+        }
         ("to-script-hash", Some(m)) => {
             let scripts_deployment_path = Path::new(m.value_of("scripts-deployment-path").unwrap());
             let config_path = Path::new(m.value_of("config-path").unwrap());
@@ -116,10 +116,44 @@ pub async fn polyjuice_call(
     to_address: &str,
     from: &str,
 ) -> Result<()> {
-    let data = GwBytes::from(hex::decode(data.trim_start_matches("0x").as_bytes())?);
-
     let mut godwoken_rpc_client = GodwokenRpcClient::new(godwoken_rpc_url);
 
+    let raw_l2transaction = build_call_raw_l2transaction(
+        &mut godwoken_rpc_client,
+        gas_limit,
+        gas_price,
+        data,
+        value,
+        to_address,
+        from,
+    )
+    .await?;
+
+    log::info!("raw l2 transaction: {}", raw_l2transaction);
+
+    let run_result = godwoken_rpc_client
+        .execute_raw_l2transaction(JsonBytes::from_bytes(raw_l2transaction.as_bytes()))
+        .await?;
+
+    let j = serde_json::to_value(run_result)?;
+    log::info!("run result: {}", serde_json::to_string_pretty(&j).unwrap());
+
+    Ok(())
+}
+
+/// Build an unsigned raw transaction to call the EVM contract.
+#[allow(clippy::too_many_arguments)]
+pub async fn build_call_raw_l2transaction(
+    godwoken_rpc_client: &mut GodwokenRpcClient,
+    gas_limit: u64,
+    gas_price: u128,
+    data: &str,
+    value: u128,
+    to_address: &str,
+    from: &str,
+) -> Result<RawL2Transaction> {
+    let data = GwBytes::from(hex::decode(data.trim_start_matches("0x").as_bytes())?);
+
     let to_address_str = to_address;
     assert_eq!(to_address.len(), 20);
     let to_id = {
@@ -136,7 +170,7 @@ pub async fn polyjuice_call(
     };
     let to_id = to_id.expect("to id not found!");
 
-    let from_script_hash = parse_account_from_str(&mut godwoken_rpc_client, from).await?;
+    let from_script_hash = parse_account_from_str(godwoken_rpc_client, from).await?;
     let from_id = godwoken_rpc_client
         .get_account_id_by_script_hash(from_script_hash)
         .await?;
@@ -154,16 +188,7 @@ pub async fn polyjuice_call(
         .args(args.pack())
         .build();
 
-    log::info!("raw l2 transaction: {}", raw_l2transaction);
-
-    let run_result = godwoken_rpc_client
-        .execute_raw_l2transaction(JsonBytes::from_bytes(raw_l2transaction.as_bytes()))
-        .await?;
-
-    let j = serde_json::to_value(run_result)?;
-    log::info!("run result: {}", serde_json::to_string_pretty(&j).unwrap());
-
-    Ok(())
+    Ok(raw_l2transaction)
 }
 
 #[allow(clippy::too_many_arguments)]
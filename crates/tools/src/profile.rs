@@ -0,0 +1,105 @@
+use anyhow::Result;
+use ckb_jsonrpc_types::JsonBytes;
+use ckb_types::prelude::Entity;
+use gw_jsonrpc_types::godwoken::ExecutionProfile;
+use std::{fmt::Write as _, path::Path};
+
+use crate::{godwoken_rpc::GodwokenRpcClient, polyjuice::build_call_raw_l2transaction};
+
+#[allow(clippy::too_many_arguments)]
+pub async fn polyjuice_profile(
+    godwoken_rpc_url: &str,
+    gas_limit: u64,
+    gas_price: u128,
+    data: &str,
+    value: u128,
+    to_address: &str,
+    from: &str,
+    sample_interval: Option<u64>,
+    output: &Path,
+) -> Result<()> {
+    let mut godwoken_rpc_client = GodwokenRpcClient::new(godwoken_rpc_url);
+
+    let raw_l2transaction = build_call_raw_l2transaction(
+        &mut godwoken_rpc_client,
+        gas_limit,
+        gas_price,
+        data,
+        value,
+        to_address,
+        from,
+    )
+    .await?;
+
+    log::info!("raw l2 transaction: {}", raw_l2transaction);
+
+    let profile = godwoken_rpc_client
+        .debug_profile_raw_l2transaction(
+            JsonBytes::from_bytes(raw_l2transaction.as_bytes()),
+            sample_interval,
+        )
+        .await?;
+
+    log::info!(
+        "exit code: {}, used cycles: {}",
+        profile.exit_code.value(),
+        profile.used_cycles.value()
+    );
+    log::info!("syscalls:\n{}", format_syscalls(&profile));
+
+    std::fs::write(output, to_folded_stacks(&profile))?;
+    log::info!("flamegraph folded stacks written to {:?}", output);
+
+    Ok(())
+}
+
+fn syscall_frame(code: u64, name: &Option<String>) -> String {
+    match name {
+        Some(name) => name.to_owned(),
+        None => format!("SYSCALL_{}", code),
+    }
+}
+
+fn format_syscalls(profile: &ExecutionProfile) -> String {
+    let mut table = format!(
+        "{:<28} {:>10} {:>12} {:>12} {:>12}\n",
+        "syscall", "count", "bytes", "cycles", "elapsed(us)"
+    );
+    for syscall in profile.syscalls.iter() {
+        let _ = writeln!(
+            table,
+            "{:<28} {:>10} {:>12} {:>12} {:>12}",
+            syscall_frame(syscall.code.value(), &syscall.name),
+            syscall.count.value(),
+            syscall.bytes.value(),
+            syscall.cycles.value(),
+            syscall.elapsed_nanos.value() / 1000,
+        );
+    }
+    table
+}
+
+/// Collapsed stacks accepted by `flamegraph.pl` and `inferno-flamegraph`,
+/// weighted by cycles. PC samples are grouped under `vm`, cycles of the
+/// syscalls are grouped under `syscalls` by syscall name.
+fn to_folded_stacks(profile: &ExecutionProfile) -> String {
+    let sample_interval = profile.sample_interval.value();
+    let mut folded = String::new();
+    for syscall in profile.syscalls.iter() {
+        let _ = writeln!(
+            folded,
+            "tx;syscalls;{} {}",
+            syscall_frame(syscall.code.value(), &syscall.name),
+            syscall.cycles.value()
+        );
+    }
+    for sample in profile.pc_samples.iter() {
+        let _ = writeln!(
+            folded,
+            "tx;vm;{:#x} {}",
+            sample.pc.value(),
+            sample.count.value().saturating_mul(sample_interval)
+        );
+    }
+    folded
+}
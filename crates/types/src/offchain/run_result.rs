@@ -2,7 +2,7 @@ use crate::bytes::Bytes;
 use crate::packed::{CellOutput, LogItem, Script};
 use crate::prelude::*;
 use sparse_merkle_tree::H256;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct RecoverAccount {
@@ -32,6 +32,8 @@ pub struct RunResult {
     pub used_cycles: u64,
     pub exit_code: i8,
     pub write: RunResultWriteState,
+    // only collected in profiling mode
+    pub profile: Option<ExecutionProfile>,
 }
 
 impl RunResult {
@@ -41,6 +43,37 @@ impl RunResult {
     }
 }
 
+/// A syscall made by the backend, recorded in call order when tracing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyscallTrace {
+    pub code: u64,
+    // cycles consumed before the syscall
+    pub cycles: u64,
+}
+
+/// Accounting of a syscall made by the backend
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyscallProfile {
+    pub count: u64,
+    // bytes copied between VM memory and the host
+    pub bytes: u64,
+    // cycles from entering the syscall to returning to the backend
+    pub cycles: u64,
+    // host time spent in the syscall
+    pub elapsed_nanos: u64,
+}
+
+/// Collected when a backend is executed in profiling mode
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecutionProfile {
+    // syscall code -> accounting
+    pub syscalls: BTreeMap<u64, SyscallProfile>,
+    // PC is sampled once per `sample_interval` cycles
+    pub sample_interval: u64,
+    // PC -> number of samples
+    pub pc_samples: BTreeMap<u64, u64>,
+}
+
 impl CellOutput {
     pub fn occupied_capacity(&self, data_capacity: usize) -> ckb_types::core::CapacityResult<u64> {
         let output = ckb_types::packed::CellOutput::new_unchecked(self.as_bytes());
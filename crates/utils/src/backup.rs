@@ -0,0 +1,164 @@
+//! Online backup
+//!
+//! A backup is a RocksDB checkpoint taken while the node runs, together with
+//! a manifest describing it.
+//!
+//! ```text
+//! <backup dir>/db             checkpoint
+//! <backup dir>/manifest.json  manifest, written after the checkpoint
+//! ```
+//!
+//! The manifest is read from the checkpoint itself, so it always matches the
+//! checkpoint content no matter how many blocks are produced meanwhile. A
+//! backup without manifest is incomplete.
+
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail, Context, Result};
+use ckb_fixed_hash::H256 as JsonH256;
+use gw_common::H256;
+use gw_db::migrate::readonly_db_version;
+use gw_db::read_only_db::ReadOnlyDB;
+use gw_db::schema::COLUMNS;
+use gw_store::migrate::init_migration_factory;
+use gw_store::readonly::StoreReadonly;
+use gw_store::traits::chain_store::ChainStore;
+use gw_store::Store;
+use serde::{Deserialize, Serialize};
+
+pub const BACKUP_MANIFEST_VERSION: u32 = 1;
+pub const BACKUP_DB_DIR: &str = "db";
+pub const BACKUP_MANIFEST_FILE: &str = "manifest.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub version: u32,
+    pub rollup_type_hash: JsonH256,
+    pub tip_block_hash: JsonH256,
+    pub tip_block_number: u64,
+    pub db_version: String,
+    /// Unix timestamp in seconds
+    pub created_at: u64,
+}
+
+/// Create a backup of a running store into `to`, `to` must not exist or be empty
+pub fn create_backup(store: &Store, to: &Path) -> Result<BackupManifest> {
+    ensure_empty_dir(to)?;
+    fs::create_dir_all(to)?;
+
+    let db_path = to.join(BACKUP_DB_DIR);
+    store
+        .create_checkpoint(&db_path)
+        .with_context(|| format!("create checkpoint at {}", db_path.display()))?;
+
+    let created_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let manifest = read_checkpoint(&db_path, created_at)?;
+
+    // Write manifest last, its presence marks a complete backup
+    let manifest_path = to.join(BACKUP_MANIFEST_FILE);
+    let tmp_path = manifest_path.with_extension("json.tmp");
+    fs::write(&tmp_path, serde_json::to_vec_pretty(&manifest)?)?;
+    fs::rename(&tmp_path, &manifest_path)?;
+
+    Ok(manifest)
+}
+
+pub fn read_backup_manifest(from: &Path) -> Result<BackupManifest> {
+    let manifest_path = from.join(BACKUP_MANIFEST_FILE);
+    let content = fs::read(&manifest_path)
+        .with_context(|| format!("read backup manifest {}", manifest_path.display()))?;
+    let manifest: BackupManifest = serde_json::from_slice(&content)?;
+    Ok(manifest)
+}
+
+/// Validate backup manifest against the checkpoint, the rollup and this binary
+pub fn verify_backup(from: &Path, rollup_type_hash: &H256) -> Result<BackupManifest> {
+    let manifest = read_backup_manifest(from)?;
+    if manifest.version != BACKUP_MANIFEST_VERSION {
+        bail!("unsupported backup manifest version {}", manifest.version);
+    }
+    if manifest.rollup_type_hash != to_json_h256(rollup_type_hash) {
+        bail!("backup of another rollup {:x}", manifest.rollup_type_hash);
+    }
+    if let Some(binary_version) = init_migration_factory().last_db_version() {
+        if manifest.db_version.as_str() > binary_version {
+            bail!(
+                "backup db version {} is higher than {}",
+                manifest.db_version,
+                binary_version
+            );
+        }
+    }
+
+    let checkpoint = read_checkpoint(&from.join(BACKUP_DB_DIR), manifest.created_at)?;
+    if checkpoint != manifest {
+        bail!(
+            "backup manifest mismatch checkpoint, tip block {} {:x}",
+            checkpoint.tip_block_number,
+            checkpoint.tip_block_hash
+        );
+    }
+
+    Ok(manifest)
+}
+
+/// Restore a verified backup into `store_path`, `store_path` must not exist or be empty.
+/// The backup is kept untouched.
+pub fn restore_backup(
+    from: &Path,
+    store_path: &Path,
+    rollup_type_hash: &H256,
+) -> Result<BackupManifest> {
+    let manifest = verify_backup(from, rollup_type_hash)?;
+    ensure_empty_dir(store_path)?;
+    fs::create_dir_all(store_path)?;
+
+    for entry in fs::read_dir(from.join(BACKUP_DB_DIR))? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            bail!("unexpected checkpoint entry {}", entry.path().display());
+        }
+        fs::copy(entry.path(), store_path.join(entry.file_name()))?;
+    }
+
+    Ok(manifest)
+}
+
+fn read_checkpoint(db_path: &Path, created_at: u64) -> Result<BackupManifest> {
+    let cf_names = (0..COLUMNS).map(|c| c.to_string());
+    let db = ReadOnlyDB::open_cf(db_path, cf_names)?
+        .ok_or_else(|| anyhow!("checkpoint {} not found", db_path.display()))?;
+    let db_version = readonly_db_version(&db)?.unwrap_or_default();
+
+    let store = StoreReadonly::new(db);
+    if !store.has_genesis()? {
+        bail!("empty checkpoint");
+    }
+    let tip_block_hash = store.get_tip_block_hash()?;
+    let tip_block_number = store
+        .get_block_number(&tip_block_hash)?
+        .ok_or_else(|| anyhow!("tip block number not found"))?;
+
+    Ok(BackupManifest {
+        version: BACKUP_MANIFEST_VERSION,
+        rollup_type_hash: to_json_h256(&store.get_chain_id()?),
+        tip_block_hash: to_json_h256(&tip_block_hash),
+        tip_block_number,
+        db_version,
+        created_at,
+    })
+}
+
+fn ensure_empty_dir(path: &Path) -> Result<()> {
+    if path.exists() && fs::read_dir(path)?.next().is_some() {
+        bail!("{} isn't empty", path.display());
+    }
+    Ok(())
+}
+
+fn to_json_h256(hash: &H256) -> JsonH256 {
+    let hash: [u8; 32] = (*hash).into();
+    JsonH256(hash)
+}
@@ -1,9 +1,36 @@
-use std::io::{ErrorKind, Read, Seek, SeekFrom};
+//! Exported blocks
+//!
+//! v1 is a raw stream of molecule `ExportedBlock`s, each one starts with its
+//! full size. v2 packs blocks into zstd compressed chunks with an index at
+//! the end of file, so blocks can be located without reading through them.
+//!
+//! ```text
+//! header: magic (8 bytes) | version (u32) | rollup type hash (32 bytes) | from block (u64) | to block (u64)
+//! chunk: first block (u64) | block count (u32) | raw size (u32) | compressed size (u32) | checksum (32 bytes) | compressed blocks
+//! ...
+//! index: first block (u64) | block count (u32) | chunk offset (u64) per chunk
+//! trailer: index offset (u64) | chunk count (u32) | index checksum (32 bytes) | index magic (8 bytes)
+//! ```
+//!
+//! Integers are little endian. Chunk checksum is the blake2b hash of the chunk
+//! header before it and the compressed blocks, index checksum is the blake2b
+//! hash of the header and index. Raw size of a chunk is less than
+//! `MAX_CHUNK_RAW_SIZE`.
+
+use std::collections::VecDeque;
+use std::convert::TryInto;
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
 
 use anyhow::{anyhow, bail, Result};
-use gw_common::{h256_ext::H256Ext, H256};
+use gw_common::{blake2b::new_blake2b, h256_ext::H256Ext, H256};
+use gw_db::schema::{COLUMN_META, META_IMPORT_BLOCK_CURSOR_KEY};
 use gw_store::{
-    readonly::StoreReadonly, state::state_db::StateContext, traits::chain_store::ChainStore,
+    readonly::StoreReadonly,
+    state::state_db::StateContext,
+    traits::{
+        chain_store::ChainStore,
+        kv_store::{KVStoreRead, KVStoreWrite},
+    },
     transaction::StoreTransaction,
 };
 use gw_types::{
@@ -104,6 +131,11 @@ pub fn read_block_size(reader: &mut impl Read) -> Result<Option<u32>> {
 }
 
 pub fn read_block(reader: &mut impl Read) -> Result<Option<(ExportedBlock, usize)>> {
+    let block = read_packed_block(reader)?;
+    Ok(block.map(|(packed, size)| (packed.into(), size)))
+}
+
+fn read_packed_block(reader: &mut impl Read) -> Result<Option<(packed::ExportedBlock, usize)>> {
     let (full_size_bytes, full_size) = match read_block_size(reader)? {
         Some(size) => (size.to_le_bytes(), size as usize),
         None => return Ok(None),
@@ -118,9 +150,10 @@ pub fn read_block(reader: &mut impl Read) -> Result<Option<(ExportedBlock, usize
 
     packed::ExportedBlockReader::verify(&buf, false)?;
     let packed = packed::ExportedBlock::new_unchecked(Bytes::from(buf));
-    Ok(Some((packed.into(), full_size)))
+    Ok(Some((packed, full_size)))
 }
 
+/// Reader of v1 exported blocks
 pub struct ExportedBlockReader<Reader: Read + Seek> {
     inner: Reader,
 }
@@ -130,6 +163,12 @@ impl<Reader: Read + Seek> ExportedBlockReader<Reader> {
         ExportedBlockReader { inner: reader }
     }
 
+    /// Seek to a block starts at `offset`, e.g. offset saved in `ImportCursor`
+    pub fn seek_block(&mut self, offset: u64) -> Result<()> {
+        self.inner.seek(SeekFrom::Start(offset))?;
+        Ok(())
+    }
+
     pub fn peek_block(&mut self) -> Result<Option<(ExportedBlock, usize)>> {
         let pos = self.inner.stream_position()?;
         let block = read_block(&mut self.inner)?;
@@ -176,6 +215,560 @@ impl<Reader: Read + Seek> Iterator for ExportedBlockReader<Reader> {
     }
 }
 
+pub const EXPORTED_BLOCK_MAGIC: &[u8; 8] = b"GWBLOCK\0";
+pub const EXPORTED_BLOCK_INDEX_MAGIC: &[u8; 8] = b"GWBLKIDX";
+pub const EXPORTED_BLOCK_VERSION: u32 = 2;
+/// Blocks are flushed into a chunk once their raw size reaches this, a
+/// block can't be larger than this
+pub const EXPORTED_BLOCK_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+/// A chunk is flushed before it reaches this, a larger raw size is rejected
+/// before decompression
+pub const MAX_CHUNK_RAW_SIZE: usize = 2 * EXPORTED_BLOCK_CHUNK_SIZE;
+
+const ZSTD_LEVEL: i32 = 3;
+const HEADER_SIZE: u64 = 60;
+const CHUNK_HEADER_SIZE: u64 = 52;
+const INDEX_ENTRY_SIZE: u64 = 20;
+const TRAILER_SIZE: u64 = 52;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportedBlockHeader {
+    pub version: u32,
+    pub rollup_type_hash: H256,
+    pub from_block: u64,
+    pub to_block: u64,
+}
+
+impl ExportedBlockHeader {
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(HEADER_SIZE as usize);
+        buf.extend_from_slice(EXPORTED_BLOCK_MAGIC);
+        buf.extend_from_slice(&self.version.to_le_bytes());
+        buf.extend_from_slice(self.rollup_type_hash.as_slice());
+        buf.extend_from_slice(&self.from_block.to_le_bytes());
+        buf.extend_from_slice(&self.to_block.to_le_bytes());
+        buf
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ChunkIndex {
+    first_block: u64,
+    block_count: u32,
+    offset: u64,
+}
+
+impl ChunkIndex {
+    fn last_block(&self) -> u64 {
+        self.first_block + self.block_count as u64 - 1
+    }
+}
+
+pub struct ExportedBlockWriter<W: Write> {
+    inner: W,
+    header: ExportedBlockHeader,
+    offset: u64,
+    next_block: u64,
+    chunk: Vec<u8>,
+    chunk_first_block: u64,
+    index: Vec<ChunkIndex>,
+}
+
+impl<W: Write> ExportedBlockWriter<W> {
+    pub fn new(
+        mut inner: W,
+        rollup_type_hash: &H256,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Self> {
+        if from_block > to_block {
+            bail!("from {} is bigger than to {}", from_block, to_block);
+        }
+
+        let header = ExportedBlockHeader {
+            version: EXPORTED_BLOCK_VERSION,
+            rollup_type_hash: *rollup_type_hash,
+            from_block,
+            to_block,
+        };
+        inner.write_all(&header.encode())?;
+
+        Ok(ExportedBlockWriter {
+            inner,
+            header,
+            offset: HEADER_SIZE,
+            next_block: from_block,
+            chunk: Vec::new(),
+            chunk_first_block: from_block,
+            index: Vec::new(),
+        })
+    }
+
+    /// Blocks must be written in order, from `from_block` to `to_block`
+    pub fn write_block(&mut self, block: ExportedBlock) -> Result<()> {
+        let block_number = block.block_number();
+        if block_number != self.next_block || block_number > self.header.to_block {
+            bail!(
+                "unexpected block {}, expect block {}",
+                block_number,
+                self.next_block
+            );
+        }
+
+        let packed: packed::ExportedBlock = block.into();
+        if packed.as_slice().len() > EXPORTED_BLOCK_CHUNK_SIZE {
+            bail!("block {} is too large to export", block_number);
+        }
+        self.chunk.extend_from_slice(packed.as_slice());
+        self.next_block += 1;
+
+        if self.chunk.len() >= EXPORTED_BLOCK_CHUNK_SIZE {
+            self.flush_chunk()?;
+        }
+        Ok(())
+    }
+
+    fn flush_chunk(&mut self) -> Result<()> {
+        if self.chunk.is_empty() {
+            return Ok(());
+        }
+
+        let compressed = zstd::bulk::compress(&self.chunk, ZSTD_LEVEL)?;
+
+        let chunk_index = ChunkIndex {
+            first_block: self.chunk_first_block,
+            block_count: (self.next_block - self.chunk_first_block) as u32,
+            offset: self.offset,
+        };
+        let mut chunk_header = Vec::with_capacity(CHUNK_HEADER_SIZE as usize);
+        chunk_header.extend_from_slice(&chunk_index.first_block.to_le_bytes());
+        chunk_header.extend_from_slice(&chunk_index.block_count.to_le_bytes());
+        chunk_header.extend_from_slice(&(self.chunk.len() as u32).to_le_bytes());
+        chunk_header.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        let checksum = chunk_checksum(&chunk_header, &compressed);
+        chunk_header.extend_from_slice(&checksum);
+        self.inner.write_all(&chunk_header)?;
+        self.inner.write_all(&compressed)?;
+
+        self.offset += CHUNK_HEADER_SIZE + compressed.len() as u64;
+        self.index.push(chunk_index);
+        self.chunk.clear();
+        self.chunk_first_block = self.next_block;
+        Ok(())
+    }
+
+    /// Flush the last chunk, then write index and trailer
+    pub fn finish(mut self) -> Result<W> {
+        if self.next_block != self.header.to_block + 1 {
+            bail!(
+                "missing blocks from {} to {}",
+                self.next_block,
+                self.header.to_block
+            );
+        }
+        self.flush_chunk()?;
+
+        let mut index = Vec::with_capacity(self.index.len() * INDEX_ENTRY_SIZE as usize);
+        for chunk_index in self.index.iter() {
+            index.extend_from_slice(&chunk_index.first_block.to_le_bytes());
+            index.extend_from_slice(&chunk_index.block_count.to_le_bytes());
+            index.extend_from_slice(&chunk_index.offset.to_le_bytes());
+        }
+        let index_checksum = index_checksum(&self.header, &index);
+
+        let mut trailer = Vec::with_capacity(TRAILER_SIZE as usize);
+        trailer.extend_from_slice(&self.offset.to_le_bytes());
+        trailer.extend_from_slice(&(self.index.len() as u32).to_le_bytes());
+        trailer.extend_from_slice(&index_checksum);
+        trailer.extend_from_slice(EXPORTED_BLOCK_INDEX_MAGIC);
+
+        self.inner.write_all(&index)?;
+        self.inner.write_all(&trailer)?;
+        self.inner.flush()?;
+
+        Ok(self.inner)
+    }
+}
+
+// `header` is the chunk header before the checksum
+fn chunk_checksum(header: &[u8], compressed: &[u8]) -> [u8; 32] {
+    let mut checksum = [0u8; 32];
+    let mut hasher = new_blake2b();
+    hasher.update(header);
+    hasher.update(compressed);
+    hasher.finalize(&mut checksum);
+    checksum
+}
+
+fn index_checksum(header: &ExportedBlockHeader, index: &[u8]) -> [u8; 32] {
+    let mut checksum = [0u8; 32];
+    let mut hasher = new_blake2b();
+    hasher.update(&header.encode());
+    hasher.update(index);
+    hasher.finalize(&mut checksum);
+    checksum
+}
+
+/// Returns true if reader starts with v2 magic, reader position is restored
+pub fn is_exported_block_v2(reader: &mut (impl Read + Seek)) -> Result<bool> {
+    let pos = reader.stream_position()?;
+    let mut magic = [0u8; 8];
+    let is_v2 = match reader.read_exact(&mut magic) {
+        Ok(()) => &magic == EXPORTED_BLOCK_MAGIC,
+        Err(ref e) if e.kind() == ErrorKind::UnexpectedEof => false,
+        Err(e) => bail!(e),
+    };
+    reader.seek(SeekFrom::Start(pos))?;
+    Ok(is_v2)
+}
+
+/// Reader of v2 exported blocks
+///
+/// Header and index are verified on open, every chunk is verified by its
+/// checksum when it is read. The size returned along with blocks is the
+/// chunk size in file, counted on the first block of each chunk.
+pub struct ExportedBlockReaderV2<Reader: Read + Seek> {
+    inner: Reader,
+    header: ExportedBlockHeader,
+    index: Vec<ChunkIndex>,
+    index_offset: u64,
+    next_chunk: usize,
+    blocks: VecDeque<(u64, packed::ExportedBlock, usize)>,
+}
+
+impl<Reader: Read + Seek> ExportedBlockReaderV2<Reader> {
+    pub fn new(mut inner: Reader) -> Result<Self> {
+        inner.seek(SeekFrom::Start(0))?;
+        let mut buf = [0u8; HEADER_SIZE as usize];
+        read_exact(&mut inner, &mut buf)?;
+        if &buf[..8] != EXPORTED_BLOCK_MAGIC {
+            bail!("not a v2 exported block file");
+        }
+        let version = u32::from_le_bytes(buf[8..12].try_into()?);
+        if version != EXPORTED_BLOCK_VERSION {
+            bail!("unsupported exported block version {}", version);
+        }
+        let header = ExportedBlockHeader {
+            version,
+            rollup_type_hash: h256_from_slice(&buf[12..44]),
+            from_block: u64::from_le_bytes(buf[44..52].try_into()?),
+            to_block: u64::from_le_bytes(buf[52..60].try_into()?),
+        };
+
+        let file_size = inner.seek(SeekFrom::End(0))?;
+        if file_size < HEADER_SIZE + TRAILER_SIZE {
+            bail!("exported block file truncated");
+        }
+        inner.seek(SeekFrom::Start(file_size - TRAILER_SIZE))?;
+        let mut trailer = [0u8; TRAILER_SIZE as usize];
+        read_exact(&mut inner, &mut trailer)?;
+        if &trailer[44..] != EXPORTED_BLOCK_INDEX_MAGIC {
+            bail!("exported block file truncated, index not found");
+        }
+        let index_offset = u64::from_le_bytes(trailer[..8].try_into()?);
+        let chunk_count = u32::from_le_bytes(trailer[8..12].try_into()?) as u64;
+        let index_size = chunk_count * INDEX_ENTRY_SIZE;
+        if index_offset < HEADER_SIZE
+            || file_size.checked_sub(index_size + TRAILER_SIZE) != Some(index_offset)
+        {
+            bail!("exported block index corrupted");
+        }
+
+        inner.seek(SeekFrom::Start(index_offset))?;
+        let mut index_buf = vec![0u8; index_size as usize];
+        read_exact(&mut inner, &mut index_buf)?;
+        if index_checksum(&header, &index_buf) != trailer[12..44] {
+            bail!("exported block index checksum mismatch");
+        }
+
+        let index: Vec<_> = index_buf
+            .chunks_exact(INDEX_ENTRY_SIZE as usize)
+            .map(|entry| ChunkIndex {
+                first_block: u64::from_le_bytes(entry[..8].try_into().expect("first block")),
+                block_count: u32::from_le_bytes(entry[8..12].try_into().expect("block count")),
+                offset: u64::from_le_bytes(entry[12..].try_into().expect("offset")),
+            })
+            .collect();
+
+        // Chunks must cover blocks from `from_block` to `to_block` in order
+        let mut next_block = header.from_block;
+        let mut next_offset = HEADER_SIZE;
+        for chunk_index in index.iter() {
+            if chunk_index.first_block != next_block
+                || chunk_index.block_count == 0
+                || chunk_index.offset < next_offset
+                || chunk_index.offset >= index_offset
+            {
+                bail!("exported block index corrupted");
+            }
+            next_block = chunk_index.last_block() + 1;
+            next_offset = chunk_index.offset + CHUNK_HEADER_SIZE;
+        }
+        if next_block != header.to_block + 1 {
+            bail!("exported block index corrupted");
+        }
+
+        Ok(ExportedBlockReaderV2 {
+            inner,
+            header,
+            index,
+            index_offset,
+            next_chunk: 0,
+            blocks: VecDeque::new(),
+        })
+    }
+
+    pub fn header(&self) -> &ExportedBlockHeader {
+        &self.header
+    }
+
+    fn read_chunk(&mut self) -> Result<bool> {
+        let chunk_index = match self.index.get(self.next_chunk) {
+            Some(chunk_index) => *chunk_index,
+            None => return Ok(false),
+        };
+        self.inner.seek(SeekFrom::Start(chunk_index.offset))?;
+
+        let mut buf = [0u8; CHUNK_HEADER_SIZE as usize];
+        read_exact(&mut self.inner, &mut buf)?;
+        let first_block = u64::from_le_bytes(buf[..8].try_into()?);
+        let block_count = u32::from_le_bytes(buf[8..12].try_into()?);
+        let raw_size = u32::from_le_bytes(buf[12..16].try_into()?) as usize;
+        let compressed_size = u32::from_le_bytes(buf[16..20].try_into()?) as usize;
+        let chunk_size = CHUNK_HEADER_SIZE + compressed_size as u64;
+        if first_block != chunk_index.first_block
+            || block_count != chunk_index.block_count
+            || chunk_size != self.chunk_size(self.next_chunk)
+        {
+            bail!("chunk of block {} corrupted", chunk_index.first_block);
+        }
+        if raw_size >= MAX_CHUNK_RAW_SIZE {
+            bail!(
+                "chunk of block {} raw size {} is too large",
+                first_block,
+                raw_size
+            );
+        }
+
+        let mut compressed = vec![0u8; compressed_size];
+        read_exact(&mut self.inner, &mut compressed)?;
+        if chunk_checksum(&buf[..20], &compressed) != buf[20..] {
+            bail!("chunk of block {} checksum mismatch", first_block);
+        }
+        let raw = zstd::bulk::decompress(&compressed, raw_size)
+            .map_err(|err| anyhow!("chunk of block {} corrupted, {}", first_block, err))?;
+        if raw.len() != raw_size {
+            bail!("chunk of block {} corrupted", first_block);
+        }
+
+        let mut blocks = VecDeque::with_capacity(block_count as usize);
+        let mut reader = raw.as_slice();
+        while let Some((block, _size)) = read_packed_block(&mut reader)? {
+            let block_number: u64 = block.block().raw().number().unpack();
+            if block_number != first_block + blocks.len() as u64 {
+                bail!(
+                    "chunk of block {} unexpected block {}",
+                    first_block,
+                    block_number
+                );
+            }
+            let size = if blocks.is_empty() {
+                chunk_size as usize
+            } else {
+                0
+            };
+            blocks.push_back((block_number, block, size));
+        }
+        if blocks.len() != block_count as usize {
+            bail!("chunk of block {} missing blocks", first_block);
+        }
+
+        self.blocks = blocks;
+        self.next_chunk += 1;
+        Ok(true)
+    }
+
+    pub fn peek_block(&mut self) -> Result<Option<(ExportedBlock, usize)>> {
+        if self.blocks.is_empty() && !self.read_chunk()? {
+            return Ok(None);
+        }
+        let block = self.blocks.front().cloned();
+        Ok(block.map(|(_number, packed, size)| (packed.into(), size)))
+    }
+
+    /// Locate by index, only the chunk of the first unskipped block is read
+    pub fn skip_blocks(&mut self, blocks: u64) -> Result<(u64, u64)> {
+        let next_block = match self.peek_block()? {
+            Some((block, _size)) => block.block_number(),
+            None => return Ok((0, 0)),
+        };
+        let target_block = next_block
+            .saturating_add(blocks)
+            .min(self.header.to_block + 1);
+
+        let mut size = 0;
+        let loaded_to_block = next_block + self.blocks.len() as u64;
+        if target_block >= loaded_to_block {
+            size += self.blocks.drain(..).map(|b| b.2 as u64).sum::<u64>();
+
+            let target_chunk = self
+                .index
+                .partition_point(|chunk_index| chunk_index.last_block() < target_block);
+            for chunk in self.next_chunk..target_chunk {
+                size += self.chunk_size(chunk);
+            }
+            self.next_chunk = target_chunk;
+            self.read_chunk()?;
+        }
+
+        while let Some((number, _block, block_size)) = self.blocks.front() {
+            if *number >= target_block {
+                break;
+            }
+            size += *block_size as u64;
+            self.blocks.pop_front();
+        }
+
+        Ok((target_block - next_block, size))
+    }
+
+    fn chunk_size(&self, chunk: usize) -> u64 {
+        let end = match self.index.get(chunk + 1) {
+            Some(next) => next.offset,
+            None => self.index_offset,
+        };
+        end - self.index[chunk].offset
+    }
+}
+
+impl<Reader: Read + Seek> Iterator for ExportedBlockReaderV2<Reader> {
+    type Item = Result<(ExportedBlock, usize)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.blocks.is_empty() {
+            match self.read_chunk() {
+                Ok(true) => (),
+                Ok(false) => return None,
+                Err(err) => {
+                    // Stop after error
+                    self.next_chunk = self.index.len();
+                    return Some(Err(err));
+                }
+            }
+        }
+        let (_number, packed, size) = self.blocks.pop_front()?;
+        Some(Ok((packed.into(), size)))
+    }
+}
+
+/// Exported block reader of either format, detected by file magic
+pub enum AnyExportedBlockReader<Reader: Read + Seek> {
+    V1(ExportedBlockReader<Reader>),
+    V2(ExportedBlockReaderV2<Reader>),
+}
+
+impl<Reader: Read + Seek> AnyExportedBlockReader<Reader> {
+    pub fn new(mut reader: Reader) -> Result<Self> {
+        if is_exported_block_v2(&mut reader)? {
+            let reader = ExportedBlockReaderV2::new(reader)?;
+            Ok(AnyExportedBlockReader::V2(reader))
+        } else {
+            Ok(AnyExportedBlockReader::V1(ExportedBlockReader::new(reader)))
+        }
+    }
+
+    /// v1 file has no header
+    pub fn header(&self) -> Option<&ExportedBlockHeader> {
+        match self {
+            AnyExportedBlockReader::V1(_) => None,
+            AnyExportedBlockReader::V2(reader) => Some(reader.header()),
+        }
+    }
+
+    pub fn peek_block(&mut self) -> Result<Option<(ExportedBlock, usize)>> {
+        match self {
+            AnyExportedBlockReader::V1(reader) => reader.peek_block(),
+            AnyExportedBlockReader::V2(reader) => reader.peek_block(),
+        }
+    }
+
+    pub fn skip_blocks(&mut self, blocks: u64) -> Result<(u64, u64)> {
+        match self {
+            AnyExportedBlockReader::V1(reader) => reader.skip_blocks(blocks),
+            AnyExportedBlockReader::V2(reader) => reader.skip_blocks(blocks),
+        }
+    }
+}
+
+impl<Reader: Read + Seek> Iterator for AnyExportedBlockReader<Reader> {
+    type Item = Result<(ExportedBlock, usize)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            AnyExportedBlockReader::V1(reader) => reader.next(),
+            AnyExportedBlockReader::V2(reader) => reader.next(),
+        }
+    }
+}
+
+fn read_exact(reader: &mut impl Read, buf: &mut [u8]) -> Result<()> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(()),
+        Err(ref e) if e.kind() == ErrorKind::UnexpectedEof => {
+            bail!("exported block file truncated")
+        }
+        Err(e) => bail!(e),
+    }
+}
+
+fn h256_from_slice(slice: &[u8]) -> H256 {
+    let mut buf = [0u8; 32];
+    buf.copy_from_slice(slice);
+    buf.into()
+}
+
+/// Import progress, saved in the same db transaction as the imported block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportCursor {
+    pub block_number: u64,
+    pub block_hash: H256,
+    /// Size of source file, a cursor is only valid for the same source
+    pub source_size: u64,
+    /// Offset of the next block in a v1 source file
+    pub source_offset: u64,
+}
+
+impl ImportCursor {
+    const SIZE: usize = 56;
+
+    pub fn load(store: &impl KVStoreRead) -> Result<Option<Self>> {
+        let slice = match store.get(COLUMN_META, META_IMPORT_BLOCK_CURSOR_KEY) {
+            Some(slice) => slice,
+            None => return Ok(None),
+        };
+        if slice.len() != Self::SIZE {
+            bail!("import block cursor corrupted");
+        }
+
+        Ok(Some(ImportCursor {
+            block_number: u64::from_le_bytes(slice[..8].try_into()?),
+            block_hash: h256_from_slice(&slice[8..40]),
+            source_size: u64::from_le_bytes(slice[40..48].try_into()?),
+            source_offset: u64::from_le_bytes(slice[48..].try_into()?),
+        }))
+    }
+
+    pub fn save(&self, tx_db: &StoreTransaction) -> Result<()> {
+        let mut buf = Vec::with_capacity(Self::SIZE);
+        buf.extend_from_slice(&self.block_number.to_le_bytes());
+        buf.extend_from_slice(self.block_hash.as_slice());
+        buf.extend_from_slice(&self.source_size.to_le_bytes());
+        buf.extend_from_slice(&self.source_offset.to_le_bytes());
+        tx_db.insert_raw(COLUMN_META, META_IMPORT_BLOCK_CURSOR_KEY, &buf)?;
+        Ok(())
+    }
+}
+
 pub fn insert_bad_block_hashes(
     tx_db: &StoreTransaction,
     bad_block_hashes_vec: Vec<Vec<H256>>,
@@ -0,0 +1,551 @@
+//! State snapshot
+//!
+//! A state snapshot holds the state of a finalized block, so a node can be
+//! bootstrapped from it instead of replaying blocks from genesis. Blocks after
+//! the snapshot block are synced by `import-block`.
+//!
+//! ```text
+//! magic (8 bytes) | version (u32) | rollup type hash (32 bytes) | block number (u64)
+//! entry: kind (u8) | payload size (u32) | payload
+//! ...
+//! end entry: kind (u8) | payload size (u32) | checksum (32 bytes)
+//! ```
+//!
+//! Integers are little endian. The first entry is always the snapshot block,
+//! the checksum is the blake2b hash of all bytes before it.
+
+use std::io::{ErrorKind, Read, Write};
+
+use anyhow::{anyhow, bail, Result};
+use gw_common::{
+    blake2b::{new_blake2b, Blake2b},
+    smt::SMT,
+    state::State,
+    H256,
+};
+use gw_db::schema::{
+    Col, COLUMN_ACCOUNT_SMT_BRANCH, COLUMN_ACCOUNT_SMT_LEAF, COLUMN_ASSET_SCRIPT,
+    COLUMN_BLOCK_STATE_RECORD, COLUMN_BLOCK_STATE_REVERSE_RECORD, COLUMN_DATA, COLUMN_INDEX,
+    COLUMN_META, COLUMN_REVERTED_BLOCK_SMT_BRANCH, COLUMN_REVERTED_BLOCK_SMT_LEAF,
+    COLUMN_REVERTED_BLOCK_SMT_ROOT, COLUMN_SCRIPT, META_BLOCK_SMT_ROOT_KEY, META_CHAIN_ID_KEY,
+    META_IMPORT_STATE_KEY, META_REVERTED_BLOCK_SMT_ROOT_KEY,
+};
+use gw_store::{
+    readonly::StoreReadonly,
+    state::state_db::{StateContext, StateTree},
+    traits::{chain_store::ChainStore, kv_store::KVStoreWrite},
+    transaction::StoreTransaction,
+    Store,
+};
+use gw_traits::CodeStore;
+use gw_types::{
+    bytes::Bytes,
+    offchain::ExportedBlock,
+    packed,
+    prelude::{Entity, Pack, Reader, Unpack},
+};
+
+use crate::export_block::{check_block_post_state, export_block, insert_bad_block_hashes};
+
+pub const STATE_SNAPSHOT_MAGIC: &[u8; 8] = b"GWSTATE\0";
+pub const STATE_SNAPSHOT_VERSION: u32 = 1;
+
+const ENTRY_BLOCK: u8 = 1;
+const ENTRY_ACCOUNT_LEAF: u8 = 2;
+const ENTRY_SCRIPT: u8 = 3;
+const ENTRY_DATA: u8 = 4;
+const ENTRY_BLOCK_HASH: u8 = 5;
+const ENTRY_REVERTED_BLOCK_HASHES: u8 = 6;
+const ENTRY_ASSET_SCRIPT: u8 = 7;
+const ENTRY_END: u8 = 0xff;
+
+// Entries written in a db transaction during import
+const IMPORT_BATCH_ENTRIES: usize = 10_000;
+
+// Columns written by committed batches of import, they are empty before import
+const IMPORT_COLUMNS: [Col; 11] = [
+    COLUMN_INDEX,
+    COLUMN_ACCOUNT_SMT_BRANCH,
+    COLUMN_ACCOUNT_SMT_LEAF,
+    COLUMN_SCRIPT,
+    COLUMN_DATA,
+    COLUMN_ASSET_SCRIPT,
+    COLUMN_BLOCK_STATE_RECORD,
+    COLUMN_BLOCK_STATE_REVERSE_RECORD,
+    COLUMN_REVERTED_BLOCK_SMT_BRANCH,
+    COLUMN_REVERTED_BLOCK_SMT_LEAF,
+    COLUMN_REVERTED_BLOCK_SMT_ROOT,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateSnapshotHeader {
+    pub version: u32,
+    pub rollup_type_hash: H256,
+    pub block_number: u64,
+}
+
+#[derive(Debug)]
+pub enum StateSnapshotEntry {
+    Block(ExportedBlock),
+    AccountLeaf {
+        key: H256,
+        value: H256,
+    },
+    Script(packed::Script),
+    /// sUDT script referenced by deposits and withdrawals
+    AssetScript(packed::Script),
+    Data {
+        data_hash: H256,
+        data: Bytes,
+    },
+    BlockHash {
+        number: u64,
+        hash: H256,
+    },
+    /// Reverted block hashes in the order they were reverted
+    RevertedBlockHashes(Vec<H256>),
+}
+
+impl StateSnapshotEntry {
+    fn encode(self) -> (u8, Vec<u8>) {
+        match self {
+            StateSnapshotEntry::Block(block) => {
+                let packed: packed::ExportedBlock = block.into();
+                (ENTRY_BLOCK, packed.as_slice().to_vec())
+            }
+            StateSnapshotEntry::AccountLeaf { key, value } => {
+                let mut payload = key.as_slice().to_vec();
+                payload.extend_from_slice(value.as_slice());
+                (ENTRY_ACCOUNT_LEAF, payload)
+            }
+            StateSnapshotEntry::Script(script) => (ENTRY_SCRIPT, script.as_slice().to_vec()),
+            StateSnapshotEntry::AssetScript(script) => {
+                (ENTRY_ASSET_SCRIPT, script.as_slice().to_vec())
+            }
+            StateSnapshotEntry::Data { data_hash, data } => {
+                let mut payload = data_hash.as_slice().to_vec();
+                payload.extend_from_slice(&data);
+                (ENTRY_DATA, payload)
+            }
+            StateSnapshotEntry::BlockHash { number, hash } => {
+                let mut payload = number.to_le_bytes().to_vec();
+                payload.extend_from_slice(hash.as_slice());
+                (ENTRY_BLOCK_HASH, payload)
+            }
+            StateSnapshotEntry::RevertedBlockHashes(hashes) => {
+                let packed: packed::Byte32Vec = hashes.pack();
+                (ENTRY_REVERTED_BLOCK_HASHES, packed.as_slice().to_vec())
+            }
+        }
+    }
+
+    fn decode(kind: u8, payload: Vec<u8>) -> Result<Self> {
+        let entry = match kind {
+            ENTRY_BLOCK => {
+                packed::ExportedBlockReader::verify(&payload, false)?;
+                let packed = packed::ExportedBlock::new_unchecked(Bytes::from(payload));
+                StateSnapshotEntry::Block(packed.into())
+            }
+            ENTRY_ACCOUNT_LEAF if payload.len() == 64 => StateSnapshotEntry::AccountLeaf {
+                key: h256_from_slice(&payload[..32]),
+                value: h256_from_slice(&payload[32..]),
+            },
+            ENTRY_SCRIPT => {
+                packed::ScriptReader::verify(&payload, false)?;
+                StateSnapshotEntry::Script(packed::Script::new_unchecked(Bytes::from(payload)))
+            }
+            ENTRY_ASSET_SCRIPT => {
+                packed::ScriptReader::verify(&payload, false)?;
+                let script = packed::Script::new_unchecked(Bytes::from(payload));
+                StateSnapshotEntry::AssetScript(script)
+            }
+            ENTRY_DATA if payload.len() >= 32 => StateSnapshotEntry::Data {
+                data_hash: h256_from_slice(&payload[..32]),
+                data: Bytes::from(payload[32..].to_vec()),
+            },
+            ENTRY_BLOCK_HASH if payload.len() == 40 => {
+                let mut number = [0u8; 8];
+                number.copy_from_slice(&payload[..8]);
+                StateSnapshotEntry::BlockHash {
+                    number: u64::from_le_bytes(number),
+                    hash: h256_from_slice(&payload[8..]),
+                }
+            }
+            ENTRY_REVERTED_BLOCK_HASHES => {
+                packed::Byte32VecReader::verify(&payload, false)?;
+                let packed = packed::Byte32Vec::new_unchecked(Bytes::from(payload));
+                StateSnapshotEntry::RevertedBlockHashes(packed.unpack())
+            }
+            ENTRY_ACCOUNT_LEAF | ENTRY_DATA | ENTRY_BLOCK_HASH => {
+                bail!("state snapshot entry {} corrupted", kind)
+            }
+            _ => bail!("unknown state snapshot entry {}", kind),
+        };
+        Ok(entry)
+    }
+}
+
+fn h256_from_slice(slice: &[u8]) -> H256 {
+    let mut buf = [0u8; 32];
+    buf.copy_from_slice(slice);
+    buf.into()
+}
+
+pub struct StateSnapshotWriter<W: Write> {
+    inner: W,
+    hasher: Blake2b,
+}
+
+impl<W: Write> StateSnapshotWriter<W> {
+    pub fn new(inner: W, rollup_type_hash: &H256, block_number: u64) -> Result<Self> {
+        let mut writer = StateSnapshotWriter {
+            inner,
+            hasher: new_blake2b(),
+        };
+        writer.write(STATE_SNAPSHOT_MAGIC)?;
+        writer.write(&STATE_SNAPSHOT_VERSION.to_le_bytes())?;
+        writer.write(rollup_type_hash.as_slice())?;
+        writer.write(&block_number.to_le_bytes())?;
+        Ok(writer)
+    }
+
+    fn write(&mut self, buf: &[u8]) -> Result<()> {
+        self.hasher.update(buf);
+        self.inner.write_all(buf)?;
+        Ok(())
+    }
+
+    pub fn write_entry(&mut self, entry: StateSnapshotEntry) -> Result<()> {
+        let (kind, payload) = entry.encode();
+        self.write(&[kind])?;
+        self.write(&(payload.len() as u32).to_le_bytes())?;
+        self.write(&payload)
+    }
+
+    /// Write the end entry with checksum
+    pub fn finish(mut self) -> Result<W> {
+        self.write(&[ENTRY_END])?;
+        self.write(&32u32.to_le_bytes())?;
+
+        let StateSnapshotWriter { mut inner, hasher } = self;
+        let mut checksum = [0u8; 32];
+        hasher.finalize(&mut checksum);
+        inner.write_all(&checksum)?;
+        inner.flush()?;
+        Ok(inner)
+    }
+}
+
+pub struct StateSnapshotReader<R: Read> {
+    inner: R,
+    hasher: Option<Blake2b>,
+    header: StateSnapshotHeader,
+}
+
+impl<R: Read> StateSnapshotReader<R> {
+    pub fn new(inner: R) -> Result<Self> {
+        let mut reader = StateSnapshotReader {
+            inner,
+            hasher: Some(new_blake2b()),
+            header: StateSnapshotHeader {
+                version: 0,
+                rollup_type_hash: H256::zero(),
+                block_number: 0,
+            },
+        };
+
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != STATE_SNAPSHOT_MAGIC {
+            bail!("not a state snapshot");
+        }
+        let mut version = [0u8; 4];
+        reader.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != STATE_SNAPSHOT_VERSION {
+            bail!("unsupported state snapshot version {}", version);
+        }
+        let mut rollup_type_hash = [0u8; 32];
+        reader.read_exact(&mut rollup_type_hash)?;
+        let mut block_number = [0u8; 8];
+        reader.read_exact(&mut block_number)?;
+
+        reader.header = StateSnapshotHeader {
+            version,
+            rollup_type_hash: rollup_type_hash.into(),
+            block_number: u64::from_le_bytes(block_number),
+        };
+        Ok(reader)
+    }
+
+    pub fn header(&self) -> &StateSnapshotHeader {
+        &self.header
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        match self.inner.read_exact(buf) {
+            Ok(()) => {}
+            Err(ref e) if e.kind() == ErrorKind::UnexpectedEof => bail!("state snapshot truncated"),
+            Err(e) => bail!(e),
+        }
+        if let Some(ref mut hasher) = self.hasher {
+            hasher.update(buf);
+        }
+        Ok(())
+    }
+
+    /// Returns `None` after the end entry, whose checksum is verified
+    pub fn read_entry(&mut self) -> Result<Option<StateSnapshotEntry>> {
+        if self.hasher.is_none() {
+            return Ok(None);
+        }
+
+        let mut kind = [0u8; 1];
+        let mut size = [0u8; 4];
+        self.read_exact(&mut kind)?;
+        self.read_exact(&mut size)?;
+        let size = u32::from_le_bytes(size) as usize;
+
+        if ENTRY_END == kind[0] {
+            if size != 32 {
+                bail!("state snapshot end entry corrupted");
+            }
+            let hasher = self.hasher.take().expect("hasher");
+            let mut expected_checksum = [0u8; 32];
+            hasher.finalize(&mut expected_checksum);
+            let mut checksum = [0u8; 32];
+            self.read_exact(&mut checksum)?;
+            if checksum != expected_checksum {
+                bail!("state snapshot checksum mismatch");
+            }
+            if self.inner.read(&mut [0u8; 1])? != 0 {
+                bail!("unexpected data after state snapshot end entry");
+            }
+            return Ok(None);
+        }
+
+        let mut payload = vec![0u8; size];
+        self.read_exact(&mut payload)?;
+        StateSnapshotEntry::decode(kind[0], payload).map(Some)
+    }
+
+    /// Read through the snapshot to verify its checksum
+    pub fn verify(mut self) -> Result<StateSnapshotHeader> {
+        while self.read_entry()?.is_some() {}
+        Ok(self.header)
+    }
+}
+
+impl<R: Read> Iterator for StateSnapshotReader<R> {
+    type Item = Result<StateSnapshotEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_entry().transpose()
+    }
+}
+
+/// Export state of a finalized block
+pub fn export_state<W: Write>(snap: &StoreReadonly, block_number: u64, writer: W) -> Result<W> {
+    let last_finalized_block_number = {
+        let tip_block_hash = snap.get_last_valid_tip_block_hash()?;
+        let tip_global_state = snap
+            .get_block_post_global_state(&tip_block_hash)?
+            .ok_or_else(|| anyhow!("tip block post global state not found"))?;
+        tip_global_state.last_finalized_block_number().unpack()
+    };
+    if block_number > last_finalized_block_number {
+        bail!(
+            "block {} isn't finalized, last finalized block {}",
+            block_number,
+            last_finalized_block_number
+        );
+    }
+    let pruned_number = snap.get_block_state_record_pruned_number()?;
+    if block_number < pruned_number {
+        bail!(
+            "state of block {} is pruned, history state is available from block {}",
+            block_number,
+            pruned_number
+        );
+    }
+
+    let rollup_type_hash = snap.get_chain_id()?;
+    let exported_block = export_block(snap, block_number)?;
+    let reverted_block_root: H256 = exported_block
+        .post_global_state
+        .reverted_block_root()
+        .unpack();
+
+    let mut writer = StateSnapshotWriter::new(writer, &rollup_type_hash, block_number)?;
+    writer.write_entry(StateSnapshotEntry::Block(exported_block))?;
+
+    for (key, value) in snap.iter_history_state(block_number) {
+        writer.write_entry(StateSnapshotEntry::AccountLeaf { key, value })?;
+    }
+    for (_script_hash, script) in snap.iter_scripts() {
+        writer.write_entry(StateSnapshotEntry::Script(script))?;
+    }
+    for script in snap.iter_asset_scripts() {
+        writer.write_entry(StateSnapshotEntry::AssetScript(script))?;
+    }
+    for (data_hash, data) in snap.iter_data() {
+        writer.write_entry(StateSnapshotEntry::Data { data_hash, data })?;
+    }
+
+    // Snapshot block is attached from its block entry
+    for number in 0..block_number {
+        let hash = snap
+            .get_block_hash_by_number(number)?
+            .ok_or_else(|| anyhow!("block {} not found", number))?;
+        writer.write_entry(StateSnapshotEntry::BlockHash { number, hash })?;
+    }
+
+    let mut reverted_block_hashes: Vec<_> = snap
+        .iter_reverted_block_smt_root(reverted_block_root)
+        .map(|(_root, block_hashes)| block_hashes)
+        .collect();
+    reverted_block_hashes.reverse();
+    for block_hashes in reverted_block_hashes {
+        writer.write_entry(StateSnapshotEntry::RevertedBlockHashes(block_hashes))?;
+    }
+
+    writer.finish()
+}
+
+/// Import state snapshot into an empty store, returns the snapshot block number.
+///
+/// Imported account SMT root is verified against the block's post account
+/// merkle state. History state before the snapshot block is unavailable.
+///
+/// Entries are committed in batches, the store is marked until the import is
+/// done. A failed import is cleared, so is an interrupted one by the next
+/// import.
+pub fn import_state<R: Read>(store: &Store, reader: StateSnapshotReader<R>) -> Result<u64> {
+    if store.has_unfinished_state_import() {
+        log::info!("clear unfinished state snapshot import");
+        clear_import(store)?;
+    } else if store.has_genesis()? {
+        bail!("store isn't empty");
+    }
+
+    let db = store.begin_transaction();
+    db.insert_raw(COLUMN_META, META_IMPORT_STATE_KEY, &[])?;
+    db.commit()?;
+
+    import_entries(store, reader).map_err(|err| {
+        if let Err(clear_err) = clear_import(store) {
+            log::error!("clear failed state snapshot import: {}", clear_err);
+        }
+        err
+    })
+}
+
+fn import_entries<R: Read>(store: &Store, mut reader: StateSnapshotReader<R>) -> Result<u64> {
+    let header = reader.header().clone();
+    let block_number = header.block_number;
+    let exported_block = match reader.read_entry()? {
+        Some(StateSnapshotEntry::Block(block)) if block.block_number() == block_number => block,
+        _ => bail!("state snapshot block {} not found", block_number),
+    };
+    let post_account = exported_block.block.raw().post_account();
+    if post_account.as_slice() != exported_block.post_global_state.account().as_slice() {
+        bail!("block {} post account diff from global state", block_number);
+    }
+    let account_count: u32 = post_account.count().unpack();
+
+    let mut account_root = H256::zero();
+    let mut db = store.begin_transaction();
+    db.setup_chain_id(header.rollup_type_hash)?;
+    db.set_block_smt_root(H256::zero())?;
+    db.set_reverted_block_smt_root(H256::zero())?;
+
+    let mut uncommitted = 0;
+    while let Some(entry) = reader.read_entry()? {
+        match entry {
+            StateSnapshotEntry::Block(_) => bail!("duplicate state snapshot block"),
+            StateSnapshotEntry::AccountLeaf { key, value } => {
+                let mut tree = state_tree(&db, account_root, account_count, block_number)?;
+                tree.update_raw(key, value)?;
+                account_root = tree.calculate_root()?;
+            }
+            StateSnapshotEntry::Script(script) => {
+                let mut tree = state_tree(&db, account_root, account_count, block_number)?;
+                tree.insert_script(script.hash().into(), script);
+            }
+            StateSnapshotEntry::AssetScript(script) => {
+                db.insert_asset_scripts(std::iter::once(script).collect())?;
+            }
+            StateSnapshotEntry::Data { data_hash, data } => {
+                let mut tree = state_tree(&db, account_root, account_count, block_number)?;
+                tree.insert_data(data_hash, data);
+            }
+            StateSnapshotEntry::BlockHash { number, hash } => {
+                db.attach_block_hash(number, &hash)?;
+            }
+            StateSnapshotEntry::RevertedBlockHashes(block_hashes) => {
+                insert_bad_block_hashes(&db, vec![block_hashes])?;
+            }
+        }
+
+        uncommitted += 1;
+        if uncommitted >= IMPORT_BATCH_ENTRIES {
+            db.commit()?;
+            db = store.begin_transaction();
+            uncommitted = 0;
+        }
+    }
+
+    let expected_account_root: H256 = post_account.merkle_root().unpack();
+    if account_root != expected_account_root {
+        bail!("import block {} account smt diff", block_number);
+    }
+
+    let block = exported_block.block;
+    db.insert_snapshot_block(
+        &block,
+        &exported_block.committed_info,
+        &exported_block.post_global_state,
+        exported_block.deposit_requests,
+    )?;
+    db.insert_asset_scripts(exported_block.deposit_asset_scripts.into_iter().collect())?;
+    db.attach_block(block)?;
+    db.set_block_state_record_pruned_number(block_number)?;
+
+    check_block_post_state(&db, block_number, &exported_block.post_global_state)?;
+    db.delete(COLUMN_META, META_IMPORT_STATE_KEY)?;
+    db.commit()?;
+
+    Ok(block_number)
+}
+
+fn clear_import(store: &Store) -> Result<()> {
+    for col in IMPORT_COLUMNS.iter() {
+        store.clear_column(*col)?;
+    }
+
+    let db = store.begin_transaction();
+    for key in vec![
+        META_CHAIN_ID_KEY,
+        META_BLOCK_SMT_ROOT_KEY,
+        META_REVERTED_BLOCK_SMT_ROOT_KEY,
+        META_IMPORT_STATE_KEY,
+    ] {
+        db.delete(COLUMN_META, key)?;
+    }
+    db.commit()?;
+    Ok(())
+}
+
+fn state_tree(
+    db: &StoreTransaction,
+    root: H256,
+    account_count: u32,
+    block_number: u64,
+) -> Result<StateTree<'_>> {
+    let tree = SMT::new(root, db.account_smt_store()?);
+    Ok(StateTree::new(
+        tree,
+        account_count,
+        StateContext::AttachBlock(block_number),
+    ))
+}
@@ -1,5 +1,7 @@
+pub mod backup;
 pub mod exponential_backoff;
 pub mod export_block;
+pub mod export_state;
 pub mod fee;
 pub mod genesis_info;
 pub mod polyjuice_parser;